@@ -0,0 +1,253 @@
+//! Procedural macros for marshal-rs.
+
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
+use std::path::PathBuf;
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("::core::compile_error!({message:?})")
+        .parse()
+        .unwrap()
+}
+
+/// Parses a single string literal, returning its contents.
+fn parse_path(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+
+    let literal: String = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err("include_marshal! expects a single string literal".to_string()),
+    };
+
+    literal
+        .strip_prefix('"')
+        .and_then(|literal| literal.strip_suffix('"'))
+        .filter(|path| !path.contains('\\'))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            "include_marshal! expects a plain string literal without escapes".to_string()
+        })
+}
+
+/// Embeds a Marshal file into the binary, validating that it loads at compile time.
+///
+/// Path is resolved relative to the directory of the calling crate's Cargo.toml.
+/// Expands to `marshal_rs::embed::Embedded`, so `marshal-rs` must be a dependency of the calling crate.
+/// # Example
+/// ```rust,ignore
+/// use marshal_rs_macros::include_marshal;
+///
+/// let system = include_marshal!("data/System.rvdata2");
+/// let value = system.load();
+/// ```
+#[proc_macro]
+pub fn include_marshal(input: TokenStream) -> TokenStream {
+    let path: String = match parse_path(input) {
+        Ok(path) => path,
+        Err(message) => return compile_error(&message),
+    };
+
+    let mut full_path: PathBuf = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    full_path.push(&path);
+
+    let bytes: Vec<u8> = match std::fs::read(&full_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return compile_error(&format!("Failed to read {}: {err}", full_path.display()))
+        }
+    };
+
+    if let Err(err) = marshal_rs::load(&bytes, None, None) {
+        return compile_error(&format!("Failed to load {}: {err}", full_path.display()));
+    }
+
+    let full_path: String = match full_path.to_str() {
+        Some(full_path) => full_path.to_string(),
+        None => return compile_error("Path to Marshal file must be valid UTF-8"),
+    };
+
+    // include_bytes! makes the compiler track the file, so changes to it trigger a rebuild
+    format!("::marshal_rs::embed::Embedded::new_unchecked(::core::include_bytes!({full_path:?}))")
+        .parse()
+        .unwrap()
+}
+
+/// Struct, that `FromValue` and `IntoValue` are derived for.
+struct DeriveInput {
+    name: String,
+    class: String,
+    /// Field names with their instance variable names, as string literals.
+    fields: Vec<(String, String)>,
+}
+
+/// Returns the value of `#[marshal(key = "...")]` attribute, if the group is this attribute's brackets.
+fn marshal_attribute(group: &Group, key: &str) -> Result<Option<String>, String> {
+    let mut tokens = group.stream().into_iter();
+
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "marshal" => {}
+        _ => return Ok(None),
+    }
+
+    let arguments: Vec<TokenTree> = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            group.stream().into_iter().collect()
+        }
+        _ => return Err(format!("Expected #[marshal({key} = \"...\")]")),
+    };
+
+    match arguments.as_slice() {
+        [TokenTree::Ident(ident), TokenTree::Punct(punct), TokenTree::Literal(literal)]
+            if ident.to_string() == key && punct.as_char() == '=' =>
+        {
+            Ok(Some(literal.to_string()))
+        }
+        _ => Err(format!("Expected #[marshal({key} = \"...\")]")),
+    }
+}
+
+/// Skips attributes and visibility, returning the value of `marshal` attribute with the key, if any.
+fn skip_prefix(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = TokenTree>>,
+    key: &str,
+) -> Result<Option<String>, String> {
+    let mut value: Option<String> = None;
+
+    loop {
+        match tokens.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                tokens.next();
+
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    if let Some(attribute) = marshal_attribute(&group, key)? {
+                        value = Some(attribute);
+                    }
+                }
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+                tokens.next();
+
+                if let Some(TokenTree::Group(group)) = tokens.peek() {
+                    if group.delimiter() == Delimiter::Parenthesis {
+                        tokens.next();
+                    }
+                }
+            }
+            _ => return Ok(value),
+        }
+    }
+}
+
+fn parse_derive_input(input: TokenStream) -> Result<DeriveInput, String> {
+    let mut tokens = input.into_iter().peekable();
+    let class: Option<String> = skip_prefix(&mut tokens, "class")?;
+
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => {}
+        _ => return Err("FromValue and IntoValue can only be derived for structs".to_string()),
+    }
+
+    let name: String = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("Expected struct name".to_string()),
+    };
+
+    let body: Group = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => group,
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+            return Err("FromValue and IntoValue can't be derived for generic structs".to_string())
+        }
+        _ => {
+            return Err(
+                "FromValue and IntoValue can only be derived for structs with named fields"
+                    .to_string(),
+            )
+        }
+    };
+
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut tokens = body.stream().into_iter().peekable();
+
+    while tokens.peek().is_some() {
+        let ivar: Option<String> = skip_prefix(&mut tokens, "name")?;
+
+        let field: String = match tokens.next() {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            _ => return Err("Expected field name".to_string()),
+        };
+
+        // Skip the type, up to the comma, that's not inside of generic arguments
+        let mut depth: usize = 0;
+
+        for token in tokens.by_ref() {
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    '<' => depth += 1,
+                    '>' => depth = depth.saturating_sub(1),
+                    ',' if depth == 0 => break,
+                    _ => {}
+                }
+            }
+        }
+
+        let ivar: String =
+            ivar.unwrap_or_else(|| format!("\"@{}\"", field.strip_prefix("r#").unwrap_or(&field)));
+        fields.push((field, ivar));
+    }
+
+    Ok(DeriveInput {
+        class: class.unwrap_or_else(|| format!("{name:?}")),
+        name,
+        fields,
+    })
+}
+
+fn derive(input: TokenStream, macro_name: &str) -> TokenStream {
+    let input: DeriveInput = match parse_derive_input(input) {
+        Ok(input) => input,
+        Err(message) => return compile_error(&message),
+    };
+
+    let fields: Vec<String> = input
+        .fields
+        .iter()
+        .map(|(field, ivar)| format!("{field}: {ivar}"))
+        .collect();
+
+    format!(
+        "::marshal_rs::{macro_name}!({}, {}, {{ {} }});",
+        input.name,
+        input.class,
+        fields.join(", ")
+    )
+    .parse()
+    .unwrap()
+}
+
+/// Derives `marshal_rs::FromValue` for a struct with named fields.
+///
+/// The struct is mapped to a Ruby class, named by `#[marshal(class = "...")]` attribute, or by the struct's name, if it's omitted.
+/// Fields are mapped to instance variables, named by `#[marshal(name = "@...")]` attribute, or by the field's name with `@` prefix.
+/// # Example
+/// ```rust,ignore
+/// use marshal_rs_macros::{FromValue, IntoValue};
+///
+/// #[derive(FromValue, IntoValue)]
+/// #[marshal(class = "RPG::Actor")]
+/// struct Actor {
+///     name: String,
+///     #[marshal(name = "@initial_level")]
+///     level: i32,
+/// }
+/// ```
+#[proc_macro_derive(FromValue, attributes(marshal))]
+pub fn derive_from_value(input: TokenStream) -> TokenStream {
+    derive(input, "impl_from_value")
+}
+
+/// Derives `marshal_rs::IntoValue` for a struct with named fields. Accepts the same attributes as `FromValue` derive.
+#[proc_macro_derive(IntoValue, attributes(marshal))]
+pub fn derive_into_value(input: TokenStream) -> TokenStream {
+    derive(input, "impl_into_value")
+}
@@ -0,0 +1,450 @@
+//! Command-line interface of marshal-rs.
+//!
+//! Requires `cli` feature. Not available with `sonic` feature enabled.
+
+#[cfg(feature = "sonic")]
+compile_error!("`cli` feature can't be used with `sonic` feature enabled.");
+
+use marshal_rs::{
+    codegen::Codegen, filter::Filter, inspect::profile, Dumper, Loader, LoaderBuilder, Preset,
+    StringMode, ValueExt,
+};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "Usage: marshal-rs <COMMAND> [OPTIONS] [FILES]
+
+Commands:
+  to-json      Convert Marshal data to JSON
+  from-json    Convert JSON back to Marshal data
+  inspect      Print an outline of the data
+  browse       Explore the data in an interactive tree viewer (requires `tui` feature)
+  stats        Print instance counts and sizes per class
+  diff         Print differences between two files
+  validate     Check that files load, and survive a round trip
+  codegen      Generate Rust structs from instances of classes in files
+  get          Print outputs of a jq-like filter, like `.@events[] | .@name`
+  select       Print all nested values, matching a condition, like `.@name == \"Boss\"`
+
+Files default to stdin, when omitted or passed as `-`.
+
+Options:
+  -o, --output <FILE>         Write output to the file instead of stdout
+  -p, --pretty                Pretty-print JSON
+  -d, --depth <DEPTH>         Depth of `inspect` outline [default: 3]
+      --string-mode <MODE>    Decode strings as `utf8` or `binary`
+      --preset <PRESET>       Load with a preset: `xp`, `vx`, `vxace` or `rails`
+      --prefix <PREFIX>       Replace `@` prefixes of instance variables
+  -h, --help                  Print this help";
+
+/// Number of elements of arrays and entries of objects, printed by `inspect`.
+const INSPECT_LIMIT: usize = 10;
+
+struct Args {
+    command: String,
+    files: Vec<String>,
+    output: Option<String>,
+    pretty: bool,
+    depth: usize,
+    string_mode: Option<StringMode>,
+    preset: Option<Preset>,
+    prefix: Option<String>,
+}
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Args>, String> {
+        let mut parsed: Args = Args {
+            command: String::new(),
+            files: Vec::new(),
+            output: None,
+            pretty: false,
+            depth: 3,
+            string_mode: None,
+            preset: None,
+            prefix: None,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Option {name} requires a value."))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => parsed.output = Some(value(&arg)?),
+                "-p" | "--pretty" => parsed.pretty = true,
+                "-d" | "--depth" => {
+                    parsed.depth = value(&arg)?
+                        .parse()
+                        .map_err(|_| "Depth must be a non-negative integer.".to_string())?
+                }
+                "--string-mode" => {
+                    parsed.string_mode = Some(match value(&arg)?.as_str() {
+                        "utf8" => StringMode::UTF8,
+                        "binary" => StringMode::Binary,
+                        mode => return Err(format!("Unknown string mode {mode}.")),
+                    })
+                }
+                "--preset" => {
+                    parsed.preset = Some(match value(&arg)?.as_str() {
+                        "xp" => Preset::RpgMakerXp,
+                        "vx" => Preset::RpgMakerVx,
+                        "vxace" => Preset::RpgMakerVxAce,
+                        "rails" => Preset::RailsCache,
+                        preset => return Err(format!("Unknown preset {preset}.")),
+                    })
+                }
+                "--prefix" => parsed.prefix = Some(value(&arg)?),
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("Unknown option {arg}."))
+                }
+                _ if parsed.command.is_empty() => parsed.command = arg,
+                _ => parsed.files.push(arg),
+            }
+        }
+
+        if parsed.command.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(parsed))
+    }
+
+    fn loader(&self) -> Loader<'_> {
+        let mut builder: LoaderBuilder = LoaderBuilder::new();
+
+        if let Some(preset) = self.preset {
+            builder = builder.preset(preset);
+        }
+
+        if let Some(string_mode) = self.string_mode {
+            builder = builder.string_mode(string_mode);
+        }
+
+        if let Some(prefix) = &self.prefix {
+            builder = builder.instance_var_prefix(prefix);
+        }
+
+        builder.build()
+    }
+
+    /// Returns files to read, or stdin, if none were passed.
+    fn inputs(&self) -> Vec<&str> {
+        if self.files.is_empty() {
+            vec!["-"]
+        } else {
+            self.files.iter().map(String::as_str).collect()
+        }
+    }
+
+    fn single_input(&self) -> Result<&str, String> {
+        match self.files.len() {
+            0 => Ok("-"),
+            1 => Ok(&self.files[0]),
+            _ => Err(format!("Command {} takes a single file.", self.command)),
+        }
+    }
+
+    fn load(&self, file: &str) -> Result<Value, String> {
+        let bytes: Vec<u8> = read(file)?;
+
+        self.loader()
+            .load(&bytes, None, None)
+            .map_err(|err| format!("{file}: {err}"))
+    }
+
+    fn json(&self, value: &Value) -> String {
+        if self.pretty {
+            serde_json::to_string_pretty(value)
+        } else {
+            serde_json::to_string(value)
+        }
+        .unwrap_or_default()
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<(), String> {
+        match &self.output {
+            Some(path) => fs::write(path, bytes).map_err(|err| format!("{path}: {err}")),
+            None => io::stdout()
+                .write_all(bytes)
+                .map_err(|err| format!("stdout: {err}")),
+        }
+    }
+}
+
+fn read(file: &str) -> Result<Vec<u8>, String> {
+    if file == "-" {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|err| format!("stdin: {err}"))?;
+
+        Ok(bytes)
+    } else {
+        fs::read(file).map_err(|err| format!("{file}: {err}"))
+    }
+}
+
+/// Returns a one-line description of the Value.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::String(string) => match string.strip_prefix("__symbol__") {
+            Some(symbol) => format!(":{symbol}"),
+            None => format!("{string:?}"),
+        },
+        Value::Array(array) => format!("Array ({})", array.len()),
+        Value::Object(object) => match object.get("__type").and_then(Value::as_str) {
+            Some("bytes") => format!("Bytes ({})", object["data"].as_array().map_or(0, Vec::len)),
+            Some("bigint") => object["value"].as_str().unwrap_or_default().to_string(),
+            Some("regexp") => format!(
+                "/{}/{}",
+                object["expression"].as_str().unwrap_or_default(),
+                object["flags"].as_str().unwrap_or_default()
+            ),
+            Some(_) => value.class_name().unwrap_or("Object").to_string(),
+            None => format!("Hash ({})", object.len()),
+        },
+        _ => value.to_string(),
+    }
+}
+
+fn outline(value: &Value, depth: usize, indent: usize, output: &mut String) {
+    if depth == 0 {
+        return;
+    }
+
+    let children: Vec<(String, &Value)> = match value {
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (format!("[{index}]"), element))
+            .collect(),
+        Value::Object(object)
+            if !matches!(
+                object.get("__type").and_then(Value::as_str),
+                Some("bytes" | "bigint" | "regexp")
+            ) =>
+        {
+            object
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "__class" | "__type"))
+                .map(|(key, entry)| {
+                    (
+                        key.strip_prefix("__symbol__").unwrap_or(key).to_string(),
+                        entry,
+                    )
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    for (key, child) in children.iter().take(INSPECT_LIMIT) {
+        output.push_str(&format!(
+            "{}{key}: {}\n",
+            "  ".repeat(indent),
+            describe(child)
+        ));
+        outline(child, depth - 1, indent + 1, output);
+    }
+
+    if children.len() > INSPECT_LIMIT {
+        output.push_str(&format!(
+            "{}... {} more\n",
+            "  ".repeat(indent),
+            children.len() - INSPECT_LIMIT
+        ));
+    }
+}
+
+/// Prints differences between flattened values. Returns whether there were any.
+fn diff(a: &Map<String, Value>, b: &Map<String, Value>, output: &mut String) -> bool {
+    let mut different: bool = false;
+
+    for (path, old) in a {
+        match b.get(path) {
+            Some(new) if new == old => {}
+            Some(new) => {
+                output.push_str(&format!("~ {path}: {old} -> {new}\n"));
+                different = true;
+            }
+            None => {
+                output.push_str(&format!("- {path}: {old}\n"));
+                different = true;
+            }
+        }
+    }
+
+    for (path, new) in b {
+        if !a.contains_key(path) {
+            output.push_str(&format!("+ {path}: {new}\n"));
+            different = true;
+        }
+    }
+
+    different
+}
+
+fn run(args: &Args) -> Result<ExitCode, String> {
+    match args.command.as_str() {
+        "to-json" => {
+            let value: Value = args.load(args.single_input()?)?;
+            args.write((args.json(&value) + "\n").as_bytes())?;
+        }
+        "from-json" => {
+            let file: &str = args.single_input()?;
+            let value: Value =
+                serde_json::from_slice(&read(file)?).map_err(|err| format!("{file}: {err}"))?;
+            let bytes: Vec<u8> = Dumper::new()
+                .try_dump(value, args.prefix.as_deref())
+                .map_err(|err| format!("{file}: {err}"))?;
+            args.write(&bytes)?;
+        }
+        "inspect" => {
+            let value: Value = args.load(args.single_input()?)?;
+            let mut output: String = describe(&value) + "\n";
+            outline(&value, args.depth, 1, &mut output);
+            args.write(output.as_bytes())?;
+        }
+        #[cfg(feature = "tui")]
+        "browse" => {
+            let value: Value = args.load(args.single_input()?)?;
+            marshal_rs::inspect::browse(&value).map_err(|err| format!("terminal: {err}"))?;
+        }
+        #[cfg(not(feature = "tui"))]
+        "browse" => {
+            return Err(
+                "Command browse requires marshal-rs to be built with `tui` feature.".to_string(),
+            )
+        }
+        "stats" => {
+            let value: Value = args.load(args.single_input()?)?;
+            let mut output: String = format!(
+                "{:<32} {:>10} {:>14} {:>14}\n",
+                "Class", "Count", "String bytes", "Size"
+            );
+
+            for (class, stats) in profile(&value).by_size() {
+                output.push_str(&format!(
+                    "{class:<32} {:>10} {:>14} {:>14}\n",
+                    stats.count, stats.string_bytes, stats.estimated_size
+                ));
+            }
+
+            args.write(output.as_bytes())?;
+        }
+        "diff" => {
+            let (a, b) = match args.files.as_slice() {
+                [a, b] => (args.load(a)?, args.load(b)?),
+                _ => return Err("Command diff takes two files.".to_string()),
+            };
+
+            let mut output: String = String::new();
+            let different: bool = diff(&a.flatten(), &b.flatten(), &mut output);
+            args.write(output.as_bytes())?;
+
+            return Ok(ExitCode::from(different as u8));
+        }
+        "validate" => {
+            let mut output: String = String::new();
+            let mut failed: bool = false;
+
+            for file in args.inputs() {
+                let result: Result<(), String> = args.load(file).and_then(|value| {
+                    let dumped: Vec<u8> = Dumper::new()
+                        .try_dump(value.clone(), args.prefix.as_deref())
+                        .map_err(|err| format!("{file}: {err}"))?;
+
+                    if args.loader().load(&dumped, None, None).ok() == Some(value) {
+                        Ok(())
+                    } else {
+                        Err(format!("{file}: value changes after a round trip"))
+                    }
+                });
+
+                match result {
+                    Ok(()) => output.push_str(&format!("OK {file}\n")),
+                    Err(err) => {
+                        output.push_str(&format!("FAILED {err}\n"));
+                        failed = true;
+                    }
+                }
+            }
+
+            args.write(output.as_bytes())?;
+            return Ok(ExitCode::from(failed as u8));
+        }
+        "get" | "select" => {
+            let (expression, file) = match args.files.as_slice() {
+                [expression] => (expression, "-"),
+                [expression, file] => (expression, file.as_str()),
+                _ => {
+                    return Err(format!(
+                        "Command {} takes an expression and a single file.",
+                        args.command
+                    ))
+                }
+            };
+
+            let filter: Filter = if args.command == "get" {
+                Filter::parse(expression)
+            } else {
+                Filter::parse(&format!(".. | select({expression})"))
+            }
+            .map_err(|err| err.to_string())?;
+
+            let mut output: String = String::new();
+
+            for value in filter
+                .eval(&args.load(file)?)
+                .map_err(|err| err.to_string())?
+            {
+                output.push_str(&args.json(&value));
+                output.push('\n');
+            }
+
+            args.write(output.as_bytes())?;
+        }
+        "codegen" => {
+            let mut codegen: Codegen = Codegen::new();
+
+            for file in args.inputs() {
+                codegen.add(&args.load(file)?);
+            }
+
+            args.write(codegen.generate().as_bytes())?;
+        }
+        command => return Err(format!("Unknown command {command}.\n\n{USAGE}")),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args: Args = match Args::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
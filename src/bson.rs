@@ -0,0 +1,136 @@
+//! Conversion of loaded JSON values to BSON and back, for importing Marshal data into MongoDB.
+//!
+//! | Ruby object                  | BSON                                             |
+//! | ---------------------------- | ------------------------------------------------ |
+//! | Integer                      | `Int32`, or `Int64` if it doesn't fit            |
+//! | Float                        | `Double`                                         |
+//! | Symbol                       | `Symbol`, or `String` with `symbols_as_strings`  |
+//! | Binary string                | `Binary` with generic subtype                    |
+//! | Regexp                       | `RegularExpression`, with `i`, `x` and `s` options |
+//! | Array                        | `Array`                                          |
+//! | Hash, object                 | `Document` with JSON keys, like `__class`        |
+//!
+//! Integers, that don't fit in `Int64`, are written as Big Integers' `{ __type: "bigint", value: "..." }` documents.
+//!
+//! `from_bson()` reads other BSON types, like `ObjectId` or `DateTime`, as their relaxed Extended JSON, like `{ "$oid": "..." }`.
+//!
+//! Requires `bson` feature. Not available with `sonic` feature enabled.
+
+use crate::value::bytes_of;
+use ::bson::{spec::BinarySubtype, Binary, Bson, Document, Regex};
+use serde_json::{json, Map, Value};
+
+/// Options of `to_bson()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BsonOptions {
+    /// Whether symbols are written as BSON strings with their names, instead of deprecated BSON symbols.
+    /// Such symbols are read back as strings. Defaults to false.
+    pub symbols_as_strings: bool,
+}
+
+/// Converts the Value to BSON. See the module documentation for the mapping.
+/// # Example
+/// ```rust
+/// use marshal_rs::bson::{from_bson, to_bson, BsonOptions};
+/// use bson::{doc, Bson};
+/// use serde_json::json;
+///
+/// let value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@id": 1 });
+/// let bson: Bson = to_bson(&value, BsonOptions::default());
+///
+/// assert_eq!(bson, Bson::Document(doc! { "__class": Bson::Symbol("Item".to_string()), "__type": "object", "__symbol__@id": 1 }));
+/// assert_eq!(from_bson(bson), value);
+/// ```
+pub fn to_bson(value: &Value, options: BsonOptions) -> Bson {
+    match value {
+        Value::Null => Bson::Null,
+        Value::Bool(bool) => Bson::Boolean(*bool),
+        Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                match i32::try_from(integer) {
+                    Ok(integer) => Bson::Int32(integer),
+                    Err(_) => Bson::Int64(integer),
+                }
+            } else if number.is_u64() {
+                to_bson(
+                    &json!({ "__type": "bigint", "value": number.to_string() }),
+                    options,
+                )
+            } else {
+                Bson::Double(number.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(string) => match string.strip_prefix("__symbol__") {
+            Some(symbol) if options.symbols_as_strings => Bson::String(symbol.to_string()),
+            Some(symbol) => Bson::Symbol(symbol.to_string()),
+            None => Bson::String(string.clone()),
+        },
+        Value::Array(array) => Bson::Array(
+            array
+                .iter()
+                .map(|element| to_bson(element, options))
+                .collect(),
+        ),
+        Value::Object(object) => {
+            if let Some(bytes) = bytes_of(value) {
+                return Bson::Binary(Binary {
+                    subtype: BinarySubtype::Generic,
+                    bytes,
+                });
+            }
+
+            if value["__type"] == "regexp" {
+                let mut flags: Vec<char> = value["flags"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .chars()
+                    .map(|flag| if flag == 'm' { 's' } else { flag })
+                    .collect();
+                flags.sort_unstable();
+
+                return Bson::RegularExpression(Regex {
+                    pattern: value["expression"].as_str().unwrap_or_default().to_string(),
+                    options: flags.into_iter().collect(),
+                });
+            }
+
+            Bson::Document(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), to_bson(value, options)))
+                    .collect::<Document>(),
+            )
+        }
+    }
+}
+
+/// Converts BSON, written by `to_bson()` or by MongoDB, to a Value. See the module documentation for the mapping.
+pub fn from_bson(bson: Bson) -> Value {
+    match bson {
+        Bson::Null | Bson::Undefined => Value::Null,
+        Bson::Boolean(bool) => bool.into(),
+        Bson::Int32(integer) => integer.into(),
+        Bson::Int64(integer) => integer.into(),
+        Bson::Double(float) => json!(float),
+        Bson::String(string) => string.into(),
+        Bson::Symbol(symbol) => format!("__symbol__{symbol}").into(),
+        Bson::Array(array) => Value::Array(array.into_iter().map(from_bson).collect()),
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, from_bson(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Bson::Binary(binary) => json!({ "__type": "bytes", "data": binary.bytes }),
+        Bson::RegularExpression(regex) => {
+            let flags: String = ['i', 'x', 's']
+                .iter()
+                .filter(|flag| regex.options.contains(**flag))
+                .map(|flag| if *flag == 's' { 'm' } else { *flag })
+                .collect();
+
+            json!({ "__type": "regexp", "expression": regex.pattern, "flags": flags })
+        }
+        bson => bson.into_relaxed_extjson(),
+    }
+}
@@ -0,0 +1,166 @@
+//! Conversions of Ruby's standard library classes, that are dumped as generic objects.
+//!
+//! | Ruby object  | Dumped as                                                                    | Converted to                    |
+//! | ------------ | ---------------------------------------------------------------------------- | ------------------------------- |
+//! | `Set`        | Object with `@hash` instance variable, mapping elements to `true`            | `RubySet`, holding elements     |
+//! | `OpenStruct` | `marshal_dump` of its table Hash, keyed by symbols                           | `OpenStruct`, holding the table |
+//! | `Range`      | Object with `excl`, `begin` and `end` instance variables without "@"         | `RubyRange`                     |
+//!
+//! All of them implement `FromValue` and `IntoValue`, and convert back to the same objects, that Ruby 1.9 and later dump.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{
+    typed::{mismatch, FromValue, IntoValue},
+    value::{hash_key, is_hash, key_value, to_symbol, ValueError},
+    ValueExt, DEFAULT_SYMBOL, RANGE_INSTANCE_VARS,
+};
+use serde_json::{json, Map, Value};
+
+const HASH_SYMBOL: &str = "__symbol__@hash";
+const TABLE_SYMBOL: &str = "__symbol__@table";
+
+/// Ruby's Set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubySet {
+    /// Elements of the Set, in their insertion order.
+    pub elements: Vec<Value>,
+    /// Default value of the underlying Hash. Ruby creates it with `Hash.new(false)`, so it's `false` for Sets, created by Ruby 1.9 and later.
+    pub default: Value,
+}
+
+impl From<Vec<Value>> for RubySet {
+    fn from(elements: Vec<Value>) -> Self {
+        RubySet {
+            elements,
+            default: Value::Bool(false),
+        }
+    }
+}
+
+impl FromValue for RubySet {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let hash: &Map<String, Value> = match value.get(HASH_SYMBOL) {
+            Some(hash) if value.is_instance_of("Set") && is_hash(hash) => hash.as_object().unwrap(),
+            _ => return Err(mismatch("Set", value)),
+        };
+
+        Ok(RubySet {
+            elements: hash
+                .keys()
+                .filter(|key| *key != DEFAULT_SYMBOL)
+                .map(|key| key_value(key))
+                .collect(),
+            default: hash.get(DEFAULT_SYMBOL).cloned().unwrap_or_default(),
+        })
+    }
+}
+
+impl IntoValue for RubySet {
+    /// Converts the Set to the object, that Ruby dumps.
+    fn into_value(self) -> Value {
+        let mut hash: Map<String, Value> = self
+            .elements
+            .iter()
+            .map(hash_key)
+            .map(|key| (key, Value::Bool(true)))
+            .collect();
+
+        if !self.default.is_null() {
+            hash.insert(DEFAULT_SYMBOL.to_string(), self.default);
+        }
+
+        json!({ "__class": to_symbol("Set"), "__type": "object", HASH_SYMBOL: hash })
+    }
+}
+
+/// Ruby's OpenStruct.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenStruct {
+    /// Fields of the OpenStruct, keyed by their names without `__symbol__` prefixes.
+    pub table: Map<String, Value>,
+}
+
+impl FromValue for OpenStruct {
+    /// Converts OpenStruct, dumped with `marshal_dump`, or, as Ruby 1.8 did, as an object with `@table` instance variable.
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let table: &Map<String, Value> = match value
+            .get("__userMarshal")
+            .or_else(|| value.get(TABLE_SYMBOL))
+        {
+            Some(table) if value.is_instance_of("OpenStruct") && is_hash(table) => {
+                table.as_object().unwrap()
+            }
+            _ => return Err(mismatch("OpenStruct", value)),
+        };
+
+        Ok(OpenStruct {
+            table: table
+                .iter()
+                .map(|(key, field)| match key.strip_prefix("__symbol__") {
+                    Some(name) => Ok((name.to_string(), field.clone())),
+                    None => Err(ValueError {
+                        message: format!("OpenStruct field name {key} is not a symbol"),
+                    }),
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl IntoValue for OpenStruct {
+    /// Converts the OpenStruct to the object, that Ruby 1.9 and later dump with `marshal_dump`.
+    fn into_value(self) -> Value {
+        let table: Map<String, Value> = self
+            .table
+            .into_iter()
+            .map(|(name, field)| (to_symbol(&name), field))
+            .collect();
+
+        json!({ "__class": to_symbol("OpenStruct"), "__type": "object", "__userMarshal": table })
+    }
+}
+
+/// Ruby's Range, like `1..10` or `"a"...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubyRange {
+    /// Beginning of the Range, `nil` for beginless Ranges.
+    pub begin: Value,
+    /// End of the Range, `nil` for endless Ranges.
+    pub end: Value,
+    /// Whether the end is excluded, like in `1...10`.
+    pub exclusive: bool,
+}
+
+impl FromValue for RubyRange {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("Range") {
+            return Err(mismatch("Range", value));
+        }
+
+        let [excl, begin, end] = RANGE_INSTANCE_VARS.map(|name| &value[name]);
+
+        Ok(RubyRange {
+            begin: begin.clone(),
+            end: end.clone(),
+            exclusive: excl.as_bool().ok_or_else(|| ValueError {
+                message: "Range excl is not a boolean".to_string(),
+            })?,
+        })
+    }
+}
+
+impl IntoValue for RubyRange {
+    /// Converts the Range to the object, that Ruby dumps, with instance variables in the same order.
+    fn into_value(self) -> Value {
+        let [excl, begin, end] = RANGE_INSTANCE_VARS;
+
+        json!({
+            "__class": to_symbol("Range"),
+            "__type": "object",
+            excl: self.exclusive,
+            begin: self.begin,
+            end: self.end,
+        })
+    }
+}
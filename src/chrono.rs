@@ -0,0 +1,507 @@
+//! Conversion of Ruby's `Time`, `Date` and `DateTime` objects to `chrono` types and back.
+//!
+//! ## Time
+//!
+//! Ruby dumps Time with `_dump`, as 8 bytes of bit-packed UTC date and time, and the instance variables of the dumped string:
+//!
+//! | Instance variable         | Meaning                                                                 |
+//! | ------------------------- | ----------------------------------------------------------------------- |
+//! | `nano_num`, `nano_den`    | Nanoseconds, that don't fit in the microseconds of the packed time      |
+//! | `submicro`                | The same nanoseconds as packed BCD digits, written for Ruby 1.9.1       |
+//! | `offset`                  | UTC offset in seconds, absent for UTC times                             |
+//! | `zone`                    | Time zone abbreviation, like `"JST"` or `"UTC"`                         |
+//! | `year`                    | Year, that doesn't fit in the packed time                               |
+//!
+//! Times are loaded as `{ __class: "__symbol__Time", __type: "object", __userDefined: [...], "__symbol__offset": ... }` objects,
+//! which `RubyTime` and `DateTime` implement `FromValue` and `IntoValue` for.
+//!
+//! ## Date and DateTime
+//!
+//! Ruby dumps Date and DateTime with `marshal_dump`, as `[nth, jd, df, sf, of, sg]` arrays:
+//!
+//! | Element | Meaning                                                                              |
+//! | ------- | ------------------------------------------------------------------------------------ |
+//! | `nth`   | Number of Julian day periods, that don't fit in `jd`. Only 0 is supported             |
+//! | `jd`    | Chronological Julian day number of UTC date                                          |
+//! | `df`    | Seconds since UTC midnight                                                           |
+//! | `sf`    | Nanoseconds, an Integer or a Rational                                                |
+//! | `of`    | UTC offset in seconds                                                                |
+//! | `sg`    | Julian day of the Gregorian calendar reform, `2299161.0` (`Date::ITALY`) by default   |
+//!
+//! Ruby 1.8 dumped them as `[ajd, of, sg]` arrays of astronomical Julian day and the offset in days, which are Rationals. Such objects are read as well,
+//! and are written in the current format.
+//!
+//! Such objects are loaded as `{ __class: "__symbol__Date", __type: "object", __userMarshal: [...] }` objects,
+//! which `RubyDate`, `RubyDateTime` and `NaiveDate` implement `FromValue` and `IntoValue` for.
+//! Julian days are mapped to proleptic Gregorian dates of `chrono`, so dates before the reform keep their days, but not their Julian calendar labels.
+//!
+//! Requires `chrono` feature. Not available with `sonic` feature enabled.
+
+use crate::{
+    typed::{mismatch, FromValue, IntoValue},
+    value::{bytes_of, to_symbol, user_defined_of, ValueError},
+    ValueExt,
+};
+use ::chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use serde_json::{json, Map, Value};
+
+const MIN_YEAR: i32 = 1900;
+const MAX_YEAR: i32 = 1900 + 0xffff;
+/// Julian day of the Gregorian calendar reform in Italy, Ruby's `Date::ITALY`.
+pub const ITALY: f64 = 2_299_161.0;
+/// Chronological Julian day of 0001-01-01 minus one, as `chrono` counts days from it.
+const JD_CE_OFFSET: i64 = 1_721_425;
+const DAY_SECONDS: i64 = 86_400;
+
+/// Ruby's Time, with the time zone information, that `DateTime<FixedOffset>` doesn't keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RubyTime {
+    pub datetime: DateTime<FixedOffset>,
+    /// Whether the Time is in UTC mode, like ones, returned by `Time#utc`.
+    pub utc: bool,
+    /// Time zone abbreviation, like `"JST"`. Times with fixed offsets, like `Time.new(2024, 1, 2, 3, 4, 5, "+09:00")`, don't have it.
+    pub zone: Option<String>,
+}
+
+impl From<DateTime<FixedOffset>> for RubyTime {
+    fn from(datetime: DateTime<FixedOffset>) -> Self {
+        RubyTime {
+            datetime,
+            utc: false,
+            zone: None,
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for RubyTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        RubyTime {
+            datetime: datetime.fixed_offset(),
+            utc: true,
+            zone: Some("UTC".to_string()),
+        }
+    }
+}
+
+fn time_error(message: &str) -> ValueError {
+    ValueError {
+        message: format!("Invalid Time: {message}"),
+    }
+}
+
+fn ivar<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value.get(to_symbol(name)).filter(|ivar| !ivar.is_null())
+}
+
+/// Returns the nanoseconds, written in instance variables.
+fn nanoseconds(value: &Value) -> Result<u32, ValueError> {
+    if let Some(numerator) = ivar(value, "nano_num") {
+        let numerator: i64 = numerator
+            .as_i64()
+            .ok_or_else(|| time_error("nano_num is not an Integer"))?;
+        let denominator: i64 = ivar(value, "nano_den")
+            .map_or(Some(1), Value::as_i64)
+            .filter(|denominator| *denominator > 0)
+            .ok_or_else(|| time_error("nano_den is not a positive Integer"))?;
+
+        return u32::try_from(numerator / denominator)
+            .ok()
+            .filter(|nanoseconds| *nanoseconds < 1000)
+            .ok_or_else(|| time_error("nanoseconds out of range"));
+    }
+
+    if let Some(submicro) = ivar(value, "submicro") {
+        let bytes: Vec<u8> = match submicro.as_str() {
+            Some(string) => string.as_bytes().to_vec(),
+            None => bytes_of(submicro).ok_or_else(|| time_error("submicro is not a String"))?,
+        };
+
+        let digits: [u8; 3] = [
+            bytes.first().map_or(0, |byte| byte >> 4),
+            bytes.first().map_or(0, |byte| byte & 0xf),
+            bytes.get(1).map_or(0, |byte| byte >> 4),
+        ];
+
+        if digits.iter().any(|digit| *digit > 9) {
+            return Err(time_error("submicro is not BCD"));
+        }
+
+        return Ok(digits
+            .iter()
+            .fold(0, |nanoseconds, digit| nanoseconds * 10 + u32::from(*digit)));
+    }
+
+    Ok(0)
+}
+
+impl FromValue for RubyTime {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("Time") {
+            return Err(mismatch("Time", value));
+        }
+
+        let bytes: Vec<u8> = user_defined_of(value)
+            .filter(|bytes| bytes.len() == 8)
+            .ok_or_else(|| time_error("expected 8 bytes of dumped data"))?;
+
+        let p: u32 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let s: u32 = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+        let offset: i32 = match ivar(value, "offset") {
+            Some(offset) => offset
+                .as_i64()
+                .and_then(|offset| i32::try_from(offset).ok())
+                .ok_or_else(|| time_error("offset is not an Integer"))?,
+            None => 0,
+        };
+        let offset: FixedOffset =
+            FixedOffset::east_opt(offset).ok_or_else(|| time_error("offset out of range"))?;
+
+        let zone: Option<String> = match ivar(value, "zone") {
+            Some(zone) => Some(match zone.as_str() {
+                Some(zone) => zone.to_string(),
+                None => bytes_of(zone)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .ok_or_else(|| time_error("zone is not a String"))?,
+            }),
+            None => None,
+        };
+
+        // Before Ruby 1.9, Time was dumped as seconds and microseconds since the epoch
+        if p & (1 << 31) == 0 {
+            let datetime: DateTime<Utc> = Utc
+                .timestamp_opt(i64::from(p), s.saturating_mul(1000))
+                .single()
+                .ok_or_else(|| time_error("microseconds out of range"))?;
+
+            return Ok(RubyTime {
+                datetime: datetime.with_timezone(&offset),
+                utc: false,
+                zone,
+            });
+        }
+
+        let year: i32 = match ivar(value, "year") {
+            Some(year) => year
+                .as_i64()
+                .and_then(|year| i32::try_from(year).ok())
+                .ok_or_else(|| time_error("year is not an Integer"))?,
+            None => ((p >> 14) & 0xffff) as i32 + MIN_YEAR,
+        };
+
+        let microseconds: u32 = s & 0xfffff;
+        let nanoseconds: u32 = nanoseconds(value)?;
+        let naive: NaiveDateTime =
+            NaiveDate::from_ymd_opt(year, ((p >> 10) & 0xf) + 1, (p >> 5) & 0x1f)
+                .and_then(|date| {
+                    date.and_hms_nano_opt(
+                        p & 0x1f,
+                        (s >> 26) & 0x3f,
+                        (s >> 20) & 0x3f,
+                        microseconds * 1000 + nanoseconds,
+                    )
+                })
+                .filter(|_| microseconds < 1_000_000)
+                .ok_or_else(|| time_error("date or time out of range"))?;
+
+        Ok(RubyTime {
+            datetime: offset.from_utc_datetime(&naive),
+            utc: (p >> 30) & 1 == 1,
+            zone,
+        })
+    }
+}
+
+impl IntoValue for RubyTime {
+    /// Converts the Time to the object, that Ruby dumps: with the same packed bytes and instance variables in the same order.
+    fn into_value(self) -> Value {
+        let naive: NaiveDateTime = self.datetime.naive_utc();
+        let year: i32 = naive.year().clamp(MIN_YEAR, MAX_YEAR);
+        let nanoseconds: u32 = naive.nanosecond() % 1_000_000_000;
+
+        let p: u32 = (1 << 31)
+            | (u32::from(self.utc) << 30)
+            | (((year - MIN_YEAR) as u32) << 14)
+            | (naive.month0() << 10)
+            | (naive.day() << 5)
+            | naive.hour();
+        let s: u32 = (naive.minute() << 26) | (naive.second() << 20) | (nanoseconds / 1000);
+
+        let mut bytes: Vec<u8> = p.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&s.to_le_bytes());
+
+        let mut object: Map<String, Value> = Map::new();
+        object.insert("__class".to_string(), to_symbol("Time").into());
+        object.insert("__type".to_string(), "object".into());
+        object.insert("__userDefined".to_string(), bytes.into());
+
+        let submicro: u32 = nanoseconds % 1000;
+
+        if submicro != 0 {
+            object.insert(to_symbol("nano_num"), submicro.into());
+            object.insert(to_symbol("nano_den"), 1.into());
+
+            let mut digits: Vec<u8> = vec![(((submicro / 100) << 4) | (submicro / 10 % 10)) as u8];
+
+            if submicro % 10 != 0 {
+                digits.push(((submicro % 10) << 4) as u8);
+            }
+
+            object.insert(
+                to_symbol("submicro"),
+                json!({ "__type": "bytes", "data": digits }),
+            );
+        }
+
+        if !self.utc {
+            object.insert(
+                to_symbol("offset"),
+                self.datetime.offset().local_minus_utc().into(),
+            );
+        }
+
+        if let Some(zone) = self.zone {
+            object.insert(to_symbol("zone"), zone.into());
+        }
+
+        if year != naive.year() {
+            object.insert(to_symbol("year"), naive.year().into());
+        }
+
+        Value::Object(object)
+    }
+}
+
+impl FromValue for DateTime<FixedOffset> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        RubyTime::from_value(value).map(|time| time.datetime)
+    }
+}
+
+impl IntoValue for DateTime<FixedOffset> {
+    fn into_value(self) -> Value {
+        RubyTime::from(self).into_value()
+    }
+}
+
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        RubyTime::from_value(value).map(|time| time.datetime.with_timezone(&Utc))
+    }
+}
+
+impl IntoValue for DateTime<Utc> {
+    fn into_value(self) -> Value {
+        RubyTime::from(self).into_value()
+    }
+}
+
+/// Ruby's Date.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RubyDate {
+    pub date: NaiveDate,
+    /// Julian day of the Gregorian calendar reform. Defaults to `ITALY`.
+    pub start: f64,
+}
+
+impl From<NaiveDate> for RubyDate {
+    fn from(date: NaiveDate) -> Self {
+        RubyDate { date, start: ITALY }
+    }
+}
+
+/// Ruby's DateTime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RubyDateTime {
+    pub datetime: DateTime<FixedOffset>,
+    /// Julian day of the Gregorian calendar reform. Defaults to `ITALY`.
+    pub start: f64,
+}
+
+impl From<DateTime<FixedOffset>> for RubyDateTime {
+    fn from(datetime: DateTime<FixedOffset>) -> Self {
+        RubyDateTime {
+            datetime,
+            start: ITALY,
+        }
+    }
+}
+
+fn date_error(message: &str) -> ValueError {
+    ValueError {
+        message: format!("Invalid Date: {message}"),
+    }
+}
+
+/// Returns the numerator and the denominator of an Integer or a Rational.
+fn rational(value: &Value) -> Option<(i128, i128)> {
+    if let Some(integer) = value.as_i64() {
+        return Some((i128::from(integer), 1));
+    }
+
+    if !value.is_instance_of("Rational") {
+        return None;
+    }
+
+    let numerator: i64 = value["__userMarshal"][0].as_i64()?;
+    let denominator: i64 = value["__userMarshal"][1].as_i64().filter(|den| *den > 0)?;
+
+    Some((i128::from(numerator), i128::from(denominator)))
+}
+
+/// Reads the dumped Date or DateTime of the class as UTC date and time, UTC offset and the start of the Gregorian calendar.
+fn read_date(value: &Value, class: &str) -> Result<(NaiveDateTime, FixedOffset, f64), ValueError> {
+    if !value.is_instance_of(class) {
+        return Err(mismatch(class, value));
+    }
+
+    let fields: &Vec<Value> = value["__userMarshal"]
+        .as_array()
+        .ok_or_else(|| date_error("expected dumped array"))?;
+
+    let (days, nanoseconds, offset, start): (i128, i128, i128, &Value) = match fields.as_slice() {
+        [nth, jd, df, sf, of, sg] => {
+            if nth != 0 {
+                return Err(date_error("Julian day periods are not supported"));
+            }
+
+            let integer = |field: &Value, name: &str| {
+                field
+                    .as_i64()
+                    .map(i128::from)
+                    .ok_or_else(|| date_error(&format!("{name} is not an Integer")))
+            };
+            let (numerator, denominator) =
+                rational(sf).ok_or_else(|| date_error("sf is not a Rational"))?;
+
+            (
+                integer(jd, "jd")?,
+                integer(df, "df")? * 1_000_000_000 + numerator / denominator,
+                integer(of, "of")?,
+                sg,
+            )
+        }
+        [ajd, of, sg] => {
+            let (numerator, denominator) =
+                rational(ajd).ok_or_else(|| date_error("ajd is not a Rational"))?;
+            let (of_numerator, of_denominator) =
+                rational(of).ok_or_else(|| date_error("of is not a Rational"))?;
+
+            // Astronomical Julian days start at noon, chronological ones at midnight
+            let halves: i128 = numerator * 2 + denominator;
+            let days: i128 = halves.div_euclid(denominator * 2);
+            let remainder: i128 = halves.rem_euclid(denominator * 2);
+
+            (
+                days,
+                remainder * i128::from(DAY_SECONDS) * 1_000_000_000 / (denominator * 2),
+                of_numerator * i128::from(DAY_SECONDS) / of_denominator,
+                sg,
+            )
+        }
+        _ => return Err(date_error("expected 6 or 3 dumped elements")),
+    };
+
+    let start: f64 = start
+        .as_f64()
+        .ok_or_else(|| date_error("sg is not a finite Float"))?;
+    let offset: FixedOffset = i32::try_from(offset)
+        .ok()
+        .and_then(FixedOffset::east_opt)
+        .ok_or_else(|| date_error("offset out of range"))?;
+
+    let date: NaiveDate = i32::try_from(days - i128::from(JD_CE_OFFSET))
+        .ok()
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .ok_or_else(|| date_error("Julian day out of range"))?;
+    let seconds: u32 = u32::try_from(nanoseconds / 1_000_000_000)
+        .ok()
+        .filter(|seconds| i64::from(*seconds) < DAY_SECONDS)
+        .ok_or_else(|| date_error("day fraction out of range"))?;
+    let naive: NaiveDateTime = date
+        .and_hms_nano_opt(
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            (nanoseconds % 1_000_000_000) as u32,
+        )
+        .ok_or_else(|| date_error("day fraction out of range"))?;
+
+    Ok((naive, offset, start))
+}
+
+/// Returns the object, that Ruby dumps for Date or DateTime of the class.
+fn write_date(class: &str, naive: NaiveDateTime, offset: i32, start: f64) -> Value {
+    let seconds: u32 = naive.num_seconds_from_midnight();
+
+    json!({
+        "__class": to_symbol(class),
+        "__type": "object",
+        "__userMarshal": [
+            0,
+            i64::from(naive.num_days_from_ce()) + JD_CE_OFFSET,
+            seconds,
+            naive.nanosecond() % 1_000_000_000,
+            offset,
+            start,
+        ],
+    })
+}
+
+impl FromValue for RubyDate {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let (naive, _, start) = read_date(value, "Date")?;
+
+        Ok(RubyDate {
+            date: naive.date(),
+            start,
+        })
+    }
+}
+
+impl IntoValue for RubyDate {
+    fn into_value(self) -> Value {
+        write_date(
+            "Date",
+            self.date.and_hms_opt(0, 0, 0).unwrap(),
+            0,
+            self.start,
+        )
+    }
+}
+
+impl FromValue for RubyDateTime {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let (naive, offset, start) = read_date(value, "DateTime")?;
+
+        Ok(RubyDateTime {
+            datetime: offset.from_utc_datetime(&naive),
+            start,
+        })
+    }
+}
+
+impl IntoValue for RubyDateTime {
+    fn into_value(self) -> Value {
+        write_date(
+            "DateTime",
+            self.datetime.naive_utc(),
+            self.datetime.offset().local_minus_utc(),
+            self.start,
+        )
+    }
+}
+
+impl FromValue for NaiveDate {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        RubyDate::from_value(value).map(|date| date.date)
+    }
+}
+
+impl IntoValue for NaiveDate {
+    fn into_value(self) -> Value {
+        RubyDate::from(self).into_value()
+    }
+}
@@ -0,0 +1,181 @@
+//! Loading and dumping with one set of options, so both directions are configured symmetrically.
+//!
+//! Options, that must match in `Loader` and `Dumper` for values to round-trip, like the instance variable prefix and the data registry, are set once on `CodecBuilder`,
+//! and can't be overridden per call. Options of a single direction are set with `CodecBuilder::loader()` and `CodecBuilder::dumper()`.
+//! # Example
+//! ```rust
+//! use marshal_rs::MarshalCodec;
+//! use serde_json::json;
+//!
+//! let mut codec = MarshalCodec::builder()
+//!     .instance_var_prefix("")
+//!     .loader(|loader| loader.max_depth(16))
+//!     .build();
+//!
+//! let item = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__name": "Sword" });
+//! let bytes: Vec<u8> = codec.dump(item.clone());
+//!
+//! // Instance variable is written as @name
+//! assert!(bytes.windows(5).any(|window| window == b"@name"));
+//! assert_eq!(codec.load(&bytes).unwrap(), item);
+//! ```
+
+use crate::{
+    data::DataRegistry, dump::DumpError, load::LoadError, pool::TablePool, Dumper, DumperBuilder,
+    Loader, LoaderBuilder,
+};
+#[cfg(not(feature = "sonic"))]
+use serde_json::Value;
+#[cfg(feature = "sonic")]
+use sonic_rs::Value;
+use std::sync::atomic::AtomicBool;
+
+/// Loader and Dumper, built from one set of options.
+pub struct MarshalCodec<'a> {
+    loader: Loader<'a>,
+    dumper: Dumper<'a>,
+}
+
+impl<'a> MarshalCodec<'a> {
+    /// Returns a codec with default options of `Loader` and `Dumper`.
+    pub fn new() -> Self {
+        CodecBuilder::new().build()
+    }
+
+    /// Returns a builder, that configures the codec.
+    pub fn builder() -> CodecBuilder<'a> {
+        CodecBuilder::new()
+    }
+
+    /// Serializes Ruby Marshal byte stream to JSON with the configured options. See `Loader::load()`.
+    pub fn load<B: AsRef<[u8]> + ?Sized>(&mut self, buffer: &B) -> Result<Value, LoadError> {
+        self.loader.load(buffer, None, None)
+    }
+
+    /// Serializes JSON object to a Marshal byte stream with the configured options. Panics in the same cases as `Dumper::dump()`.
+    pub fn dump(&mut self, value: Value) -> Vec<u8> {
+        self.dumper.dump(value, None)
+    }
+
+    /// Like `dump()`, but returns an Err, if the dump was cancelled, or an instance variable name was rejected. See `Dumper::try_dump()`.
+    pub fn try_dump(&mut self, value: Value) -> Result<Vec<u8>, DumpError> {
+        self.dumper.try_dump(value, None)
+    }
+
+    /// Returns the Loader, for example to read warnings and duplicates of the last load.
+    pub fn loader(&self) -> &Loader<'a> {
+        &self.loader
+    }
+}
+
+impl<'a> Default for MarshalCodec<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder of `MarshalCodec`.
+#[derive(Clone, Copy, Debug)]
+pub struct CodecBuilder<'a> {
+    loader: LoaderBuilder<'a>,
+    dumper: DumperBuilder<'a>,
+    instance_var_prefix: Option<&'a str>,
+    pool: Option<&'a TablePool>,
+    cancel_flag: Option<&'a AtomicBool>,
+    data_registry: Option<&'a DataRegistry>,
+}
+
+impl<'a> CodecBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            loader: LoaderBuilder::new(),
+            dumper: DumperBuilder::new(),
+            instance_var_prefix: None,
+            pool: None,
+            cancel_flag: None,
+            data_registry: None,
+        }
+    }
+
+    /// Sets the prefix, that replaces "@" prefixes of instance variables when loading, and is replaced back when dumping.
+    pub fn instance_var_prefix(mut self, prefix: &'a str) -> Self {
+        self.instance_var_prefix = Some(prefix);
+        self
+    }
+
+    /// Sets the pool, that both the Loader and the Dumper take their tables from.
+    pub fn pool(mut self, pool: &'a TablePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Sets the flag, that cancels loads and dumps in progress, when it's set to true.
+    pub fn cancel_flag(mut self, flag: &'a AtomicBool) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Sets the registry, whose handlers convert values of `__data` key when loading, and convert them back when dumping.
+    pub fn data_registry(mut self, registry: &'a DataRegistry) -> Self {
+        self.data_registry = Some(registry);
+        self
+    }
+
+    /// Configures options, that only affect loading, like the string mode and limits.
+    ///
+    /// Options, shared with dumping, that `configure` sets, are ignored, as they're set by this builder for both directions.
+    pub fn loader<F: FnOnce(LoaderBuilder<'a>) -> LoaderBuilder<'a>>(
+        mut self,
+        configure: F,
+    ) -> Self {
+        self.loader = configure(self.loader);
+        self
+    }
+
+    /// Configures options, that only affect dumping, like the capacity and the instance variable policy.
+    ///
+    /// Options, shared with loading, that `configure` sets, are ignored, as they're set by this builder for both directions.
+    pub fn dumper<F: FnOnce(DumperBuilder<'a>) -> DumperBuilder<'a>>(
+        mut self,
+        configure: F,
+    ) -> Self {
+        self.dumper = configure(self.dumper);
+        self
+    }
+
+    pub fn build(self) -> MarshalCodec<'a> {
+        let mut loader: LoaderBuilder = self.loader.clear_shared();
+        let mut dumper: DumperBuilder = self.dumper.clear_shared();
+
+        if let Some(prefix) = self.instance_var_prefix {
+            loader = loader.instance_var_prefix(prefix);
+            dumper = dumper.instance_var_prefix(prefix);
+        }
+
+        if let Some(pool) = self.pool {
+            loader = loader.pool(pool);
+            dumper = dumper.pool(pool);
+        }
+
+        if let Some(flag) = self.cancel_flag {
+            loader = loader.cancel_flag(flag);
+            dumper = dumper.cancel_flag(flag);
+        }
+
+        if let Some(registry) = self.data_registry {
+            loader = loader.data_registry(registry);
+            dumper = dumper.data_registry(registry);
+        }
+
+        MarshalCodec {
+            loader: loader.build(),
+            dumper: dumper.build(),
+        }
+    }
+}
+
+impl<'a> Default for CodecBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
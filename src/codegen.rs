@@ -0,0 +1,358 @@
+//! Generation of Rust struct definitions from sample values.
+//!
+//! `Codegen` collects instances of Ruby classes from loaded values, infers types of their instance variables, and emits structs,
+//! which derive `FromValue` and `IntoValue` from `marshal-rs-macros` crate.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{load, load::LoadError, value::is_leaf_object, ValueExt};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Rust keywords, that can be used as raw identifiers.
+const KEYWORDS: [&str; 47] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while",
+];
+
+/// Inferred type of an instance variable.
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    /// No non-nil values were seen.
+    Never,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array(Box<FieldType>),
+    Class(String),
+    /// Values of incompatible types, or values without a typed representation, like Hashes.
+    Any,
+    Option(Box<FieldType>),
+}
+
+impl FieldType {
+    fn infer(value: &Value) -> FieldType {
+        match value {
+            Value::Null => FieldType::Option(Box::new(FieldType::Never)),
+            Value::Bool(_) => FieldType::Bool,
+            Value::Number(number) => {
+                if number.is_f64() {
+                    FieldType::Float
+                } else {
+                    FieldType::Integer
+                }
+            }
+            Value::String(_) => FieldType::String,
+            Value::Array(array) => FieldType::Array(Box::new(
+                array
+                    .iter()
+                    .map(FieldType::infer)
+                    .fold(FieldType::Never, FieldType::merge),
+            )),
+            Value::Object(_) => match class_of(value) {
+                Some(class) => FieldType::Class(class.to_string()),
+                None => FieldType::Any,
+            },
+        }
+    }
+
+    fn merge(self, other: FieldType) -> FieldType {
+        match (self, other) {
+            (FieldType::Never, other) | (other, FieldType::Never) => other,
+            (FieldType::Option(a), FieldType::Option(b)) => {
+                FieldType::Option(Box::new(a.merge(*b)))
+            }
+            (FieldType::Option(a), other) | (other, FieldType::Option(a)) => {
+                FieldType::Option(Box::new(a.merge(other)))
+            }
+            (FieldType::Integer, FieldType::Float) | (FieldType::Float, FieldType::Integer) => {
+                FieldType::Float
+            }
+            (FieldType::Array(a), FieldType::Array(b)) => FieldType::Array(Box::new(a.merge(*b))),
+            (a, b) if a == b => a,
+            _ => FieldType::Any,
+        }
+    }
+}
+
+/// Returns the class of the Value, if it's a plain object, which can be mapped to a struct.
+fn class_of(value: &Value) -> Option<&str> {
+    if value["__type"] != "object"
+        || ["__data", "__wrapped", "__userDefined", "__userMarshal"]
+            .iter()
+            .any(|key| value.get(key).is_some())
+    {
+        return None;
+    }
+
+    value.class_name()
+}
+
+#[derive(Debug, Clone, Default)]
+struct ClassSchema {
+    instances: usize,
+    /// Instance variables in order of appearance, with their types and amount of instances, that have them.
+    fields: Vec<(String, FieldType, usize)>,
+}
+
+/// Generator of Rust structs from sample values.
+/// # Example
+/// ```rust
+/// use marshal_rs::codegen::Codegen;
+/// use serde_json::json;
+///
+/// let mut codegen = Codegen::new();
+/// codegen.add(&json!([
+///     { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Ralph", "__symbol__@level": 1 },
+///     { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Ulrika", "__symbol__@level": null }
+/// ]));
+///
+/// let code = codegen.generate();
+///
+/// assert!(code.contains("#[marshal(class = \"RPG::Actor\")]\npub struct Actor {"));
+/// assert!(code.contains("    pub name: String,\n    pub level: Option<i64>,\n"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Codegen {
+    classes: BTreeMap<String, ClassSchema>,
+}
+
+impl Codegen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects instances of classes from the Value and its nested values.
+    pub fn add(&mut self, value: &Value) {
+        if is_leaf_object(value) {
+            return;
+        }
+
+        match value {
+            Value::Array(array) => array.iter().for_each(|element| self.add(element)),
+            Value::Object(object) => {
+                if let Some(class) = class_of(value) {
+                    let schema: &mut ClassSchema =
+                        self.classes.entry(class.to_string()).or_default();
+                    schema.instances += 1;
+
+                    for (key, entry) in object {
+                        let ivar: &str = match key.strip_prefix("__symbol__") {
+                            Some(ivar) if ivar.starts_with('@') => ivar,
+                            _ => continue,
+                        };
+
+                        let field_type: FieldType = FieldType::infer(entry);
+
+                        match schema.fields.iter_mut().find(|(name, ..)| name == ivar) {
+                            Some((_, existing, count)) => {
+                                *existing = existing.clone().merge(field_type);
+                                *count += 1;
+                            }
+                            None => schema.fields.push((ivar.to_string(), field_type, 1)),
+                        }
+                    }
+                }
+
+                for (key, entry) in object {
+                    if !matches!(key.as_str(), "__class" | "__type") {
+                        self.add(entry);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Loads the Marshal data and collects instances of classes from it.
+    pub fn add_bytes(&mut self, bytes: &[u8]) -> Result<(), LoadError> {
+        self.add(&load(bytes, None, None)?);
+        Ok(())
+    }
+
+    /// Returns struct names of collected classes. Names are the last segments of class paths, or full paths, joined together, on collisions.
+    fn struct_names(&self) -> BTreeMap<&str, String> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+        for class in self.classes.keys() {
+            *counts.entry(short_name(class)).or_default() += 1;
+        }
+
+        self.classes
+            .keys()
+            .map(|class| {
+                let name: String = if counts[&short_name(class)] > 1 {
+                    class.split("::").map(sanitize_type_name).collect()
+                } else {
+                    short_name(class)
+                };
+
+                (class.as_str(), name)
+            })
+            .collect()
+    }
+
+    /// Returns whether the class contains an instance of the target class, without indirection.
+    fn contains_directly(&self, class: &str, target: &str, visited: &mut BTreeSet<String>) -> bool {
+        if !visited.insert(class.to_string()) {
+            return false;
+        }
+
+        let schema: &ClassSchema = match self.classes.get(class) {
+            Some(schema) => schema,
+            None => return false,
+        };
+
+        schema.fields.iter().any(|(_, field_type, _)| {
+            let mut field_type: &FieldType = field_type;
+
+            while let FieldType::Option(inner) = field_type {
+                field_type = inner;
+            }
+
+            match field_type {
+                FieldType::Class(field_class) => {
+                    field_class == target || self.contains_directly(field_class, target, visited)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    fn render_type(
+        &self,
+        field_type: &FieldType,
+        class: &str,
+        names: &BTreeMap<&str, String>,
+    ) -> String {
+        match field_type {
+            FieldType::Never | FieldType::Any => "serde_json::Value".to_string(),
+            FieldType::Bool => "bool".to_string(),
+            FieldType::Integer => "i64".to_string(),
+            FieldType::Float => "f64".to_string(),
+            FieldType::String => "String".to_string(),
+            FieldType::Array(element) => {
+                format!("Vec<{}>", self.render_type(element, class, names))
+            }
+            FieldType::Class(field_class) => {
+                let name: &str = &names[field_class.as_str()];
+
+                // Recursive structs must be boxed to have a known size
+                if field_class == class
+                    || self.contains_directly(field_class, class, &mut BTreeSet::new())
+                {
+                    format!("Box<{name}>")
+                } else {
+                    name.to_string()
+                }
+            }
+            FieldType::Option(inner) => match **inner {
+                FieldType::Never | FieldType::Any => "serde_json::Value".to_string(),
+                _ => format!("Option<{}>", self.render_type(inner, class, names)),
+            },
+        }
+    }
+
+    /// Emits definitions of structs for all collected classes.
+    pub fn generate(&self) -> String {
+        let names: BTreeMap<&str, String> = self.struct_names();
+        let mut code: String = String::from(
+            "// Generated by marshal-rs codegen.\n\nuse marshal_rs_macros::{FromValue, IntoValue};\n",
+        );
+
+        for (class, schema) in &self.classes {
+            code += &format!(
+                "\n#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]\n#[marshal(class = {class:?})]\npub struct {} {{\n",
+                names[class.as_str()]
+            );
+
+            for (ivar, field_type, count) in &schema.fields {
+                let mut field_type: FieldType = field_type.clone();
+
+                // Instance variables, missing from some instances, are nil in them
+                if *count < schema.instances {
+                    field_type = FieldType::Option(Box::new(FieldType::Never)).merge(field_type);
+                }
+
+                let field: String = field_name(ivar);
+
+                if field.strip_prefix("r#").unwrap_or(&field) != &ivar[1..] {
+                    code += &format!("    #[marshal(name = {ivar:?})]\n");
+                }
+
+                code += &format!(
+                    "    pub {field}: {},\n",
+                    self.render_type(&field_type, class, &names)
+                );
+            }
+
+            code += "}\n";
+        }
+
+        code
+    }
+}
+
+fn sanitize_type_name(segment: &str) -> String {
+    let mut name: String = segment
+        .chars()
+        .map(|char| {
+            if char.is_ascii_alphanumeric() {
+                char
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.starts_with(|char: char| !char.is_ascii_alphabetic()) || name.is_empty() {
+        name.insert(0, 'C');
+    }
+
+    name
+}
+
+fn short_name(class: &str) -> String {
+    sanitize_type_name(class.rsplit("::").next().unwrap_or(class))
+}
+
+fn field_name(ivar: &str) -> String {
+    let mut name: String = ivar
+        .trim_start_matches('@')
+        .chars()
+        .map(|char| {
+            if char.is_ascii_alphanumeric() || char == '_' {
+                char
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.is_empty() || name.starts_with(|char: char| char.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    if KEYWORDS.contains(&name.as_str()) {
+        name.insert_str(0, "r#");
+    } else if matches!(name.as_str(), "self" | "Self" | "super" | "crate" | "_") {
+        name.push('_');
+    }
+
+    name
+}
+
+/// Generates struct definitions from the sample values.
+pub fn generate_structs(values: &[Value]) -> String {
+    let mut codegen: Codegen = Codegen::new();
+
+    for value in values {
+        codegen.add(value);
+    }
+
+    codegen.generate()
+}
@@ -0,0 +1,347 @@
+//! Conversion of whole directories of Marshal files to JSON files and back, like RPG Maker `Data` directories.
+//!
+//! Files are converted in parallel. Errors of single files don't stop the conversion, and are collected to the report instead.
+//! JSON files are named after Marshal files with `.json` appended (`Map001.rvdata2.json`), so converting them back restores original names.
+//!
+//! Single files are converted with `marshal_file_to_json_file()` and `json_file_to_marshal_file()`, which write their output atomically.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{dump::Dumper, load::Loader, rpgmaker::Engine, StringMode};
+use serde_json::Value;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// Callback, that's called after each file is processed, with the file's path, the number of processed files and the total number of files.
+pub type ProgressCallback = Box<dyn Fn(&Path, usize, usize) + Send + Sync>;
+
+/// Options of `dir_to_json()`, `json_to_dir()` and single file conversions.
+pub struct ConvertOptions {
+    /// Extensions of Marshal files, without dots. Defaults to RPG Maker extensions: `rxdata`, `rvdata` and `rvdata2`.
+    pub extensions: Vec<String>,
+    /// Names of files to skip, with or without extensions, like `Scripts`.
+    pub skip: Vec<String>,
+    /// String mode to load files with. If None, files with RPG Maker extensions are loaded with the engine's preset.
+    pub string_mode: Option<StringMode>,
+    pub instance_var_prefix: Option<String>,
+    /// Whether JSON should be pretty-printed.
+    pub pretty: bool,
+    /// Number of threads. If 0, available parallelism is used.
+    pub threads: usize,
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            extensions: [Engine::Xp, Engine::Vx, Engine::VxAce]
+                .iter()
+                .map(|engine| engine.extension().to_string())
+                .collect(),
+            skip: Vec::new(),
+            string_mode: None,
+            instance_var_prefix: None,
+            pretty: false,
+            threads: 0,
+            progress: None,
+        }
+    }
+}
+
+/// Result of a directory conversion. Paths are relative to the source directory, and sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConvertReport {
+    pub converted: Vec<PathBuf>,
+    /// Files, that matched the skip-list.
+    pub skipped: Vec<PathBuf>,
+    /// Files, that failed to convert, with error messages.
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+/// Recursively collects paths of files under `dir`, relative to `root`.
+fn discover(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+
+        if path.is_dir() {
+            discover(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(())
+}
+
+fn is_skipped(path: &Path, skip: &[String]) -> bool {
+    let names = [path.file_name(), path.file_stem()];
+
+    skip.iter().any(|skipped| {
+        names
+            .iter()
+            .flatten()
+            .any(|name| name.to_str() == Some(skipped.as_str()))
+    })
+}
+
+/// Loads the Marshal file with the string mode of options, or with the preset of the RPG Maker engine, that the file's extension belongs to.
+fn load_file(file: &Path, bytes: &[u8], options: &ConvertOptions) -> Result<Value, String> {
+    let mut loader: Loader = match file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Engine::from_extension)
+    {
+        Some(engine) if options.string_mode.is_none() => engine.loader(),
+        _ => Loader::new(),
+    };
+
+    loader
+        .load(
+            bytes,
+            options.string_mode,
+            options.instance_var_prefix.as_deref(),
+        )
+        .map_err(|err| err.to_string())
+}
+
+/// Writes the file with `write` to a temporary file next to it first, and renames it to the path only if writing succeeds,
+/// so the file is never left partially written. Parent directories are created.
+fn write_atomically<W>(path: &Path, write: W) -> io::Result<()>
+where
+    W: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    let temporary: PathBuf = path.with_file_name(name);
+
+    let result: io::Result<()> = File::create(&temporary).and_then(|file| {
+        let mut writer: BufWriter<File> = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.into_inner()?.sync_all()
+    });
+
+    match result.and_then(|_| fs::rename(&temporary, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temporary);
+            Err(err)
+        }
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Converts files of `src`, for which `target` returns a destination path, with `convert`, writing results under `dst`.
+fn convert_dir<T, C>(
+    src: &Path,
+    dst: &Path,
+    options: &ConvertOptions,
+    target: T,
+    convert: C,
+) -> io::Result<ConvertReport>
+where
+    T: Fn(&Path) -> Option<PathBuf>,
+    C: Fn(&Path, &[u8]) -> Result<Vec<u8>, String> + Sync,
+{
+    let mut files: Vec<PathBuf> = Vec::new();
+    discover(src, src, &mut files)?;
+    files.sort();
+
+    let mut report: ConvertReport = ConvertReport::default();
+    let mut jobs: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    for file in files {
+        if let Some(output) = target(&file) {
+            if is_skipped(&file, &options.skip) {
+                report.skipped.push(file);
+            } else {
+                jobs.push((file, output));
+            }
+        }
+    }
+
+    let threads: usize = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+
+    let next: AtomicUsize = AtomicUsize::new(0);
+    let done: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, Result<(), String>)>> = Mutex::new(Vec::new());
+
+    let run = || loop {
+        let index: usize = next.fetch_add(1, Ordering::Relaxed);
+        let (file, output) = match jobs.get(index) {
+            Some(job) => job,
+            None => break,
+        };
+
+        let result: Result<(), String> = fs::read(src.join(file))
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| convert(file, &bytes))
+            .and_then(|bytes| {
+                let output: PathBuf = dst.join(output);
+
+                if let Some(parent) = output.parent() {
+                    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                }
+
+                fs::write(output, bytes).map_err(|err| err.to_string())
+            });
+
+        results.lock().unwrap().push((index, result));
+
+        let done: usize = done.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if let Some(progress) = &options.progress {
+            progress(file, done, jobs.len());
+        }
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 1..threads.min(jobs.len()) {
+            scope.spawn(run);
+        }
+
+        run();
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+
+    for (index, result) in results {
+        let file: PathBuf = jobs[index].0.clone();
+
+        match result {
+            Ok(()) => report.converted.push(file),
+            Err(message) => report.errors.push((file, message)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Converts Marshal files of `src` directory and its subdirectories to JSON files under `dst`.
+///
+/// Returns an Err when directories can't be read. Errors of single files are collected to the report.
+pub fn dir_to_json<S: AsRef<Path>, D: AsRef<Path>>(
+    src: S,
+    dst: D,
+    options: &ConvertOptions,
+) -> io::Result<ConvertReport> {
+    let target = |file: &Path| {
+        let extension: &str = file.extension()?.to_str()?;
+
+        options
+            .extensions
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(extension))
+            .then(|| {
+                let mut name = file.as_os_str().to_owned();
+                name.push(".json");
+                PathBuf::from(name)
+            })
+    };
+
+    let convert = |file: &Path, bytes: &[u8]| {
+        let value: Value = load_file(file, bytes, options)?;
+
+        if options.pretty {
+            serde_json::to_vec_pretty(&value)
+        } else {
+            serde_json::to_vec(&value)
+        }
+        .map_err(|err| err.to_string())
+    };
+
+    convert_dir(src.as_ref(), dst.as_ref(), options, target, convert)
+}
+
+/// Converts JSON files of `src` directory and its subdirectories, produced by `dir_to_json()`, back to Marshal files under `dst`.
+///
+/// `extensions`, `string_mode` and `pretty` options are not used. Returns an Err when directories can't be read. Errors of single files are collected to the report.
+pub fn json_to_dir<S: AsRef<Path>, D: AsRef<Path>>(
+    src: S,
+    dst: D,
+    options: &ConvertOptions,
+) -> io::Result<ConvertReport> {
+    let target = |file: &Path| {
+        let extension: &str = file.extension()?.to_str()?;
+
+        extension
+            .eq_ignore_ascii_case("json")
+            .then(|| file.with_extension(""))
+    };
+
+    let convert = |_: &Path, bytes: &[u8]| {
+        let value: Value = serde_json::from_slice(bytes).map_err(|err| err.to_string())?;
+        Dumper::new()
+            .try_dump(value, options.instance_var_prefix.as_deref())
+            .map_err(|err| err.to_string())
+    };
+
+    convert_dir(src.as_ref(), dst.as_ref(), options, target, convert)
+}
+
+/// Converts the Marshal file to the JSON file, pretty-printed, if `pretty` option is set.
+///
+/// The file is loaded with `string_mode` and `instance_var_prefix` options, or with the preset of the RPG Maker engine, that its extension belongs to.
+/// JSON is written to a temporary file first, and replaces `dst` only when it's completely written. Other options are not used.
+/// Returns an Err with `InvalidData` kind, if the file can't be loaded.
+/// # Example
+/// ```rust no_run
+/// use marshal_rs::convert::{marshal_file_to_json_file, ConvertOptions};
+///
+/// marshal_file_to_json_file("Data/Actors.rvdata2", "Json/Actors.json", &ConvertOptions::default()).unwrap();
+/// ```
+pub fn marshal_file_to_json_file<S: AsRef<Path>, D: AsRef<Path>>(
+    src: S,
+    dst: D,
+    options: &ConvertOptions,
+) -> io::Result<()> {
+    let src: &Path = src.as_ref();
+    let value: Value = load_file(src, &fs::read(src)?, options).map_err(invalid_data)?;
+
+    write_atomically(dst.as_ref(), |writer| {
+        if options.pretty {
+            serde_json::to_writer_pretty(&mut *writer, &value)
+        } else {
+            serde_json::to_writer(&mut *writer, &value)
+        }
+        .map_err(io::Error::from)
+    })
+}
+
+/// Converts the JSON file, produced by `marshal_file_to_json_file()`, back to the Marshal file.
+///
+/// Marshal data is written to a temporary file first, and replaces `dst` only when it's completely written. Only `instance_var_prefix` option is used.
+/// Returns an Err with `InvalidData` kind, if the file isn't valid JSON, or can't be dumped.
+pub fn json_file_to_marshal_file<S: AsRef<Path>, D: AsRef<Path>>(
+    src: S,
+    dst: D,
+    options: &ConvertOptions,
+) -> io::Result<()> {
+    let value: Value =
+        serde_json::from_slice(&fs::read(src)?).map_err(|err| invalid_data(err.to_string()))?;
+    let bytes: Vec<u8> = Dumper::new()
+        .try_dump(value, options.instance_var_prefix.as_deref())
+        .map_err(|err| invalid_data(err.to_string()))?;
+
+    write_atomically(dst.as_ref(), |writer| writer.write_all(&bytes))
+}
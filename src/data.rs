@@ -0,0 +1,81 @@
+//! Handlers of objects, that C extensions dump with `_dump_data`.
+//!
+//! Ruby writes such objects as their class, followed by the value, that `_dump_data` returned. By default, the value is loaded as is into `__data` key of the object.
+//! Handlers, registered in a `DataRegistry` for specific classes, convert the value into a more convenient one when loading, and back when dumping.
+//! # Example
+//! ```rust
+//! use marshal_rs::{data::{DataHandler, DataRegistry}, Dumper, Loader};
+//! use serde_json::{json, Value};
+//!
+//! // Dumps [x, y] Array of coordinates
+//! struct Point;
+//!
+//! impl DataHandler for Point {
+//!     fn load(&self, data: Value) -> Result<Value, String> {
+//!         Ok(json!({ "x": data[0], "y": data[1] }))
+//!     }
+//!
+//!     fn dump(&self, value: &Value) -> Result<Value, String> {
+//!         Ok(json!([value["x"], value["y"]]))
+//!     }
+//! }
+//!
+//! let mut registry = DataRegistry::new();
+//! registry.register("Point", Point);
+//!
+//! // Point with [1, 2] data
+//! let bytes: &[u8] = b"\x04\x08d:\x0aPoint[\x07i\x06i\x07";
+//! let value = Loader::builder().data_registry(&registry).build().load(bytes, None, None).unwrap();
+//!
+//! assert_eq!(value["__data"], json!({ "x": 1, "y": 2 }));
+//! assert_eq!(Dumper::builder().data_registry(&registry).build().dump(value, None), bytes);
+//! ```
+
+#[cfg(not(feature = "sonic"))]
+use serde_json::Value;
+#[cfg(feature = "sonic")]
+use sonic_rs::Value;
+use std::collections::HashMap;
+
+/// Converter of values, that `_dump_data` of a class returns.
+pub trait DataHandler: Send + Sync {
+    /// Converts the loaded value, that `_dump_data` returned, into the value of `__data` key. Returns an Err with a message, if the value is invalid.
+    fn load(&self, data: Value) -> Result<Value, String>;
+
+    /// Converts the value of `__data` key back into the value, that `_load_data` accepts. Returns an Err with a message, if the value is invalid.
+    fn dump(&self, value: &Value) -> Result<Value, String>;
+}
+
+/// Registry of `DataHandler`s by class names, which Loaders and Dumpers borrow.
+#[derive(Default)]
+pub struct DataRegistry {
+    handlers: HashMap<String, Box<dyn DataHandler>>,
+}
+
+impl DataRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the handler for the class, replacing the previous one. Names of classes in modules are written with "::", like `"Module::Class"`.
+    pub fn register(&mut self, class: &str, handler: impl DataHandler + 'static) {
+        self.handlers.insert(class.to_string(), Box::new(handler));
+    }
+
+    /// Removes the handler of the class, returning whether it was registered.
+    pub fn unregister(&mut self, class: &str) -> bool {
+        self.handlers.remove(class).is_some()
+    }
+
+    /// Returns the handler of the class, that may be prefixed with `__symbol__`.
+    pub fn get(&self, class: &str) -> Option<&dyn DataHandler> {
+        let class: &str = class.strip_prefix("__symbol__").unwrap_or(class);
+        self.handlers.get(class).map(Box::as_ref)
+    }
+}
+
+impl std::fmt::Debug for DataRegistry {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
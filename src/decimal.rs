@@ -0,0 +1,132 @@
+//! Conversion of Ruby's `BigDecimal` objects to `rust_decimal` decimals and back.
+//!
+//! Ruby dumps BigDecimal with `_dump`, as a string of its maximum precision and its digits in scientific notation, like `"18:0.15e1"` for `1.5`.
+//! Such objects are loaded as `{ __class: "__symbol__BigDecimal", __type: "object", __userDefined: [...] }` objects,
+//! which `RubyDecimal` and `Decimal` implement `FromValue` and `IntoValue` for.
+//!
+//! `NaN` and infinite BigDecimals, as well as ones, that don't fit in 96 bits of `Decimal` mantissa, can't be converted.
+//!
+//! Requires `decimal` feature. Not available with `sonic` feature enabled.
+
+use crate::{
+    typed::{mismatch, FromValue, IntoValue},
+    value::{to_symbol, user_defined_of, ValueError},
+    ValueExt,
+};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Number of decimal digits in a single word of BigDecimal.
+const WORD_DIGITS: usize = 9;
+
+/// Ruby's BigDecimal, with the precision marker of its dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RubyDecimal {
+    pub decimal: Decimal,
+    /// Maximum precision, that Ruby allocated for the BigDecimal, in digits. It's always a multiple of 9.
+    pub precision: usize,
+}
+
+impl From<Decimal> for RubyDecimal {
+    /// Estimates the precision, that Ruby allocates for a BigDecimal, parsed from the decimal's string.
+    fn from(decimal: Decimal) -> Self {
+        let (digits, exponent) = digits(&decimal);
+        let integer: usize = usize::try_from(exponent).unwrap_or_default();
+        let fraction: usize = usize::try_from(digits.len() as i64 - exponent).unwrap_or_default();
+        let words: usize = ((integer + WORD_DIGITS - 1) / WORD_DIGITS
+            + (fraction + WORD_DIGITS - 1) / WORD_DIGITS)
+            .max(1);
+
+        RubyDecimal {
+            decimal,
+            precision: words * WORD_DIGITS,
+        }
+    }
+}
+
+fn decimal_error(message: &str) -> ValueError {
+    ValueError {
+        message: format!("Invalid BigDecimal: {message}"),
+    }
+}
+
+/// Returns significant digits of the decimal without trailing zeros, and the exponent of `0.digits` notation.
+fn digits(decimal: &Decimal) -> (String, i64) {
+    if decimal.is_zero() {
+        return (String::new(), 0);
+    }
+
+    let digits: String = decimal.mantissa().unsigned_abs().to_string();
+    let exponent: i64 = digits.len() as i64 - i64::from(decimal.scale());
+
+    (digits.trim_end_matches('0').to_string(), exponent)
+}
+
+impl FromValue for RubyDecimal {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("BigDecimal") {
+            return Err(mismatch("BigDecimal", value));
+        }
+
+        let bytes: Vec<u8> =
+            user_defined_of(value).ok_or_else(|| decimal_error("expected dumped data"))?;
+        let dumped: &str =
+            std::str::from_utf8(&bytes).map_err(|_| decimal_error("dumped data is not ASCII"))?;
+        let (precision, number) = dumped
+            .split_once(':')
+            .ok_or_else(|| decimal_error("missing precision"))?;
+        let precision: usize = precision
+            .parse()
+            .map_err(|_| decimal_error("precision is not a number"))?;
+
+        if matches!(number, "NaN" | "Infinity" | "+Infinity" | "-Infinity") {
+            return Err(decimal_error(&format!("{number} can't be represented")));
+        }
+
+        let mut decimal: Decimal = if number.contains(['e', 'E']) {
+            Decimal::from_scientific(number)
+        } else {
+            Decimal::from_str(number)
+        }
+        .map_err(|err| decimal_error(&err.to_string()))?;
+
+        // Parsing drops the sign of zero
+        decimal.set_sign_negative(number.starts_with('-'));
+
+        Ok(RubyDecimal { decimal, precision })
+    }
+}
+
+impl IntoValue for RubyDecimal {
+    /// Converts the decimal to the object, that Ruby dumps, with digits formatted like `BigDecimal#to_s`.
+    fn into_value(self) -> Value {
+        let sign: &str = if self.decimal.is_sign_negative() {
+            "-"
+        } else {
+            ""
+        };
+        let number: String = match digits(&self.decimal) {
+            (digits, _) if digits.is_empty() => format!("{sign}0.0"),
+            (digits, exponent) => format!("{sign}0.{digits}e{exponent}"),
+        };
+
+        json!({
+            "__class": to_symbol("BigDecimal"),
+            "__type": "object",
+            "__userDefined": format!("{}:{number}", self.precision).into_bytes(),
+        })
+    }
+}
+
+impl FromValue for Decimal {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        RubyDecimal::from_value(value).map(|decimal| decimal.decimal)
+    }
+}
+
+impl IntoValue for Decimal {
+    fn into_value(self) -> Value {
+        RubyDecimal::from(self).into_value()
+    }
+}
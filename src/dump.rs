@@ -1,55 +1,171 @@
 //! Utilities for serializing JSON objects back to Marshal byte streams.
 
-use crate::{Constants, DEFAULT_SYMBOL, ENCODING_SHORT_SYMBOL, EXTENDS_SYMBOL, MARSHAL_VERSION};
+use crate::{
+    data::DataRegistry,
+    pool::TablePool,
+    raw::{encode_int, VERSION_HEADER},
+    Constants, DEFAULT_SYMBOL, ENCODING_LONG_SYMBOL, ENCODING_SHORT_SYMBOL, EXTENDS_SYMBOL,
+    RANGE_INSTANCE_VARS, SYMBOL_KEYS_SYMBOL,
+};
+#[cfg(feature = "encodings")]
+use encoding_rs::{Encoding, UTF_8};
+#[cfg(feature = "bigint")]
 use num_bigint::{BigInt, Sign};
 #[cfg(not(feature = "sonic"))]
-use serde_json::{from_str, from_value, Value};
+use serde_json::{from_str, from_value, json, to_string, Map, Value};
 #[cfg(feature = "sonic")]
-use sonic_rs::{from_str, from_value, json, prelude::*, Array, JsonType, Object, Value};
+use sonic_rs::{from_str, from_value, json, prelude::*, to_string, Array, JsonType, Object, Value};
 #[cfg(not(feature = "sonic"))]
 use std::collections::HashMap;
-use std::{mem, str::FromStr};
+#[cfg(feature = "bigint")]
+use std::str::FromStr;
+use std::{
+    mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 #[cfg(feature = "sonic")]
-pub struct Dumper<'a> {
-    buffer: Vec<u8>,
-    symbols: Vec<Value>,
-    objects: Vec<Value>,
-    instance_var_prefix: Option<&'a str>,
-}
+pub(crate) type DumperTable = Vec<Value>;
 #[cfg(not(feature = "sonic"))]
+pub(crate) type DumperTable = HashMap<Value, usize>;
+
+/// Keys of loaded objects, that aren't instance variables or Hash entries.
+const INTERNAL_KEYS: [&str; 6] = [
+    "__class",
+    "__type",
+    "__data",
+    "__wrapped",
+    "__userDefined",
+    "__userMarshal",
+];
+
+/// Returns whether the key of a loaded object is an instance variable, and not one of `INTERNAL_KEYS` or the extended modules.
+fn is_instance_var_key(key: &str) -> bool {
+    key != EXTENDS_SYMBOL && !INTERNAL_KEYS.contains(&key)
+}
+
+/// Range of integers, that Ruby dumps as Fixnums. Integers outside of it are dumped as Bignums.
+pub(crate) const FIXNUM_MIN: i128 = -(1 << 30);
+pub(crate) const FIXNUM_MAX: i128 = (1 << 30) - 1;
+
+/// Defines how instance variables of objects, whose names aren't "@" followed by an identifier, are handled.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum InstanceVarPolicy {
+    /// Write the names as is. Ruby itself writes some instance variables without "@", like `mesg` and `bt` of exceptions.
+    #[default]
+    Pass,
+    /// Return an error from `Dumper::try_dump()`.
+    Error,
+    /// Prefix the names, that don't start with "@", with "@". Names, that are still invalid, return an error from `Dumper::try_dump()`.
+    Prefix,
+}
+
+/// Returns whether the name is "@" followed by an identifier, so Ruby can read it as an instance variable.
+fn is_instance_var_name(name: &str) -> bool {
+    let mut chars = name.strip_prefix('@').unwrap_or_default().chars();
+
+    chars.next().map_or(false, |char| {
+        char.is_ascii_alphabetic() || char == '_' || !char.is_ascii()
+    }) && chars.all(|char| char.is_ascii_alphanumeric() || char == '_' || !char.is_ascii())
+}
+
+/// Returns the sign and little-endian bytes of the magnitude of the decimal integer, like `num-bigint` does, for builds without `bigint` feature.
+#[cfg(not(feature = "bigint"))]
+fn decimal_magnitude(decimal: &str) -> Option<(bool, Vec<u8>)> {
+    let (negative, digits) = match decimal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, decimal.strip_prefix('+').unwrap_or(decimal)),
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = Vec::new();
+
+    for digit in digits.chars() {
+        let mut carry: u32 = digit.to_digit(10)?;
+
+        for byte in bytes.iter_mut() {
+            let product: u32 = *byte as u32 * 10 + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+
+        if carry > 0 {
+            bytes.push(carry as u8);
+        }
+    }
+
+    if bytes.is_empty() {
+        return Some((false, vec![0]));
+    }
+
+    Some((negative, bytes))
+}
+
+#[derive(Debug)]
+pub struct DumpError {
+    pub(crate) message: String,
+}
+
+impl std::fmt::Display for DumpError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DumpError {}
+
 pub struct Dumper<'a> {
-    buffer: Vec<u8>,
-    symbols: HashMap<Value, usize>,
-    objects: HashMap<Value, usize>,
+    pub(crate) buffer: Vec<u8>,
+    symbols: DumperTable,
+    objects: DumperTable,
     instance_var_prefix: Option<&'a str>,
+    default_instance_var_prefix: Option<&'a str>,
+    capacity: usize,
+    pool: Option<&'a TablePool>,
+    /// Number of bytes, counted instead of being written, when measuring.
+    measured: Option<usize>,
+    cancel_flag: Option<&'a AtomicBool>,
+    /// Whether the dump in progress was cancelled, and no more structures are written.
+    cancelled: bool,
+    instance_var_policy: InstanceVarPolicy,
+    data_registry: Option<&'a DataRegistry>,
+    /// Error, that stopped the dump in progress.
+    error: Option<DumpError>,
 }
 
 impl<'a> Dumper<'a> {
     pub fn new() -> Self {
-        #[cfg(feature = "sonic")]
-        {
-            Self {
-                buffer: Vec::with_capacity(128),
-                symbols: Vec::new(),
-                objects: Vec::new(),
-                instance_var_prefix: None,
-            }
-        }
-        #[cfg(not(feature = "sonic"))]
-        {
-            Self {
-                buffer: Vec::with_capacity(128),
-                symbols: HashMap::new(),
-                objects: HashMap::new(),
-                instance_var_prefix: None,
-            }
+        Self {
+            buffer: Vec::new(),
+            symbols: DumperTable::default(),
+            objects: DumperTable::default(),
+            instance_var_prefix: None,
+            default_instance_var_prefix: None,
+            capacity: 128,
+            pool: None,
+            measured: None,
+            cancel_flag: None,
+            cancelled: false,
+            instance_var_policy: InstanceVarPolicy::Pass,
+            data_registry: None,
+            error: None,
         }
     }
 
+    /// Returns a builder, that configures the Dumper.
+    pub fn builder() -> DumperBuilder<'a> {
+        DumperBuilder::new()
+    }
+
     /// Serializes JSON object to a Marshal byte stream.
     ///
     /// instance_var_prefix argument takes a string, and replaces instance variables' prefixes with Ruby's "@" prefix. It's value must be the same, as in load() function.
+    /// If it's None, the prefix, configured with `DumperBuilder`, is used.
+    ///
+    /// Panics, if the dump is cancelled, or a value can't be dumped, like an instance variable name, rejected by `InstanceVarPolicy`. Use `try_dump()` to get an Err instead.
     /// # Example
     /// ```rust
     /// use marshal_rs::Dumper;
@@ -66,24 +182,159 @@ impl<'a> Dumper<'a> {
     /// assert_eq!(&bytes, &[0x04, 0x08, 0x30]);
     /// ```
     pub fn dump(&mut self, value: Value, instance_var_prefix: Option<&'a str>) -> Vec<u8> {
-        self.instance_var_prefix = instance_var_prefix;
+        self.try_dump(value, instance_var_prefix)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Serializes JSON object to a Marshal byte stream, like `dump()`, but borrows the value instead of consuming it.
+    ///
+    /// The value isn't modified, so it can be dumped again or used further without cloning it. Panics in the same cases as `dump()`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::Dumper;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "__symbol__key": [1, "string"] });
+    /// let bytes: Vec<u8> = Dumper::new().dump_ref(&value, None);
+    ///
+    /// assert_eq!(bytes, Dumper::new().dump(value.clone(), None));
+    /// assert_eq!(value, json!({ "__symbol__key": [1, "string"] }));
+    /// ```
+    ///
+    /// Not available with `sonic` feature enabled.
+    #[cfg(not(feature = "sonic"))]
+    pub fn dump_ref(&mut self, value: &Value, instance_var_prefix: Option<&'a str>) -> Vec<u8> {
+        self.prepare_buffer();
+        self.write_document(instance_var_prefix, |dumper| dumper.write_structure(value));
+        self.take_buffer().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Serializes plain JSON data straight to a Marshal byte stream, without interpreting any keys or prefixes of `marshal-rs`.
+    ///
+    /// Strings are written as UTF-8 strings, objects as Hashes with string keys, and numbers as Fixnums, Bignums or Floats, so the data is read in Ruby just like `JSON.parse()` would produce it.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::Dumper;
+    /// use serde_json::json;
+    ///
+    /// let config = json!({ "name": "__symbol__a", "__type": "object" });
+    /// let bytes: Vec<u8> = Dumper::new().dump_json(&config);
+    ///
+    /// assert_eq!(&bytes[..4], b"\x04\x08{\x07");
+    /// ```
+    ///
+    /// Available with `serde` feature enabled.
+    #[cfg(feature = "serde")]
+    pub fn dump_json(&mut self, value: &serde_json::Value) -> Vec<u8> {
+        self.prepare_buffer();
+        self.write_document(None, |dumper| dumper.write_json(value));
+        self.take_buffer().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn prepare_buffer(&mut self) {
+        if let Some(buffer) = self.pool.and_then(TablePool::take_buffer) {
+            self.buffer = buffer;
+        }
 
-        self.write_buffer(&MARSHAL_VERSION.to_be_bytes());
-        self.write_structure(value);
+        self.buffer.reserve(self.capacity);
+    }
+
+    /// Returns the written bytes, or an Err, if the dump was stopped by an error or cancelled, so incomplete data is never returned.
+    fn take_buffer(&mut self) -> Result<Vec<u8>, DumpError> {
+        let bytes: Vec<u8> = mem::take(&mut self.buffer);
+
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        if mem::take(&mut self.cancelled) {
+            return Err(DumpError {
+                message: format!("Dump was cancelled after {} bytes.", bytes.len()),
+            });
+        }
+
+        Ok(bytes)
+    }
+
+    /// Serializes JSON object to a Marshal byte stream, like `dump()`, but returns an Err, if the dump was cancelled with the flag, set by `DumperBuilder::cancel_flag()`, an instance variable name was rejected by `InstanceVarPolicy`, or a value couldn't be converted, like a Big Integer with an invalid decimal string.
+    ///
+    /// `dump()` panics in such cases, so this method should be used, when the cancel flag or the policy is set, or the value comes from untrusted data.
+    pub fn try_dump(
+        &mut self,
+        value: Value,
+        instance_var_prefix: Option<&'a str>,
+    ) -> Result<Vec<u8>, DumpError> {
+        self.prepare_buffer();
+
+        #[cfg(feature = "sonic")]
+        self.write_document(instance_var_prefix, |dumper| dumper.write_structure(value));
+        #[cfg(not(feature = "sonic"))]
+        self.write_document(instance_var_prefix, |dumper| dumper.write_structure(&value));
+
+        self.take_buffer()
+    }
+
+    /// Returns the exact number of bytes, that `dump()` would produce for the value, without producing them.
+    ///
+    /// Repeated symbols are counted as symbol links, just like `dump()` writes them. Useful for quota checks and pre-sizing output buffers.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::Dumper;
+    /// use serde_json::json;
+    ///
+    /// let value = json!(["__symbol__a", "__symbol__a", 300]);
+    ///
+    /// assert_eq!(Dumper::new().measure(value.clone(), None), Dumper::new().dump(value, None).len());
+    /// ```
+    pub fn measure(&mut self, value: Value, instance_var_prefix: Option<&'a str>) -> usize {
+        self.measured = Some(0);
+
+        #[cfg(feature = "sonic")]
+        self.write_document(instance_var_prefix, |dumper| dumper.write_structure(value));
+        #[cfg(not(feature = "sonic"))]
+        self.write_document(instance_var_prefix, |dumper| dumper.write_structure(&value));
+
+        self.measured.take().unwrap_or_default()
+    }
+
+    /// Writes the header and the structure with `write_structure`, managing the tables around it.
+    fn write_document(
+        &mut self,
+        instance_var_prefix: Option<&'a str>,
+        write_structure: impl FnOnce(&mut Self),
+    ) {
+        self.instance_var_prefix = instance_var_prefix.or(self.default_instance_var_prefix);
+        self.cancelled = false;
+        self.error = None;
+
+        if let Some(pool) = self.pool {
+            (self.symbols, self.objects) = pool.take_dumper_tables();
+        }
+
+        self.write_buffer(&VERSION_HEADER);
+        write_structure(self);
 
         self.objects.clear();
         self.symbols.clear();
         self.instance_var_prefix = None;
 
-        mem::take(&mut self.buffer)
+        if let Some(pool) = self.pool {
+            pool.give_dumper_tables(mem::take(&mut self.symbols), mem::take(&mut self.objects));
+        }
     }
 
     fn write_byte(&mut self, byte: u8) {
-        self.buffer.push(byte);
+        match &mut self.measured {
+            Some(measured) => *measured += 1,
+            None => self.buffer.push(byte),
+        }
     }
 
     fn write_buffer(&mut self, bytes: &[u8]) {
-        self.buffer.extend(bytes);
+        match &mut self.measured {
+            Some(measured) => *measured += bytes.len(),
+            None => self.buffer.extend(bytes),
+        }
     }
 
     fn write_bytes(&mut self, bytes: &[u8]) {
@@ -91,56 +342,144 @@ impl<'a> Dumper<'a> {
         self.write_buffer(bytes);
     }
 
-    fn write_bignum(&mut self, bignum: BigInt) {
-        let (sign, mut bytes) = bignum.to_bytes_le();
+    /// Writes the Big Integer from its decimal string. Stops the dump, if the string isn't a valid integer.
+    fn write_decimal_bignum(&mut self, decimal: Option<&str>) {
+        #[cfg(feature = "bigint")]
+        let parsed: Option<(bool, Vec<u8>)> = decimal
+            .and_then(|decimal| BigInt::from_str(decimal).ok())
+            .map(|bignum| {
+                let (sign, bytes) = bignum.to_bytes_le();
+                (sign == Sign::Minus, bytes)
+            });
+        #[cfg(not(feature = "bigint"))]
+        let parsed: Option<(bool, Vec<u8>)> = decimal.and_then(decimal_magnitude);
+
+        match parsed {
+            Some((negative, bytes)) => self.write_raw_bignum(negative, &bytes),
+            None => {
+                self.error = Some(DumpError {
+                    message: format!(
+                        "{} isn't a valid Big Integer value.",
+                        decimal.unwrap_or("null")
+                    ),
+                })
+            }
+        }
+    }
 
+    /// Writes the Big Integer from the little-endian bytes of its magnitude, as it's loaded without `bigint` feature.
+    fn write_raw_bignum(&mut self, negative: bool, bytes: &[u8]) {
         self.write_byte(Constants::Bignum as u8);
-        self.write_byte(if sign == Sign::Plus {
-            Constants::Positive
-        } else {
+        self.write_byte(if negative {
             Constants::Negative
+        } else {
+            Constants::Positive
         } as u8);
 
-        bytes[0] = 0;
-        bytes.push(0);
+        // Length is written in 16-bit words
+        self.write_number(((bytes.len() + 1) / 2) as i32);
+        self.write_buffer(bytes);
 
-        self.write_byte(bytes.len() as u8);
-        self.write_buffer(&bytes);
+        if bytes.len() % 2 == 1 {
+            self.write_byte(0);
+        }
     }
 
-    fn write_number(&mut self, number: i32) {
-        let mut buf: Vec<u8> = Vec::with_capacity(5);
-
-        match number {
-            0 => buf.push(0),
-            1..=122 => buf.push(number as u8 + 5),
-            -123..=-1 => buf.push(number as u8 - 5),
-            -256..=255 => {
-                buf.push(1);
-                buf.push(number as u8);
-            }
-            -65535..=65534 => {
-                buf.push(if number < 0 { 254 } else { 2 });
-                buf.extend(&(number as i16).to_le_bytes());
-            }
-            -16777216..=16777215 => {
-                buf.push(if number < 0 { 253 } else { 3 });
-                buf.extend(&number.to_le_bytes()[0..3]);
-            }
-            -1073741824..=1073741823 => {
-                buf.push(if number < 0 { 252 } else { 4 });
-                buf.extend(&number.to_le_bytes()[0..4]);
-            }
-            _ => {}
+    /// Writes the integer as Fixnum, if it fits in 31 bits, or as Bignum otherwise, like Ruby does, so large integers aren't truncated.
+    fn write_integer(&mut self, integer: i128) {
+        if (FIXNUM_MIN..=FIXNUM_MAX).contains(&integer) {
+            self.write_byte(Constants::Fixnum as u8);
+            self.write_number(integer as i32);
+            return;
         }
 
-        self.write_buffer(&buf);
+        let bytes: [u8; 16] = integer.unsigned_abs().to_le_bytes();
+        let length: usize = bytes
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .unwrap_or_default()
+            + 1;
+
+        self.write_raw_bignum(integer < 0, &bytes[..length]);
+    }
+
+    fn write_number(&mut self, number: i32) {
+        let (bytes, length) = encode_int(number);
+        self.write_buffer(&bytes[..length]);
     }
 
     fn write_string(&mut self, string: &str) {
         self.write_bytes(string.as_bytes())
     }
 
+    /// Writes the string of bytes, with the name of its encoding, if it's not decoded, as it's kept without `encodings` feature.
+    fn write_binary_string(&mut self, bytes: &[u8], encoding: Option<&str>) {
+        if encoding.is_some() {
+            self.write_byte(Constants::InstanceVar as u8);
+        }
+
+        self.write_byte(Constants::String as u8);
+        self.write_bytes(bytes);
+
+        if let Some(encoding) = encoding {
+            self.write_number(1);
+            self.write_symbol(ENCODING_LONG_SYMBOL.into());
+            self.write_byte(Constants::String as u8);
+            self.write_string(encoding);
+        }
+    }
+
+    /// Writes the string with UTF-8 encoding, like Ruby writes its regular strings.
+    fn write_utf8_string(&mut self, string: &str) {
+        self.write_byte(Constants::InstanceVar as u8);
+        self.write_byte(Constants::String as u8);
+        self.write_string(string);
+        self.write_number(1);
+        self.write_symbol(ENCODING_SHORT_SYMBOL.into());
+        self.write_byte(Constants::True as u8);
+    }
+
+    /// Writes the string of `{ __type: "string" }` object in its encoding: without encoding for binary strings, or encoded to any other encoding, that `encoding_rs` supports.
+    ///
+    /// Strings in unsupported encodings, and strings, that can't be encoded without losses, are written with UTF-8 encoding.
+    fn write_encoded_string(&mut self, string: &str, encoding: Option<&str>) {
+        let encoding: &str = match encoding {
+            Some(encoding) => encoding,
+            None => return self.write_utf8_string(string),
+        };
+
+        if encoding.eq_ignore_ascii_case("ASCII-8BIT") || encoding.eq_ignore_ascii_case("BINARY") {
+            return self.write_binary_string(string.as_bytes(), None);
+        }
+
+        // encoding_rs treats US-ASCII label as windows-1252, so it's handled here
+        if encoding.eq_ignore_ascii_case("US-ASCII") {
+            if !string.is_ascii() {
+                return self.write_utf8_string(string);
+            }
+
+            self.write_byte(Constants::InstanceVar as u8);
+            self.write_byte(Constants::String as u8);
+            self.write_string(string);
+            self.write_number(1);
+            self.write_symbol(ENCODING_SHORT_SYMBOL.into());
+            self.write_byte(Constants::False as u8);
+            return;
+        }
+
+        #[cfg(feature = "encodings")]
+        if let Some(target) = Encoding::for_label(encoding.as_bytes()) {
+            let (encoded, _, unmappable) = target.encode(string);
+
+            // encoding_rs can't encode to UTF-16, and falls back to UTF-8
+            if target != UTF_8 && !unmappable && target.output_encoding() == target {
+                return self.write_binary_string(&encoded, Some(encoding));
+            }
+        }
+
+        self.write_utf8_string(string);
+    }
+
     fn write_float(&mut self, float: f64) {
         let string: String = float.to_string();
 
@@ -158,7 +497,15 @@ impl<'a> Dumper<'a> {
     }
 
     fn write_symbol(&mut self, mut symbol: Value) {
-        if let Some(stripped) = symbol.as_str().unwrap().strip_prefix("__symbol__") {
+        let name: &str = match symbol.as_str() {
+            Some(name) => name,
+            None => {
+                let symbol: String = to_string(&symbol).unwrap_or_default();
+                return self.fail(format!("{symbol} isn't a valid symbol."));
+            }
+        };
+
+        if let Some(stripped) = name.strip_prefix("__symbol__") {
             symbol = stripped.into();
         }
 
@@ -178,7 +525,7 @@ impl<'a> Dumper<'a> {
             self.write_number(pos as i32);
         } else {
             self.write_byte(Constants::Symbol as u8);
-            self.write_bytes(symbol.as_str().unwrap().as_bytes());
+            self.write_bytes(symbol.as_str().unwrap_or_default().as_bytes());
 
             #[cfg(feature = "sonic")]
             {
@@ -191,87 +538,238 @@ impl<'a> Dumper<'a> {
         }
     }
 
-    fn write_extended(&mut self, extended: Vec<Value>) {
-        for symbol in extended {
-            self.write_byte(Constants::Extended as u8);
-            self.write_symbol(symbol);
+    /// Writes modules, that the object is extended with. Stops the dump, if they aren't an array.
+    fn write_extended(&mut self, object: &Value) {
+        let extended: &Value = &object[EXTENDS_SYMBOL];
+
+        if extended.is_null() {
+            return;
         }
-    }
 
-    fn write_class(&mut self, data_type: Constants, object: &mut Value) {
-        if !object[EXTENDS_SYMBOL].is_null() {
-            #[cfg(feature = "sonic")]
-            {
-                self.write_extended(from_value(&object[EXTENDS_SYMBOL]).unwrap());
+        match extended.as_array() {
+            Some(extended) => {
+                for symbol in extended.iter() {
+                    self.write_byte(Constants::Extended as u8);
+                    self.write_symbol(symbol.clone());
+                }
             }
-            #[cfg(not(feature = "sonic"))]
-            {
-                self.write_extended(from_value(object[EXTENDS_SYMBOL].take()).unwrap());
+            None => {
+                let extended: String = to_string(extended).unwrap_or_default();
+                self.fail(format!("Extended modules {extended} aren't an array."));
             }
         }
+    }
+
+    fn write_class(&mut self, data_type: Constants, object: &Value) {
+        self.write_extended(object);
 
         self.write_byte(data_type as u8);
-        self.write_symbol(object["__class"].take());
+        self.write_symbol(object["__class"].clone());
     }
 
-    fn write_user_class(&mut self, object: &mut Value) {
-        if !object[EXTENDS_SYMBOL].is_null() {
-            #[cfg(feature = "sonic")]
-            {
-                self.write_extended(from_value(&object[EXTENDS_SYMBOL]).unwrap());
-            }
-            #[cfg(not(feature = "sonic"))]
-            {
-                self.write_extended(from_value(object[EXTENDS_SYMBOL].take()).unwrap());
-            }
-        }
+    fn write_user_class(&mut self, object: &Value) {
+        self.write_extended(object);
 
         if !object["__wrapped"].is_null() {
             self.write_byte(Constants::UserClass as u8);
-            self.write_symbol(object["__class"].take())
+            self.write_symbol(object["__class"].clone())
         }
     }
 
-    fn write_instance_var(&mut self, mut object: Value) {
-        let object = object.as_object_mut().unwrap();
+    /// Returns the value of `__data` key, converted with the handler from the data registry, if there's one.
+    ///
+    /// Returns None and stops the dump, if the handler fails.
+    fn dump_data(&mut self, object: &Value) -> Option<Value> {
+        let class: &str = object["__class"].as_str().unwrap_or_default();
+
+        match self.data_registry.and_then(|registry| registry.get(class)) {
+            Some(handler) => match handler.dump(&object["__data"]) {
+                Ok(data) => Some(data),
+                Err(message) => {
+                    self.error = Some(DumpError {
+                        message: format!(
+                            "Data handler of {} failed: {message}",
+                            class.trim_start_matches("__symbol__")
+                        ),
+                    });
+                    None
+                }
+            },
+            None => Some(object["__data"].clone()),
+        }
+    }
 
-        for key in [
-            "__class",
-            "__type",
-            "__data",
-            "__wrapped",
-            "__userDefined",
-            "__userMarshal",
-        ] {
-            #[cfg(feature = "sonic")]
-            {
-                object.remove(&key);
+    /// Returns the symbol of the instance variable, replacing the instance variable prefix unless the name is in `unprefixed`, and applying `policy` to the resulting name.
+    ///
+    /// Returns None and stops the dump, if the policy rejects the name.
+    fn instance_var_symbol(
+        &mut self,
+        key: &str,
+        unprefixed: &[&str],
+        policy: InstanceVarPolicy,
+    ) -> Option<String> {
+        let mut name: String = key.strip_prefix("__symbol__").unwrap_or(key).to_owned();
+
+        if !unprefixed.contains(&key) {
+            if let Some(prefix) = self.instance_var_prefix {
+                if let Some(stripped) = name.strip_prefix(prefix) {
+                    name = format!("@{stripped}");
+                }
             }
-            #[cfg(not(feature = "sonic"))]
-            {
-                object.shift_remove(key);
+
+            if policy == InstanceVarPolicy::Prefix && !name.starts_with('@') {
+                name.insert(0, '@');
+            }
+
+            if policy != InstanceVarPolicy::Pass && !is_instance_var_name(&name) {
+                self.error = Some(DumpError {
+                    message: format!("{name} isn't a valid instance variable name."),
+                });
+                return None;
             }
         }
 
-        let object_length: usize = object.len();
-        self.write_number(object_length as i32);
+        Some(format!("__symbol__{name}"))
+    }
 
-        if object_length > 0 {
-            for (key, value) in object.iter_mut() {
-                let mut key: String = key.to_owned();
+    /// Writes instance variables of the object. Names in `unprefixed` are written as is, without replacing the instance variable prefix.
+    #[cfg(feature = "sonic")]
+    fn write_instance_var(
+        &mut self,
+        mut object: Value,
+        unprefixed: &[&str],
+        policy: InstanceVarPolicy,
+    ) {
+        // All names are checked before the count is written, so a rejected name doesn't leave the count without its entries
+        let instance_vars: Option<Vec<(String, Value)>> = object
+            .as_object_mut()
+            .unwrap()
+            .iter_mut()
+            .filter(|(key, _)| is_instance_var_key(key))
+            .map(|(key, value)| {
+                Some((
+                    self.instance_var_symbol(key, unprefixed, policy)?,
+                    value.take(),
+                ))
+            })
+            .collect();
+
+        let instance_vars: Vec<(String, Value)> = match instance_vars {
+            Some(instance_vars) => instance_vars,
+            None => return,
+        };
 
-                if let Some(prefix) = self.instance_var_prefix {
-                    key.replace_range(10..10 + prefix.len(), "@");
-                }
+        self.write_number(instance_vars.len() as i32);
+
+        for (symbol, value) in instance_vars {
+            self.write_symbol(symbol.into());
+            self.write_structure(value);
+        }
+    }
+
+    /// Writes instance variables of the object. Names in `unprefixed` are written as is, without replacing the instance variable prefix.
+    #[cfg(not(feature = "sonic"))]
+    fn write_instance_var(
+        &mut self,
+        object: &Value,
+        unprefixed: &[&str],
+        policy: InstanceVarPolicy,
+    ) {
+        let object: &Map<String, Value> = match object.as_object() {
+            Some(object) => object,
+            None => return self.fail(format!("Instance variables {object} aren't an object.")),
+        };
+
+        // All names are checked before the count is written, so a rejected name doesn't leave the count without its entries
+        let instance_vars: Option<Vec<(String, &Value)>> = object
+            .iter()
+            .filter(|(key, _)| is_instance_var_key(key))
+            .map(|(key, value)| Some((self.instance_var_symbol(key, unprefixed, policy)?, value)))
+            .collect();
+
+        let instance_vars: Vec<(String, &Value)> = match instance_vars {
+            Some(instance_vars) => instance_vars,
+            None => return,
+        };
+
+        self.write_number(instance_vars.len() as i32);
+
+        for (symbol, value) in instance_vars {
+            self.write_symbol(symbol.into());
+            self.write_structure(value);
+        }
+    }
+
+    /// Stops the dump with the error, if it isn't stopped already.
+    fn fail(&mut self, message: String) {
+        if self.error.is_none() {
+            self.error = Some(DumpError { message });
+        }
+    }
 
-                self.write_symbol(key.as_str().into());
-                self.write_structure(value.take());
+    /// Writes the key of the Hash, converting prefixed keys back to the values, they were made from.
+    ///
+    /// Stops the dump, if the prefixed key can't be converted.
+    fn write_hash_key(&mut self, key: &str, symbol_keys: bool) {
+        let key_value: Option<Value> = if key == "__nil__null" {
+            Some(Value::default())
+        } else if key == "__boolean__true" || key == "__boolean__false" {
+            Some((key == "__boolean__true").into())
+        } else if let Some(stripped) = key.strip_prefix("__integer__") {
+            // Integers, that don't fit in 64 bits, are written as Big Integers
+            if let Ok(integer) = stripped.parse::<i128>() {
+                self.write_integer(integer);
+                return;
             }
+
+            None
+        } else if let Some(stripped) = key.strip_prefix("__float__") {
+            stripped.parse::<f64>().ok().map(|float| json!(float))
+        } else if let Some(stripped) = key
+            .strip_prefix("__array__")
+            .or_else(|| key.strip_prefix("__object__"))
+        {
+            from_str(stripped).ok()
+        } else if symbol_keys {
+            Some(format!("__symbol__{key}").into())
+        } else {
+            Some(key.into())
+        };
+
+        match key_value {
+            #[cfg(feature = "sonic")]
+            Some(key_value) => self.write_structure(key_value),
+            #[cfg(not(feature = "sonic"))]
+            Some(key_value) => self.write_structure(&key_value),
+            None => self.fail(format!(
+                "Hash key {key} can't be converted to a Ruby value."
+            )),
         }
     }
 
+    /// Returns whether the dump was cancelled or stopped by an error, checking the cancel flag.
+    fn is_cancelled(&mut self) -> bool {
+        if self.error.is_some() {
+            return true;
+        }
+
+        if self.cancelled
+            || self
+                .cancel_flag
+                .map_or(false, |flag| flag.load(Ordering::Relaxed))
+        {
+            self.cancelled = true;
+        }
+
+        self.cancelled
+    }
+
+    #[cfg(feature = "sonic")]
     fn write_structure(&mut self, mut value: Value) {
-        #[cfg(feature = "sonic")]
+        if self.is_cancelled() {
+            return;
+        }
+
         {
             /*if let Some(value) = self.objects.iter().position(|val| *val == value) {
                 self.write_byte(Constants::Link as u8);
@@ -290,8 +788,9 @@ impl<'a> Dumper<'a> {
                 }
                 JsonType::Number => {
                     if let Some(integer) = value.as_i64() {
-                        self.write_byte(Constants::Fixnum as u8);
-                        self.write_number(integer as i32);
+                        self.write_integer(integer.into());
+                    } else if let Some(integer) = value.as_u64() {
+                        self.write_integer(integer.into());
                     } else if let Some(float) = value.as_f64() {
                         /*if !self.objects.contains(&value) {
                             self.objects.push(value);
@@ -311,8 +810,15 @@ impl<'a> Dumper<'a> {
                                     self.objects.push(value["data"].take());
                                 }*/
 
-                                self.write_byte(Constants::String as u8);
-                                self.write_bytes(&buf);
+                                self.write_binary_string(&buf, value["encoding"].as_str());
+                            }
+                            "string" => self.write_encoded_string(
+                                value["value"].as_str().unwrap_or_default(),
+                                value["encoding"].as_str(),
+                            ),
+                            "float" => {
+                                self.write_byte(Constants::Float as u8);
+                                self.write_string(value["value"].as_str().unwrap_or("nan"));
                             }
                             "object" => {
                                 /*if !self.objects.contains(&value) {
@@ -321,7 +827,10 @@ impl<'a> Dumper<'a> {
 
                                 if value.get("__data").is_some() {
                                     self.write_class(Constants::Data, &mut value);
-                                    self.write_structure(value["__data"].take());
+
+                                    if let Some(data) = self.dump_data(&value) {
+                                        self.write_structure(data);
+                                    }
                                 } else if value.get("__wrapped").is_some() {
                                     self.write_user_class(&mut value);
                                     self.write_structure(value["__wrapped"].take());
@@ -347,14 +856,29 @@ impl<'a> Dumper<'a> {
                                     );
 
                                     if has_instance_var {
-                                        self.write_instance_var(value);
+                                        self.write_instance_var(
+                                            value,
+                                            &[],
+                                            InstanceVarPolicy::Pass,
+                                        );
                                     }
                                 } else if value.get("__userMarshal").is_some() {
                                     self.write_class(Constants::UserMarshal, &mut value);
                                     self.write_structure(value["__userMarshal"].take());
                                 } else {
+                                    let unprefixed: &[&str] =
+                                        if value["__class"] == "__symbol__Range" {
+                                            &RANGE_INSTANCE_VARS
+                                        } else {
+                                            &[]
+                                        };
+
                                     self.write_class(Constants::Object, &mut value);
-                                    self.write_instance_var(value);
+                                    self.write_instance_var(
+                                        value,
+                                        unprefixed,
+                                        self.instance_var_policy,
+                                    );
                                 }
                             }
                             "struct" => {
@@ -363,7 +887,11 @@ impl<'a> Dumper<'a> {
                                 } */
 
                                 self.write_class(Constants::Struct, &mut value);
-                                self.write_instance_var(value["__members"].take());
+                                self.write_instance_var(
+                                    value["__members"].take(),
+                                    &[],
+                                    InstanceVarPolicy::Pass,
+                                );
                             }
                             "class" => {
                                 /*if !self.objects.contains(&value) {
@@ -371,7 +899,13 @@ impl<'a> Dumper<'a> {
                                 } */
 
                                 self.write_byte(Constants::Class as u8);
-                                self.write_string(value["__name"].take().as_str().unwrap());
+                                self.write_string(
+                                    value
+                                        .get("__class")
+                                        .or_else(|| value.get("__name"))
+                                        .and_then(|name| name.as_str())
+                                        .unwrap(),
+                                );
                             }
                             "module" => {
                                 /*if !self.objects.contains(&value) {
@@ -384,7 +918,13 @@ impl<'a> Dumper<'a> {
                                     Constants::Module
                                 } as u8);
 
-                                self.write_string(value["__name"].take().as_str().unwrap());
+                                self.write_string(
+                                    value
+                                        .get("__class")
+                                        .or_else(|| value.get("__name"))
+                                        .and_then(|name| name.as_str())
+                                        .unwrap(),
+                                );
                             }
                             "regexp" => {
                                 /*if !self.objects.contains(&value) {
@@ -416,9 +956,15 @@ impl<'a> Dumper<'a> {
                                     self.objects.push(value.clone());
                                 } */
 
-                                let bigint =
-                                    BigInt::from_str(value["value"].as_str().unwrap()).unwrap();
-                                self.write_bignum(bigint);
+                                if value["data"].is_array() {
+                                    let bytes: Vec<u8> = from_value(&value["data"]).unwrap();
+                                    self.write_raw_bignum(
+                                        value["negative"].as_bool().unwrap_or_default(),
+                                        &bytes,
+                                    );
+                                } else {
+                                    self.write_decimal_bignum(value["value"].as_str());
+                                }
                             }
                             _ => unreachable!(),
                         }
@@ -440,6 +986,10 @@ impl<'a> Dumper<'a> {
 
                         self.write_byte(hash_type as u8);
 
+                        let symbol_keys: bool = object
+                            .remove(&SYMBOL_KEYS_SYMBOL)
+                            .map_or(false, |marker| marker.as_bool() == Some(true));
+
                         for key in [
                             "__class",
                             "__type",
@@ -456,20 +1006,7 @@ impl<'a> Dumper<'a> {
                         self.write_number(entries.len() as i32);
 
                         for (key, value) in entries {
-                            let key_value = if let Some(stripped) = key.strip_prefix("__integer__")
-                            {
-                                stripped.parse::<u64>().unwrap().into()
-                            } else if let Some(stripped) = key.strip_prefix("__float__") {
-                                json!(stripped.parse::<f64>().unwrap())
-                            } else if let Some(stripped) = key.strip_prefix("__array__") {
-                                from_str(stripped).unwrap()
-                            } else if let Some(stripped) = key.strip_prefix("__object__") {
-                                from_str(stripped).unwrap()
-                            } else {
-                                key.into()
-                            };
-
-                            self.write_structure(key_value);
+                            self.write_hash_key(key, symbol_keys);
                             self.write_structure(value.take());
                         }
 
@@ -501,17 +1038,19 @@ impl<'a> Dumper<'a> {
                             self.objects.push(value.clone());
                         } */
 
-                        self.write_byte(Constants::InstanceVar as u8);
-                        self.write_byte(Constants::String as u8);
-                        self.write_string(string);
-                        self.write_number(1);
-                        self.write_symbol(ENCODING_SHORT_SYMBOL.into());
-                        self.write_byte(Constants::True as u8);
+                        self.write_utf8_string(string);
                     }
                 }
             }
         }
-        #[cfg(not(feature = "sonic"))]
+    }
+
+    #[cfg(not(feature = "sonic"))]
+    fn write_structure(&mut self, value: &Value) {
+        if self.is_cancelled() {
+            return;
+        }
+
         {
             /*if let Some(&value) = self.objects.get(&value) {
                 self.write_byte(Constants::Link as u8);
@@ -522,7 +1061,7 @@ impl<'a> Dumper<'a> {
             match value {
                 Value::Null => self.write_byte(Constants::Nil as u8),
                 Value::Bool(bool) => {
-                    self.write_byte(if bool {
+                    self.write_byte(if *bool {
                         Constants::True
                     } else {
                         Constants::False
@@ -530,8 +1069,9 @@ impl<'a> Dumper<'a> {
                 }
                 Value::Number(_) => {
                     if let Some(integer) = value.as_i64() {
-                        self.write_byte(Constants::Fixnum as u8);
-                        self.write_number(integer as i32);
+                        self.write_integer(integer.into());
+                    } else if let Some(integer) = value.as_u64() {
+                        self.write_integer(integer.into());
                     } else if let Some(float) = value.as_f64() {
                         /*if !self.objects.contains_key(&value) {
                             self.objects.insert(value, self.objects.len());
@@ -543,91 +1083,152 @@ impl<'a> Dumper<'a> {
                 }
                 Value::Object(_) => {
                     if let Some(object_type) = value.get("__type") {
-                        match object_type.as_str().unwrap() {
+                        match object_type.as_str().unwrap_or_default() {
                             "bytes" => {
-                                let buf: Vec<u8> = from_value(value["data"].clone()).unwrap();
+                                let buf: Vec<u8> = match from_value(value["data"].clone()) {
+                                    Ok(buf) => buf,
+                                    Err(_) => {
+                                        return self.fail(format!(
+                                            "Bytes {} aren't an array of bytes.",
+                                            value["data"]
+                                        ))
+                                    }
+                                };
 
                                 //self.objects.insert(value["data"].take(), self.objects.len());
 
-                                self.write_byte(Constants::String as u8);
-                                self.write_bytes(&buf);
+                                self.write_binary_string(&buf, value["encoding"].as_str());
+                            }
+                            "string" => self.write_encoded_string(
+                                value["value"].as_str().unwrap_or_default(),
+                                value["encoding"].as_str(),
+                            ),
+                            "float" => {
+                                self.write_byte(Constants::Float as u8);
+                                self.write_string(value["value"].as_str().unwrap_or("nan"));
                             }
                             "object" => {
                                 //self.objects.insert(value.clone(), self.objects.len());
 
                                 if value.get("__data").is_some() {
-                                    self.write_class(Constants::Data, &mut value);
-                                    self.write_structure(value["__data"].take());
-                                } else if value.get("__wrapped").is_some() {
-                                    self.write_user_class(&mut value);
-                                    self.write_structure(value["__wrapped"].take());
-                                } else if value.get("__userDefined").is_some() {
-                                    let object = value.as_object_mut().unwrap();
-                                    let mut object_length: usize = object.len();
+                                    self.write_class(Constants::Data, value);
 
-                                    for (key, _) in object {
-                                        if key.starts_with("__") {
-                                            object_length -= 1;
-                                        }
+                                    if let Some(data) = self.dump_data(value) {
+                                        self.write_structure(&data);
                                     }
-
-                                    let has_instance_var: bool = object_length > 0;
+                                } else if value.get("__wrapped").is_some() {
+                                    self.write_user_class(value);
+                                    self.write_structure(&value["__wrapped"]);
+                                } else if value.get("__userDefined").is_some() {
+                                    let has_instance_var: bool =
+                                        value.as_object().unwrap().keys().any(|key| {
+                                            !key.starts_with("__") || key.starts_with("__symbol__")
+                                        });
 
                                     if has_instance_var {
                                         self.write_byte(Constants::InstanceVar as u8);
                                     }
 
-                                    self.write_class(Constants::UserDefined, &mut value);
-                                    self.write_bytes(
-                                        &from_value::<Vec<u8>>(value["__userDefined"].take())
-                                            .unwrap(),
-                                    );
+                                    let bytes: Vec<u8> =
+                                        match from_value(value["__userDefined"].clone()) {
+                                            Ok(bytes) => bytes,
+                                            Err(_) => {
+                                                return self.fail(format!(
+                                                    "User defined data {} isn't an array of bytes.",
+                                                    value["__userDefined"]
+                                                ))
+                                            }
+                                        };
+
+                                    self.write_class(Constants::UserDefined, value);
+                                    self.write_bytes(&bytes);
 
                                     if has_instance_var {
-                                        self.write_instance_var(value);
+                                        self.write_instance_var(
+                                            value,
+                                            &[],
+                                            InstanceVarPolicy::Pass,
+                                        );
                                     }
                                 } else if value.get("__userMarshal").is_some() {
-                                    self.write_class(Constants::UserMarshal, &mut value);
-                                    self.write_structure(value["__userMarshal"].take());
+                                    self.write_class(Constants::UserMarshal, value);
+                                    self.write_structure(&value["__userMarshal"]);
                                 } else {
-                                    self.write_class(Constants::Object, &mut value);
-                                    self.write_instance_var(value);
+                                    let unprefixed: &[&str] =
+                                        if value["__class"] == "__symbol__Range" {
+                                            &RANGE_INSTANCE_VARS
+                                        } else {
+                                            &[]
+                                        };
+
+                                    self.write_class(Constants::Object, value);
+                                    self.write_instance_var(
+                                        value,
+                                        unprefixed,
+                                        self.instance_var_policy,
+                                    );
                                 }
                             }
                             "struct" => {
                                 //self.objects.insert(value.clone(), self.objects.len());
 
-                                self.write_class(Constants::Struct, &mut value);
-                                self.write_instance_var(value["__members"].take());
+                                self.write_class(Constants::Struct, value);
+                                self.write_instance_var(
+                                    &value["__members"],
+                                    &[],
+                                    InstanceVarPolicy::Pass,
+                                );
                             }
                             "class" => {
                                 //self.objects.insert(value.clone(), self.objects.len());
 
+                                let name: &str = match value["__class"]
+                                    .as_str()
+                                    .or_else(|| value["__name"].as_str())
+                                {
+                                    Some(name) => name,
+                                    None => return self.fail("Class name isn't a string.".into()),
+                                };
+
                                 self.write_byte(Constants::Class as u8);
-                                self.write_string(value["__name"].take().as_str().unwrap());
+                                self.write_string(name);
                             }
                             "module" => {
                                 //self.objects.insert(value.clone(), self.objects.len());
 
-                                self.write_byte(if let Some(old) = value.get("__old") {
-                                    if old.as_bool().unwrap() {
-                                        Constants::ModuleOld
-                                    } else {
-                                        Constants::Module
-                                    }
+                                let name: &str = match value["__class"]
+                                    .as_str()
+                                    .or_else(|| value["__name"].as_str())
+                                {
+                                    Some(name) => name,
+                                    None => return self.fail("Module name isn't a string.".into()),
+                                };
+
+                                self.write_byte(if value["__old"] == true {
+                                    Constants::ModuleOld
                                 } else {
                                     Constants::Module
                                 } as u8);
 
-                                self.write_string(value["__name"].take().as_str().unwrap());
+                                self.write_string(name);
                             }
                             "regexp" => {
                                 //self.objects.insert(value.clone(), self.objects.len());
 
+                                let (expression, flags) =
+                                    match (value["expression"].as_str(), value["flags"].as_str()) {
+                                        (Some(expression), Some(flags)) => (expression, flags),
+                                        _ => {
+                                            return self.fail(
+                                                "Regexp expression and flags aren't strings."
+                                                    .into(),
+                                            )
+                                        }
+                                    };
+
                                 self.write_byte(Constants::Regexp as u8);
-                                self.write_string(value["expression"].as_str().unwrap());
+                                self.write_string(expression);
 
-                                let flags = value["flags"].as_str().unwrap();
                                 let mut options: u8 = 0;
 
                                 if flags.contains("i") {
@@ -649,19 +1250,31 @@ impl<'a> Dumper<'a> {
                                     self.objects.insert(value.clone(), self.objects.len());
                                 } */
 
-                                let bigint =
-                                    BigInt::from_str(value["value"].as_str().unwrap()).unwrap();
-                                self.write_bignum(bigint);
+                                if value["data"].is_array() {
+                                    let bytes: Vec<u8> = match from_value(value["data"].clone()) {
+                                        Ok(bytes) => bytes,
+                                        Err(_) => {
+                                            return self.fail(format!(
+                                                "Big Integer data {} isn't an array of bytes.",
+                                                value["data"]
+                                            ))
+                                        }
+                                    };
+
+                                    self.write_raw_bignum(
+                                        value["negative"].as_bool().unwrap_or_default(),
+                                        &bytes,
+                                    );
+                                } else {
+                                    self.write_decimal_bignum(value["value"].as_str());
+                                }
                             }
-                            _ => unreachable!(),
+                            _ => self.fail(format!("Unknown object type {object_type}.")),
                         }
                     } else {
                         //self.objects.insert(value.clone(), self.objects.len());
 
-                        let object = value.as_object_mut().unwrap();
-                        let default_value: Option<Value> = object
-                            .get_mut(DEFAULT_SYMBOL)
-                            .map(|default_value| default_value.take());
+                        let default_value: Option<&Value> = value.get(DEFAULT_SYMBOL);
 
                         let hash_type = if default_value.is_some() {
                             Constants::HashDefault
@@ -671,37 +1284,23 @@ impl<'a> Dumper<'a> {
 
                         self.write_byte(hash_type as u8);
 
-                        for key in [
-                            "__class",
-                            "__type",
-                            "__data",
-                            "__wrapped",
-                            "__userDefined",
-                            "__userMarshal",
-                            DEFAULT_SYMBOL,
-                        ] {
-                            object.shift_remove(key);
-                        }
+                        let symbol_keys: bool = value[SYMBOL_KEYS_SYMBOL] == true;
+                        let entries: Vec<(&String, &Value)> = value
+                            .as_object()
+                            .unwrap()
+                            .iter()
+                            .filter(|(key, _)| {
+                                *key != DEFAULT_SYMBOL
+                                    && *key != SYMBOL_KEYS_SYMBOL
+                                    && !INTERNAL_KEYS.contains(&key.as_str())
+                            })
+                            .collect();
 
-                        let entries = object.iter_mut();
                         self.write_number(entries.len() as i32);
 
                         for (key, value) in entries {
-                            let key_value = if let Some(stripped) = key.strip_prefix("__integer__")
-                            {
-                                stripped.parse::<u16>().unwrap().into()
-                            } else if let Some(stripped) = key.strip_prefix("__float__") {
-                                stripped.parse::<f64>().unwrap().into()
-                            } else if let Some(stripped) = key.strip_prefix("__array__") {
-                                from_str(stripped).unwrap()
-                            } else if let Some(stripped) = key.strip_prefix("__object__") {
-                                from_str(stripped).unwrap()
-                            } else {
-                                key.as_str().into()
-                            };
-
-                            self.write_structure(key_value);
-                            self.write_structure(value.take());
+                            self.write_hash_key(key, symbol_keys);
+                            self.write_structure(value);
                         }
 
                         if let Some(default_value) = default_value {
@@ -709,13 +1308,12 @@ impl<'a> Dumper<'a> {
                         }
                     }
                 }
-                Value::Array(_) => {
-                    let array = value.as_array_mut().unwrap();
+                Value::Array(array) => {
                     self.write_byte(Constants::Array as u8);
                     self.write_number(array.len() as i32);
 
                     for element in array {
-                        self.write_structure(element.take());
+                        self.write_structure(element);
                     }
                 }
                 Value::String(_) => {
@@ -728,17 +1326,59 @@ impl<'a> Dumper<'a> {
                             self.objects.insert(value.clone(), self.objects.len());
                         } */
 
-                        self.write_byte(Constants::InstanceVar as u8);
-                        self.write_byte(Constants::String as u8);
-                        self.write_string(string);
-                        self.write_number(1);
-                        self.write_symbol(ENCODING_SHORT_SYMBOL.into());
-                        self.write_byte(Constants::True as u8);
+                        self.write_utf8_string(string);
                     }
                 }
             }
         }
     }
+
+    /// Writes the plain JSON value for `dump_json()`.
+    #[cfg(feature = "serde")]
+    fn write_json(&mut self, value: &serde_json::Value) {
+        if self.is_cancelled() {
+            return;
+        }
+
+        match value {
+            serde_json::Value::Null => self.write_byte(Constants::Nil as u8),
+            serde_json::Value::Bool(bool) => {
+                self.write_byte(if *bool {
+                    Constants::True
+                } else {
+                    Constants::False
+                } as u8);
+            }
+            serde_json::Value::Number(number) => {
+                if let Some(integer) = number.as_i64() {
+                    self.write_integer(integer.into());
+                } else if let Some(integer) = number.as_u64() {
+                    self.write_integer(integer.into());
+                } else if let Some(float) = number.as_f64() {
+                    self.write_byte(Constants::Float as u8);
+                    self.write_float(float);
+                }
+            }
+            serde_json::Value::String(string) => self.write_utf8_string(string),
+            serde_json::Value::Array(array) => {
+                self.write_byte(Constants::Array as u8);
+                self.write_number(array.len() as i32);
+
+                for element in array {
+                    self.write_json(element);
+                }
+            }
+            serde_json::Value::Object(object) => {
+                self.write_byte(Constants::Hash as u8);
+                self.write_number(object.len() as i32);
+
+                for (key, value) in object {
+                    self.write_utf8_string(key);
+                    self.write_json(value);
+                }
+            }
+        }
+    }
 }
 
 impl<'a> Default for Dumper<'a> {
@@ -747,9 +1387,115 @@ impl<'a> Default for Dumper<'a> {
     }
 }
 
+/// Builder of `Dumper`.
+#[derive(Clone, Copy, Debug)]
+pub struct DumperBuilder<'a> {
+    instance_var_prefix: Option<&'a str>,
+    capacity: usize,
+    pool: Option<&'a TablePool>,
+    cancel_flag: Option<&'a AtomicBool>,
+    instance_var_policy: InstanceVarPolicy,
+    data_registry: Option<&'a DataRegistry>,
+}
+
+impl<'a> DumperBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            instance_var_prefix: None,
+            capacity: 128,
+            pool: None,
+            cancel_flag: None,
+            instance_var_policy: InstanceVarPolicy::Pass,
+            data_registry: None,
+        }
+    }
+
+    /// Sets the instance variable prefix, used when `Dumper::dump()` is called without one.
+    pub fn instance_var_prefix(mut self, prefix: &'a str) -> Self {
+        self.instance_var_prefix = Some(prefix);
+        self
+    }
+
+    /// Sets the capacity in bytes, that's reserved for the output of each dump. Defaults to 128.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the pool, that the Dumper takes its tables and output buffers from at the start of each dump, and returns its tables to at the end.
+    pub fn pool(mut self, pool: &'a TablePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Sets the flag, that cancels the dump in progress, when it's set to true. The flag is checked before writing each value.
+    /// Cancelled dumps return an Err from `Dumper::try_dump()`. The flag isn't reset by the Dumper.
+    pub fn cancel_flag(mut self, flag: &'a AtomicBool) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Sets how instance variables of objects, whose names aren't "@" followed by an identifier, like `"__symbol__name"`, are handled. Defaults to `InstanceVarPolicy::Pass`.
+    ///
+    /// Names are checked after the instance variable prefix is replaced. Struct members and instance variables of Range and objects with `_dump` aren't checked, as Ruby writes them without "@".
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{Dumper, InstanceVarPolicy};
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__name": "Sword" });
+    ///
+    /// let mut dumper = Dumper::builder().instance_var_policy(InstanceVarPolicy::Error).build();
+    /// assert!(dumper.try_dump(value.clone(), None).is_err());
+    ///
+    /// let mut dumper = Dumper::builder().instance_var_policy(InstanceVarPolicy::Prefix).build();
+    /// let prefixed = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" });
+    /// assert_eq!(dumper.try_dump(value, None).unwrap(), Dumper::new().dump(prefixed, None));
+    /// ```
+    pub fn instance_var_policy(mut self, policy: InstanceVarPolicy) -> Self {
+        self.instance_var_policy = policy;
+        self
+    }
+
+    /// Sets the registry, whose handlers convert values of `__data` key back, before they're written as values, that `_dump_data` of their classes returned.
+    /// Failed handlers return an Err from `Dumper::try_dump()`.
+    pub fn data_registry(mut self, registry: &'a DataRegistry) -> Self {
+        self.data_registry = Some(registry);
+        self
+    }
+
+    /// Resets options, that `MarshalCodec` shares with the Loader.
+    pub(crate) fn clear_shared(mut self) -> Self {
+        self.instance_var_prefix = None;
+        self.pool = None;
+        self.cancel_flag = None;
+        self.data_registry = None;
+        self
+    }
+
+    pub fn build(self) -> Dumper<'a> {
+        let mut dumper: Dumper = Dumper::new();
+        dumper.capacity = self.capacity;
+        dumper.default_instance_var_prefix = self.instance_var_prefix;
+        dumper.pool = self.pool;
+        dumper.cancel_flag = self.cancel_flag;
+        dumper.instance_var_policy = self.instance_var_policy;
+        dumper.data_registry = self.data_registry;
+        dumper
+    }
+}
+
+impl<'a> Default for DumperBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Serializes JSON object to a Marshal byte stream.
 ///
 /// instance_var_prefix argument takes a string, and replaces instance variables' prefixes with Ruby's "@" prefix. It's value must be the same, as in load() function.
+///
+/// Panics, if a value can't be dumped, like a Big Integer with an invalid decimal string. Use `Dumper::try_dump()` to get an Err instead.
 /// # Example
 /// ```rust
 /// use marshal_rs::dump;
@@ -765,3 +1511,23 @@ impl<'a> Default for Dumper<'a> {
 pub fn dump(value: Value, instance_var_prefix: Option<&str>) -> Vec<u8> {
     Dumper::new().dump(value, instance_var_prefix)
 }
+
+/// Serializes plain JSON data straight to a Marshal byte stream, without interpreting any keys or prefixes of `marshal-rs`, like `Dumper::dump_json()`.
+///
+/// Useful for sending configs and other plain data to Ruby processes, as `__symbol__` strings and `__type` objects are written as they are.
+/// # Example
+/// ```rust
+/// use marshal_rs::{dump_json, load};
+/// use serde_json::json;
+///
+/// let config = json!({ "retries": 3, "ratio": 0.5, "hosts": ["a", "b"], "limit": 9007199254740993u64 });
+/// let bytes: Vec<u8> = dump_json(&config);
+///
+/// assert_eq!(load(&bytes, None, None).unwrap()["hosts"], json!(["a", "b"]));
+/// ```
+///
+/// Available with `serde` feature enabled.
+#[cfg(feature = "serde")]
+pub fn dump_json(value: &serde_json::Value) -> Vec<u8> {
+    Dumper::new().dump_json(value)
+}
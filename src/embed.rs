@@ -0,0 +1,40 @@
+//! Marshal data, embedded into the binary at compile time.
+//!
+//! Values of `Embedded` are created by `include_marshal!` macro of `marshal-rs-macros` crate, which validates the data at compile time.
+//! # Example
+//! ```rust,ignore
+//! use marshal_rs_macros::include_marshal;
+//!
+//! // Path is relative to the directory of the crate's Cargo.toml
+//! let system = include_marshal!("data/System.rvdata2");
+//! let value = system.load();
+//! ```
+
+use crate::load;
+#[cfg(not(feature = "sonic"))]
+use serde_json::Value;
+#[cfg(feature = "sonic")]
+use sonic_rs::Value;
+
+/// Static Marshal byte stream, that's known to be loadable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Embedded {
+    bytes: &'static [u8],
+}
+
+impl Embedded {
+    /// Wraps the bytes without validation. Intended to be called by `include_marshal!` macro, which validates the bytes at compile time.
+    #[doc(hidden)]
+    pub const fn new_unchecked(bytes: &'static [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    /// Loads the embedded data.
+    pub fn load(&self) -> Value {
+        load(self.bytes, None, None).expect("embedded Marshal data was validated at compile time")
+    }
+}
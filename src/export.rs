@@ -0,0 +1,888 @@
+//! Export of loaded values to interchange formats, and import back.
+//!
+//! # CSV
+//! `to_csv()` turns an Array of objects, like RPG Maker database files (`Actors.rvdata2`, `Items.rvdata2`), into a table:
+//! the `class` column holds objects' classes, and other columns hold instance variables, in the order of their first appearance.
+//! `nil` elements, like the first element of RPG Maker databases, are written as rows with empty cells.
+//!
+//! | Value                        | Cell                                   |
+//! | ---------------------------- | -------------------------------------- |
+//! | `null`                       | empty                                  |
+//! | Boolean, number              | `true`, `false`, `42`, `1.5`           |
+//! | String                       | `text`                                 |
+//! | Symbol                       | `:name`                                |
+//! | Array, object                | JSON, like `[1,2]`                     |
+//!
+//! `from_csv()` reads cells back with the same rules. Strings, that would be read as other values, like `"42"` or `""`, are prefixed with `'`, the spreadsheets' text marker, which is removed on import.
+//!
+//! # NDJSON
+//! `to_ndjson()` writes each element of an Array as a JSON document on its own line, so large datasets can be piped to line-based tools, like `jq` or DuckDB.
+//! Elements are written one by one, and `NdjsonReader` reads them back one by one, so neither side builds a whole JSON document.
+//!
+//! # Arrow and Parquet
+//! `to_record_batch()` turns an Array of objects into an Arrow record batch, with the same columns as CSV, and `to_parquet()` writes it to a Parquet file.
+//! Columns' types are inferred from their non-`null` values:
+//!
+//! | Values                       | Arrow type                             |
+//! | ---------------------------- | -------------------------------------- |
+//! | Booleans                     | `Boolean`                              |
+//! | Integers                     | `Int64`                                |
+//! | Numbers                      | `Float64`                              |
+//! | Bytes                        | `Binary`                               |
+//! | Other values                 | `Utf8`, with CSV cells' text           |
+//!
+//! All columns are nullable, and `nil` elements are written as rows of nulls.
+//! Requires `arrow` feature.
+//!
+//! # SQLite
+//! `to_sqlite()` writes objects of an Array to an SQLite database, with a table per class, named like `RPG::Item`, and a column per instance variable, named like `@name`.
+//! Each table has `__rowid` primary key. Cells hold values like in CSV, except booleans are `0` or `1`, and bytes are BLOBs.
+//!
+//! Nested Arrays, Hashes and objects are written as JSON text, or, with `NestedMode::ChildTables`:
+//! - Objects are written to tables of their classes, and the cell holds their `__rowid`.
+//! - Arrays and Hashes are written to child tables, named like `RPG::Item.@effects`, with `__parent` column holding `__rowid` of the row, `__key` column holding the index or the key, and `value` column.
+//!
+//! Requires `sqlite` feature.
+//!
+//! Not available with `sonic` feature enabled.
+//! # Example
+//! ```rust
+//! use marshal_rs::export::{from_csv, to_csv, CsvOptions};
+//! use serde_json::json;
+//!
+//! let items = json!([
+//!     null,
+//!     { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 1, "__symbol__@name": "Potion" },
+//! ]);
+//!
+//! let csv: String = to_csv(&items, CsvOptions::default()).unwrap();
+//! assert_eq!(csv, "class,@id,@name\n,,\nRPG::Item,1,Potion\n");
+//! assert_eq!(from_csv(&csv, CsvOptions::default()).unwrap(), items);
+//! ```
+//! ```rust
+//! use marshal_rs::export::{from_ndjson, to_ndjson};
+//! use serde_json::json;
+//!
+//! let mut ndjson: Vec<u8> = Vec::new();
+//! to_ndjson(&mut ndjson, &json!([null, { "a": 1 }])).unwrap();
+//!
+//! assert_eq!(ndjson, b"null\n{\"a\":1}\n");
+//! assert_eq!(from_ndjson(ndjson.as_slice()).unwrap(), json!([null, { "a": 1 }]));
+//! ```
+
+#[cfg(any(feature = "arrow", feature = "sqlite"))]
+use crate::value::bytes_of;
+use crate::value::ValueError;
+#[cfg(feature = "sqlite")]
+use crate::value::{is_hash, key_value};
+#[cfg(feature = "arrow")]
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+};
+#[cfg(feature = "arrow")]
+use arrow_schema::{DataType, Field, Schema};
+#[cfg(feature = "sqlite")]
+use rusqlite::{types::Value as SqlValue, Connection};
+use serde_json::{json, Map, Value};
+use std::io::{self, BufRead, Write};
+#[cfg(feature = "sqlite")]
+use std::path::Path;
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+
+/// Name of the column with objects' classes.
+const CLASS_COLUMN: &str = "class";
+
+/// Options of `to_csv()` and `from_csv()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Delimiter of cells. Defaults to `,`.
+    pub delimiter: char,
+    /// Whether strings, that would be read as other values, are prefixed with `'` on export, and whether the prefix is removed on import.
+    /// If false, all cells are exported as is, and imported as strings, except empty cells, which are imported as `null`. Defaults to true.
+    pub coerce: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            coerce: true,
+        }
+    }
+}
+
+fn export_error(message: String) -> ValueError {
+    ValueError { message }
+}
+
+/// Converts an instance variable key to a column name.
+fn column_name(key: &str) -> &str {
+    key.strip_prefix("__symbol__").unwrap_or(key)
+}
+
+/// Converts a column name to an instance variable key. Metadata keys, like `__extends`, are kept as is.
+fn column_key(name: &str) -> String {
+    if name.starts_with("__") {
+        name.to_string()
+    } else {
+        format!("__symbol__{name}")
+    }
+}
+
+/// Reads a cell with coercion rules.
+fn infer(cell: &str) -> Value {
+    if cell.is_empty() {
+        return Value::Null;
+    }
+
+    if let Some(text) = cell.strip_prefix('\'') {
+        return text.into();
+    }
+
+    if let Some(symbol) = cell.strip_prefix(':') {
+        if !symbol.is_empty() {
+            return format!("__symbol__{symbol}").into();
+        }
+    }
+
+    match cell {
+        "true" => return true.into(),
+        "false" => return false.into(),
+        _ => {}
+    }
+
+    if let Ok(integer) = cell.parse::<i64>() {
+        return integer.into();
+    }
+
+    if cell.starts_with(|char: char| char == '-' || char.is_ascii_digit()) {
+        if let Ok(float) = cell.parse::<f64>() {
+            if float.is_finite() {
+                return json!(float);
+            }
+        }
+    }
+
+    if cell.starts_with(['[', '{']) {
+        if let Ok(value) = serde_json::from_str::<Value>(cell) {
+            return value;
+        }
+    }
+
+    cell.into()
+}
+
+fn cell(value: &Value, coerce: bool) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => {
+            if let Some(symbol) = string.strip_prefix("__symbol__") {
+                format!(":{symbol}")
+            } else if coerce && (string.starts_with('\'') || infer(string) != *value) {
+                format!("'{string}")
+            } else {
+                string.clone()
+            }
+        }
+        Value::Bool(_) | Value::Number(_) | Value::Array(_) | Value::Object(_) => value.to_string(),
+    }
+}
+
+fn write_row<'s, I: IntoIterator<Item = &'s str>>(csv: &mut String, cells: I, delimiter: char) {
+    for (index, cell) in cells.into_iter().enumerate() {
+        if index > 0 {
+            csv.push(delimiter);
+        }
+
+        if cell.contains([delimiter, '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&cell.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(cell);
+        }
+    }
+
+    csv.push('\n');
+}
+
+/// Returns rows of an Array of objects, and keys of their instance variables, in the order of their first appearance.
+fn table(value: &Value) -> Result<(&Vec<Value>, Vec<&str>), ValueError> {
+    let rows: &Vec<Value> = value
+        .as_array()
+        .ok_or_else(|| export_error("Expected an Array of objects.".to_string()))?;
+
+    let mut columns: Vec<&str> = Vec::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        let object: &Map<String, Value> = match row {
+            Value::Null => continue,
+            Value::Object(object) if object.get("__type") == Some(&json!("object")) => object,
+            _ => {
+                return Err(export_error(format!(
+                    "Expected an object at index {index}, found {row}."
+                )))
+            }
+        };
+
+        for key in object.keys() {
+            if key != "__class" && key != "__type" && !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    Ok((rows, columns))
+}
+
+/// Converts an Array of objects to CSV with a header row. See the module documentation for the mapping.
+///
+/// Returns an Err, if the value isn't an Array, or any of its elements isn't an object or `null`.
+pub fn to_csv(value: &Value, options: CsvOptions) -> Result<String, ValueError> {
+    let (rows, columns) = table(value)?;
+    let mut csv: String = String::new();
+
+    write_row(
+        &mut csv,
+        std::iter::once(CLASS_COLUMN).chain(columns.iter().map(|key| column_name(key))),
+        options.delimiter,
+    );
+
+    for row in rows {
+        let class: String = row["__class"]
+            .as_str()
+            .map(|class| column_name(class).to_string())
+            .unwrap_or_default();
+
+        let cells: Vec<String> = std::iter::once(class)
+            .chain(columns.iter().map(|key| match row.get(key) {
+                Some(value) => cell(value, options.coerce),
+                None => String::new(),
+            }))
+            .collect();
+
+        write_row(
+            &mut csv,
+            cells.iter().map(String::as_str),
+            options.delimiter,
+        );
+    }
+
+    Ok(csv)
+}
+
+/// Splits CSV to rows of cells, handling quoted cells with delimiters, quotes and line breaks.
+fn parse_rows(csv: &str, delimiter: char) -> Result<Vec<Vec<String>>, ValueError> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut cell: String = String::new();
+    let mut chars = csv.chars().peekable();
+    let mut line: usize = 1;
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if cell.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    Some('"') => break,
+                    Some(char) => {
+                        if char == '\n' {
+                            line += 1;
+                        }
+
+                        cell.push(char)
+                    }
+                    None => {
+                        return Err(export_error(format!(
+                            "Unterminated quoted cell at CSV line {line}."
+                        )))
+                    }
+                }
+            },
+            _ if char == delimiter => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+                line += 1;
+            }
+            _ => cell.push(char),
+        }
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Converts CSV, written by `to_csv()`, back to an Array of objects.
+///
+/// Rows with an empty `class` cell are read as `null`. Empty cells of objects are read as `null` instance variables.
+///
+/// Returns an Err, if CSV is malformed, has no `class` column, or its rows have different numbers of cells.
+pub fn from_csv(csv: &str, options: CsvOptions) -> Result<Value, ValueError> {
+    let mut rows = parse_rows(csv, options.delimiter)?.into_iter();
+    let header: Vec<String> = rows.next().unwrap_or_default();
+
+    let class_index: usize = header
+        .iter()
+        .position(|name| name == CLASS_COLUMN)
+        .ok_or_else(|| export_error(format!("CSV has no {CLASS_COLUMN} column.")))?;
+
+    let mut array: Vec<Value> = Vec::new();
+
+    for (index, row) in rows.enumerate() {
+        if row.len() != header.len() {
+            return Err(export_error(format!(
+                "Row {} has {} cells, expected {}.",
+                index + 1,
+                row.len(),
+                header.len()
+            )));
+        }
+
+        if row[class_index].is_empty() {
+            array.push(Value::Null);
+            continue;
+        }
+
+        let mut object: Map<String, Value> = Map::new();
+        object.insert(
+            "__class".to_string(),
+            format!("__symbol__{}", row[class_index]).into(),
+        );
+        object.insert("__type".to_string(), "object".into());
+
+        for (name, cell) in header.iter().zip(row) {
+            if name == CLASS_COLUMN {
+                continue;
+            }
+
+            let value: Value = if options.coerce {
+                infer(&cell)
+            } else if cell.is_empty() {
+                Value::Null
+            } else {
+                cell.into()
+            };
+
+            object.insert(column_key(name), value);
+        }
+
+        array.push(Value::Object(object));
+    }
+
+    Ok(Value::Array(array))
+}
+
+/// Writes each element of an Array as a JSON document on its own line.
+///
+/// Each element is written with a separate call, so `writer` should be buffered, like `BufWriter`.
+///
+/// Returns an Err with `InvalidInput` kind, if the value isn't an Array, or any error of the writer.
+pub fn to_ndjson<W: Write>(mut writer: W, value: &Value) -> io::Result<()> {
+    let array: &Vec<Value> = value.as_array().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Expected an Array to write as NDJSON.",
+        )
+    })?;
+
+    for element in array {
+        serde_json::to_writer(&mut writer, element)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()
+}
+
+/// Iterator over JSON documents of NDJSON, read line by line. Blank lines are skipped.
+#[derive(Debug)]
+pub struct NdjsonReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = io::Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(err) => return Some(Err(err)),
+            }
+
+            let line: &str = self.line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            return Some(serde_json::from_str(line).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid JSON at NDJSON line {}: {err}", self.line_number),
+                )
+            }));
+        }
+    }
+}
+
+/// Reads all JSON documents of NDJSON into an Array. Use `NdjsonReader` to process documents one by one.
+pub fn from_ndjson<R: BufRead>(reader: R) -> io::Result<Value> {
+    NdjsonReader::new(reader)
+        .collect::<io::Result<Vec<Value>>>()
+        .map(Value::Array)
+}
+
+/// Returns the narrowest Arrow type, that holds all non-`null` values of the column.
+#[cfg(feature = "arrow")]
+fn column_type<'v, I: Iterator<Item = &'v Value>>(values: I) -> DataType {
+    let mut data_type: Option<DataType> = None;
+
+    for value in values {
+        let value_type: DataType = match value {
+            Value::Null => continue,
+            Value::Bool(_) => DataType::Boolean,
+            Value::Number(number) if number.is_i64() => DataType::Int64,
+            Value::Number(_) => DataType::Float64,
+            Value::Object(_) if bytes_of(value).is_some() => DataType::Binary,
+            _ => return DataType::Utf8,
+        };
+
+        data_type = Some(match data_type {
+            None => value_type,
+            Some(data_type) if data_type == value_type => data_type,
+            Some(DataType::Int64 | DataType::Float64)
+                if matches!(value_type, DataType::Int64 | DataType::Float64) =>
+            {
+                DataType::Float64
+            }
+            Some(_) => return DataType::Utf8,
+        });
+    }
+
+    data_type.unwrap_or(DataType::Utf8)
+}
+
+/// Converts an Array of objects to an Arrow record batch with inferred schema. See the module documentation for the mapping.
+///
+/// Returns an Err, if the value isn't an Array, or any of its elements isn't an object or `null`.
+#[cfg(feature = "arrow")]
+pub fn to_record_batch(value: &Value) -> Result<RecordBatch, ValueError> {
+    let (rows, columns) = table(value)?;
+
+    let mut fields: Vec<Field> = vec![Field::new(CLASS_COLUMN, DataType::Utf8, true)];
+    let mut arrays: Vec<ArrayRef> = vec![Arc::new(StringArray::from(
+        rows.iter()
+            .map(|row| row["__class"].as_str().map(column_name))
+            .collect::<Vec<Option<&str>>>(),
+    ))];
+
+    for key in columns {
+        let values: Vec<&Value> = rows
+            .iter()
+            .map(|row| row.get(key).unwrap_or(&Value::Null))
+            .collect();
+        let data_type: DataType = column_type(values.iter().copied());
+
+        let array: ArrayRef = match data_type {
+            DataType::Boolean => Arc::new(BooleanArray::from(
+                values
+                    .iter()
+                    .map(|value| value.as_bool())
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Int64 => Arc::new(Int64Array::from(
+                values
+                    .iter()
+                    .map(|value| value.as_i64())
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Float64 => Arc::new(Float64Array::from(
+                values
+                    .iter()
+                    .map(|value| value.as_f64())
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Binary => {
+                let bytes: Vec<Option<Vec<u8>>> =
+                    values.iter().map(|value| bytes_of(value)).collect();
+
+                Arc::new(BinaryArray::from_iter(bytes))
+            }
+            _ => Arc::new(StringArray::from(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Value::Null => None,
+                        _ => Some(cell(value, false)),
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+        };
+
+        fields.push(Field::new(column_name(key), data_type, true));
+        arrays.push(array);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+        .map_err(|err| export_error(err.to_string()))
+}
+
+/// Converts an Array of objects to an Arrow record batch with `to_record_batch()`, and writes it to `writer` as a Parquet file.
+///
+/// Returns an Err, if the value can't be converted, or Parquet writer fails.
+#[cfg(feature = "arrow")]
+pub fn to_parquet<W: Write + Send>(writer: W, value: &Value) -> Result<(), ValueError> {
+    let batch: RecordBatch = to_record_batch(value)?;
+
+    let mut parquet_writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+        .map_err(|err| export_error(err.to_string()))?;
+
+    parquet_writer
+        .write(&batch)
+        .map_err(|err| export_error(err.to_string()))?;
+    parquet_writer
+        .close()
+        .map_err(|err| export_error(err.to_string()))?;
+
+    Ok(())
+}
+
+/// How `to_sqlite()` writes nested Arrays, Hashes and objects.
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedMode {
+    /// Nested values are written as JSON text.
+    Json,
+    /// Objects are written to tables of their classes, and Arrays and Hashes to child tables.
+    ChildTables,
+}
+
+/// Options of `to_sqlite()`.
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqliteOptions {
+    /// How nested values are written. Defaults to `NestedMode::Json`.
+    pub nested: NestedMode,
+    /// Whether existing tables with the same names are dropped. If false, writing to a database, that already has such tables, fails. Defaults to false.
+    pub replace: bool,
+}
+
+#[cfg(feature = "sqlite")]
+impl Default for SqliteOptions {
+    fn default() -> Self {
+        Self {
+            nested: NestedMode::Json,
+            replace: false,
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+struct SqlTable {
+    name: String,
+    /// Table of the parent rows, if it's a child table.
+    parent: Option<String>,
+    columns: Vec<String>,
+    /// Cells of rows, with indices of their columns.
+    rows: Vec<Vec<(usize, SqlValue)>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqlTable {
+    fn set(&mut self, row: usize, column: &str, value: SqlValue) {
+        let index: usize = match self.columns.iter().position(|name| name == column) {
+            Some(index) => index,
+            None => {
+                self.columns.push(column.to_string());
+                self.columns.len() - 1
+            }
+        };
+
+        self.rows[row].push((index, value));
+    }
+
+    /// Returns the type of the column, that matches all of its values, or an empty string if they're mixed.
+    fn column_type(&self, column: usize) -> &'static str {
+        let mut column_type: Option<&'static str> = None;
+
+        for (_, value) in self
+            .rows
+            .iter()
+            .flatten()
+            .filter(|(index, _)| *index == column)
+        {
+            let value_type: &'static str = match value {
+                SqlValue::Null => continue,
+                SqlValue::Integer(_) => "INTEGER",
+                SqlValue::Real(_) => "REAL",
+                SqlValue::Text(_) => "TEXT",
+                SqlValue::Blob(_) => "BLOB",
+            };
+
+            column_type = Some(match column_type {
+                None => value_type,
+                Some(column_type) if column_type == value_type => column_type,
+                Some("INTEGER" | "REAL") if matches!(value_type, "INTEGER" | "REAL") => "REAL",
+                Some(_) => return "",
+            });
+        }
+
+        column_type.unwrap_or("")
+    }
+}
+
+#[cfg(feature = "sqlite")]
+struct SqlTables {
+    tables: Vec<SqlTable>,
+    nested: NestedMode,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqlTables {
+    fn table(&mut self, name: &str, parent: Option<&str>) -> usize {
+        match self.tables.iter().position(|table| table.name == name) {
+            Some(index) => index,
+            None => {
+                self.tables.push(SqlTable {
+                    name: name.to_string(),
+                    parent: parent.map(str::to_string),
+                    columns: Vec::new(),
+                    rows: Vec::new(),
+                });
+                self.tables.len() - 1
+            }
+        }
+    }
+
+    /// Adds an empty row to the table, returning its index and rowid.
+    fn add_row(&mut self, table: usize) -> (usize, i64) {
+        let rows: &mut Vec<Vec<(usize, SqlValue)>> = &mut self.tables[table].rows;
+        rows.push(Vec::new());
+        (rows.len() - 1, rows.len() as i64)
+    }
+
+    /// Writes the object to the table of its class, returning its rowid.
+    fn insert_object(&mut self, object: &Map<String, Value>) -> i64 {
+        let class: &str = object
+            .get("__class")
+            .and_then(Value::as_str)
+            .map_or("Object", column_name);
+        let table: usize = self.table(class, None);
+        let (row, rowid) = self.add_row(table);
+
+        for (key, value) in object {
+            if key == "__class" || key == "__type" {
+                continue;
+            }
+
+            let column: &str = column_name(key);
+
+            if let Some(value) = self.cell(class, column, rowid, value) {
+                self.tables[table].set(row, column, value);
+            }
+        }
+
+        rowid
+    }
+
+    /// Converts the value to a cell of the row. Returns None, if the value is written to a child table instead.
+    fn cell(&mut self, table: &str, column: &str, rowid: i64, value: &Value) -> Option<SqlValue> {
+        if self.nested == NestedMode::ChildTables {
+            if let Value::Object(object) = value {
+                if object.get("__type") == Some(&json!("object")) {
+                    return Some(SqlValue::Integer(self.insert_object(object)));
+                }
+            }
+
+            let entries: Vec<(Value, &Value)> = match value {
+                Value::Array(array) => array
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| (json!(index), entry))
+                    .collect(),
+                Value::Object(object) if is_hash(value) => object
+                    .iter()
+                    .map(|(key, entry)| (key_value(key), entry))
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            if value.is_array() || is_hash(value) {
+                let name: String = format!("{table}.{column}");
+                let child: usize = self.table(&name, Some(table));
+
+                for (key, entry) in entries {
+                    let (row, child_rowid) = self.add_row(child);
+                    let key: SqlValue = sql_value(&key);
+
+                    self.tables[child].set(row, "__parent", SqlValue::Integer(rowid));
+                    self.tables[child].set(row, "__key", key);
+
+                    if let Some(entry) = self.cell(&name, "value", child_rowid, entry) {
+                        self.tables[child].set(row, "value", entry);
+                    }
+                }
+
+                return None;
+            }
+        }
+
+        Some(sql_value(value))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(bool) => SqlValue::Integer(*bool as i64),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => SqlValue::Integer(integer),
+            None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::Object(_) if bytes_of(value).is_some() => {
+            SqlValue::Blob(bytes_of(value).unwrap_or_default())
+        }
+        _ => SqlValue::Text(cell(value, false)),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Writes an object, or an Array of objects, to SQLite database at `path`, creating it if it doesn't exist. See the module documentation for the mapping.
+///
+/// All tables are written in a single transaction, so the database isn't changed, if writing fails.
+///
+/// Returns an Err, if the value isn't an object or an Array of objects and `null`s, or SQLite fails, for example, because the table already exists.
+#[cfg(feature = "sqlite")]
+pub fn to_sqlite<P: AsRef<Path>>(
+    path: P,
+    value: &Value,
+    options: SqliteOptions,
+) -> Result<(), ValueError> {
+    let mut tables: SqlTables = SqlTables {
+        tables: Vec::new(),
+        nested: options.nested,
+    };
+
+    let objects: Vec<&Value> = match value {
+        Value::Array(array) => array.iter().filter(|element| !element.is_null()).collect(),
+        _ => vec![value],
+    };
+
+    for (index, object) in objects.into_iter().enumerate() {
+        match object {
+            Value::Object(object) if object.get("__type") == Some(&json!("object")) => {
+                tables.insert_object(object);
+            }
+            _ => {
+                return Err(export_error(format!(
+                    "Expected an object at index {index}, found {object}."
+                )))
+            }
+        }
+    }
+
+    let sqlite_error = |err: rusqlite::Error| export_error(err.to_string());
+
+    let mut connection: Connection = Connection::open(path).map_err(sqlite_error)?;
+    let transaction = connection.transaction().map_err(sqlite_error)?;
+
+    for table in &tables.tables {
+        let name: String = quote(&table.name);
+
+        if options.replace {
+            transaction
+                .execute(&format!("DROP TABLE IF EXISTS {name}"), [])
+                .map_err(sqlite_error)?;
+        }
+
+        let mut definitions: Vec<String> = vec!["__rowid INTEGER PRIMARY KEY".to_string()];
+
+        for (index, column) in table.columns.iter().enumerate() {
+            let mut definition: String = quote(column);
+
+            match &table.parent {
+                Some(parent) if column == "__parent" => {
+                    definition += &format!(" INTEGER REFERENCES {}(__rowid)", quote(parent));
+                }
+                _ => {
+                    let column_type: &str = table.column_type(index);
+
+                    if !column_type.is_empty() {
+                        definition.push(' ');
+                        definition += column_type;
+                    }
+                }
+            }
+
+            definitions.push(definition);
+        }
+
+        transaction
+            .execute(
+                &format!("CREATE TABLE {name} ({})", definitions.join(", ")),
+                [],
+            )
+            .map_err(sqlite_error)?;
+
+        let columns: Vec<String> = table.columns.iter().map(|column| quote(column)).collect();
+        let mut statement = transaction
+            .prepare(&format!(
+                "INSERT INTO {name} (__rowid{}) VALUES (?{})",
+                columns
+                    .iter()
+                    .map(|column| format!(", {column}"))
+                    .collect::<String>(),
+                ", ?".repeat(columns.len())
+            ))
+            .map_err(sqlite_error)?;
+
+        for (index, cells) in table.rows.iter().enumerate() {
+            let mut row: Vec<SqlValue> = vec![SqlValue::Null; columns.len() + 1];
+            row[0] = SqlValue::Integer(index as i64 + 1);
+
+            for (column, value) in cells {
+                row[column + 1] = value.clone();
+            }
+
+            statement
+                .execute(rusqlite::params_from_iter(row))
+                .map_err(sqlite_error)?;
+        }
+    }
+
+    transaction.commit().map_err(sqlite_error)
+}
@@ -0,0 +1,574 @@
+//! jq-like filter expressions over loaded JSON values.
+//!
+//! Supported syntax:
+//! * `.` - the input itself.
+//! * `.name`, `.@name`, `.["name"]` - value of the key. If the key doesn't exist, it's looked up with `__symbol__` prefix, so instance variables and symbol keys can be written without it.
+//! * `.[0]`, `.[-1]` - element of the array.
+//! * `.[]` - all elements of the array, or values of the Hash or object, except metadata.
+//! * `..` - the input and all of its nested values.
+//! * `a | b` - passes each output of `a` to `b`.
+//! * `==`, `!=`, `<`, `<=`, `>`, `>=`, `and`, `or` - comparisons and logical operators.
+//! * `"string"`, `1`, `true`, `false`, `null` - literals.
+//! * `select(condition)`, `not`, `length`, `keys`, `class` - built-in functions. `class` returns the Ruby class name of the input.
+//!
+//! Unlike jq, looking up keys and indices, which don't apply to the input, produces `null` instead of an error.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{
+    inspect::ruby_class,
+    value::{is_leaf_object, ValueError, METADATA_KEYS},
+};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    DotDot,
+    /// `.name`
+    Field(String),
+    Ident(String),
+    Literal(Value),
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    Pipe,
+    Compare(CompareOp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Builtin {
+    Not,
+    Length,
+    Keys,
+    Class,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Select(Box<Expr>),
+    Builtin(Builtin),
+}
+
+fn is_ident_char(char: char) -> bool {
+    char.is_alphanumeric() || matches!(char, '_' | '@' | '$')
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, ValueError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    let error = |position: usize, message: &str| ValueError {
+        message: format!("{message} at position {position} of filter"),
+    };
+
+    while let Some((position, char)) = chars.next() {
+        let token: Token = match char {
+            _ if char.is_whitespace() => continue,
+            '.' => match chars.peek() {
+                Some((_, '.')) => {
+                    chars.next();
+                    Token::DotDot
+                }
+                Some(&(_, next)) if is_ident_char(next) => {
+                    let mut name: String = String::new();
+
+                    while let Some(&(_, next)) = chars.peek() {
+                        if !is_ident_char(next) {
+                            break;
+                        }
+
+                        name.push(next);
+                        chars.next();
+                    }
+
+                    Token::Field(name)
+                }
+                _ => Token::Dot,
+            },
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '|' => Token::Pipe,
+            '=' | '!' | '<' | '>' => {
+                let equals: bool = chars.peek().map_or(false, |(_, next)| *next == '=');
+
+                if equals {
+                    chars.next();
+                }
+
+                Token::Compare(match (char, equals) {
+                    ('=', true) => CompareOp::Equal,
+                    ('!', true) => CompareOp::NotEqual,
+                    ('<', false) => CompareOp::Less,
+                    ('<', true) => CompareOp::LessEqual,
+                    ('>', false) => CompareOp::Greater,
+                    ('>', true) => CompareOp::GreaterEqual,
+                    _ => return Err(error(position, "Unknown operator")),
+                })
+            }
+            '"' => {
+                let mut end: Option<usize> = None;
+                let mut escaped: bool = false;
+
+                for (index, next) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == '"' {
+                        end = Some(index);
+                        break;
+                    }
+                }
+
+                let end: usize = end.ok_or_else(|| error(position, "Unterminated string"))?;
+
+                Token::Literal(
+                    serde_json::from_str(&expression[position..=end])
+                        .map_err(|_| error(position, "Malformed string"))?,
+                )
+            }
+            _ if char == '-' || char.is_ascii_digit() => {
+                let mut end: usize = position + 1;
+
+                while let Some(&(index, next)) = chars.peek() {
+                    if !(next.is_ascii_digit() || matches!(next, '.' | 'e' | 'E' | '+' | '-')) {
+                        break;
+                    }
+
+                    end = index + 1;
+                    chars.next();
+                }
+
+                Token::Literal(
+                    serde_json::from_str::<serde_json::Number>(&expression[position..end])
+                        .map_err(|_| error(position, "Malformed number"))?
+                        .into(),
+                )
+            }
+            _ if is_ident_char(char) => {
+                let mut name: String = char.to_string();
+
+                while let Some(&(_, next)) = chars.peek() {
+                    if !is_ident_char(next) {
+                        break;
+                    }
+
+                    name.push(next);
+                    chars.next();
+                }
+
+                match name.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Ident(name),
+                }
+            }
+            _ => return Err(error(position, &format!("Unexpected character {char:?}"))),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token: Option<Token> = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ValueError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            token => Err(ValueError {
+                message: format!("Expected {expected:?} in filter, found {token:?}"),
+            }),
+        }
+    }
+
+    fn parse_pipe(&mut self) -> Result<Expr, ValueError> {
+        let mut expr: Expr = self.parse_or()?;
+
+        while self.peek() == Some(&Token::Pipe) {
+            self.next();
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.parse_or()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ValueError> {
+        let mut expr: Expr = self.parse_and()?;
+
+        while self.peek() == Some(&Token::Ident("or".to_string())) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ValueError> {
+        let mut expr: Expr = self.parse_compare()?;
+
+        while self.peek() == Some(&Token::Ident("and".to_string())) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_compare()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_compare(&mut self) -> Result<Expr, ValueError> {
+        let expr: Expr = self.parse_postfix()?;
+
+        if let Some(&Token::Compare(op)) = self.peek() {
+            self.next();
+            return Ok(Expr::Compare(
+                Box::new(expr),
+                op,
+                Box::new(self.parse_postfix()?),
+            ));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ValueError> {
+        let mut expr: Expr = match self.next() {
+            Some(Token::Dot) => Expr::Identity,
+            Some(Token::DotDot) => Expr::Recurse,
+            Some(Token::Field(name)) => Expr::Field(Box::new(Expr::Identity), name),
+            Some(Token::Literal(value)) => Expr::Literal(value),
+            Some(Token::LeftParen) => {
+                let expr: Expr = self.parse_pipe()?;
+                self.expect(Token::RightParen)?;
+                expr
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "select" => {
+                    self.expect(Token::LeftParen)?;
+                    let condition: Expr = self.parse_pipe()?;
+                    self.expect(Token::RightParen)?;
+                    Expr::Select(Box::new(condition))
+                }
+                "not" => Expr::Builtin(Builtin::Not),
+                "length" => Expr::Builtin(Builtin::Length),
+                "keys" => Expr::Builtin(Builtin::Keys),
+                "class" => Expr::Builtin(Builtin::Class),
+                _ => {
+                    return Err(ValueError {
+                        message: format!("Unknown function {name} in filter"),
+                    })
+                }
+            },
+            token => {
+                return Err(ValueError {
+                    message: format!("Unexpected {token:?} in filter"),
+                })
+            }
+        };
+
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let name: String = name.clone();
+                    self.next();
+                    expr = Expr::Field(Box::new(expr), name);
+                }
+                // `.a.[0]` is the same as `.a[0]`
+                Some(Token::Dot)
+                    if self.tokens.get(self.position + 1) == Some(&Token::LeftBracket) =>
+                {
+                    self.next();
+                }
+                Some(Token::LeftBracket) => {
+                    self.next();
+
+                    if self.peek() == Some(&Token::RightBracket) {
+                        self.next();
+                        expr = Expr::Iterate(Box::new(expr));
+                    } else {
+                        let index: Expr = self.parse_pipe()?;
+                        self.expect(Token::RightBracket)?;
+                        expr = Expr::Index(Box::new(expr), Box::new(index));
+                    }
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn lookup(value: &Value, key: &Value) -> Value {
+    match (value, key) {
+        (Value::Object(object), Value::String(key)) => object
+            .get(key)
+            .or_else(|| object.get(&format!("__symbol__{key}")))
+            .cloned()
+            .unwrap_or(Value::Null),
+        (Value::Array(array), Value::Number(index)) => {
+            let index: Option<usize> = index.as_i64().and_then(|index| {
+                if index < 0 {
+                    array.len().checked_sub(index.unsigned_abs() as usize)
+                } else {
+                    Some(index as usize)
+                }
+            });
+
+            index
+                .and_then(|index| array.get(index))
+                .cloned()
+                .unwrap_or(Value::Null)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn entries(value: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    value
+        .as_object()
+        .filter(|_| !is_leaf_object(value))
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !METADATA_KEYS.contains(&key.as_str()))
+}
+
+fn recurse(value: &Value, output: &mut Vec<Value>) {
+    output.push(value.clone());
+
+    match value {
+        Value::Array(array) => array.iter().for_each(|element| recurse(element, output)),
+        Value::Object(_) => entries(value).for_each(|(_, entry)| recurse(entry, output)),
+        _ => {}
+    }
+}
+
+fn compare(a: &Value, op: CompareOp, b: &Value) -> Result<bool, ValueError> {
+    let ordering: Option<Ordering> = match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    Ok(match op {
+        CompareOp::Equal => a == b || ordering == Some(Ordering::Equal),
+        CompareOp::NotEqual => a != b && ordering != Some(Ordering::Equal),
+        _ => {
+            let ordering: Ordering = ordering.ok_or_else(|| ValueError {
+                message: format!("Cannot compare {} with {}", ruby_class(a), ruby_class(b)),
+            })?;
+
+            match op {
+                CompareOp::Less => ordering.is_lt(),
+                CompareOp::LessEqual => ordering.is_le(),
+                CompareOp::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }
+        }
+    })
+}
+
+fn evaluate(expr: &Expr, input: &Value) -> Result<Vec<Value>, ValueError> {
+    let mut output: Vec<Value> = Vec::new();
+
+    match expr {
+        Expr::Identity => output.push(input.clone()),
+        Expr::Recurse => recurse(input, &mut output),
+        Expr::Literal(value) => output.push(value.clone()),
+        Expr::Field(base, name) => {
+            let key: Value = Value::String(name.clone());
+
+            for value in evaluate(base, input)? {
+                output.push(lookup(&value, &key));
+            }
+        }
+        Expr::Index(base, index) => {
+            let keys: Vec<Value> = evaluate(index, input)?;
+
+            for value in evaluate(base, input)? {
+                for key in &keys {
+                    output.push(lookup(&value, key));
+                }
+            }
+        }
+        Expr::Iterate(base) => {
+            for value in evaluate(base, input)? {
+                match &value {
+                    Value::Array(array) => output.extend(array.iter().cloned()),
+                    Value::Object(_) if !is_leaf_object(&value) => {
+                        output.extend(entries(&value).map(|(_, entry)| entry.clone()))
+                    }
+                    _ => {
+                        return Err(ValueError {
+                            message: format!("Cannot iterate over {}", ruby_class(&value)),
+                        })
+                    }
+                }
+            }
+        }
+        Expr::Pipe(left, right) => {
+            for value in evaluate(left, input)? {
+                output.extend(evaluate(right, &value)?);
+            }
+        }
+        Expr::Compare(left, op, right) => {
+            let rights: Vec<Value> = evaluate(right, input)?;
+
+            for left in evaluate(left, input)? {
+                for right in &rights {
+                    output.push(Value::Bool(compare(&left, *op, right)?));
+                }
+            }
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let is_and: bool = matches!(expr, Expr::And(..));
+
+            for left in evaluate(left, input)? {
+                // Right side is only evaluated when the left one doesn't decide the result
+                if is_truthy(&left) != is_and {
+                    output.push(Value::Bool(!is_and));
+                    continue;
+                }
+
+                for right in evaluate(right, input)? {
+                    output.push(Value::Bool(is_truthy(&right)));
+                }
+            }
+        }
+        Expr::Select(condition) => {
+            if evaluate(condition, input)?.iter().any(is_truthy) {
+                output.push(input.clone());
+            }
+        }
+        Expr::Builtin(builtin) => output.push(match builtin {
+            Builtin::Not => Value::Bool(!is_truthy(input)),
+            Builtin::Length => match input {
+                Value::Null => 0.into(),
+                Value::String(string) => string.chars().count().into(),
+                Value::Array(array) => array.len().into(),
+                Value::Object(_) if !is_leaf_object(input) => entries(input).count().into(),
+                _ => {
+                    return Err(ValueError {
+                        message: format!("{} has no length", ruby_class(input)),
+                    })
+                }
+            },
+            Builtin::Keys => match input {
+                Value::Object(_) if !is_leaf_object(input) => Value::Array(
+                    entries(input)
+                        .map(|(key, _)| Value::String(key.clone()))
+                        .collect(),
+                ),
+                Value::Array(array) => (0..array.len()).collect::<Vec<usize>>().into(),
+                _ => {
+                    return Err(ValueError {
+                        message: format!("{} has no keys", ruby_class(input)),
+                    })
+                }
+            },
+            Builtin::Class => Value::String(ruby_class(input)),
+        }),
+    }
+
+    Ok(output)
+}
+
+/// Parsed filter expression, which can be evaluated against many values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses the filter expression.
+    ///
+    /// Returns an Err when the expression is malformed.
+    pub fn parse(expression: &str) -> Result<Self, ValueError> {
+        let mut parser: Parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+        };
+
+        let expr: Expr = parser.parse_pipe()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(ValueError {
+                message: format!("Unexpected {token:?} in filter"),
+            });
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Evaluates the filter against the Value, returning all of its outputs.
+    pub fn eval(&self, value: &Value) -> Result<Vec<Value>, ValueError> {
+        evaluate(&self.expr, value)
+    }
+}
+
+/// Parses the filter expression and evaluates it against the Value, returning all of its outputs.
+///
+/// Returns an Err when the expression is malformed, or can't be evaluated against the Value.
+/// # Example
+/// ```rust
+/// use marshal_rs::filter::eval;
+/// use serde_json::json;
+///
+/// let map = json!({
+///     "__class": "__symbol__RPG::Map",
+///     "__type": "object",
+///     "__symbol__@events": [
+///         { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Guard", "__symbol__@x": 1 },
+///         { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss", "__symbol__@x": 7 }
+///     ]
+/// });
+///
+/// let bosses = eval(".@events[] | select(.@name == \"Boss\") | .@x", &map).unwrap();
+///
+/// assert_eq!(bosses, [json!(7)]);
+/// ```
+pub fn eval(expression: &str, value: &Value) -> Result<Vec<Value>, ValueError> {
+    Filter::parse(expression)?.eval(value)
+}
@@ -0,0 +1,215 @@
+//! Recovery of Marshal documents from arbitrary binary data, like memory dumps and corrupted archives, and of text from documents, that can't be loaded.
+//! # Example
+//! ```rust
+//! use marshal_rs::{dump, forensics::carve};
+//! use serde_json::json;
+//!
+//! let mut blob: Vec<u8> = b"garbage".to_vec();
+//! blob.extend(dump(json!([1, "two"]), None));
+//! blob.extend(b"\x04\x08\xff");
+//!
+//! let documents = carve(&blob);
+//!
+//! assert_eq!(documents.len(), 2);
+//! assert_eq!(documents[0].0, 7);
+//! assert_eq!(documents[0].1.as_ref().unwrap(), &json!([1, "two"]));
+//! assert!(documents[1].1.is_err());
+//! ```
+
+use crate::{
+    load::{LoadError, HARDENED_MAX_DEPTH, HARDENED_MAX_LENGTH, HARDENED_MEMORY_BUDGET},
+    raw::{Constants, Reader, VERSION_HEADER},
+    Loader,
+};
+#[cfg(feature = "encodings")]
+use encoding_rs::Encoding;
+#[cfg(not(feature = "sonic"))]
+use serde_json::Value;
+#[cfg(feature = "sonic")]
+use sonic_rs::Value;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Parses the document at the start of the buffer with limits of `Loader::hardened()`, but accepting any classes and data after the document.
+fn parse(buffer: &[u8]) -> Result<(Value, usize), LoadError> {
+    let mut loader: Loader = Loader::builder()
+        .max_depth(HARDENED_MAX_DEPTH)
+        .max_length(HARDENED_MAX_LENGTH)
+        .memory_budget(HARDENED_MEMORY_BUDGET)
+        .build();
+
+    catch_unwind(AssertUnwindSafe(|| {
+        loader.load_with_length(buffer, None, None)
+    }))
+    .unwrap_or_else(|_| {
+        Err(LoadError {
+            message: "Marshal data is malformed.".to_string(),
+            ..Default::default()
+        })
+    })
+}
+
+/// Finds Marshal documents in the buffer by their version header, and loads each of them with limits of `Loader::hardened()`.
+///
+/// Returns offsets of all found headers with results of loading them, in order. Scanning continues after the end of each loaded document, and after the header of each failed one.
+/// Panics of the Loader on malformed data are caught and returned as Errs, though the panic hook still reports them.
+pub fn carve(buffer: &[u8]) -> Vec<(usize, Result<Value, LoadError>)> {
+    let mut documents: Vec<(usize, Result<Value, LoadError>)> = Vec::new();
+    let mut offset: usize = 0;
+
+    while let Some(position) = buffer
+        .get(offset..)
+        .and_then(|rest| rest.windows(2).position(|window| window == VERSION_HEADER))
+    {
+        let start: usize = offset + position;
+
+        match parse(&buffer[start..]) {
+            Ok((value, length)) => {
+                documents.push((start, Ok(value)));
+                offset = start + length;
+            }
+            Err(err) => {
+                documents.push((start, Err(err)));
+                offset = start + VERSION_HEADER.len();
+            }
+        }
+    }
+
+    documents
+}
+
+/// String or symbol, found by `extract_strings()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedString {
+    /// Position of the type tag of the string or symbol.
+    pub offset: usize,
+    pub symbol: bool,
+    pub text: String,
+    /// Name of the encoding, that the text was decoded from.
+    pub encoding: String,
+    /// Whether the encoding was guessed, because the string or symbol isn't followed by an encoding instance variable.
+    pub guessed: bool,
+}
+
+/// Reads the single encoding instance variable, that Ruby writes after contents of strings and symbols. Returns the name of the encoding, and the position of the end of the instance variable.
+///
+/// Symbol links can't be resolved without the preceding structure, so linked names are assumed to be `E` or `encoding` by the type of the value.
+fn read_encoding(reader: &mut Reader) -> Option<(String, usize)> {
+    if reader.read_int().ok()? != 1 {
+        return None;
+    }
+
+    let name: Option<&[u8]> = match Constants::try_from(reader.read_byte().ok()?).ok()? {
+        Constants::Symbol => Some(reader.read_chunk().ok()?),
+        Constants::Symlink => {
+            reader.read_int().ok()?;
+            None
+        }
+        _ => return None,
+    };
+
+    let encoding: String = match (name, Constants::try_from(reader.read_byte().ok()?).ok()?) {
+        (None | Some(b"E"), Constants::True) => "UTF-8".to_string(),
+        (None | Some(b"E"), Constants::False) => "US-ASCII".to_string(),
+        (None | Some(b"encoding"), Constants::String) => {
+            String::from_utf8(reader.read_chunk().ok()?.to_vec()).ok()?
+        }
+        _ => return None,
+    };
+
+    Some((encoding, reader.position()))
+}
+
+/// Decodes the bytes without replacement of invalid sequences. Returns None, if the encoding is unknown, or the bytes are invalid in it.
+fn decode(bytes: &[u8], encoding: &str) -> Option<String> {
+    match encoding {
+        "UTF-8" | "US-ASCII" | "ASCII-8BIT" | "BINARY" => {
+            std::str::from_utf8(bytes).ok().map(str::to_string)
+        }
+        #[cfg(feature = "encodings")]
+        _ => Some(
+            Encoding::for_label(encoding.as_bytes())?
+                .decode_without_bom_handling_and_without_replacement(bytes)?
+                .into_owned(),
+        ),
+        #[cfg(not(feature = "encodings"))]
+        _ => None,
+    }
+}
+
+/// Reads the string or symbol at the offset. Returns it with the position of its end, including its encoding instance variable.
+fn read_string(buffer: &[u8], offset: usize) -> Option<(ExtractedString, usize)> {
+    let mut reader: Reader = Reader::new(buffer);
+    reader.set_position(offset);
+
+    let symbol: bool = match Constants::try_from(reader.read_byte().ok()?).ok()? {
+        Constants::String => false,
+        Constants::Symbol => true,
+        _ => return None,
+    };
+
+    let bytes: &[u8] = reader.read_chunk().ok().filter(|bytes| !bytes.is_empty())?;
+    let contents_end: usize = reader.position();
+
+    let (encoding, end, guessed) = match read_encoding(&mut reader) {
+        Some((encoding, end)) => (encoding, end, false),
+        None => ("UTF-8".to_string(), contents_end, true),
+    };
+
+    let text: String = decode(bytes, &encoding)?;
+
+    if text
+        .chars()
+        .any(|char| char.is_control() && !matches!(char, '\t' | '\n' | '\r'))
+    {
+        return None;
+    }
+
+    Some((
+        ExtractedString {
+            offset,
+            symbol,
+            text,
+            encoding,
+            guessed,
+        },
+        end,
+    ))
+}
+
+/// Extracts all strings and symbols, that can be decoded into text without control characters, from the buffer, even if it's not a valid Marshal document.
+///
+/// Encodings are read from the instance variables, that follow strings and symbols. Those without them are decoded as UTF-8, with `guessed` set.
+/// Strings and symbols, whose texts are shorter than `min_length` characters, are skipped, as short ones are often found in random bytes.
+/// # Example
+/// ```rust
+/// use marshal_rs::{dump, forensics::extract_strings};
+/// use serde_json::json;
+///
+/// let mut bytes: Vec<u8> = dump(json!({ "__symbol__name": "Sword" }), None);
+/// // Corrupt the length of the Hash
+/// bytes[3] = 0xff;
+///
+/// let strings = extract_strings(&bytes, 2);
+/// let texts: Vec<&str> = strings.iter().map(|string| string.text.as_str()).collect();
+///
+/// assert_eq!(texts, ["name", "Sword"]);
+/// assert!(strings[0].symbol);
+/// assert_eq!(strings[1].encoding, "UTF-8");
+/// assert!(!strings[1].guessed);
+/// ```
+pub fn extract_strings(buffer: &[u8], min_length: usize) -> Vec<ExtractedString> {
+    let mut strings: Vec<ExtractedString> = Vec::new();
+    let mut position: usize = 0;
+
+    while position < buffer.len() {
+        match read_string(buffer, position) {
+            Some((string, end)) if string.text.chars().count() >= min_length => {
+                strings.push(string);
+                position = end;
+            }
+            _ => position += 1,
+        }
+    }
+
+    strings
+}
@@ -0,0 +1,165 @@
+//! Generation of arbitrary values for fuzzing.
+//!
+//! `arbitrary::Arbitrary` can't be implemented for `serde_json::Value` outside of `serde_json`, so generated values are wrapped in `ArbitraryValue`.
+//!
+//! Requires `arbitrary` feature. Not available with `sonic` feature enabled.
+
+use crate::value::to_symbol;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use serde_json::{json, Map, Value};
+
+/// Maximum nesting depth of generated values.
+const MAX_DEPTH: usize = 4;
+/// Maximum number of elements of generated arrays, hashes and objects.
+const MAX_LENGTH: usize = 8;
+
+/// A Value, that can be passed to `dump()`, generated from unstructured fuzzer input.
+///
+/// Generated values honor the invariants of `dump()`:
+/// * Integers fit into Marshal fixnums.
+/// * Floats are finite.
+/// * Strings don't start with `__`, so they aren't mistaken for symbols or prefixed keys.
+/// * Hash keys are strings, symbols or non-negative 16-bit integers.
+/// * Objects and structs have non-empty class names, and instance variables' names start with `@`.
+///
+/// Bignums, classes, modules and user-defined objects are not generated.
+/// # Example
+/// ```rust
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use marshal_rs::{dump, fuzz::ArbitraryValue, load};
+///
+/// let mut unstructured = Unstructured::new(b"some fuzzer input");
+/// let ArbitraryValue(value) = ArbitraryValue::arbitrary(&mut unstructured).unwrap();
+///
+/// assert_eq!(load(&dump(value.clone(), None), None, None).unwrap(), value);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitraryValue(pub Value);
+
+impl<'a> Arbitrary<'a> for ArbitraryValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, 0).map(ArbitraryValue)
+    }
+}
+
+impl From<ArbitraryValue> for Value {
+    fn from(value: ArbitraryValue) -> Self {
+        value.0
+    }
+}
+
+/// Generates a name, consisting of the passed first character and ASCII alphanumerics.
+fn arbitrary_name(u: &mut Unstructured, first: char) -> Result<String> {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+
+    let mut name: String = first.to_string();
+
+    for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+        name.push(*u.choose(CHARS)? as char);
+    }
+
+    Ok(name)
+}
+
+fn arbitrary_class(u: &mut Unstructured) -> Result<String> {
+    let first: u8 = u.int_in_range(b'A'..=b'Z')?;
+    arbitrary_name(u, first as char)
+}
+
+fn arbitrary_string(u: &mut Unstructured) -> Result<String> {
+    let string: String = u.arbitrary()?;
+
+    Ok(if string.starts_with("__") {
+        string.trim_start_matches('_').to_string()
+    } else {
+        string
+    })
+}
+
+fn arbitrary_float(u: &mut Unstructured) -> Result<f64> {
+    let float: f64 = u.arbitrary()?;
+    Ok(if float.is_finite() { float } else { 0.0 })
+}
+
+fn arbitrary_ivars(u: &mut Unstructured, depth: usize) -> Result<Map<String, Value>> {
+    let mut ivars: Map<String, Value> = Map::new();
+
+    for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+        let name: String = arbitrary_name(u, '@')?;
+        ivars.insert(to_symbol(&name), arbitrary_value(u, depth + 1)?);
+    }
+
+    Ok(ivars)
+}
+
+fn arbitrary_value(u: &mut Unstructured, depth: usize) -> Result<Value> {
+    // Only scalars are generated past the maximum depth
+    let kinds: u8 = if depth < MAX_DEPTH { 11 } else { 6 };
+
+    Ok(match u.int_in_range(0..=kinds - 1)? {
+        0 => Value::Null,
+        1 => Value::Bool(u.arbitrary()?),
+        2 => json!(u.int_in_range(-(1i32 << 30)..=(1 << 30) - 1)?),
+        3 => json!(arbitrary_float(u)?),
+        4 => Value::String(arbitrary_string(u)?),
+        5 => Value::String(to_symbol(&arbitrary_name(u, 'a')?)),
+        6 => {
+            let mut array: Vec<Value> = Vec::new();
+
+            for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+                array.push(arbitrary_value(u, depth + 1)?);
+            }
+
+            Value::Array(array)
+        }
+        7 => {
+            let mut hash: Map<String, Value> = Map::new();
+
+            for _ in 0..u.int_in_range(0..=MAX_LENGTH)? {
+                let key: String = match u.int_in_range(0..=2)? {
+                    0 => arbitrary_string(u)?,
+                    1 => to_symbol(&arbitrary_name(u, 'a')?),
+                    _ => format!("__integer__{}", u.arbitrary::<u16>()?),
+                };
+
+                hash.insert(key, arbitrary_value(u, depth + 1)?);
+            }
+
+            Value::Object(hash)
+        }
+        8 => {
+            let mut object: Map<String, Value> = Map::new();
+            object.insert(
+                "__class".to_string(),
+                to_symbol(&arbitrary_class(u)?).into(),
+            );
+            object.insert("__type".to_string(), "object".into());
+            object.extend(arbitrary_ivars(u, depth)?);
+            Value::Object(object)
+        }
+        9 => json!({
+            "__class": to_symbol(&arbitrary_class(u)?),
+            "__type": "struct",
+            "__members": arbitrary_ivars(u, depth)?,
+        }),
+        _ => {
+            if u.arbitrary()? {
+                json!({ "__type": "bytes", "data": u.arbitrary::<Vec<u8>>()? })
+            } else {
+                let mut flags: String = String::new();
+
+                for flag in ['i', 'x', 'm'] {
+                    if u.arbitrary()? {
+                        flags.push(flag);
+                    }
+                }
+
+                json!({
+                    "__type": "regexp",
+                    "expression": arbitrary_string(u)?,
+                    "flags": flags,
+                })
+            }
+        }
+    })
+}
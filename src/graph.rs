@@ -0,0 +1,356 @@
+//! Utilities for analyzing object links of Marshal byte streams.
+//!
+//! `load()` resolves object links by copying linked values, so the link structure is only visible in the byte stream itself.
+//! This module walks the byte stream and builds a graph of the objects it contains, along with the links between them.
+//!
+//! Requires `graph` feature.
+
+use crate::{
+    load::LoadError,
+    raw::{Constants, Reader},
+};
+use petgraph::{
+    dot::{Config, Dot},
+    graph::{DiGraph, NodeIndex},
+    Direction,
+};
+
+/// An entry of the Marshal object table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    /// Index of the object in the object table, by which links reference it.
+    pub index: usize,
+    /// Kind of the object, for example `Array` or `Object`.
+    pub kind: &'static str,
+    /// Class of the object, if it has one.
+    pub class: Option<String>,
+    /// Position of the object's type byte in the byte stream.
+    pub offset: usize,
+}
+
+impl std::fmt::Display for GraphNode {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "#{} {}", self.index, self.kind)?;
+
+        if let Some(class) = &self.class {
+            write!(formatter, " {class}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Relation between two objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphEdge {
+    /// The object is written inside of the parent.
+    Contains,
+    /// The parent references the object, written earlier, with a link.
+    Link,
+}
+
+impl std::fmt::Display for GraphEdge {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GraphEdge::Contains => write!(formatter, "contains"),
+            GraphEdge::Link => write!(formatter, "link"),
+        }
+    }
+}
+
+/// Graph of objects of a Marshal byte stream.
+///
+/// Node indices of the graph match objects' indices in the object table.
+pub struct ObjectGraph {
+    pub graph: DiGraph<GraphNode, GraphEdge>,
+    cycles: Vec<(usize, usize)>,
+}
+
+impl ObjectGraph {
+    /// Returns indices of the objects, that are referenced by at least one link.
+    pub fn shared(&self) -> Vec<usize> {
+        self.graph
+            .node_indices()
+            .filter(|&node| {
+                self.graph
+                    .edges_directed(node, Direction::Incoming)
+                    .any(|edge| *edge.weight() == GraphEdge::Link)
+            })
+            .map(|node| node.index())
+            .collect()
+    }
+
+    /// Returns links, which point to an object from inside of that object, as pairs of parent's and referenced object's indices.
+    pub fn cycles(&self) -> &[(usize, usize)] {
+        &self.cycles
+    }
+
+    pub fn has_cycles(&self) -> bool {
+        !self.cycles.is_empty()
+    }
+
+    /// Exports the graph in Graphviz DOT format. Links are drawn with dashed lines.
+    pub fn to_dot(&self) -> String {
+        format!(
+            "{}",
+            Dot::with_attr_getters(
+                &self.graph,
+                &[Config::EdgeNoLabel],
+                &|_, edge| {
+                    if *edge.weight() == GraphEdge::Link {
+                        "style = dashed".to_string()
+                    } else {
+                        String::new()
+                    }
+                },
+                &|_, _| String::new(),
+            )
+        )
+    }
+}
+
+struct GraphBuilder<'a> {
+    reader: Reader<'a>,
+    symbols: Vec<String>,
+    graph: DiGraph<GraphNode, GraphEdge>,
+    parents: Vec<NodeIndex>,
+    cycles: Vec<(usize, usize)>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    fn read_symbol(&mut self) -> Result<Option<String>, LoadError> {
+        let structure_type: u8 = self.reader.read_byte()?;
+
+        if structure_type == Constants::Symbol {
+            let symbol: String = String::from_utf8_lossy(self.reader.read_chunk()?).to_string();
+            self.symbols.push(symbol.clone());
+            Ok(Some(symbol))
+        } else if structure_type == Constants::Symlink {
+            let pos: i32 = self.reader.read_int()?;
+            Ok(self.symbols.get(pos as usize).cloned())
+        } else {
+            self.reader.set_position(self.reader.position() - 1);
+            self.read_next()?;
+            Ok(None)
+        }
+    }
+
+    fn add_node(&mut self, kind: &'static str, class: Option<String>, offset: usize) -> NodeIndex {
+        let node: NodeIndex = self.graph.add_node(GraphNode {
+            index: self.graph.node_count(),
+            kind,
+            class,
+            offset,
+        });
+
+        if let Some(&parent) = self.parents.last() {
+            self.graph.add_edge(parent, node, GraphEdge::Contains);
+        }
+
+        node
+    }
+
+    fn read_children(&mut self, node: NodeIndex, amount: usize) -> Result<(), LoadError> {
+        self.parents.push(node);
+
+        for _ in 0..amount {
+            self.read_next()?;
+        }
+
+        self.parents.pop();
+        Ok(())
+    }
+
+    fn read_next(&mut self) -> Result<Option<NodeIndex>, LoadError> {
+        let offset: usize = self.reader.position();
+        let structure_type: Constants = self.reader.read_type()?;
+
+        Ok(match structure_type {
+            Constants::Nil | Constants::True | Constants::False => None,
+            Constants::Fixnum => {
+                self.reader.read_int()?;
+                None
+            }
+            Constants::Symbol | Constants::Symlink => {
+                self.reader.set_position(offset);
+                self.read_symbol()?;
+                None
+            }
+            Constants::Link => {
+                let pos: usize = self.reader.read_int()? as usize;
+
+                if pos >= self.graph.node_count() {
+                    return Err(LoadError {
+                        message: format!("Link to unknown object {pos} at position {offset}."),
+                        ..Default::default()
+                    });
+                }
+
+                let target: NodeIndex = NodeIndex::new(pos);
+
+                if let Some(&parent) = self.parents.last() {
+                    self.graph.add_edge(parent, target, GraphEdge::Link);
+
+                    if self.parents.contains(&target) {
+                        self.cycles.push((parent.index(), pos));
+                    }
+                }
+
+                Some(target)
+            }
+            Constants::InstanceVar => {
+                let node: Option<NodeIndex> = self.read_next()?;
+                let size: usize = self.reader.read_length()?;
+
+                if let Some(node) = node {
+                    self.parents.push(node);
+                }
+
+                for _ in 0..size {
+                    self.read_symbol()?;
+                    self.read_next()?;
+                }
+
+                if node.is_some() {
+                    self.parents.pop();
+                }
+
+                node
+            }
+            Constants::Extended => {
+                self.read_symbol()?;
+                self.read_next()?
+            }
+            Constants::UserClass => {
+                self.read_symbol()?;
+                self.read_next()?
+            }
+            Constants::Array => {
+                let node: NodeIndex = self.add_node("Array", None, offset);
+                let size: usize = self.reader.read_length()?;
+                self.read_children(node, size)?;
+                Some(node)
+            }
+            Constants::Hash | Constants::HashDefault => {
+                let node: NodeIndex = self.add_node("Hash", None, offset);
+                let size: usize = self.reader.read_length()?;
+
+                self.read_children(
+                    node,
+                    size * 2 + (structure_type == Constants::HashDefault) as usize,
+                )?;
+                Some(node)
+            }
+            Constants::Object | Constants::Struct => {
+                let class: Option<String> = self.read_symbol()?;
+                let node: NodeIndex = self.add_node(
+                    if structure_type == Constants::Object {
+                        "Object"
+                    } else {
+                        "Struct"
+                    },
+                    class,
+                    offset,
+                );
+                let size: usize = self.reader.read_length()?;
+
+                self.parents.push(node);
+
+                for _ in 0..size {
+                    self.read_symbol()?;
+                    self.read_next()?;
+                }
+
+                self.parents.pop();
+                Some(node)
+            }
+            Constants::Data | Constants::UserMarshal => {
+                let class: Option<String> = self.read_symbol()?;
+                let node: NodeIndex = self.add_node(
+                    if structure_type == Constants::Data {
+                        "Data"
+                    } else {
+                        "UserMarshal"
+                    },
+                    class,
+                    offset,
+                );
+                self.read_children(node, 1)?;
+                Some(node)
+            }
+            Constants::UserDefined => {
+                let class: Option<String> = self.read_symbol()?;
+                self.reader.read_chunk()?;
+                Some(self.add_node("UserDefined", class, offset))
+            }
+            Constants::Bignum => {
+                self.reader.read_byte()?;
+                let length: usize = self.reader.read_length()?;
+                self.reader.read_bytes(length * 2)?;
+                Some(self.add_node("Bignum", None, offset))
+            }
+            Constants::Class | Constants::Module | Constants::ModuleOld => {
+                let name: String = String::from_utf8_lossy(self.reader.read_chunk()?).to_string();
+                Some(self.add_node(
+                    if structure_type == Constants::Class {
+                        "Class"
+                    } else {
+                        "Module"
+                    },
+                    Some(name),
+                    offset,
+                ))
+            }
+            Constants::Float => {
+                self.reader.read_chunk()?;
+                Some(self.add_node("Float", None, offset))
+            }
+            Constants::String => {
+                self.reader.read_chunk()?;
+                Some(self.add_node("String", None, offset))
+            }
+            Constants::Regexp => {
+                self.reader.read_chunk()?;
+                self.reader.read_byte()?;
+                Some(self.add_node("Regexp", None, offset))
+            }
+            _ => unreachable!(),
+        })
+    }
+}
+
+/// Builds a graph of objects and links between them from a Marshal byte stream.
+///
+/// Returns an Err when:
+/// * Passed byte stream is of non-4.8 Marshal version (indicated by two first bytes).
+/// * Passed byte stream's data is invalid.
+/// # Example
+/// ```rust
+/// use marshal_rs::graph::analyze;
+///
+/// // [a, a], where a = []
+/// let graph = analyze(b"\x04\x08[\x07[\x00@\x06").unwrap();
+///
+/// assert_eq!(graph.graph.node_count(), 2);
+/// assert_eq!(graph.shared(), vec![1]);
+/// assert!(!graph.has_cycles());
+/// ```
+pub fn analyze(buffer: &[u8]) -> Result<ObjectGraph, LoadError> {
+    let mut reader: Reader = Reader::new(buffer);
+    reader.read_version()?;
+
+    let mut builder: GraphBuilder = GraphBuilder {
+        reader,
+        symbols: Vec::new(),
+        graph: DiGraph::new(),
+        parents: Vec::new(),
+        cycles: Vec::new(),
+    };
+
+    builder.read_next()?;
+
+    Ok(ObjectGraph {
+        graph: builder.graph,
+        cycles: builder.cycles,
+    })
+}
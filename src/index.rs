@@ -0,0 +1,316 @@
+//! Index of files with multiple consecutive Marshal documents, like append-only logs, for random access to documents.
+//!
+//! Indexing scans the structure of documents to find their boundaries, without building values, so memory use doesn't depend on the file's size.
+//! # Example
+//! ```rust
+//! use marshal_rs::{dump, index::DocumentIndex};
+//! use serde_json::json;
+//! use std::io::Cursor;
+//!
+//! let bytes: Vec<u8> = [dump(json!("first"), None), dump(json!([1, 2]), None)].concat();
+//! let mut index = DocumentIndex::build(Cursor::new(bytes)).unwrap();
+//!
+//! assert_eq!(index.len(), 2);
+//! assert_eq!(index.load(1, None, None).unwrap(), json!([1, 2]));
+//! ```
+
+use crate::{
+    load::{LoadError, Loader},
+    raw::{invalid_length, Constants, VERSION_HEADER},
+    StringMode,
+};
+#[cfg(not(feature = "sonic"))]
+use serde_json::Value;
+#[cfg(feature = "sonic")]
+use sonic_rs::Value;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+/// Location of a document in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DocumentEntry {
+    pub offset: u64,
+    pub length: u64,
+}
+
+enum ScanError {
+    /// The stream ended in the middle of a document.
+    Truncated,
+    Failed(LoadError),
+}
+
+impl From<LoadError> for ScanError {
+    fn from(err: LoadError) -> Self {
+        ScanError::Failed(err)
+    }
+}
+
+impl From<io::Error> for ScanError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            ScanError::Truncated
+        } else {
+            ScanError::Failed(LoadError {
+                message: err.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Skips over documents of a stream, tracking the position.
+struct Scanner<R: Read> {
+    reader: R,
+    position: u64,
+}
+
+impl<R: Read> Scanner<R> {
+    fn byte(&mut self) -> Result<u8, ScanError> {
+        let mut byte: [u8; 1] = [0];
+        self.reader.read_exact(&mut byte)?;
+        self.position += 1;
+        Ok(byte[0])
+    }
+
+    fn skip(&mut self, amount: u64) -> Result<(), ScanError> {
+        let skipped: u64 = io::copy(&mut (&mut self.reader).take(amount), &mut io::sink())?;
+        self.position += skipped;
+
+        if skipped < amount {
+            return Err(ScanError::Truncated);
+        }
+
+        Ok(())
+    }
+
+    fn int(&mut self) -> Result<i32, ScanError> {
+        let length: i8 = self.byte()? as i8;
+
+        Ok(match length {
+            0 => 0,
+            -4..=4 => {
+                let mut buffer: [u8; 4] = [if length < 0 { 255u8 } else { 0u8 }; 4];
+
+                for byte in buffer.iter_mut().take(length.unsigned_abs() as usize) {
+                    *byte = self.byte()?;
+                }
+
+                i32::from_le_bytes(buffer)
+            }
+            5..=127 => (length - 5) as i32,
+            _ => (length + 5) as i32,
+        })
+    }
+
+    /// Reads a non-negative integer, like a length or a count.
+    fn count(&mut self) -> Result<u64, ScanError> {
+        let position: u64 = self.position;
+        let count: i32 = self.int()?;
+
+        u64::try_from(count)
+            .map_err(|_| ScanError::Failed(invalid_length(count, position as usize)))
+    }
+
+    fn chunk(&mut self) -> Result<(), ScanError> {
+        let length: u64 = self.count()?;
+        self.skip(length)
+    }
+
+    fn pairs(&mut self) -> Result<(), ScanError> {
+        for _ in 0..self.count()? {
+            self.value()?;
+            self.value()?;
+        }
+
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<(), ScanError> {
+        let position: u64 = self.position;
+
+        let structure: Constants = Constants::try_from(self.byte()?).map_err(|err| LoadError {
+            message: format!("{} Position: {position}", err.message.trim_end_matches('.')),
+            ..Default::default()
+        })?;
+
+        match structure {
+            Constants::True | Constants::False | Constants::Nil => {}
+            Constants::Fixnum | Constants::Symlink | Constants::Link => {
+                self.int()?;
+            }
+            Constants::Symbol
+            | Constants::Class
+            | Constants::Module
+            | Constants::ModuleOld
+            | Constants::Float
+            | Constants::String => self.chunk()?,
+            Constants::Regexp => {
+                self.chunk()?;
+                self.byte()?;
+            }
+            Constants::Bignum => {
+                self.byte()?;
+                let length: u64 = self.count()?;
+                self.skip(length * 2)?;
+            }
+            Constants::InstanceVar => {
+                self.value()?;
+                self.pairs()?;
+            }
+            Constants::Array => {
+                for _ in 0..self.count()? {
+                    self.value()?;
+                }
+            }
+            Constants::Hash => self.pairs()?,
+            Constants::HashDefault => {
+                self.pairs()?;
+                self.value()?;
+            }
+            Constants::Object | Constants::Struct => {
+                self.value()?;
+                self.pairs()?;
+            }
+            Constants::Extended
+            | Constants::UserClass
+            | Constants::UserMarshal
+            | Constants::Data => {
+                self.value()?;
+                self.value()?;
+            }
+            Constants::UserDefined => {
+                self.value()?;
+                self.chunk()?;
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Skips the next document, returning its entry, or None if the stream ends before the document is complete.
+    fn document(&mut self) -> Result<Option<DocumentEntry>, LoadError> {
+        let offset: u64 = self.position;
+
+        let result: Result<(), ScanError> = (|| {
+            let version: [u8; 2] = [self.byte()?, self.byte()?];
+
+            if version != VERSION_HEADER {
+                return Err(ScanError::Failed(LoadError {
+                    message: format!("Invalid Marshal version at position {offset}."),
+                    ..Default::default()
+                }));
+            }
+
+            self.value()
+        })();
+
+        match result {
+            Ok(()) => Ok(Some(DocumentEntry {
+                offset,
+                length: self.position - offset,
+            })),
+            Err(ScanError::Truncated) => Ok(None),
+            Err(ScanError::Failed(err)) => Err(err),
+        }
+    }
+}
+
+/// Index of Marshal documents in a seekable stream.
+pub struct DocumentIndex<R> {
+    reader: R,
+    entries: Vec<DocumentEntry>,
+}
+
+impl<R: Read + Seek> DocumentIndex<R> {
+    /// Indexes all complete documents of the stream, starting from its beginning.
+    ///
+    /// A document, that's cut off at the end of the stream, like one, that's still being appended, isn't indexed, and can be indexed later with `update()`.
+    ///
+    /// Returns an Err when the stream can't be read, or its data is invalid.
+    pub fn build(reader: R) -> Result<Self, LoadError> {
+        let mut index: Self = Self::from_entries(reader, Vec::new());
+        index.update()?;
+        Ok(index)
+    }
+
+    /// Creates an index with entries, that were built previously, without scanning the stream.
+    pub fn from_entries(reader: R, entries: Vec<DocumentEntry>) -> Self {
+        Self { reader, entries }
+    }
+
+    /// Indexes documents, that were appended after the last indexed document. Returns the number of new documents.
+    pub fn update(&mut self) -> Result<usize, LoadError> {
+        let start: u64 = self
+            .entries
+            .last()
+            .map_or(0, |entry| entry.offset + entry.length);
+
+        self.reader.seek(SeekFrom::Start(start)).map_err(io_error)?;
+
+        let mut scanner: Scanner<BufReader<&mut R>> = Scanner {
+            reader: BufReader::new(&mut self.reader),
+            position: start,
+        };
+
+        let count: usize = self.entries.len();
+
+        while let Some(entry) = scanner.document()? {
+            self.entries.push(entry);
+        }
+
+        Ok(self.entries.len() - count)
+    }
+
+    /// Returns the number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[DocumentEntry] {
+        &self.entries
+    }
+
+    /// Reads the bytes of the document at `index`.
+    ///
+    /// Returns an Err when there's no such document, or the stream can't be read.
+    pub fn read(&mut self, index: usize) -> Result<Vec<u8>, LoadError> {
+        let entry: DocumentEntry = *self.entries.get(index).ok_or_else(|| LoadError {
+            message: format!("No document at index {index}."),
+            ..Default::default()
+        })?;
+
+        let mut bytes: Vec<u8> = vec![0; entry.length as usize];
+        self.reader
+            .seek(SeekFrom::Start(entry.offset))
+            .map_err(io_error)?;
+        self.reader.read_exact(&mut bytes).map_err(io_error)?;
+
+        Ok(bytes)
+    }
+
+    /// Loads the document at `index`. Arguments are the same as `load()` arguments.
+    pub fn load(
+        &mut self,
+        index: usize,
+        string_mode: Option<StringMode>,
+        instance_var_prefix: Option<&str>,
+    ) -> Result<Value, LoadError> {
+        let bytes: Vec<u8> = self.read(index)?;
+        Loader::new().load(&bytes, string_mode, instance_var_prefix)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+fn io_error(err: io::Error) -> LoadError {
+    LoadError {
+        message: err.to_string(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,470 @@
+//! Utilities for inspecting the contents of loaded JSON values.
+//!
+//! With `tui` feature enabled, `browse()` opens an interactive tree viewer of a value in the terminal.
+//!
+//! Not available with `sonic` feature enabled.
+
+#[cfg(feature = "tui")]
+mod browse;
+
+#[cfg(feature = "tui")]
+pub use browse::{browse, Browser};
+
+use crate::{
+    dump::{FIXNUM_MAX, FIXNUM_MIN},
+    value::{is_hash, key_value},
+    ValueExt, DEFAULT_SYMBOL, EXTENDS_SYMBOL, SYMBOL_KEYS_SYMBOL,
+};
+use serde_json::{Map, Value};
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Write};
+
+/// Maximum number of characters of strings, written by `summarize()`.
+const SUMMARY_STRING_LENGTH: usize = 40;
+
+/// Statistics of instances of a single class.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassStats {
+    /// Number of instances.
+    pub count: usize,
+    /// Total length of strings in bytes, including strings in nested values.
+    pub string_bytes: usize,
+    /// Estimated size of instances serialized to Marshal, including nested values.
+    pub estimated_size: usize,
+}
+
+/// Statistics of a Value tree, grouped by Ruby class names.
+///
+/// Values of core types are grouped under their Ruby classes, for example `String`, `Array` or `NilClass`.
+///
+/// As statistics include nested values, instances nested in each other are counted more than once in totals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassProfile {
+    pub classes: BTreeMap<String, ClassStats>,
+}
+
+impl ClassProfile {
+    /// Returns classes sorted by estimated serialized size, from the largest.
+    pub fn by_size(&self) -> Vec<(&str, &ClassStats)> {
+        let mut classes: Vec<(&str, &ClassStats)> = self
+            .classes
+            .iter()
+            .map(|(class, stats)| (class.as_str(), stats))
+            .collect();
+
+        classes.sort_by_key(|(_, stats)| Reverse(stats.estimated_size));
+        classes
+    }
+}
+
+/// Returns the length of fixnum, serialized to Marshal.
+pub(crate) fn fixnum_size(number: i64) -> usize {
+    match number {
+        -123..=122 => 1,
+        -256..=255 => 2,
+        -65536..=65535 => 3,
+        -16777216..=16777215 => 4,
+        _ => 5,
+    }
+}
+
+fn chunk_size(length: usize) -> usize {
+    fixnum_size(length as i64) + length
+}
+
+/// Returns the Ruby class name of the Value.
+pub(crate) fn ruby_class(value: &Value) -> String {
+    if let Some(class) = value.class_name() {
+        return class.to_string();
+    }
+
+    match value {
+        Value::Null => "NilClass",
+        Value::Bool(true) => "TrueClass",
+        Value::Bool(false) => "FalseClass",
+        Value::Number(number) => {
+            if number.is_f64() {
+                "Float"
+            } else {
+                "Integer"
+            }
+        }
+        Value::String(string) => {
+            if string.starts_with("__symbol__") {
+                "Symbol"
+            } else {
+                "String"
+            }
+        }
+        Value::Array(_) => "Array",
+        Value::Object(_) => match value["__type"].as_str() {
+            Some("bytes" | "string") => "String",
+            Some("bigint") => "Integer",
+            Some("regexp") => "Regexp",
+            _ => "Hash",
+        },
+    }
+    .to_string()
+}
+
+/// Returns estimated serialized size of the key of Hash or instance variable.
+fn key_size(key: &str, hash: bool) -> usize {
+    if let Some(symbol) = key.strip_prefix("__symbol__") {
+        1 + chunk_size(symbol.len())
+    } else if !hash {
+        1 + chunk_size(key.len())
+    } else if matches!(key, "__nil__null" | "__boolean__true" | "__boolean__false") {
+        1
+    } else if let Some(integer) = key.strip_prefix("__integer__") {
+        1 + integer.parse::<i64>().map_or(5, fixnum_size)
+    } else if let Some(float) = key.strip_prefix("__float__") {
+        1 + chunk_size(float.len())
+    } else if let Some(json) = key
+        .strip_prefix("__array__")
+        .or_else(|| key.strip_prefix("__object__"))
+    {
+        json.len()
+    } else {
+        2 + chunk_size(key.len()) + 5
+    }
+}
+
+/// Returns estimated serialized size and string bytes of the Value, and records it with its nested values to the profile, if passed.
+pub(crate) fn estimate(
+    value: &Value,
+    mut classes: Option<&mut BTreeMap<String, ClassStats>>,
+) -> (usize, usize) {
+    let (size, string_bytes): (usize, usize) = match value {
+        Value::Null | Value::Bool(_) => (1, 0),
+        Value::Number(number) => {
+            if let Some(integer) = number
+                .as_i64()
+                .filter(|&integer| (FIXNUM_MIN..=FIXNUM_MAX).contains(&i128::from(integer)))
+            {
+                (1 + fixnum_size(integer), 0)
+            } else if number.is_f64() {
+                (1 + chunk_size(number.to_string().len()), 0)
+            } else {
+                let magnitude: u64 = number
+                    .as_i64()
+                    .map_or_else(|| number.as_u64().unwrap_or_default(), i64::unsigned_abs);
+                let words: usize = (64 - magnitude.leading_zeros() as usize + 15) / 16;
+
+                // Bignum: sign, length and 16-bit words
+                (2 + fixnum_size(words as i64) + words * 2, 0)
+            }
+        }
+        Value::String(string) => {
+            if let Some(symbol) = string.strip_prefix("__symbol__") {
+                (1 + chunk_size(symbol.len()), 0)
+            } else {
+                // Instance variable wrapper, string and encoding
+                (2 + chunk_size(string.len()) + 5, string.len())
+            }
+        }
+        Value::Array(array) => {
+            let mut result: (usize, usize) = (1 + fixnum_size(array.len() as i64), 0);
+
+            for element in array {
+                let (size, string_bytes) = estimate(element, classes.as_deref_mut());
+                result.0 += size;
+                result.1 += string_bytes;
+            }
+
+            result
+        }
+        Value::Object(object) => match value["__type"].as_str() {
+            Some("bytes") => {
+                let length: usize = value["data"].as_array().map_or(0, Vec::len);
+
+                match value["encoding"].as_str() {
+                    // Instance variable wrapper, string, encoding symbol and its name
+                    Some(encoding) => (6 + chunk_size(length) + chunk_size(encoding.len()), length),
+                    None => (1 + chunk_size(length), length),
+                }
+            }
+            Some("string") => {
+                let length: usize = value["value"].as_str().map_or(0, str::len);
+
+                match value["encoding"].as_str() {
+                    Some(encoding)
+                        if encoding.eq_ignore_ascii_case("ASCII-8BIT")
+                            || encoding.eq_ignore_ascii_case("BINARY") =>
+                    {
+                        (1 + chunk_size(length), length)
+                    }
+                    // Estimated like a UTF-8 string, as encoded length is unknown until it's encoded
+                    _ => (2 + chunk_size(length) + 5, length),
+                }
+            }
+            Some("bigint") => match value["data"].as_array() {
+                // Bytes of the magnitude, kept without `bigint` feature
+                Some(data) => (3 + (data.len() + 1) / 2 * 2, 0),
+                None => (
+                    3 + (value["value"].as_str().map_or(0, str::len) * 10 / 24 + 2) / 2 * 2,
+                    0,
+                ),
+            },
+            Some("regexp") => {
+                let length: usize = value["expression"].as_str().map_or(0, str::len);
+                (2 + chunk_size(length), length)
+            }
+            Some("class" | "module") => {
+                let length: usize = value.class_name().map_or(0, str::len);
+                (1 + chunk_size(length), 0)
+            }
+            _ => {
+                // Keys of Hashes with the marker are dumped as symbols, like instance variables
+                let hash: bool = is_hash(value) && value[SYMBOL_KEYS_SYMBOL] != true;
+                let mut result: (usize, usize) = (1, 0);
+                let mut entries: usize = 0;
+
+                if let Some(class) = value.class_name() {
+                    result.0 += 1 + chunk_size(class.len());
+                }
+
+                let object: &Map<String, Value> = if value["__type"] == "struct" {
+                    value["__members"].as_object().unwrap_or(object)
+                } else {
+                    object
+                };
+
+                for (key, entry) in object {
+                    match key.as_str() {
+                        "__class" | "__type" | "__old" | "__members" | EXTENDS_SYMBOL
+                        | SYMBOL_KEYS_SYMBOL => continue,
+                        "__userDefined" => {
+                            let length: usize = entry.as_array().map_or(0, Vec::len);
+                            result.0 += chunk_size(length);
+                            result.1 += length;
+                            continue;
+                        }
+                        "__data" | "__wrapped" | "__userMarshal" | DEFAULT_SYMBOL => {}
+                        _ => {
+                            entries += 1;
+                            result.0 += key_size(key, hash);
+                        }
+                    }
+
+                    let (size, string_bytes) = estimate(entry, classes.as_deref_mut());
+                    result.0 += size;
+                    result.1 += string_bytes;
+                }
+
+                if hash || entries > 0 {
+                    result.0 += fixnum_size(entries as i64);
+                }
+
+                result
+            }
+        },
+    };
+
+    if let Some(classes) = classes {
+        let stats: &mut ClassStats = classes.entry(ruby_class(value)).or_default();
+        stats.count += 1;
+        stats.string_bytes += string_bytes;
+        stats.estimated_size += size;
+    }
+
+    (size, string_bytes)
+}
+
+/// Builds a profile of the Value tree, summarizing instance counts, string bytes and estimated serialized size per class.
+/// # Example
+/// ```rust
+/// use marshal_rs::inspect::profile;
+/// use serde_json::json;
+///
+/// let value = json!([
+///     { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" },
+///     { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Shield" }
+/// ]);
+///
+/// let profile = profile(&value);
+///
+/// assert_eq!(profile.classes["Item"].count, 2);
+/// assert_eq!(profile.classes["Item"].string_bytes, 11);
+/// assert_eq!(profile.classes["Array"].count, 1);
+/// ```
+pub fn profile(value: &Value) -> ClassProfile {
+    let mut classes: BTreeMap<String, ClassStats> = BTreeMap::new();
+    estimate(value, Some(&mut classes));
+    ClassProfile { classes }
+}
+
+/// Returns a short Ruby-like representation of a leaf Value, or None for containers.
+fn summarize_leaf(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(bool) => bool.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(string) => match string.strip_prefix("__symbol__") {
+            Some(symbol) => format!(":{symbol}"),
+            None if string.chars().count() > SUMMARY_STRING_LENGTH => {
+                let prefix: String = string.chars().take(SUMMARY_STRING_LENGTH).collect();
+                format!("{prefix:?}… ({} bytes)", string.len())
+            }
+            None => format!("{string:?}"),
+        },
+        Value::Array(_) => return None,
+        Value::Object(_) => match value["__type"].as_str() {
+            Some("bytes") => format!(
+                "String ({} bytes, binary)",
+                value["data"].as_array().map_or(0, Vec::len)
+            ),
+            Some("string") => format!(
+                "{:?} ({})",
+                value["value"].as_str().unwrap_or_default(),
+                value["encoding"].as_str().unwrap_or("UTF-8")
+            ),
+            Some("bigint") => match value["value"].as_str() {
+                Some(digits) => digits.to_string(),
+                None => format!(
+                    "Integer ({} bytes)",
+                    value["data"].as_array().map_or(0, Vec::len)
+                ),
+            },
+            Some("regexp") => format!(
+                "/{}/{}",
+                value["expression"].as_str().unwrap_or_default(),
+                value["flags"].as_str().unwrap_or_default()
+            ),
+            Some("class" | "module") => format!(
+                "{} {}",
+                if value["__type"] == "class" {
+                    "Class"
+                } else {
+                    "Module"
+                },
+                value.class_name().unwrap_or_default()
+            ),
+            _ if value.get("__userDefined").is_some() => format!(
+                "{} (_dump, {} bytes)",
+                ruby_class(value),
+                value["__userDefined"].as_array().map_or(0, Vec::len)
+            ),
+            _ => return None,
+        },
+    })
+}
+
+/// Returns the label of the Hash key, instance variable or struct member.
+fn summarize_key(key: &str, hash: bool) -> String {
+    if !hash {
+        return key.strip_prefix("__symbol__").unwrap_or(key).to_string();
+    }
+
+    let key: Value = key_value(key);
+    summarize_leaf(&key).unwrap_or_else(|| ruby_class(&key))
+}
+
+/// Returns labelled children of the container: elements of Arrays, entries of Hashes, instance variables of objects and members of Structs.
+pub(crate) fn entries(value: &Value) -> Vec<(String, &Value)> {
+    let object: &Map<String, Value> = match value {
+        Value::Array(array) => {
+            return array
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (index.to_string(), element))
+                .collect()
+        }
+        Value::Object(_) if summarize_leaf(value).is_some() => return Vec::new(),
+        Value::Object(object) if value["__type"] == "struct" => {
+            value["__members"].as_object().unwrap_or(object)
+        }
+        Value::Object(object) => object,
+        _ => return Vec::new(),
+    };
+    let hash: bool = is_hash(value);
+
+    object
+        .iter()
+        .filter_map(|(key, entry)| match key.as_str() {
+            "__class" | "__type" | "__old" | "__members" | EXTENDS_SYMBOL | SYMBOL_KEYS_SYMBOL => {
+                None
+            }
+            DEFAULT_SYMBOL => Some(("default".to_string(), entry)),
+            "__data" | "__wrapped" | "__userMarshal" => {
+                Some((key.trim_start_matches("__").to_string(), entry))
+            }
+            _ => Some((summarize_key(key, hash), entry)),
+        })
+        .collect()
+}
+
+/// Returns a one-line description of the Value: Ruby-like representation of leaves, and class and size of containers.
+pub(crate) fn summarize_header(value: &Value, entries: usize) -> String {
+    if let Some(leaf) = summarize_leaf(value) {
+        return leaf;
+    }
+
+    if value.is_array() {
+        return format!("Array ({entries})");
+    }
+
+    let noun: &str = if is_hash(value) {
+        "entries"
+    } else if value["__type"] == "struct" {
+        "members"
+    } else {
+        "ivars"
+    };
+
+    format!("{} ({entries} {noun})", ruby_class(value))
+}
+
+fn summarize_value(
+    value: &Value,
+    output: &mut String,
+    indent: usize,
+    depth: usize,
+    max_depth: usize,
+    max_items: usize,
+) {
+    let entries: Vec<(String, &Value)> = entries(value);
+    output.push_str(&summarize_header(value, entries.len()));
+
+    if entries.is_empty() {
+        return;
+    }
+
+    if depth >= max_depth {
+        output.push_str(" …");
+        return;
+    }
+
+    let padding: String = "  ".repeat(indent + 1);
+
+    for (key, entry) in entries.iter().take(max_items) {
+        let _ = write!(output, "\n{padding}{key}: ");
+        summarize_value(entry, output, indent + 1, depth + 1, max_depth, max_items);
+    }
+
+    if entries.len() > max_items {
+        let _ = write!(output, "\n{padding}… {} more", entries.len() - max_items);
+    }
+}
+
+/// Returns a compact overview of the Value: classes and sizes of containers, and up to `max_items` entries of each of them,
+/// up to `max_depth` levels deep. Long strings are truncated, and `_dump` payloads are shown by their sizes.
+/// # Example
+/// ```rust
+/// use marshal_rs::inspect::summarize;
+/// use serde_json::json;
+///
+/// let value = json!({
+///     "__class": "__symbol__RPG::Map", "__type": "object",
+///     "__symbol__@events": { "__integer__1": { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss" } },
+///     "__symbol__@data": [1, 2, 3]
+/// });
+///
+/// assert_eq!(
+///     summarize(&value, 1, 2),
+///     "RPG::Map (2 ivars)\n  @events: Hash (1 entries) …\n  @data: Array (3) …"
+/// );
+/// ```
+pub fn summarize(value: &Value, max_depth: usize, max_items: usize) -> String {
+    let mut output: String = String::new();
+    summarize_value(value, &mut output, 0, 0, max_depth, max_items);
+    output
+}
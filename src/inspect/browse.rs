@@ -0,0 +1,414 @@
+//! Interactive terminal viewer of loaded values.
+//!
+//! Requires `tui` feature.
+
+use super::{entries, ruby_class, summarize_header};
+use crate::{value::is_hash, ValueExt, DEFAULT_SYMBOL, EXTENDS_SYMBOL};
+use ratatui::{
+    backend::Backend,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListState, Paragraph},
+    Frame, Terminal,
+};
+use serde_json::Value;
+use std::{collections::HashSet, io};
+
+/// Number of rows, that PageUp and PageDown move the selection by.
+const PAGE_ROWS: usize = 20;
+
+const HELP: &str = "↑↓ move  ←→ collapse/expand  Enter toggle  / search  n next match  q quit";
+
+/// Visible row of the tree.
+struct Row<'a> {
+    /// Indices of entries, leading from the root to the node.
+    path: Vec<usize>,
+    key: Option<String>,
+    value: &'a Value,
+    /// Number of entries of the node, or 0 for leaves.
+    entries: usize,
+}
+
+/// State of the tree viewer. `browse()` runs it in the terminal, but it can also be drawn and driven by key events manually,
+/// for example, to embed the viewer in other applications.
+pub struct Browser<'a> {
+    root: &'a Value,
+    expanded: HashSet<Vec<usize>>,
+    rows: Vec<Row<'a>>,
+    state: ListState,
+    /// Search query, while it's being typed.
+    input: Option<String>,
+    query: String,
+    status: String,
+}
+
+impl<'a> Browser<'a> {
+    /// Creates a viewer of the Value, with its root expanded and selected.
+    pub fn new(root: &'a Value) -> Self {
+        let mut browser: Browser = Browser {
+            root,
+            expanded: HashSet::from([Vec::new()]),
+            rows: Vec::new(),
+            state: ListState::default().with_selected(Some(0)),
+            input: None,
+            query: String::new(),
+            status: HELP.to_string(),
+        };
+
+        browser.update_rows();
+        browser
+    }
+
+    fn update_rows(&mut self) {
+        fn visit<'a>(
+            value: &'a Value,
+            key: Option<String>,
+            path: &mut Vec<usize>,
+            expanded: &HashSet<Vec<usize>>,
+            rows: &mut Vec<Row<'a>>,
+        ) {
+            let entries: Vec<(String, &Value)> = entries(value);
+
+            rows.push(Row {
+                path: path.clone(),
+                key,
+                value,
+                entries: entries.len(),
+            });
+
+            if !expanded.contains(path) {
+                return;
+            }
+
+            for (index, (key, entry)) in entries.into_iter().enumerate() {
+                path.push(index);
+                visit(entry, Some(key), path, expanded, rows);
+                path.pop();
+            }
+        }
+
+        let mut rows: Vec<Row> = Vec::new();
+        visit(self.root, None, &mut Vec::new(), &self.expanded, &mut rows);
+        self.rows = rows;
+    }
+
+    fn selected(&self) -> usize {
+        self.state.selected().unwrap_or_default()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.state
+            .select(Some(index.min(self.rows.len().saturating_sub(1))));
+    }
+
+    fn select_path(&mut self, path: &[usize]) {
+        for length in 0..path.len() {
+            self.expanded.insert(path[..length].to_vec());
+        }
+
+        self.update_rows();
+
+        if let Some(index) = self.rows.iter().position(|row| row.path == path) {
+            self.select(index);
+        }
+    }
+
+    fn toggle(&mut self, expand: bool) {
+        let row: &Row = &self.rows[self.selected()];
+
+        if row.entries == 0 {
+            return;
+        }
+
+        let path: Vec<usize> = row.path.clone();
+
+        if expand {
+            self.expanded.insert(path);
+        } else {
+            self.expanded.remove(&path);
+        }
+
+        self.update_rows();
+    }
+
+    /// Selects the next node after the selected one, which key or description contains the query, expanding its parents.
+    fn find_next(&mut self) {
+        fn find(
+            value: &Value,
+            key: &str,
+            path: &mut Vec<usize>,
+            after: &[usize],
+            query: &str,
+            passed: &mut bool,
+        ) -> Option<Vec<usize>> {
+            let entries: Vec<(String, &Value)> = entries(value);
+
+            if *passed
+                && format!("{key}: {}", summarize_header(value, entries.len()))
+                    .to_lowercase()
+                    .contains(query)
+            {
+                return Some(path.clone());
+            }
+
+            if path == after {
+                *passed = true;
+            }
+
+            for (index, (key, entry)) in entries.into_iter().enumerate() {
+                path.push(index);
+
+                if let Some(found) = find(entry, &key, path, after, query, passed) {
+                    return Some(found);
+                }
+
+                path.pop();
+            }
+
+            None
+        }
+
+        if self.query.is_empty() {
+            return;
+        }
+
+        let query: String = self.query.to_lowercase();
+        let after: Vec<usize> = self.rows[self.selected()].path.clone();
+        let mut passed: bool = false;
+        let mut found: Option<Vec<usize>> =
+            find(self.root, "", &mut Vec::new(), &after, &query, &mut passed);
+
+        if found.is_none() {
+            // Wrap around to the beginning
+            found = find(self.root, "", &mut Vec::new(), &[], &query, &mut true);
+        }
+
+        match found {
+            Some(path) => {
+                self.select_path(&path);
+                self.status = format!("/{}", self.query);
+            }
+            None => self.status = format!("Not found: {}", self.query),
+        }
+    }
+
+    /// Handles a key press. Returns `false`, when the viewer should be closed.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.kind != KeyEventKind::Press {
+            return true;
+        }
+
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Char(char) => input.push(char),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    self.query = self.input.take().unwrap_or_default();
+                    self.find_next();
+                }
+                KeyCode::Esc => {
+                    self.input = None;
+                    self.status = HELP.to_string();
+                }
+                _ => {}
+            }
+
+            return true;
+        }
+
+        let selected: usize = self.selected();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') => self.select(selected + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(selected.saturating_sub(1)),
+            KeyCode::PageDown => self.select(selected + PAGE_ROWS),
+            KeyCode::PageUp => self.select(selected.saturating_sub(PAGE_ROWS)),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Right | KeyCode::Char('l') => {
+                if self.expanded.contains(&self.rows[selected].path) {
+                    self.select(selected + 1);
+                } else {
+                    self.toggle(true);
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                let path: &[usize] = &self.rows[selected].path;
+
+                if self.rows[selected].entries != 0 && self.expanded.contains(path) {
+                    self.toggle(false);
+                } else if let Some((_, parent)) = path.split_last() {
+                    let parent: Vec<usize> = parent.to_vec();
+                    self.select_path(&parent);
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let expanded: bool = self.expanded.contains(&self.rows[selected].path);
+                self.toggle(!expanded);
+            }
+            KeyCode::Char('/') => {
+                self.input = Some(String::new());
+            }
+            KeyCode::Char('n') => self.find_next(),
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Returns lines, describing the selected node: its path, class and Marshal-specific flags.
+    fn metadata(&self) -> Vec<Line<'static>> {
+        let row: &Row = &self.rows[self.selected()];
+        let value: &Value = row.value;
+        let mut keys: Vec<&str> = Vec::new();
+        let mut node: &Row = row;
+
+        // Rows of ancestors precede the row, so walk back to collect keys of the path
+        for ancestor in self.rows[..self.selected()].iter().rev() {
+            if ancestor.path.len() < node.path.len() {
+                keys.extend(node.key.as_deref());
+                node = ancestor;
+            }
+        }
+
+        keys.reverse();
+
+        let mut flags: Vec<String> = Vec::new();
+
+        if let Some(bytes) = value.get("__userDefined").and_then(Value::as_array) {
+            flags.push(format!("_dump ({} bytes)", bytes.len()));
+        }
+
+        for (key, flag) in [
+            ("__userMarshal", "marshal_dump"),
+            ("__data", "Data"),
+            ("__wrapped", "String/Regexp/Array/Hash subclass"),
+        ] {
+            if value.get(key).is_some() {
+                flags.push(flag.to_string());
+            }
+        }
+
+        if let Some(modules) = value.get(EXTENDS_SYMBOL).and_then(Value::as_array) {
+            let modules: Vec<&str> = modules
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|module| module.strip_prefix("__symbol__").unwrap_or(module))
+                .collect();
+            flags.push(format!("extended with {}", modules.join(", ")));
+        }
+
+        if is_hash(value) && value.get(DEFAULT_SYMBOL).is_some() {
+            flags.push("has default value".to_string());
+        }
+
+        if value["__type"] == "regexp" {
+            flags.push(format!(
+                "flags: {}",
+                value["flags"].as_str().unwrap_or_default()
+            ));
+        }
+
+        vec![
+            Line::from(format!("Path: {}", keys.join(" › "))),
+            Line::from(format!(
+                "Class: {}  Type: {}",
+                ruby_class(value),
+                value["__type"].as_str().unwrap_or(match value {
+                    Value::Object(_) => "hash",
+                    Value::Array(_) => "array",
+                    _ => "value",
+                })
+            )),
+            Line::from(format!(
+                "Flags: {}",
+                if flags.is_empty() {
+                    "none".to_string()
+                } else {
+                    flags.join(", ")
+                }
+            )),
+            Line::from(format!(
+                "Estimated Marshal size: {} bytes",
+                value.estimate_marshal_size()
+            )),
+        ]
+    }
+
+    /// Draws the tree, metadata of the selected node and the status line to the frame.
+    pub fn render(&mut self, frame: &mut Frame) {
+        let [tree, metadata, status] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let items: Vec<Line> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let marker: &str = if row.entries == 0 {
+                    " "
+                } else if self.expanded.contains(&row.path) {
+                    "▾"
+                } else {
+                    "▸"
+                };
+                let header: String = summarize_header(row.value, row.entries);
+
+                Line::from(match &row.key {
+                    Some(key) => format!("{}{marker} {key}: {header}", "  ".repeat(row.path.len())),
+                    None => format!("{marker} {header}"),
+                })
+            })
+            .collect();
+
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            tree,
+            &mut self.state,
+        );
+        frame.render_widget(
+            Paragraph::new(self.metadata()).block(Block::new().borders(Borders::TOP)),
+            metadata,
+        );
+        frame.render_widget(
+            Paragraph::new(match &self.input {
+                Some(input) => format!("/{input}"),
+                None => self.status.clone(),
+            }),
+            status,
+        );
+    }
+
+    /// Draws the viewer and handles key presses, until it's closed.
+    pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Opens an interactive tree viewer of the Value in the terminal, and blocks until it's closed with `q` or `Esc`.
+///
+/// Nodes are expanded and collapsed with arrow keys or Enter, `/` searches keys and descriptions of all nodes,
+/// and `n` jumps to the next match. Class, type and flags of the selected node are shown below the tree.
+pub fn browse(value: &Value) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result: io::Result<()> = Browser::new(value).run(&mut terminal);
+
+    ratatui::try_restore()?;
+    result
+}
@@ -17,6 +17,14 @@
 //!
 //!`dump()`, in turn, takes `Value` as its only argument and serializes it back to `Vec<u8>` Marshal byte stream. It does not preserve strings' initial encoding, writing all strings as UTF-8 encoded.
 //!
+//!`serde_json::Value` comes from `serde` feature, which is enabled by default. Builds with `sonic` feature don't need it, so `default-features = false` drops `serde_json` from their dependency tree. One of these two features must be enabled.
+//!
+//!`dump_json()` of `serde` feature serializes plain JSON data, like configs for Ruby processes, straight to Marshal, writing strings as strings and objects as Hashes with string keys, without interpreting `__symbol__` prefixes and `__type` objects.
+//!
+//!`load_json()` does the opposite: it converts Marshal data straight to plain JSON, with symbols as strings and objects as maps of their instance variables, for pipelines, that never dump the data back.
+//!
+//!`MarshalCodec` loads and dumps with one set of options, so options, that must match in both directions, like the instance variable prefix, can't get out of sync.
+//!
 //!If serializes Ruby data to JSON using the table:
 //!
 //!| Ruby object                                    | Serialized to JSON                                                        |
@@ -32,6 +40,10 @@
 //!| `{}` (Hash)                                    | `{}` (Plain object)                                                       |
 //!| `Object.new` (Including structs, modules etc.) | `{ "__class": "__symbol__Object", "__type": "object" }` (Plain object)    |
 //!
+//!Big Integers are converted to decimal strings with `num-bigint`, when `bigint` feature is enabled, which it is by default. Without it, they're kept as `{ __type: "bigint", negative: false, data: [...] }` objects with little-endian bytes of their magnitude, that are dumped back unchanged. Big Integers with decimal `value` strings are dumped in both cases.
+//!
+//!NaN and infinite Floats can't be JSON numbers, so they're loaded as `null` by default. `LoaderBuilder::non_finite_float_policy()` loads them as `"NaN"` and `"Infinity"` strings, as `{ __type: "float", value: "inf" }` objects, that are dumped back as Floats, or rejects them.
+//!
 //!### Strings
 //!
 //!By default, Ruby strings, that include encoding instance variable, are serialized to JSON strings, and those which don't, serialized to `{ __type: "bytes", data: [...] }` objects.
@@ -42,13 +54,19 @@
 //!
 //!`StringMode::Binary` converts all strings to objects.
 //!
+//!Strings in encodings other than UTF-8 are decoded with `encoding_rs`, when `encodings` feature is enabled, which it is by default. Without it, they're kept as `{ __type: "bytes", data: [...], encoding: "Shift_JIS" }` objects, that are dumped back with their encodings.
+//!
+//!Individual strings can be dumped in other encodings than UTF-8 with `{ __type: "string", value: "...", encoding: "Shift_JIS" }` objects, which `ValueExt::with_string_encoding()` creates. `"ASCII-8BIT"` encoding dumps the string as raw bytes, like binary strings of scripts. US-ASCII strings, that Ruby marks with `E => false` instance variable, are loaded as such objects with `"US-ASCII"` encoding, so they keep it when dumped back.
+//!
 //!### Objects and Symbols
 //!
 //!For objects, that cannot be serialized in JSON (such as Objects and Symbols), `marshal-rs` uses approach of stringifying and adding prefixes and properties. It stringifyies symbols and prefixes them with `__symbol__`, and serializes objects' classes and types as `__class` keys and `__type` keys respectively.
 //!
 //!### Hash keys
 //!
-//!For Hash keys, that in Ruby may be represented using `Integer`, `Float`, `Object` etc, `marshal-rs` tries to preserve key type with prefixing stringifiyed key with it type. For example, Ruby `{1 => nil}` Hash will be converted to `{"__integer__1": null}` object.
+//!For Hash keys, that in Ruby may be represented using `Integer`, `Float`, `Object` etc, `marshal-rs` tries to preserve key type with prefixing stringifiyed key with it type. For example, Ruby `{1 => nil}` Hash will be converted to `{"__integer__1": null}` object. `nil`, `true` and `false` keys are converted to `"__nil__null"`, `"__boolean__true"` and `"__boolean__false"` keys.
+//!
+//!Symbol keys are prefixed with `__symbol__`, like other symbols. With `Loader::set_symbol_keys_as_strings(true)`, Hashes, keyed only by symbols, are loaded with plain string keys and `"__ruby_symbol_keys__": true` marker instead, so they're dumped back with symbol keys.
 //!
 //!### Instance variables
 //!
@@ -59,6 +77,7 @@
 //!
 //!This code uses UnsafeCell along with unsafe blocks multiple times in load() function.
 //!However, in current implementation, this unsafe code will NOT ever cause any data races or instabilities.
+//!Loaders are `Send`, even though they store `Rc`s of loaded objects, because the `Rc`s are never shared outside of a single `load()` call.
 //!
 //!## Quick example
 //!
@@ -101,69 +120,104 @@
 //!
 //!Project is licensed under WTFPL.
 
-// Ruby object marks constants enum
-#[allow(dead_code)]
-#[repr(u8)]
-#[derive(PartialEq, Clone, Copy)]
-enum Constants {
-    True = 84,         // 'T'
-    False = 70,        // 'F'
-    Nil = 48,          // '0'
-    Fixnum = 105,      // 'i'
-    Symbol = 58,       // ':'
-    Symlink = 59,      // ';'
-    Link = 64,         // '@'
-    InstanceVar = 73,  // 'I'
-    Extended = 101,    // 'e'
-    Array = 91,        // '['
-    Bignum = 108,      // 'l'
-    Class = 99,        // 'c'
-    Module = 109,      // 'm'
-    ModuleOld = 77,    // 'M'
-    Data = 100,        // 'd'
-    Float = 102,       // 'f'
-    Hash = 123,        // '{'
-    HashDefault = 125, // '}'
-    Object = 111,      // 'o'
-    Regexp = 47,       // '/'
-    String = 34,       // '"'
-    Struct = 83,       // 'S'
-    UserClass = 67,    // 'C'
-    UserDefined = 117, // 'u'
-    UserMarshal = 85,  // 'U'
-    Positive = 43,     // '+'
-    Negative = 45,     // '-'
-
-    // Regular expression flags
-    RegexpIgnore = 1,
-    RegexpExtended = 2,
-    RegexpMultiline = 4,
-}
-
-impl std::ops::BitAnd<Constants> for u8 {
-    type Output = u8;
-
-    fn bitand(self, rhs: Constants) -> Self::Output {
-        self & (rhs as u8)
-    }
-}
+#[cfg(not(any(feature = "serde", feature = "sonic")))]
+compile_error!("Either `serde` (enabled by default) or `sonic` feature must be enabled, as they provide the `Value` type.");
 
-impl PartialEq<Constants> for u8 {
-    fn eq(&self, other: &Constants) -> bool {
-        *self == *other as u8
-    }
-}
+use raw::Constants;
 
 // Required constants
 const ENCODING_SHORT_SYMBOL: &str = "__symbol__E";
 const ENCODING_LONG_SYMBOL: &str = "__symbol__encoding";
 const EXTENDS_SYMBOL: &str = "__ruby_extends__";
 const DEFAULT_SYMBOL: &str = "__ruby_default__";
-const MARSHAL_VERSION: u16 = 0x0408; // The latest and probably final version of Ruby Marshal is 4.8
+/// Marker of Hashes, whose Symbol keys were loaded as plain strings.
+const SYMBOL_KEYS_SYMBOL: &str = "__ruby_symbol_keys__";
+/// Instance variables of Range, that Ruby writes without "@" prefixes.
+const RANGE_INSTANCE_VARS: [&str; 3] = ["__symbol__excl", "__symbol__begin", "__symbol__end"];
 
+#[cfg(all(feature = "bson", not(feature = "sonic")))]
+pub mod bson;
+#[cfg(not(feature = "sonic"))]
+pub mod builtins;
+#[cfg(all(feature = "chrono", not(feature = "sonic")))]
+pub mod chrono;
+pub mod codec;
+#[cfg(not(feature = "sonic"))]
+pub mod codegen;
+#[cfg(not(feature = "sonic"))]
+pub mod convert;
+pub mod data;
+#[cfg(all(feature = "decimal", not(feature = "sonic")))]
+pub mod decimal;
 pub mod dump;
+pub mod embed;
+#[cfg(not(feature = "sonic"))]
+pub mod export;
+#[cfg(not(feature = "sonic"))]
+pub mod filter;
+pub mod forensics;
+#[cfg(all(feature = "arbitrary", not(feature = "sonic")))]
+pub mod fuzz;
+#[cfg(feature = "graph")]
+pub mod graph;
+pub mod index;
+#[cfg(not(feature = "sonic"))]
+pub mod inspect;
 pub mod load;
+#[cfg(not(feature = "sonic"))]
+pub mod merge;
+#[cfg(not(feature = "sonic"))]
+pub mod php;
+#[cfg(all(feature = "bigint", not(feature = "sonic")))]
+pub mod pickle;
+#[cfg(feature = "serde")]
+pub mod plain;
+pub mod pool;
+pub mod prelude;
+#[cfg(all(feature = "python", not(feature = "sonic")))]
+pub mod python;
+pub mod raw;
+#[cfg(not(feature = "sonic"))]
+pub mod ron;
+#[cfg(all(feature = "rpg", not(feature = "sonic")))]
+pub mod rpg;
+#[cfg(not(feature = "sonic"))]
+pub mod rpgmaker;
+#[cfg(all(feature = "rubygems", not(feature = "sonic")))]
+pub mod rubygems;
+#[cfg(all(feature = "test-utils", not(feature = "sonic")))]
+pub mod test_utils;
+pub mod testgen;
+#[cfg(not(feature = "sonic"))]
+pub mod tracked;
+#[cfg(not(feature = "sonic"))]
+pub mod translate;
+#[cfg(not(feature = "sonic"))]
+pub mod typed;
+#[cfg(not(feature = "sonic"))]
+pub mod value;
+pub mod verify;
+#[cfg(all(feature = "wasm", not(feature = "sonic")))]
+pub mod wasm;
+#[cfg(all(feature = "yaml", not(feature = "sonic")))]
+pub mod yaml;
 
 // Convenient re-exports
-pub use dump::{dump, Dumper};
-pub use load::{load, Loader, StringMode};
+pub use codec::{CodecBuilder, MarshalCodec};
+#[cfg(feature = "serde")]
+pub use dump::dump_json;
+pub use dump::{dump, Dumper, DumperBuilder, InstanceVarPolicy};
+pub use load::{
+    load, DisallowedClassPolicy, DuplicateKeyPolicy, Loader, LoaderBuilder, NonFiniteFloatPolicy,
+    Preset, StringMode,
+};
+#[cfg(feature = "serde")]
+pub use plain::{load_json, PlainJsonOptions};
+#[cfg(not(feature = "sonic"))]
+pub use typed::{FromValue, IntoValue};
+#[cfg(all(feature = "encodings", not(feature = "sonic")))]
+pub use value::TranscodeReport;
+#[cfg(not(feature = "sonic"))]
+pub use value::{AtKey, DuplicateGroup, Path, PathSegment, ValueError, ValueExt, ValueKind};
+#[cfg(all(feature = "regex", not(feature = "sonic")))]
+pub use value::{ReplaceOptions, ReplaceReport};
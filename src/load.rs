@@ -1,28 +1,332 @@
 //! Utilities for serializing Marshal byte streams to JSON.
 
 use crate::{
+    data::DataRegistry,
+    pool::TablePool,
+    raw::{check_version, invalid_length, MarshalVersion, Reader},
     Constants, DEFAULT_SYMBOL, ENCODING_LONG_SYMBOL, ENCODING_SHORT_SYMBOL, EXTENDS_SYMBOL,
-    MARSHAL_VERSION,
+    RANGE_INSTANCE_VARS, SYMBOL_KEYS_SYMBOL,
 };
+#[cfg(feature = "encodings")]
 use encoding_rs::{Encoding, UTF_8};
+#[cfg(feature = "bigint")]
 use num_bigint::BigInt;
 #[cfg(not(feature = "sonic"))]
 use serde_json::{from_value, json, to_string, Value};
 #[cfg(feature = "sonic")]
 use sonic_rs::{from_value, json, prelude::*, to_string, Value};
-use std::{cell::UnsafeCell, mem::transmute, rc::Rc};
+use std::{
+    cell::UnsafeCell,
+    cmp::Reverse,
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Maximum nesting depth of `Loader::hardened()`.
+pub const HARDENED_MAX_DEPTH: usize = 64;
+/// Maximum length of a single string, Array, Hash or object of `Loader::hardened()`.
+pub const HARDENED_MAX_LENGTH: usize = 1 << 20;
+/// Memory budget of `Loader::hardened()`, in bytes.
+pub const HARDENED_MEMORY_BUDGET: usize = 1 << 28;
+/// Class name, that disallowed classes are replaced with by `DisallowedClassPolicy::Placeholder`. Ruby can't load it, unless it's defined.
+pub const PLACEHOLDER_CLASS: &str = "MarshalRs::DisallowedClass";
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum StringMode {
     UTF8,
     Binary,
 }
 
+/// Defines how duplicate Hash keys, instance variables and struct members are handled.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum DuplicateKeyPolicy {
+    /// Return an error.
+    Error,
+    /// Keep the first value.
+    FirstWins,
+    /// Keep the last value.
+    #[default]
+    LastWins,
+    /// Keep the last value, and collect the overwritten ones, which can be retrieved with `Loader::duplicates()`.
+    Collect,
+}
+
+/// Defines how classes, that aren't allowed by `LoaderBuilder::allowed_classes()` or are denied by `LoaderBuilder::denied_classes()`, are handled.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum DisallowedClassPolicy {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Replace the class name with `PLACEHOLDER_CLASS`, keeping the data of the object, and add a warning, which can be retrieved with `Loader::warnings()`.
+    Placeholder,
+}
+
+/// Defines how NaN and infinite Floats, that JSON numbers can't represent, are loaded.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum NonFiniteFloatPolicy {
+    /// Load them as `null`, which is dumped back as `nil`.
+    #[default]
+    Null,
+    /// Load them as `"NaN"`, `"Infinity"` and `"-Infinity"` strings, like JavaScript names them. They're dumped back as strings.
+    String,
+    /// Load them as `{ "__type": "float", "value": "nan" }` objects, with `"nan"`, `"inf"` or `"-inf"` values, which are dumped back as Floats.
+    Tagged,
+    /// Return an error.
+    Error,
+}
+
+/// Loader configurations, appropriate for data of specific ecosystems.
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum Preset {
+    /// RPG Maker XP data (`.rxdata`). It's written by Ruby 1.8, which doesn't mark strings' encodings, so strings are decoded as UTF-8, when valid.
+    RpgMakerXp,
+    /// RPG Maker VX data (`.rvdata`). Like RPG Maker XP, it's written by Ruby 1.8, so strings are decoded as UTF-8, when valid.
+    RpgMakerVx,
+    /// RPG Maker VX Ace data (`.rvdata2`). Strings are decoded according to their encodings.
+    RpgMakerVxAce,
+    /// Entries of Rails cache stores. As Ruby never writes duplicate Hash keys, they're treated as a sign of corrupted data and rejected.
+    RailsCache,
+}
+
 type ComplexRc = Rc<UnsafeCell<Value>>;
+pub(crate) type SymbolTable = Vec<Value>;
+/// Objects, in the order of their appearance. Only objects, that are referenced by links, are stored.
+type ObjectTable = Vec<Option<ComplexRc>>;
+
+/// Structure, read by the Loader.
+///
+/// Objects, that are referenced by links, are shared with the objects table, so links can copy them, including ones, that are still being read.
+/// Other values are owned, and are moved into their parents without copying.
+enum Node {
+    Owned(Value),
+    Shared(ComplexRc),
+}
+
+impl Node {
+    fn get(&self) -> &Value {
+        match self {
+            Node::Owned(value) => value,
+            Node::Shared(rc) => unsafe { &*rc.get() },
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut Value {
+        match self {
+            Node::Owned(value) => value,
+            Node::Shared(rc) => unsafe { &mut *rc.get() },
+        }
+    }
+
+    /// Returns the value, copying it, if it's still shared.
+    fn into_value(self) -> Value {
+        match self {
+            Node::Owned(value) => value,
+            Node::Shared(rc) => match Rc::try_unwrap(rc) {
+                Ok(cell) => cell.into_inner(),
+                Err(rc) => unsafe { &*rc.get() }.clone(),
+            },
+        }
+    }
+}
+
+/// Walks the structure of a document without building values, to find objects, that are referenced by links.
+struct LinkScanner<'b> {
+    reader: Reader<'b>,
+    /// Whether each object, in the order of their appearance, is referenced by a link.
+    linked: Vec<bool>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'b> LinkScanner<'b> {
+    fn length(&mut self) -> Result<usize, LoadError> {
+        self.reader.read_length()
+    }
+
+    fn pairs(&mut self) -> Result<(), LoadError> {
+        for _ in 0..self.length()? {
+            self.value()?;
+            self.value()?;
+        }
+
+        Ok(())
+    }
+
+    /// Scans the next structure, registering objects in the same order, as the Loader does.
+    fn value(&mut self) -> Result<(), LoadError> {
+        self.depth += 1;
+
+        if self.depth > self.max_depth {
+            return Err(LoadError {
+                message: "Nesting depth exceeds the limit.".to_string(),
+                ..Default::default()
+            });
+        }
+
+        match self.reader.read_type()? {
+            Constants::Nil | Constants::True | Constants::False => {}
+            Constants::Fixnum | Constants::Symlink => {
+                self.reader.read_int()?;
+            }
+            Constants::Link => {
+                let index: Option<usize> = usize::try_from(self.reader.read_int()?).ok();
+
+                match index.and_then(|index| self.linked.get_mut(index)) {
+                    Some(linked) => *linked = true,
+                    None => {
+                        return Err(LoadError {
+                            message: "Invalid link.".to_string(),
+                            ..Default::default()
+                        })
+                    }
+                }
+            }
+            Constants::Symbol => {
+                self.reader.read_chunk()?;
+            }
+            Constants::Float
+            | Constants::String
+            | Constants::Class
+            | Constants::Module
+            | Constants::ModuleOld => {
+                self.reader.read_chunk()?;
+                self.linked.push(false);
+            }
+            Constants::Regexp => {
+                self.reader.read_chunk()?;
+                self.reader.read_byte()?;
+                self.linked.push(false);
+            }
+            Constants::Bignum => {
+                self.reader.read_byte()?;
+                let length: usize = self.length()?;
+                self.reader.read_bytes(length * 2)?;
+                self.linked.push(false);
+            }
+            Constants::InstanceVar => {
+                self.value()?;
+                self.pairs()?;
+            }
+            Constants::Extended => {
+                self.value()?;
+                self.value()?;
+            }
+            Constants::Array => {
+                self.linked.push(false);
+
+                for _ in 0..self.length()? {
+                    self.value()?;
+                }
+            }
+            Constants::Hash => {
+                self.linked.push(false);
+                self.pairs()?;
+            }
+            Constants::HashDefault => {
+                self.linked.push(false);
+                self.pairs()?;
+                self.value()?;
+            }
+            Constants::Object | Constants::Struct => {
+                self.value()?;
+                self.linked.push(false);
+                self.pairs()?;
+            }
+            Constants::Data | Constants::UserClass | Constants::UserMarshal => {
+                self.value()?;
+                self.linked.push(false);
+                self.value()?;
+            }
+            Constants::UserDefined => {
+                self.value()?;
+                self.linked.push(false);
+                self.reader.read_chunk()?;
+            }
+            _ => unreachable!(),
+        }
 
-#[derive(Debug)]
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+/// Returns whether each object of the document, in the order of their appearance, is referenced by a link, or None, if the document can't be scanned.
+pub(crate) fn scan_links(buffer: &[u8]) -> Option<Vec<bool>> {
+    let mut scanner: LinkScanner = LinkScanner {
+        reader: Reader::new(buffer),
+        linked: Vec::new(),
+        depth: 0,
+        max_depth: usize::MAX,
+    };
+
+    scanner.reader.read_version().ok()?;
+    scanner.value().ok()?;
+    Some(scanner.linked)
+}
+
+/// Estimates the number of heap bytes, that the value owns. If `deep` is false, nested values are only counted as slots of their containers.
+fn heap_size(value: &Value, deep: bool) -> usize {
+    let slot: usize = std::mem::size_of::<Value>();
+
+    if let Some(string) = value.as_str() {
+        string.len()
+    } else if let Some(array) = value.as_array() {
+        array.len() * slot
+            + if deep {
+                array.iter().map(|element| heap_size(element, true)).sum()
+            } else {
+                0
+            }
+    } else if let Some(object) = value.as_object() {
+        object
+            .iter()
+            .map(|(key, entry)| {
+                key.len()
+                    + std::mem::size_of::<String>()
+                    + slot
+                    + if deep { heap_size(entry, true) } else { 0 }
+            })
+            .sum()
+    } else {
+        0
+    }
+}
+
+/// Kind of `LoadError`, for errors, that callers may need to tell apart from others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum LoadErrorKind {
+    /// Error, that's only described by its message.
+    #[default]
+    Other,
+    /// Length of a string, symbol or structure at `offset` is negative, or larger than the remaining data can hold.
+    InvalidLength { value: i32, offset: usize },
+}
+
+#[derive(Debug, Default)]
 pub struct LoadError {
-    message: String,
+    pub(crate) message: String,
+    pub(crate) kind: LoadErrorKind,
+}
+
+impl LoadError {
+    /// Returns the kind of the error.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{load, load::LoadErrorKind};
+    ///
+    /// // Array of -1 elements
+    /// let err = load(b"\x04\x08[\xfa", None, None).unwrap_err();
+    ///
+    /// assert_eq!(err.kind(), LoadErrorKind::InvalidLength { value: -1, offset: 3 });
+    /// ```
+    pub fn kind(&self) -> LoadErrorKind {
+        self.kind
+    }
 }
 
 impl std::fmt::Display for LoadError {
@@ -33,13 +337,76 @@ impl std::fmt::Display for LoadError {
 
 impl std::error::Error for LoadError {}
 
+/// Statistics of a single structure type, collected by a profiling Loader.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    /// Number of structures of the type.
+    pub count: usize,
+    /// Total number of bytes, that structures of the type occupy, including nested structures.
+    pub bytes: usize,
+    /// Total time, spent reading structures of the type, including nested structures.
+    pub time: Duration,
+    /// Total time, spent reading structures of the type, excluding nested structures.
+    pub self_time: Duration,
+}
+
+/// Statistics of the last load, grouped by structure types, collected when `LoaderBuilder::profile()` is enabled.
+///
+/// As bytes and time include nested structures, the same bytes are counted for every type, that they're nested in.
+/// Strings with encodings, objects with instance variables, and `_dump` objects with them are counted under both `Constants::InstanceVar` and their own types.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadProfile {
+    pub types: BTreeMap<Constants, TypeStats>,
+}
+
+impl LoadProfile {
+    /// Returns structure types sorted by time, spent reading them excluding nested structures, from the largest.
+    pub fn by_self_time(&self) -> Vec<(Constants, &TypeStats)> {
+        let mut types: Vec<(Constants, &TypeStats)> = self
+            .types
+            .iter()
+            .map(|(&structure_type, stats)| (structure_type, stats))
+            .collect();
+
+        types.sort_by_key(|(_, stats)| Reverse(stats.self_time));
+        types
+    }
+}
+
 pub struct Loader<'a> {
     buffer: &'a [u8],
     byte_position: usize,
-    symbols: Vec<ComplexRc>,
-    objects: Vec<ComplexRc>,
+    symbols: SymbolTable,
+    linked: Vec<bool>,
+    all_linked: bool,
     instance_var_prefix: Option<&'a str>,
     string_mode: Option<StringMode>,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    non_finite_float_policy: NonFiniteFloatPolicy,
+    duplicates: Vec<(String, Value)>,
+    warnings: Vec<String>,
+    default_string_mode: Option<StringMode>,
+    default_instance_var_prefix: Option<&'a str>,
+    strict: bool,
+    lossless: bool,
+    max_depth: Option<usize>,
+    max_length: Option<usize>,
+    allowed_classes: Option<&'a [&'a str]>,
+    denied_classes: &'a [&'a str],
+    disallowed_class_policy: DisallowedClassPolicy,
+    depth: usize,
+    track_allocations: bool,
+    memory_budget: Option<usize>,
+    allocated: usize,
+    pool: Option<&'a TablePool>,
+    cancel_flag: Option<&'a AtomicBool>,
+    data_registry: Option<&'a DataRegistry>,
+    symbol_keys_as_strings: bool,
+    struct_members_as_strings: bool,
+    profile: Option<LoadProfile>,
+    detected_version: Option<MarshalVersion>,
+    /// Time, spent reading the structures, nested in the structure being read.
+    nested_time: Duration,
 }
 
 impl<'a> Loader<'a> {
@@ -48,22 +415,200 @@ impl<'a> Loader<'a> {
             buffer: &[],
             byte_position: 0,
             symbols: Vec::new(),
-            objects: Vec::new(),
+            linked: Vec::new(),
+            all_linked: false,
             instance_var_prefix: None,
             string_mode: None,
+            duplicate_key_policy: DuplicateKeyPolicy::LastWins,
+            non_finite_float_policy: NonFiniteFloatPolicy::Null,
+            duplicates: Vec::new(),
+            warnings: Vec::new(),
+            default_string_mode: None,
+            default_instance_var_prefix: None,
+            strict: false,
+            lossless: false,
+            max_depth: None,
+            max_length: None,
+            allowed_classes: None,
+            denied_classes: &[],
+            disallowed_class_policy: DisallowedClassPolicy::Error,
+            depth: 0,
+            track_allocations: false,
+            memory_budget: None,
+            allocated: 0,
+            pool: None,
+            cancel_flag: None,
+            data_registry: None,
+            symbol_keys_as_strings: false,
+            struct_members_as_strings: false,
+            profile: None,
+            detected_version: None,
+            nested_time: Duration::ZERO,
         }
     }
 
+    /// Returns a Loader for untrusted data, configured with `LoaderBuilder::hardened()`.
+    ///
+    /// It rejects malformed data, duplicate keys and lossy conversions, enforces conservative limits, and doesn't accept any classes.
+    /// To accept specific classes, use `Loader::builder().hardened().allowed_classes()` instead.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::Loader;
+    /// use serde_json::json;
+    ///
+    /// let mut loader = Loader::hardened();
+    ///
+    /// assert_eq!(loader.load(b"\x04\x08[\x06i\x06", None, None).unwrap(), json!([1]));
+    /// // Object.new
+    /// assert!(loader.load(b"\x04\x08o:\x0bObject\x00", None, None).is_err());
+    /// ```
+    pub fn hardened() -> Self {
+        LoaderBuilder::new().hardened().build()
+    }
+
+    /// Returns a builder, that configures the Loader.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{load::Preset, Loader};
+    /// use serde_json::json;
+    ///
+    /// let mut loader = Loader::builder().preset(Preset::RpgMakerXp).build();
+    ///
+    /// // String without encoding
+    /// assert_eq!(loader.load(b"\x04\x08\"\x06a", None, None).unwrap(), json!("a"));
+    /// ```
+    pub fn builder() -> LoaderBuilder<'a> {
+        LoaderBuilder::new()
+    }
+
+    /// Sets the policy of handling duplicate Hash keys, instance variables and struct members. Defaults to `DuplicateKeyPolicy::LastWins`.
+    pub fn set_duplicate_key_policy(&mut self, policy: DuplicateKeyPolicy) {
+        self.duplicate_key_policy = policy;
+    }
+
+    /// Sets the policy of loading NaN and infinite Floats. Defaults to `NonFiniteFloatPolicy::Null`.
+    pub fn set_non_finite_float_policy(&mut self, policy: NonFiniteFloatPolicy) {
+        self.non_finite_float_policy = policy;
+    }
+
+    /// Restricts classes and modules of objects, structs, class references and extensions to the list. See `LoaderBuilder::allowed_classes()`.
+    pub fn set_allowed_classes(&mut self, classes: &'a [&'a str]) {
+        self.allowed_classes = Some(classes);
+    }
+
+    /// Rejects classes and modules in the list, even if they're allowed. See `LoaderBuilder::denied_classes()`.
+    pub fn set_denied_classes(&mut self, classes: &'a [&'a str]) {
+        self.denied_classes = classes;
+    }
+
+    /// Sets the policy of handling disallowed classes. Defaults to `DisallowedClassPolicy::Error`.
+    pub fn set_disallowed_class_policy(&mut self, policy: DisallowedClassPolicy) {
+        self.disallowed_class_policy = policy;
+    }
+
+    /// Sets whether Hashes, keyed only by symbols, are loaded with plain string keys, like `{ "name": 1 }` instead of `{ "__symbol__name": 1 }`. Defaults to false.
+    ///
+    /// Such Hashes are marked with `"__ruby_symbol_keys__": true` entry, so they're dumped back with symbol keys. Hashes with keys of other types, or symbols, that start with "__", are loaded as usual.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{dump, Loader};
+    /// use serde_json::json;
+    ///
+    /// let mut loader = Loader::new();
+    /// loader.set_symbol_keys_as_strings(true);
+    ///
+    /// // { name: "Sword" }
+    /// let bytes: &[u8] = b"\x04\x08{\x06:\x09nameI\"\x0aSword\x06:\x06ET";
+    /// let value = loader.load(bytes, None, None).unwrap();
+    ///
+    /// assert_eq!(value, json!({ "name": "Sword", "__ruby_symbol_keys__": true }));
+    /// assert_eq!(dump(value, None), bytes);
+    /// ```
+    pub fn set_symbol_keys_as_strings(&mut self, enabled: bool) {
+        self.symbol_keys_as_strings = enabled;
+    }
+
+    /// Sets whether names of struct members are loaded as plain strings, like `{ "x": 1 }` instead of `{ "__symbol__x": 1 }`, keeping their order. Defaults to false.
+    ///
+    /// Members are always symbols, so the Dumper writes both forms the same. Names, that start with "__", keep their prefixes.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{dump, Loader};
+    /// use serde_json::json;
+    ///
+    /// let mut loader = Loader::new();
+    /// loader.set_struct_members_as_strings(true);
+    ///
+    /// // Point.new(1, 2)
+    /// let bytes: &[u8] = b"\x04\x08S:\x0aPoint\x07:\x06xi\x06:\x06yi\x07";
+    /// let value = loader.load(bytes, None, None).unwrap();
+    ///
+    /// assert_eq!(value["__members"], json!({ "x": 1, "y": 2 }));
+    /// assert_eq!(dump(value, None), bytes);
+    /// ```
+    pub fn set_struct_members_as_strings(&mut self, enabled: bool) {
+        self.struct_members_as_strings = enabled;
+    }
+
+    /// Sets the flag, that cancels the load in progress, when it's set to true. See `LoaderBuilder::cancel_flag()`.
+    pub fn set_cancel_flag(&mut self, flag: &'a AtomicBool) {
+        self.cancel_flag = Some(flag);
+    }
+
+    /// Returns the warnings, produced by the last load.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the estimated number of bytes, allocated for the Value of the last load, if allocations are tracked, or 0 otherwise.
+    ///
+    /// The estimate counts strings, Array slots and object entries, including copies of linked objects, but not the allocator's overhead.
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Returns the statistics of the last load per structure type, if profiling is enabled with `LoaderBuilder::profile()`.
+    pub fn profile(&self) -> Option<&LoadProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Returns the version, that the buffer of the last load started with, even if it's not supported, or None, if the buffer was too short.
+    ///
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{raw::MarshalVersion, Loader};
+    ///
+    /// let mut loader = Loader::new();
+    /// let error = loader.load(b"\x04\x060", None, None).unwrap_err();
+    ///
+    /// assert_eq!(loader.detected_version(), Some(MarshalVersion { major: 4, minor: 6 }));
+    /// assert!(error.to_string().contains("found 4.6"));
+    /// ```
+    pub fn detected_version(&self) -> Option<MarshalVersion> {
+        self.detected_version
+    }
+
+    /// Returns the keys and overwritten values of duplicates, collected during the last load with `DuplicateKeyPolicy::Collect`.
+    pub fn duplicates(&self) -> &[(String, Value)] {
+        &self.duplicates
+    }
+
     /// Serializes Ruby Marshal byte stream to JSON.
     ///
+    /// buffer argument takes anything, that can be viewed as a bytes slice, like `Vec<u8>`, or `bytes::Bytes` received from network, so it doesn't have to be copied.
+    /// It's only borrowed for the duration of the call, so the same Loader can be reused for buffers, that are dropped between loads.
+    ///
     /// string_mode arguments takes a StringMode enum value, and decodes strings either as binary data or as string objects.
     ///
     /// instance_var_prefix argument takes a string, and replaces instance variables' "@" prefixes by this string.
     ///
+    /// If arguments are None, values, configured with `LoaderBuilder`, are used.
+    ///
     /// Returns a Result, indicating whether load was successful or not.
     /// Returns an Err when:
     /// * Passed byte stream is of non-4.8 Marshal version (indicated by two first bytes).
     /// * Passed byte stream's data is invalid.
+    /// * Passed byte stream's data violates limits or restrictions, configured with `LoaderBuilder`.
     /// # Example
     /// ```rust
     /// use marshal_rs::Loader;
@@ -81,44 +626,199 @@ impl<'a> Loader<'a> {
     /// let json: serde_json::Value = loader.load(&bytes, None, None).unwrap();
     /// assert_eq!(json, json!(null));
     /// ```
-    pub fn load(
+    pub fn load<'b, B: AsRef<[u8]> + ?Sized>(
         &mut self,
-        buffer: &'a [u8],
+        buffer: &'b B,
         string_mode: Option<StringMode>,
-        instance_var_prefix: Option<&'a str>,
-    ) -> Result<Value, LoadError> {
-        self.buffer = buffer;
-        self.string_mode = string_mode;
-        self.instance_var_prefix = instance_var_prefix;
-
-        let marshal_version: u16 = u16::from_be_bytes(if let Some(bytes) = self.buffer.get(0..2) {
-            bytes.try_into().unwrap()
-        } else {
+        instance_var_prefix: Option<&'b str>,
+    ) -> Result<Value, LoadError>
+    where
+        'a: 'b,
+    {
+        let (value, length) = self.load_with_length(buffer, string_mode, instance_var_prefix)?;
+
+        if self.strict && length < buffer.as_ref().len() {
             return Err(LoadError {
-                message: "Marshal data is too short. Wasn't even able to read starting version \
-                          bytes."
-                    .to_string(),
+                message: format!(
+                    "Unexpected data after the end of Marshal data at position {length}."
+                ),
+                ..Default::default()
             });
-        });
+        }
 
-        if marshal_version != MARSHAL_VERSION {
-            return Err(LoadError {
-                message: "Incompatible Marshal file format or version.".to_string(),
-            });
+        Ok(value)
+    }
+
+    /// Like `load()`, but also returns the number of bytes, that the loaded Marshal document occupies.
+    ///
+    /// Bytes after the document are ignored, so files with multiple consecutive documents, like RPG Maker saves, can be loaded document by document.
+    pub fn load_with_length<'b, B: AsRef<[u8]> + ?Sized>(
+        &mut self,
+        buffer: &'b B,
+        string_mode: Option<StringMode>,
+        instance_var_prefix: Option<&'b str>,
+    ) -> Result<(Value, usize), LoadError>
+    where
+        'a: 'b,
+    {
+        let mut loader: Loader<'b> = self.borrow_for(buffer.as_ref());
+        let result: Result<(Value, usize), LoadError> =
+            loader.load_buffer(string_mode, instance_var_prefix);
+
+        self.symbols = loader.symbols;
+        self.linked = loader.linked;
+        self.duplicates = loader.duplicates;
+        self.warnings = loader.warnings;
+        self.allocated = loader.allocated;
+        self.profile = loader.profile;
+        self.detected_version = loader.detected_version;
+
+        result
+    }
+
+    /// Returns a Loader with the same configuration and tables, that reads the buffer.
+    ///
+    /// The buffer is never stored in this Loader, so it doesn't have to outlive the Loader, and the same Loader can read buffers of any lifetimes.
+    /// Tables are moved back by `load_with_length()`, so their allocations are reused.
+    fn borrow_for<'b>(&mut self, buffer: &'b [u8]) -> Loader<'b>
+    where
+        'a: 'b,
+    {
+        Loader {
+            buffer,
+            byte_position: 0,
+            symbols: std::mem::take(&mut self.symbols),
+            linked: std::mem::take(&mut self.linked),
+            all_linked: false,
+            instance_var_prefix: None,
+            string_mode: None,
+            duplicate_key_policy: self.duplicate_key_policy,
+            non_finite_float_policy: self.non_finite_float_policy,
+            duplicates: std::mem::take(&mut self.duplicates),
+            warnings: std::mem::take(&mut self.warnings),
+            default_string_mode: self.default_string_mode,
+            default_instance_var_prefix: self.default_instance_var_prefix,
+            strict: self.strict,
+            lossless: self.lossless,
+            max_depth: self.max_depth,
+            max_length: self.max_length,
+            allowed_classes: self.allowed_classes,
+            denied_classes: self.denied_classes,
+            disallowed_class_policy: self.disallowed_class_policy,
+            depth: 0,
+            track_allocations: self.track_allocations,
+            memory_budget: self.memory_budget,
+            allocated: 0,
+            pool: self.pool,
+            cancel_flag: self.cancel_flag,
+            data_registry: self.data_registry,
+            symbol_keys_as_strings: self.symbol_keys_as_strings,
+            struct_members_as_strings: self.struct_members_as_strings,
+            profile: self.profile.take(),
+            detected_version: None,
+            nested_time: Duration::ZERO,
         }
+    }
 
-        self.byte_position += 2;
+    /// Reads the document from the buffer, that the Loader was created for by `borrow_for()`.
+    fn load_buffer(
+        &mut self,
+        string_mode: Option<StringMode>,
+        instance_var_prefix: Option<&'a str>,
+    ) -> Result<(Value, usize), LoadError> {
+        self.string_mode = string_mode.or(self.default_string_mode);
+        self.instance_var_prefix = instance_var_prefix.or(self.default_instance_var_prefix);
+        self.duplicates.clear();
+        self.warnings.clear();
+
+        // Previous load might've failed midway, leaving its state behind
+        self.symbols.clear();
+        self.byte_position = 0;
+        self.depth = 0;
+        self.allocated = 0;
+
+        if let Some(profile) = &mut self.profile {
+            profile.types.clear();
+        }
+
+        if let Some(pool) = self.pool {
+            self.symbols = pool.take_loader_table();
+        }
 
-        let read: ComplexRc = self.read_next()?;
+        self.find_links();
+
+        let mut state: LoadState = LoadState {
+            loader: self,
+            objects: Vec::new(),
+        };
+        let result: Result<(Node, usize), LoadError> = state.read_document();
+
+        // Objects, shared with the root, are dropped with the table
+        drop(state);
 
         self.symbols.clear();
-        self.objects.clear();
         self.byte_position = 0;
 
-        // We just cleared all of the references to this Rc, and can safely unsafely unwrap
-        let value: Value = unsafe { Rc::try_unwrap(read).unwrap_unchecked().into_inner() };
+        if let Some(pool) = self.pool {
+            pool.give_loader_table(std::mem::take(&mut self.symbols));
+        }
 
-        Ok(value)
+        let (read, length) = result?;
+
+        // We just cleared all of the references to the root, so it's moved out without copying
+        Ok((read.into_value(), length))
+    }
+
+    /// Finds objects of the document, that are referenced by links.
+    ///
+    /// If the document can't be scanned, all objects are treated as linked, and the Loader reports the error, when it reaches it.
+    fn find_links(&mut self) {
+        let mut scanner: LinkScanner = LinkScanner {
+            reader: Reader::new(self.buffer),
+            linked: std::mem::take(&mut self.linked),
+            depth: 0,
+            max_depth: self.max_depth.unwrap_or(usize::MAX),
+        };
+
+        scanner.linked.clear();
+        self.all_linked = scanner
+            .reader
+            .read_version()
+            .and_then(|_| scanner.value())
+            .is_err();
+        self.linked = scanner.linked;
+    }
+
+    /// Resolves the conflict, if the key is already present in the object, according to the duplicate key policy.
+    ///
+    /// Returns whether the new value should be written.
+    fn resolve_duplicate(&mut self, object: &Value, key: &str) -> Result<bool, LoadError> {
+        let existing: &Value = if let Some(existing) = object.get(key) {
+            existing
+        } else {
+            return Ok(true);
+        };
+
+        if self.duplicate_key_policy == DuplicateKeyPolicy::Error {
+            return Err(LoadError {
+                message: format!(
+                    "Duplicate key {key} before position {}.",
+                    self.byte_position
+                ),
+                ..Default::default()
+            });
+        }
+
+        self.warnings.push(format!(
+            "Duplicate key {key} before position {}.",
+            self.byte_position
+        ));
+
+        if self.duplicate_key_policy == DuplicateKeyPolicy::Collect {
+            self.duplicates.push((key.to_string(), existing.clone()));
+        }
+
+        Ok(self.duplicate_key_policy != DuplicateKeyPolicy::FirstWins)
     }
 
     fn read_byte(&mut self) -> Result<u8, LoadError> {
@@ -127,6 +827,7 @@ impl<'a> Loader<'a> {
         } else {
             return Err(LoadError {
                 message: "Marshal data is too short.".to_string(),
+                ..Default::default()
             });
         };
 
@@ -136,8 +837,9 @@ impl<'a> Loader<'a> {
 
     fn read_bytes(&mut self, amount: usize) -> Result<&[u8], LoadError> {
         let bytes: &[u8] = if let Some(bytes) = self
-            .buffer
-            .get(self.byte_position..self.byte_position + amount)
+            .byte_position
+            .checked_add(amount)
+            .and_then(|end| self.buffer.get(self.byte_position..end))
         {
             bytes
         } else {
@@ -146,6 +848,7 @@ impl<'a> Loader<'a> {
                     "Marshal data is too short. Last position: {}",
                     self.byte_position
                 ),
+                ..Default::default()
             });
         };
 
@@ -154,292 +857,792 @@ impl<'a> Loader<'a> {
     }
 
     fn read_fixnum(&mut self) -> Result<i32, LoadError> {
-        let fixnum_length: i8 = self.read_byte()? as i8;
-
-        Ok(match fixnum_length {
-            // Fixnum is zero
-            0 => 0,
-            // These values mark the length of fixnum in bytes
-            -4..=4 => {
-                let absolute: i8 = fixnum_length.abs();
-                let bytes: &[u8] = self.read_bytes(absolute as usize)?;
-                let mut buffer: [u8; 4] = [if fixnum_length < 0 { 255u8 } else { 0u8 }; 4];
-
-                let len: usize = bytes.len().min(4);
-                buffer[..len].copy_from_slice(&bytes[..len]);
-
-                i32::from_le_bytes(buffer)
-            }
-            // Otherwise fixnum is a single byte and we read it
-            _ => {
-                if fixnum_length > 0 {
-                    (fixnum_length - 5) as i32
-                } else {
-                    (fixnum_length + 5) as i32
-                }
+        let mut reader: Reader = Reader::new(self.buffer);
+        reader.set_position(self.byte_position);
+
+        let fixnum: i32 = reader.read_int()?;
+        self.byte_position = reader.position();
+        Ok(fixnum)
+    }
+
+    /// Reads the length of a structure, checking it against the length limit.
+    fn read_length(&mut self) -> Result<usize, LoadError> {
+        let position: usize = self.byte_position;
+        let length: i32 = self.read_fixnum()?;
+
+        let length: usize =
+            usize::try_from(length).map_err(|_| invalid_length(length, position))?;
+
+        if let Some(max_length) = self.max_length {
+            if length > max_length {
+                return Err(LoadError {
+                    message: format!(
+                        "Length {length} exceeds the limit of {max_length} at position {position}."
+                    ),
+                    ..Default::default()
+                });
             }
-        })
+        }
+
+        Ok(length)
+    }
+
+    /// Reads the number of elements, entries or 16-bit words of a structure, checking, that the remaining data can hold them, as each of them takes at least one byte.
+    fn read_count(&mut self) -> Result<usize, LoadError> {
+        let position: usize = self.byte_position;
+        let count: usize = self.read_length()?;
+
+        if count > self.buffer.len().saturating_sub(self.byte_position) {
+            return Err(invalid_length(count as i32, position));
+        }
+
+        Ok(count)
     }
 
     fn read_chunk(&mut self) -> Result<&[u8], LoadError> {
-        let amount: i32 = self.read_fixnum()?;
-        self.read_bytes(amount as usize)
+        let amount: usize = self.read_length()?;
+        self.read_bytes(amount)
     }
 
     fn read_string(&mut self) -> Result<String, LoadError> {
+        let position: usize = self.byte_position;
+        let lossless: bool = self.lossless;
         let chunk: &[u8] = self.read_chunk()?;
+
+        if lossless {
+            return String::from_utf8(chunk.to_vec()).map_err(|_| LoadError {
+                message: format!("Invalid UTF-8 name at position {position}."),
+                ..Default::default()
+            });
+        }
+
         Ok(String::from_utf8_lossy(chunk).to_string())
     }
 
-    fn read_next(&mut self) -> Result<ComplexRc, LoadError> {
-        let structure_type: Constants = unsafe { transmute(self.read_byte()?) };
-        Ok(match structure_type {
-            Constants::Nil => Rc::from(UnsafeCell::from(json!(null))),
-            Constants::True => Rc::from(UnsafeCell::from(Value::from(true))),
-            Constants::False => Rc::from(UnsafeCell::from(Value::from(false))),
-            Constants::Fixnum => Rc::from(UnsafeCell::from(Value::from(self.read_fixnum()?))),
-            Constants::Symlink => {
-                let pos: i32 = self.read_fixnum()?;
-                self.symbols[pos as usize].clone()
-            }
-            Constants::Link => {
-                let pos: i32 = self.read_fixnum()?;
-                self.objects[pos as usize].clone()
-            }
-            Constants::Symbol => {
-                let prefix: String = String::from("__symbol__");
-                let symbol: &String = &self.read_string()?;
+    /// Returns whether the class is allowed. If it's not, returns an Err, or, with `DisallowedClassPolicy::Placeholder`, adds a warning and returns false.
+    fn check_class(&mut self, class: &str) -> Result<bool, LoadError> {
+        let class: &str = class.strip_prefix("__symbol__").unwrap_or(class);
 
-                let symbol: Value = ((prefix + symbol).as_str()).into();
+        if !self.denied_classes.contains(&class)
+            && self
+                .allowed_classes
+                .map_or(true, |allowed| allowed.contains(&class))
+        {
+            return Ok(true);
+        }
 
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(symbol));
-                self.symbols.push(rc.clone());
-                rc
-            }
-            Constants::InstanceVar => {
-                let object: ComplexRc = self.read_next()?;
-                let size: i32 = self.read_fixnum()?;
+        let message: String = format!(
+            "Class {class} is not allowed before position {}.",
+            self.byte_position
+        );
 
-                for _ in 0..size {
-                    let key: ComplexRc = self.read_next()?;
-                    let mut value: Option<Vec<u8>> = None;
+        if self.disallowed_class_policy == DisallowedClassPolicy::Error {
+            return Err(LoadError {
+                message,
+                ..Default::default()
+            });
+        }
 
-                    if let Some(data) = unsafe { &mut *self.read_next()?.get() }.get_mut("data") {
-                        #[cfg(feature = "sonic")]
-                        {
-                            value = from_value(data).unwrap();
-                        }
-                        #[cfg(not(feature = "sonic"))]
-                        {
-                            value = from_value(data.take()).unwrap();
+        self.warnings.push(message);
+        Ok(false)
+    }
+
+    /// Replaces symbol keys of the Hash with plain strings and marks it, if all of its keys are symbols, that don't start with "__".
+    fn stringify_symbol_keys(hash: &mut Value) {
+        let keys: Vec<String> = match hash.as_object() {
+            Some(object) if !object.is_empty() => {
+                object.iter().map(|(key, _)| key.to_string()).collect()
+            }
+            _ => return,
+        };
+
+        if !keys.iter().all(|key| {
+            key.strip_prefix("__symbol__")
+                .map_or(false, |name| !name.starts_with("__"))
+        }) {
+            return;
+        }
+
+        let mut stringified: Value = json!({});
+
+        for key in keys {
+            stringified[&key["__symbol__".len()..]] = hash[&key].take();
+        }
+
+        stringified[SYMBOL_KEYS_SYMBOL] = true.into();
+        *hash = stringified;
+    }
+
+    /// Converts the value, that `_dump_data` of the class returned, with the handler from the data registry, if there's one.
+    fn load_data(&self, class: Option<&str>, data: Value) -> Result<Value, LoadError> {
+        let class: &str = class.unwrap_or_default();
+
+        match self.data_registry.and_then(|registry| registry.get(class)) {
+            Some(handler) => handler.load(data).map_err(|message| LoadError {
+                message: format!(
+                    "Data handler of {} failed: {message}",
+                    class.trim_start_matches("__symbol__")
+                ),
+                ..Default::default()
+            }),
+            None => Ok(data),
+        }
+    }
+
+    /// Adds the structure, that started at the position, to the profile.
+    fn record(
+        &mut self,
+        structure_type: Option<u8>,
+        position: usize,
+        started: Instant,
+        outer_nested_time: Duration,
+    ) {
+        let elapsed: Duration = started.elapsed();
+
+        if let (Some(profile), Some(structure_type)) = (
+            &mut self.profile,
+            structure_type.and_then(|byte| Constants::try_from(byte).ok()),
+        ) {
+            let stats: &mut TypeStats = profile.types.entry(structure_type).or_default();
+
+            stats.count += 1;
+            stats.bytes += self.byte_position - position;
+            stats.time += elapsed;
+            stats.self_time += elapsed.saturating_sub(self.nested_time);
+        }
+
+        // For the parent, this structure is nested
+        self.nested_time = outer_nested_time + elapsed;
+    }
+
+    /// Adds the bytes, allocated for the structure, to the allocated amount, checking it against the memory budget.
+    ///
+    /// Links are copied into their parents, so linked objects are counted again in full.
+    /// Containers are counted without their elements, which are counted, when they're read.
+    fn account(&mut self, structure_type: Option<u8>, value: &Value) -> Result<(), LoadError> {
+        let structure_type: Option<Constants> =
+            structure_type.and_then(|byte| Constants::try_from(byte).ok());
+
+        let size: usize = match structure_type {
+            // Values of these are already counted, when they're read
+            Some(Constants::InstanceVar) | Some(Constants::Extended) => 0,
+            Some(Constants::Array)
+            | Some(Constants::Hash)
+            | Some(Constants::HashDefault)
+            | Some(Constants::Object)
+            | Some(Constants::Struct)
+            | Some(Constants::Data)
+            | Some(Constants::UserClass)
+            | Some(Constants::UserMarshal) => heap_size(value, false),
+            _ => heap_size(value, true),
+        };
+
+        self.allocated = self.allocated.saturating_add(size);
+
+        if let Some(budget) = self.memory_budget {
+            if self.allocated > budget {
+                return Err(LoadError {
+                    message: format!(
+                        "Memory budget of {budget} bytes exceeded before position {}.",
+                        self.byte_position
+                    ),
+                    ..Default::default()
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// State of a single load: the Loader and the objects table, whose objects are shared with nodes, that are being read.
+///
+/// The table only lives for the duration of the load, so the Loader itself never holds shared objects, and can be sent to other threads.
+struct LoadState<'l, 'a> {
+    loader: &'l mut Loader<'a>,
+    objects: ObjectTable,
+}
+
+impl<'l, 'a> Deref for LoadState<'l, 'a> {
+    type Target = Loader<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.loader
+    }
+}
+
+impl<'l, 'a> DerefMut for LoadState<'l, 'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.loader
+    }
+}
+
+impl<'l, 'a> LoadState<'l, 'a> {
+    fn read_document(&mut self) -> Result<(Node, usize), LoadError> {
+        self.detected_version = MarshalVersion::detect(self.buffer);
+        check_version(self.buffer)?;
+        self.byte_position += 2;
+
+        let read: Node = self.read_next()?;
+        Ok((read, self.byte_position))
+    }
+
+    /// Reads the class name of an object, checking it against allowed classes.
+    fn read_class(&mut self) -> Result<Value, LoadError> {
+        let class: Value = self.read_next()?.into_value();
+
+        if self.check_class(class.as_str().unwrap_or_default())? {
+            Ok(class)
+        } else {
+            Ok(json!(format!("__symbol__{PLACEHOLDER_CLASS}")))
+        }
+    }
+
+    fn read_link(&mut self, symbol: bool) -> Result<Node, LoadError> {
+        let position: usize = self.byte_position;
+        let index: i32 = self.read_fixnum()?;
+        let index: Option<usize> = usize::try_from(index).ok();
+
+        let node: Option<Node> = if symbol {
+            index
+                .and_then(|index| self.symbols.get(index))
+                .map(|symbol| Node::Owned(symbol.clone()))
+        } else {
+            index
+                .and_then(|index| self.objects.get(index))
+                .and_then(|object| object.clone())
+                .map(Node::Shared)
+        };
+
+        node.ok_or_else(|| LoadError {
+            message: format!(
+                "Invalid link {} at position {position}.",
+                index.unwrap_or(0)
+            ),
+            ..Default::default()
+        })
+    }
+
+    /// Adds the object to the objects table. Only objects, that are referenced by links, are stored, and others are owned by their nodes.
+    fn register(&mut self, value: Value) -> Node {
+        let index: usize = self.objects.len();
+
+        if self.all_linked || self.linked.get(index).copied().unwrap_or(false) {
+            let rc: ComplexRc = Rc::new(UnsafeCell::new(value));
+            self.objects.push(Some(rc.clone()));
+            Node::Shared(rc)
+        } else {
+            self.objects.push(None);
+            Node::Owned(value)
+        }
+    }
+
+    /// Reads the next structure, checking the nesting depth.
+    fn read_next(&mut self) -> Result<Node, LoadError> {
+        if self
+            .cancel_flag
+            .map_or(false, |flag| flag.load(Ordering::Relaxed))
+        {
+            return Err(LoadError {
+                message: format!("Load was cancelled at position {}.", self.byte_position),
+                ..Default::default()
+            });
+        }
+
+        self.depth += 1;
+
+        if let Some(max_depth) = self.max_depth {
+            if self.depth > max_depth {
+                return Err(LoadError {
+                    message: format!(
+                        "Nesting depth exceeds the limit of {max_depth} at position {}.",
+                        self.byte_position
+                    ),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let position: usize = self.byte_position;
+        let structure_type: Option<u8> = self.buffer.get(position).copied();
+        let started: Option<Instant> = self.profile.as_ref().map(|_| Instant::now());
+        let outer_nested_time: Duration = std::mem::take(&mut self.nested_time);
+
+        let result: Result<Node, LoadError> = self.read_structure();
+        self.depth -= 1;
+
+        if let Some(started) = started {
+            self.record(structure_type, position, started, outer_nested_time);
+        }
+
+        let node: Node = result?;
+
+        if self.track_allocations {
+            self.account(structure_type, node.get())?;
+        }
+
+        Ok(node)
+    }
+
+    /// Reads the object with instance variables, converting strings with encodings to JSON strings.
+    fn read_instance_var(&mut self) -> Result<Node, LoadError> {
+        let mut object: Node = self.read_next()?;
+        let size: usize = self.read_count()?;
+
+        for _ in 0..size {
+            let key: Node = self.read_next()?;
+            let mut ivar: Value = self.read_next()?.into_value();
+
+            // Instance variables of `_dump` strings, like Time's `offset` and `zone`, are kept on the object,
+            // so dumper writes them back
+            if object.get().get("__userDefined").is_some() {
+                if let (Some(mut key), Some(map)) = (
+                    key.get().as_str().map(str::to_owned),
+                    object.get_mut().as_object_mut(),
+                ) {
+                    if let Some(prefix) = self.instance_var_prefix {
+                        if key.starts_with("__symbol__@") {
+                            key.replace_range(10..11, prefix);
+                        }
+                    }
+
+                    #[cfg(feature = "sonic")]
+                    map.insert(&key, ivar);
+                    #[cfg(not(feature = "sonic"))]
+                    map.insert(key, ivar);
+                }
+
+                continue;
+            }
+
+            let mut value: Option<Vec<u8>> = None;
+
+            if let Some(data) = ivar.get_mut("data") {
+                #[cfg(feature = "sonic")]
+                {
+                    value = from_value(data).ok();
+                }
+                #[cfg(not(feature = "sonic"))]
+                {
+                    value = from_value(data.take()).ok();
+                }
+            }
+
+            if (object.get()["__type"].as_str() == Some("bytes"))
+                && matches!(
+                    key.get().as_str(),
+                    Some(ENCODING_LONG_SYMBOL) | Some(ENCODING_SHORT_SYMBOL)
+                )
+                && self.string_mode != Some(StringMode::Binary)
+            {
+                let bytes: Value = object.get()["data"].clone();
+                let array: Vec<u8>;
+
+                #[cfg(feature = "sonic")]
+                {
+                    array = from_value(&bytes).map_err(|_| LoadError {
+                            message: format!(
+                                "Encoding instance variable of a value, that isn't a string, before position {}.",
+                                self.byte_position
+                            ),
+                            ..Default::default()
+                        })?;
+                }
+                #[cfg(not(feature = "sonic"))]
+                {
+                    array = from_value(bytes).map_err(|_| LoadError {
+                            message: format!(
+                                "Encoding instance variable of a value, that isn't a string, before position {}.",
+                                self.byte_position
+                            ),
+                            ..Default::default()
+                        })?;
+                }
+
+                if key.get() == ENCODING_SHORT_SYMBOL {
+                    let string: String = match String::from_utf8(array) {
+                        Ok(string) => string,
+                        Err(_) if self.lossless => {
+                            return Err(LoadError {
+                                message: format!(
+                                    "Invalid UTF-8 string before position {}.",
+                                    self.byte_position
+                                ),
+                                ..Default::default()
+                            })
                         }
+                        Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+                    };
+
+                    // `E => false` marks US-ASCII strings, which are kept marked, so they're dumped back the same
+                    if ivar.as_bool() == Some(false) {
+                        *object.get_mut() =
+                            json!({ "__type": "string", "value": string, "encoding": "US-ASCII" });
+                    } else {
+                        *object.get_mut() = string.as_str().into();
+                    }
+                } else {
+                    let label: Vec<u8> = value.unwrap_or_default();
+
+                    // Without encoding tables, the string is kept as is, along with its encoding's name
+                    #[cfg(not(feature = "encodings"))]
+                    {
+                        object.get_mut()["encoding"] =
+                            String::from_utf8_lossy(&label).as_ref().into();
                     }
 
-                    if (unsafe { &*object.get() }["__type"].as_str().unwrap() == "bytes")
-                        && [
-                            Value::from(ENCODING_LONG_SYMBOL),
-                            Value::from(ENCODING_SHORT_SYMBOL),
-                        ]
-                        .contains(unsafe { &*key.get() })
-                        && self.string_mode != Some(StringMode::Binary)
+                    #[cfg(feature = "encodings")]
                     {
-                        let bytes: Value = unsafe { &*object.get() }["data"].clone();
-                        let array: Vec<u8>;
+                        let encoding: Option<&'static Encoding> = Encoding::for_label(&label);
+                        let (cow, _, had_errors) = encoding.unwrap_or(UTF_8).decode(&array);
+
+                        if self.lossless && (encoding.is_none() || had_errors) {
+                            return Err(LoadError {
+                                    message: format!(
+                                        "String in {} encoding can't be decoded without losses before position {}.",
+                                        String::from_utf8_lossy(&label),
+                                        self.byte_position
+                                    ),
+                                    ..Default::default()
+                                });
+                        }
 
                         #[cfg(feature = "sonic")]
                         {
-                            array = from_value(&bytes).unwrap()
+                            *object.get_mut() = cow.into();
                         }
                         #[cfg(not(feature = "sonic"))]
                         {
-                            array = from_value(bytes).unwrap()
-                        }
-
-                        if unsafe { &*key.get() } == ENCODING_SHORT_SYMBOL {
-                            unsafe {
-                                *object.get() = (std::str::from_utf8_unchecked(&array)).into();
-                            }
-                        } else {
-                            let (cow, _, _) = Encoding::for_label(&value.unwrap())
-                                .unwrap_or(UTF_8)
-                                .decode(&array);
-                            unsafe {
-                                #[cfg(feature = "sonic")]
-                                {
-                                    *object.get() = cow.into();
-                                }
-                                #[cfg(not(feature = "sonic"))]
-                                {
-                                    *object.get() = (cow.into_owned()).into();
-                                }
-                            }
-
-                            *self.objects.last_mut().unwrap() = object.clone()
+                            *object.get_mut() = (cow.into_owned()).into();
                         }
                     }
                 }
-
-                object
             }
-            Constants::Extended => {
-                let symbol: ComplexRc = self.read_next()?;
-                let object: ComplexRc = self.read_next()?;
+        }
 
-                unsafe {
-                    if (*object.get()).is_object() && (*object.get()).get(EXTENDS_SYMBOL).is_none()
-                    {
-                        (*object.get())[EXTENDS_SYMBOL] = json!([]);
-                        (*object.get())[EXTENDS_SYMBOL]
-                            .as_array_mut()
-                            .unwrap()
-                            .insert(0, (*symbol.get()).take());
-                    }
-                }
+        Ok(object)
+    }
 
-                object
+    /// Reads the Hash, converting its keys to strings.
+    fn read_hash(&mut self, structure_type: Constants) -> Result<Node, LoadError> {
+        let hash_size: usize = self.read_count()?;
+        let mut node: Node = self.register(json!({}));
+
+        for _ in 0..hash_size {
+            let key: Node = self.read_next()?;
+            let value: Value = self.read_next()?.into_value();
+
+            let key: String = if let Some(key) = key.get().as_i64() {
+                "__integer__".to_string() + &to_string(&key).unwrap()
+            } else if let Some(key) = key.get().as_f64() {
+                "__float__".to_string() + &to_string(&key).unwrap()
+            } else if let Some(key) = key.get().as_array() {
+                "__array__".to_string() + &to_string(key).unwrap()
+            } else if let Some(key) = key.get().as_object() {
+                "__object__".to_string() + &to_string(&key).unwrap()
+            } else if let Some(key) = key.get().as_str() {
+                key.to_string()
+            } else if let Some(key) = key.get().as_bool() {
+                format!("__boolean__{key}")
+            } else if key.get().is_null() {
+                "__nil__null".to_string()
+            } else {
+                return Err(LoadError {
+                    message: format!(
+                        "Unsupported Hash key before position {}.",
+                        self.byte_position
+                    ),
+                    ..Default::default()
+                });
+            };
+
+            if self.resolve_duplicate(node.get(), &key)? {
+                node.get_mut()[&key] = value;
             }
-            Constants::Array => {
-                let size: i32 = self.read_fixnum()?;
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(json!(vec![0; size as usize])));
-                self.objects.push(rc.clone());
+        }
 
-                for i in 0..size as usize {
-                    unsafe {
-                        (*rc.get())[i] = (*self.read_next()?.get()).clone();
-                    }
+        if self.symbol_keys_as_strings {
+            Loader::stringify_symbol_keys(node.get_mut());
+        }
+
+        if structure_type == Constants::HashDefault {
+            let default: Value = self.read_next()?.into_value();
+            node.get_mut()[DEFAULT_SYMBOL] = default;
+        }
+
+        Ok(node)
+    }
+
+    /// Reads the struct, converting names of its members to strings.
+    fn read_struct(&mut self) -> Result<Node, LoadError> {
+        let class: Value = self.read_class()?;
+        let mut node: Node = self.register(json!({ "__class": class, "__type": "struct" }));
+
+        let struct_size: usize = self.read_count()?;
+        let mut hash: Value = json!({});
+
+        for _ in 0..struct_size {
+            let key: Value = self.read_next()?.into_value();
+            let value: Value = self.read_next()?.into_value();
+
+            let mut key_string: String = String::new();
+
+            if let Some(key_str) = key.as_str() {
+                key_string += match key_str.strip_prefix("__symbol__") {
+                    Some(name) if self.struct_members_as_strings && !name.starts_with("__") => name,
+                    _ => key_str,
+                };
+            } else if let Some(key_num) = key.as_i64() {
+                key_string += "__integer__";
+                key_string += &key_num.to_string();
+            } else if key.is_array() {
+                let buffer: Option<Vec<u8>>;
+
+                #[cfg(feature = "sonic")]
+                {
+                    buffer = from_value(&key).ok();
+                }
+                #[cfg(not(feature = "sonic"))]
+                {
+                    buffer = from_value(key).ok();
                 }
 
-                rc
+                key_string = buffer
+                    .and_then(|buffer| String::from_utf8(buffer).ok())
+                    .ok_or_else(|| LoadError {
+                        message: format!(
+                            "Invalid struct member name before position {}.",
+                            self.byte_position
+                        ),
+                        ..Default::default()
+                    })?;
+            } else if let Some(type_) = key["__type"].as_str() {
+                if type_ == "object" {
+                    key_string += "__object__";
+                    key_string += &to_string(&key).unwrap();
+                }
             }
-            Constants::Bignum => {
-                let sign: u8 = self.read_byte()?;
-                let length: i32 = self.read_fixnum()? << 1;
-                let bytes: &[u8] = self.read_bytes(length as usize)?;
-                let result: BigInt = BigInt::from_bytes_le(
-                    if sign == Constants::Positive {
-                        num_bigint::Sign::Plus
-                    } else {
-                        num_bigint::Sign::Minus
-                    },
-                    bytes,
-                );
 
-                let bignum: Value = json!({"__type": "bigint", "value": result.to_string()});
+            if self.resolve_duplicate(&hash, &key_string)? {
+                hash[&key_string] = value;
+            }
+        }
 
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(bignum));
-                self.objects.push(rc.clone());
-                rc
+        node.get_mut()["__members"] = hash;
+        Ok(node)
+    }
+
+    /// Reads the Float, converting NaN and infinities with `NonFiniteFloatPolicy`.
+    fn read_float(&mut self) -> Result<Node, LoadError> {
+        let position: usize = self.byte_position;
+        let string: &str = &self.read_string()?;
+
+        if self.lossless
+            && matches!(string, "inf" | "-inf" | "nan")
+            && self.non_finite_float_policy == NonFiniteFloatPolicy::Null
+        {
+            let name: &str = match string {
+                "nan" => "NaN",
+                "inf" => "Infinity",
+                _ => "-Infinity",
+            };
+
+            return Err(LoadError {
+                message: format!("{name} can't be represented at position {position}."),
+                ..Default::default()
+            });
+        }
+
+        if self.strict
+            && !matches!(string, "inf" | "-inf" | "nan")
+            && string.parse::<f64>().is_err()
+        {
+            return Err(LoadError {
+                message: format!("Invalid Float {string} at position {position}."),
+                ..Default::default()
+            });
+        }
+
+        let float: Option<f64> = match string {
+            "inf" => Some(f64::INFINITY),
+            "-inf" => Some(-f64::INFINITY),
+            "nan" => None,
+            _ => match string.parse::<f64>() {
+                Ok(float) => Some(float),
+                // Old Ruby versions wrote the mantissa after the number, separated by a null byte
+                Err(_) => string
+                    .split('\0')
+                    .next()
+                    .and_then(|number| number.parse::<f64>().ok()),
+            },
+        };
+
+        let value: Value = match (string, self.non_finite_float_policy) {
+            ("nan" | "inf" | "-inf", NonFiniteFloatPolicy::String) => match string {
+                "nan" => "NaN",
+                "inf" => "Infinity",
+                _ => "-Infinity",
             }
-            Constants::Class => {
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(
-                    json!({ "__class": self.read_string()?, "__type": "class" }),
-                ));
-                self.objects.push(rc.clone());
-                rc
+            .into(),
+            ("nan" | "inf" | "-inf", NonFiniteFloatPolicy::Tagged) => {
+                json!({ "__type": "float", "value": string })
             }
-            Constants::Module | Constants::ModuleOld => {
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(
-                    json!({ "__class": self.read_string()?, "__type": "module", "__old": structure_type == Constants::ModuleOld }),
-                ));
-                self.objects.push(rc.clone());
-                rc
-            }
-            Constants::Float => {
-                let string: &str = &self.read_string()?;
-
-                let float: Option<f64> = match string {
-                    "inf" => Some(f64::INFINITY),
-                    "-inf" => Some(-f64::INFINITY),
-                    "nan" => None,
-                    _ => {
-                        let mut chars: std::str::Chars = string.chars();
-                        let first_char: Option<char> = chars.next();
-
-                        let mut float: String = String::new();
-
-                        if let Some(first_char) = first_char {
-                            if first_char.is_numeric() || first_char == '-' {
-                                float.push(first_char);
-
-                                float += &chars
-                                    .take_while(|&ch| ch == '.' || ch.is_numeric())
-                                    .collect::<String>();
-
-                                Some(float.parse::<f64>().unwrap_or(0f64))
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                };
+            ("nan" | "inf" | "-inf", NonFiniteFloatPolicy::Error) => {
+                return Err(LoadError {
+                    message: format!("Float {string} can't be represented at position {position}."),
+                    ..Default::default()
+                })
+            }
+            _ => match float {
+                Some(value) => json!(value),
+                None => json!(null),
+            },
+        };
+
+        Ok(self.register(value))
+    }
 
-                let object: ComplexRc = Rc::from(UnsafeCell::from(match float {
-                    Some(value) => json!(value),
-                    None => json!(null),
-                }));
+    /// Reads the object with its instance variables.
+    fn read_object(&mut self) -> Result<Node, LoadError> {
+        let class: Value = self.read_class()?;
+        let mut node: Node = self.register(json!({ "__class": class, "__type": "object" }));
+
+        let object_size: usize = self.read_count()?;
+
+        for _ in 0..object_size {
+            let position: usize = self.byte_position;
+            let key: Value = self.read_next()?.into_value();
+            let value: Value = self.read_next()?.into_value();
+
+            let mut key_string: String = match key.as_str() {
+                Some(key) if key.starts_with("__symbol__@") => key.to_string(),
+                Some(key)
+                    if node.get()["__class"] == "__symbol__Range"
+                        && RANGE_INSTANCE_VARS.contains(&key) =>
+                {
+                    key.to_string()
+                }
+                _ if self.strict => {
+                    return Err(LoadError {
+                        message: format!("Invalid instance variable name at position {position}."),
+                        ..Default::default()
+                    })
+                }
+                Some(key) => key.to_string(),
+                None => to_string(&key).unwrap(),
+            };
 
-                self.objects.push(object.clone());
-                object
+            if let Some(prefix) = self.instance_var_prefix {
+                if key_string.starts_with("__symbol__@") {
+                    key_string.replace_range(10..11, prefix);
+                }
             }
-            Constants::Hash | Constants::HashDefault => {
-                let hash_size: i32 = self.read_fixnum()?;
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(json!({})));
-                self.objects.push(rc.clone());
-
-                for _ in 0..hash_size {
-                    let key: ComplexRc = self.read_next()?;
-                    let value: ComplexRc = self.read_next()?;
-
-                    let key: String = if let Some(key) = unsafe { &*key.get() }.as_i64() {
-                        "__integer__".to_string() + &to_string(&key).unwrap()
-                    } else if let Some(key) = unsafe { &*key.get() }.as_f64() {
-                        "__float__".to_string() + &to_string(&key).unwrap()
-                    } else if let Some(key) = unsafe { &*key.get() }.as_array() {
-                        "__array__".to_string() + &to_string(key).unwrap()
-                    } else if let Some(key) = unsafe { &*key.get() }.as_object() {
-                        "__object__".to_string() + &to_string(&key).unwrap()
-                    } else if let Some(key) = unsafe { &*key.get() }.as_str() {
-                        key.to_string()
-                    } else {
-                        unreachable!()
-                    };
 
-                    unsafe { (*rc.get())[&key] = (*value.get()).clone() };
+            if self.resolve_duplicate(node.get(), &key_string)? {
+                node.get_mut()[key_string.as_str()] = value;
+            }
+        }
+
+        Ok(node)
+    }
+
+    fn read_structure(&mut self) -> Result<Node, LoadError> {
+        let position: usize = self.byte_position;
+        let structure_type: Constants =
+            Constants::try_from(self.read_byte()?).map_err(|err| LoadError {
+                message: format!("{} Position: {position}", err.message.trim_end_matches('.')),
+                ..Default::default()
+            })?;
+        Ok(match structure_type {
+            Constants::Nil => Node::Owned(json!(null)),
+            Constants::True => Node::Owned(Value::from(true)),
+            Constants::False => Node::Owned(Value::from(false)),
+            Constants::Fixnum => Node::Owned(Value::from(self.read_fixnum()?)),
+            Constants::Symlink => self.read_link(true)?,
+            Constants::Link => self.read_link(false)?,
+            Constants::Symbol => {
+                let prefix: String = String::from("__symbol__");
+                let symbol: &String = &self.read_string()?;
+
+                let symbol: Value = ((prefix + symbol).as_str()).into();
+
+                self.symbols.push(symbol.clone());
+                Node::Owned(symbol)
+            }
+            Constants::InstanceVar => self.read_instance_var()?,
+            Constants::Extended => {
+                let symbol: Value = self.read_class()?;
+                let mut object: Node = self.read_next()?;
+                let value: &mut Value = object.get_mut();
+
+                if value.is_object() && value.get(EXTENDS_SYMBOL).is_none() {
+                    value[EXTENDS_SYMBOL] = json!([]);
+                    value[EXTENDS_SYMBOL]
+                        .as_array_mut()
+                        .unwrap()
+                        .insert(0, symbol);
                 }
 
-                if structure_type == Constants::HashDefault {
-                    unsafe { (*rc.get())[DEFAULT_SYMBOL] = (*self.read_next()?.get()).clone() };
+                object
+            }
+            Constants::Array => {
+                let size: usize = self.read_count()?;
+                let mut node: Node = self.register(json!(vec![0; size]));
+
+                for i in 0..size {
+                    let element: Value = self.read_next()?.into_value();
+                    node.get_mut()[i] = element;
                 }
 
-                rc
+                node
             }
-            Constants::Object => {
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(
-                    json!({ "__class": unsafe { &*self.read_next()?.get() }.clone(), "__type": "object" }),
-                ));
-                self.objects.push(rc.clone());
+            Constants::Bignum => {
+                let sign: u8 = self.read_byte()?;
+                let length: usize = self.read_count()? * 2;
+                let bytes: &[u8] = self.read_bytes(length)?;
+
+                #[cfg(feature = "bigint")]
+                let bignum: Value = {
+                    let result: BigInt = BigInt::from_bytes_le(
+                        if sign == Constants::Positive {
+                            num_bigint::Sign::Plus
+                        } else {
+                            num_bigint::Sign::Minus
+                        },
+                        bytes,
+                    );
 
-                let object_size: i32 = self.read_fixnum()?;
+                    json!({"__type": "bigint", "value": result.to_string()})
+                };
+                // Without `bigint` feature, the magnitude is kept as little-endian bytes, that Marshal stores
+                #[cfg(not(feature = "bigint"))]
+                let bignum: Value = json!({"__type": "bigint", "negative": sign != Constants::Positive, "data": bytes});
 
-                for _ in 0..object_size {
-                    let key: Value = unsafe { &*self.read_next()?.get() }.clone();
-                    let value: Value = unsafe { &*self.read_next()?.get() }.clone();
+                self.register(bignum)
+            }
+            Constants::Class => {
+                let mut name: String = self.read_string()?;
 
-                    let mut key_string: String = key.as_str().unwrap().to_string();
+                if !self.check_class(&name)? {
+                    name = PLACEHOLDER_CLASS.to_string();
+                }
 
-                    if let Some(prefix) = self.instance_var_prefix {
-                        key_string.replace_range(10..11, prefix);
-                    }
+                self.register(json!({ "__class": name, "__type": "class" }))
+            }
+            Constants::Module | Constants::ModuleOld => {
+                let mut name: String = self.read_string()?;
 
-                    unsafe {
-                        (*rc.get())[key_string.as_str()] = value;
-                    }
+                if !self.check_class(&name)? {
+                    name = PLACEHOLDER_CLASS.to_string();
                 }
 
-                rc
+                self.register(
+                    json!({ "__class": name, "__type": "module", "__old": structure_type == Constants::ModuleOld }),
+                )
             }
+            Constants::Float => self.read_float()?,
+            Constants::Hash | Constants::HashDefault => self.read_hash(structure_type)?,
+            Constants::Object => self.read_object()?,
             Constants::Regexp => {
                 let string: String = self.read_string()?;
                 let regex_type: u8 = self.read_byte()?;
@@ -460,9 +1663,7 @@ impl<'a> Loader<'a> {
                 let regexp: Value =
                     json!({"__type": "regexp", "expression": string, "flags": flags});
 
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(regexp));
-                self.objects.push(rc.clone());
-                rc
+                self.register(regexp)
             }
             Constants::String => {
                 let string_mode: Option<StringMode> = self.string_mode;
@@ -478,86 +1679,32 @@ impl<'a> Loader<'a> {
                     json!({ "__type": "bytes", "data": string_bytes })
                 };
 
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(object));
-                self.objects.push(rc.clone());
-                rc
-            }
-            Constants::Struct => {
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(
-                    json!({ "__class": unsafe { &*self.read_next()?.get() }, "__type": "struct" }),
-                ));
-                self.objects.push(rc.clone());
-
-                let struct_size: i32 = self.read_fixnum()?;
-                let mut hash: Value = json!({});
-
-                for _ in 0..struct_size {
-                    let key: Value = unsafe { &*self.read_next()?.get() }.clone();
-                    let value: Value = unsafe { &*self.read_next()?.get() }.clone();
-
-                    let mut key_string: String = String::new();
-
-                    if let Some(key_str) = key.as_str() {
-                        key_string += key_str;
-                    } else if let Some(key_num) = key.as_i64() {
-                        key_string += "__integer__";
-                        key_string += &key_num.to_string();
-                    } else if key.is_array() {
-                        let buffer: Vec<u8>;
-
-                        #[cfg(feature = "sonic")]
-                        {
-                            buffer = from_value(&key).unwrap();
-                        }
-                        #[cfg(not(feature = "sonic"))]
-                        {
-                            buffer = from_value(key).unwrap();
-                        }
-
-                        key_string = String::from_utf8(buffer).unwrap();
-                    } else if let Some(type_) = key["__type"].as_str() {
-                        if type_ == "object" {
-                            key_string += "__object__";
-                            key_string += &to_string(&key).unwrap();
-                        }
-                    }
-
-                    hash[&key_string] = value;
-                }
-
-                unsafe {
-                    (*rc.get())["__members"] = hash;
-                }
-                rc
+                self.register(object)
             }
+            Constants::Struct => self.read_struct()?,
             Constants::Data
             | Constants::UserClass
             | Constants::UserDefined
             | Constants::UserMarshal => {
-                let rc: ComplexRc = Rc::from(UnsafeCell::from(
-                    json!({ "__class": unsafe { &*self.read_next()?.get() }, "__type": "object" }),
-                ));
-                self.objects.push(rc.clone());
-
-                unsafe {
-                    match structure_type {
-                        Constants::Data => {
-                            (*rc.get())["__data"] = (*self.read_next()?.get()).clone()
-                        }
-                        Constants::UserClass => {
-                            (*rc.get())["__wrapped"] = (*self.read_next()?.get()).clone()
-                        }
-                        Constants::UserDefined => {
-                            (*rc.get())["__userDefined"] = (self.read_chunk()?).into()
-                        }
-                        Constants::UserMarshal => {
-                            (*rc.get())["__userMarshal"] = (*self.read_next()?.get()).clone()
-                        }
-                        _ => unreachable!(),
+                let class: Value = self.read_class()?;
+                let mut node: Node = self.register(json!({ "__class": class, "__type": "object" }));
+
+                let (key, value): (&str, Value) = match structure_type {
+                    Constants::Data => {
+                        let data: Value = self.read_next()?.into_value();
+                        (
+                            "__data",
+                            self.load_data(node.get()["__class"].as_str(), data)?,
+                        )
                     }
-                }
+                    Constants::UserClass => ("__wrapped", self.read_next()?.into_value()),
+                    Constants::UserDefined => ("__userDefined", (self.read_chunk()?).into()),
+                    Constants::UserMarshal => ("__userMarshal", self.read_next()?.into_value()),
+                    _ => unreachable!(),
+                };
 
-                rc
+                node.get_mut()[key] = value;
+                node
             }
             _ => unreachable!(),
         })
@@ -570,8 +1717,290 @@ impl<'a> Default for Loader<'a> {
     }
 }
 
+/// Builder of `Loader`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoaderBuilder<'a> {
+    string_mode: Option<StringMode>,
+    instance_var_prefix: Option<&'a str>,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    non_finite_float_policy: NonFiniteFloatPolicy,
+    strict: bool,
+    lossless: bool,
+    max_depth: Option<usize>,
+    max_length: Option<usize>,
+    allowed_classes: Option<&'a [&'a str]>,
+    denied_classes: &'a [&'a str],
+    disallowed_class_policy: DisallowedClassPolicy,
+    track_allocations: bool,
+    memory_budget: Option<usize>,
+    pool: Option<&'a TablePool>,
+    cancel_flag: Option<&'a AtomicBool>,
+    data_registry: Option<&'a DataRegistry>,
+    symbol_keys_as_strings: bool,
+    struct_members_as_strings: bool,
+    profile: bool,
+}
+
+impl<'a> LoaderBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the preset's configuration. Options, set after the preset, override it.
+    pub fn preset(mut self, preset: Preset) -> Self {
+        match preset {
+            Preset::RpgMakerXp | Preset::RpgMakerVx => {
+                self.string_mode = Some(StringMode::UTF8);
+                self.duplicate_key_policy = DuplicateKeyPolicy::LastWins;
+            }
+            Preset::RpgMakerVxAce => {
+                self.string_mode = None;
+                self.duplicate_key_policy = DuplicateKeyPolicy::LastWins;
+            }
+            Preset::RailsCache => {
+                self.string_mode = None;
+                self.duplicate_key_policy = DuplicateKeyPolicy::Error;
+            }
+        }
+
+        self
+    }
+
+    /// Sets the string mode, used when `Loader::load()` is called without one.
+    pub fn string_mode(mut self, string_mode: StringMode) -> Self {
+        self.string_mode = Some(string_mode);
+        self
+    }
+
+    /// Sets the instance variable prefix, used when `Loader::load()` is called without one.
+    pub fn instance_var_prefix(mut self, prefix: &'a str) -> Self {
+        self.instance_var_prefix = Some(prefix);
+        self
+    }
+
+    pub fn duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = policy;
+        self
+    }
+
+    /// Sets the policy of loading NaN and infinite Floats, that JSON numbers can't represent.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{Dumper, Loader, NonFiniteFloatPolicy};
+    /// use serde_json::json;
+    ///
+    /// // [NaN, -Infinity]
+    /// let bytes: &[u8] = b"\x04\x08[\x07f\x08nanf\x09-inf";
+    ///
+    /// let mut loader = Loader::builder().non_finite_float_policy(NonFiniteFloatPolicy::String).build();
+    /// assert_eq!(loader.load(bytes, None, None).unwrap(), json!(["NaN", "-Infinity"]));
+    ///
+    /// let mut loader = Loader::builder().non_finite_float_policy(NonFiniteFloatPolicy::Tagged).build();
+    /// let value = loader.load(bytes, None, None).unwrap();
+    ///
+    /// assert_eq!(value[1], json!({ "__type": "float", "value": "-inf" }));
+    /// assert_eq!(Dumper::new().dump(value, None), bytes);
+    /// ```
+    pub fn non_finite_float_policy(mut self, policy: NonFiniteFloatPolicy) -> Self {
+        self.non_finite_float_policy = policy;
+        self
+    }
+
+    /// Enables rejection of data, that Ruby never produces, instead of tolerating it: trailing bytes after the document, malformed Floats and invalid instance variable names.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Enables rejection of data, that can't be loaded without losses: invalid UTF-8 in symbols and class names, strings in unknown encodings or invalid in their encodings, and NaN and infinite Floats, that are loaded as `null`.
+    pub fn lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Sets the maximum nesting depth of structures.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum length of a single string, symbol, Array, Hash, object or struct.
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = Some(length);
+        self
+    }
+
+    /// Restricts classes and modules of objects, structs, class references and extensions to the list. Names are passed without `__symbol__` prefixes, like `"RPG::Actor"`.
+    pub fn allowed_classes(mut self, classes: &'a [&'a str]) -> Self {
+        self.allowed_classes = Some(classes);
+        self
+    }
+
+    /// Rejects classes and modules in the list, even if they're allowed by `allowed_classes()`. Names are passed without `__symbol__` prefixes, like `"ERB"`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{load::{DisallowedClassPolicy, PLACEHOLDER_CLASS}, Loader};
+    ///
+    /// let mut loader = Loader::builder().denied_classes(&["ERB"]).build();
+    ///
+    /// // ERB.allocate
+    /// assert!(loader.load(b"\x04\x08o:\x08ERB\x00", None, None).is_err());
+    ///
+    /// loader.set_disallowed_class_policy(DisallowedClassPolicy::Placeholder);
+    /// let value = loader.load(b"\x04\x08o:\x08ERB\x00", None, None).unwrap();
+    ///
+    /// assert_eq!(value["__class"], format!("__symbol__{PLACEHOLDER_CLASS}"));
+    /// assert_eq!(loader.warnings(), ["Class ERB is not allowed before position 8."]);
+    /// ```
+    pub fn denied_classes(mut self, classes: &'a [&'a str]) -> Self {
+        self.denied_classes = classes;
+        self
+    }
+
+    pub fn disallowed_class_policy(mut self, policy: DisallowedClassPolicy) -> Self {
+        self.disallowed_class_policy = policy;
+        self
+    }
+
+    /// Enables estimation of bytes, allocated for loaded Values, which can be retrieved with `Loader::allocated()`.
+    pub fn track_allocations(mut self, track: bool) -> Self {
+        self.track_allocations = track;
+        self
+    }
+
+    /// Sets the maximum estimated number of bytes, allocated for a loaded Value, and enables allocation tracking.
+    ///
+    /// Unlike length limits, the budget also catches small data, that expands to huge Values, as linked objects are copied on each link.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.track_allocations = true;
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Sets the pool, that the Loader takes its tables from at the start of each load, and returns them to at the end.
+    pub fn pool(mut self, pool: &'a TablePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Sets the flag, that cancels the load in progress, when it's set to true, for example, from another thread, or by a GUI's cancel button.
+    /// The flag is checked before reading each value, and a cancelled load returns an Err. The flag isn't reset by the Loader.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::Loader;
+    /// use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    ///
+    /// let cancelled = Arc::new(AtomicBool::new(false));
+    /// let flag = Arc::clone(&cancelled);
+    ///
+    /// let handle = std::thread::spawn(move || {
+    ///     let mut loader = Loader::builder().cancel_flag(&flag).build();
+    ///     loader.load(b"\x04\x08[\x06i\x06", None, None).map_err(|err| err.to_string())
+    /// });
+    ///
+    /// cancelled.store(true, Ordering::Relaxed);
+    /// // Either the load finished before the flag was set, or it was cancelled
+    /// let _ = handle.join().unwrap();
+    /// ```
+    pub fn cancel_flag(mut self, flag: &'a AtomicBool) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Sets the registry, whose handlers convert values, that `_dump_data` of their classes returned, before they're stored in `__data` key.
+    pub fn data_registry(mut self, registry: &'a DataRegistry) -> Self {
+        self.data_registry = Some(registry);
+        self
+    }
+
+    /// Sets whether Hashes, keyed only by symbols, are loaded with plain string keys. See `Loader::set_symbol_keys_as_strings()`.
+    pub fn symbol_keys_as_strings(mut self, enabled: bool) -> Self {
+        self.symbol_keys_as_strings = enabled;
+        self
+    }
+
+    /// Sets whether names of struct members are loaded as plain strings. See `Loader::set_struct_members_as_strings()`.
+    pub fn struct_members_as_strings(mut self, enabled: bool) -> Self {
+        self.struct_members_as_strings = enabled;
+        self
+    }
+
+    /// Enables collection of counts, bytes and reading time per structure type, which can be retrieved with `Loader::profile()` after each load.
+    ///
+    /// Timing adds noticeable overhead to each structure, so profiling should be disabled, when it's not needed.
+    /// It uses `std::time::Instant`, which is not available on `wasm32-unknown-unknown`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{raw::Constants, Loader};
+    ///
+    /// let mut loader = Loader::builder().profile(true).build();
+    ///
+    /// // [1, 2, "a"]
+    /// loader.load(b"\x04\x08[\x08i\x06i\x07I\"\x06a\x06:\x06ET", None, None).unwrap();
+    /// let profile = loader.profile().unwrap();
+    ///
+    /// assert_eq!(profile.types[&Constants::Fixnum].count, 2);
+    /// assert_eq!(profile.types[&Constants::String].bytes, 3);
+    /// assert_eq!(profile.types[&Constants::Array].bytes, 15);
+    /// ```
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Configures the loader for untrusted data: enables strict and lossless modes, rejects duplicate keys, limits nesting depth to `HARDENED_MAX_DEPTH`, lengths to `HARDENED_MAX_LENGTH` and allocations to `HARDENED_MEMORY_BUDGET`, and doesn't accept any classes.
+    ///
+    /// Options, set after this call, override it, so classes can be allowed with `allowed_classes()`.
+    pub fn hardened(mut self) -> Self {
+        self.strict = true;
+        self.lossless = true;
+        self.duplicate_key_policy = DuplicateKeyPolicy::Error;
+        self.max_depth = Some(HARDENED_MAX_DEPTH);
+        self.max_length = Some(HARDENED_MAX_LENGTH);
+        self.allowed_classes = Some(&[]);
+        self.track_allocations = true;
+        self.memory_budget = Some(HARDENED_MEMORY_BUDGET);
+        self
+    }
+
+    /// Resets options, that `MarshalCodec` shares with the Dumper.
+    pub(crate) fn clear_shared(mut self) -> Self {
+        self.instance_var_prefix = None;
+        self.pool = None;
+        self.cancel_flag = None;
+        self.data_registry = None;
+        self
+    }
+
+    pub fn build(self) -> Loader<'a> {
+        let mut loader: Loader = Loader::new();
+        loader.default_string_mode = self.string_mode;
+        loader.default_instance_var_prefix = self.instance_var_prefix;
+        loader.duplicate_key_policy = self.duplicate_key_policy;
+        loader.non_finite_float_policy = self.non_finite_float_policy;
+        loader.strict = self.strict;
+        loader.lossless = self.lossless;
+        loader.max_depth = self.max_depth;
+        loader.max_length = self.max_length;
+        loader.allowed_classes = self.allowed_classes;
+        loader.denied_classes = self.denied_classes;
+        loader.disallowed_class_policy = self.disallowed_class_policy;
+        loader.track_allocations = self.track_allocations;
+        loader.memory_budget = self.memory_budget;
+        loader.pool = self.pool;
+        loader.cancel_flag = self.cancel_flag;
+        loader.data_registry = self.data_registry;
+        loader.symbol_keys_as_strings = self.symbol_keys_as_strings;
+        loader.struct_members_as_strings = self.struct_members_as_strings;
+        loader.profile = self.profile.then(LoadProfile::default);
+        loader
+    }
+}
+
 /// Serializes Ruby Marshal byte stream to JSON.
 ///
+/// buffer argument takes anything, that can be viewed as a bytes slice, like `Vec<u8>`, or `bytes::Bytes` received from network, so it doesn't have to be copied.
+///
 /// string_mode arguments takes a StringMode enum value, and decodes strings either as binary data or as string objects.
 ///
 /// instance_var_prefix argument takes a string, and replaces instance variables' "@" prefixes by this string.
@@ -594,8 +2023,8 @@ impl<'a> Default for Loader<'a> {
 /// let json: serde_json::Value = load(&bytes, None, None).unwrap();
 /// assert_eq!(json, json!(null));
 /// ```
-pub fn load(
-    buffer: &[u8],
+pub fn load<B: AsRef<[u8]> + ?Sized>(
+    buffer: &B,
     string_mode: Option<StringMode>,
     instance_var_prefix: Option<&str>,
 ) -> Result<Value, LoadError> {
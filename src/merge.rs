@@ -0,0 +1,173 @@
+//! Three-way merging of loaded JSON values.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::value::{is_leaf_object, Path, PathSegment};
+use serde_json::{Map, Value};
+
+/// Location, where both sides changed the base differently.
+///
+/// Values are None, where the side removed the value, or where the base didn't have it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub path: Path,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let show = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .map_or("(none)".to_string(), Value::to_string)
+        };
+
+        write!(
+            formatter,
+            "Conflict at {}: base {}, ours {}, theirs {}",
+            self.path,
+            show(&self.base),
+            show(&self.ours),
+            show(&self.theirs)
+        )
+    }
+}
+
+/// Returns whether both values are Hashes, or instances of the same class with the same type, so their entries can be merged one by one.
+fn mergeable_objects(a: &Value, b: &Value) -> bool {
+    if !a.is_object() || !b.is_object() || is_leaf_object(a) || is_leaf_object(b) {
+        return false;
+    }
+
+    a.get("__type") == b.get("__type") && a.get("__class") == b.get("__class")
+}
+
+fn merge_objects(
+    base: &Map<String, Value>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    path: &mut Path,
+    conflicts: &mut Vec<Conflict>,
+) -> Map<String, Value> {
+    let mut merged: Map<String, Value> = Map::new();
+
+    // Our order of keys is kept, with keys, added by them, appended
+    let keys = ours
+        .keys()
+        .chain(theirs.keys().filter(|key| !ours.contains_key(*key)))
+        .chain(
+            base.keys()
+                .filter(|key| !ours.contains_key(*key) && !theirs.contains_key(*key)),
+        );
+
+    for key in keys {
+        path.push(PathSegment::Key(key.clone()));
+
+        if let Some(value) = merge_values(
+            base.get(key),
+            ours.get(key),
+            theirs.get(key),
+            path,
+            conflicts,
+        ) {
+            merged.insert(key.clone(), value);
+        }
+
+        path.pop();
+    }
+
+    merged
+}
+
+fn merge_values(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &mut Path,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+
+    if ours == base {
+        return theirs.cloned();
+    }
+
+    // Both sides changed the value, but changes might not overlap
+    if let (Some(base), Some(ours), Some(theirs)) = (base, ours, theirs) {
+        match (base, ours, theirs) {
+            (Value::Object(base_map), Value::Object(ours_map), Value::Object(theirs_map))
+                if mergeable_objects(base, ours) && mergeable_objects(base, theirs) =>
+            {
+                return Some(Value::Object(merge_objects(
+                    base_map, ours_map, theirs_map, path, conflicts,
+                )));
+            }
+            (Value::Array(base), Value::Array(ours), Value::Array(theirs))
+                if base.len() == ours.len() && base.len() == theirs.len() =>
+            {
+                let mut merged: Vec<Value> = Vec::with_capacity(base.len());
+
+                for (index, ((base, ours), theirs)) in base.iter().zip(ours).zip(theirs).enumerate()
+                {
+                    path.push(PathSegment::Index(index));
+                    merged.push(
+                        merge_values(Some(base), Some(ours), Some(theirs), path, conflicts)
+                            .unwrap_or(Value::Null),
+                    );
+                    path.pop();
+                }
+
+                return Some(Value::Array(merged));
+            }
+            _ => {}
+        }
+    }
+
+    conflicts.push(Conflict {
+        path: path.clone(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    });
+
+    ours.cloned()
+}
+
+/// Merges changes, made to the base by two sides, like `git merge` merges text files.
+///
+/// Objects and Hashes are merged key by key, and arrays are merged element by element, if none of the sides changed their length.
+/// Values, changed by both sides differently, are reported as conflicts.
+/// # Example
+/// ```rust
+/// use marshal_rs::merge::three_way;
+/// use serde_json::json;
+///
+/// let base = json!({ "__symbol__@name": "Sword", "__symbol__@price": 100 });
+/// let ours = json!({ "__symbol__@name": "Schwert", "__symbol__@price": 100 });
+/// let theirs = json!({ "__symbol__@name": "Sword", "__symbol__@price": 150 });
+///
+/// assert_eq!(
+///     three_way(&base, &ours, &theirs).unwrap(),
+///     json!({ "__symbol__@name": "Schwert", "__symbol__@price": 150 })
+/// );
+/// ```
+pub fn three_way(base: &Value, ours: &Value, theirs: &Value) -> Result<Value, Vec<Conflict>> {
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    let merged: Option<Value> = merge_values(
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &mut Path::new(),
+        &mut conflicts,
+    );
+
+    if conflicts.is_empty() {
+        Ok(merged.unwrap_or(Value::Null))
+    } else {
+        Err(conflicts)
+    }
+}
@@ -0,0 +1,516 @@
+//! Conversion of loaded JSON values to PHP's `serialize()` format and back, for migrations between Ruby and PHP applications.
+//!
+//! | Ruby object                  | PHP                                              |
+//! | ---------------------------- | ------------------------------------------------ |
+//! | `nil`, `true`, `false`       | `N;`, `b:1;`, `b:0;`                             |
+//! | Integer, Float               | `i:1;`, `d:1.5;`                                 |
+//! | Big Integer                  | `s:` with decimal digits                         |
+//! | String, Symbol, binary string | `s:`                                            |
+//! | Regexp                       | `s:` with PCRE pattern, like `/expression/i`     |
+//! | Array                        | `a:` with keys `0`, `1`, ...                     |
+//! | Hash                         | `a:` with integer and string keys                |
+//! | Object with instance vars    | `O:` with properties, named without `@`          |
+//! | Struct                       | `O:` with members as properties                  |
+//! | Object with `_dump` data     | `C:` with the data                               |
+//!
+//! Ruby class names are written as PHP names with namespaces, like `RPG\Item` for `RPG::Item`.
+//! PHP arrays can't hold other keys than integers and strings, so Float keys are written as strings, and Array and object keys as their JSON keys.
+//! Other objects are written as `a:` with their JSON keys, so they survive the round trip.
+//!
+//! `from_php()` reads PHP arrays with keys `0`, `1`, ... as Arrays, and other arrays as Hashes, so empty Hashes are read as empty Arrays.
+//! Strings are read as strings if they're valid UTF-8, and as binary strings otherwise. Structs are read as objects.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::value::{bytes_of, is_hash, key_value, to_symbol, ValueError};
+use serde_json::{json, Map, Value};
+
+/// Maximum nesting of arrays and objects, read by `from_php()`.
+const MAX_DEPTH: usize = 128;
+
+fn write_string(php: &mut Vec<u8>, bytes: &[u8]) {
+    php.extend_from_slice(format!("s:{}:\"", bytes.len()).as_bytes());
+    php.extend_from_slice(bytes);
+    php.extend_from_slice(b"\";");
+}
+
+/// Converts Ruby class name to PHP class name.
+fn php_class(class: &str) -> String {
+    class.trim_start_matches("__symbol__").replace("::", "\\")
+}
+
+fn write_key(php: &mut Vec<u8>, key: &str) {
+    match key_value(key) {
+        Value::Number(number) if number.is_i64() => {
+            php.extend_from_slice(format!("i:{number};").as_bytes())
+        }
+        Value::String(string) => write_string(
+            php,
+            string
+                .strip_prefix("__symbol__")
+                .unwrap_or(&string)
+                .as_bytes(),
+        ),
+        Value::Number(number) => write_string(php, number.to_string().as_bytes()),
+        _ => write_string(php, key.as_bytes()),
+    }
+}
+
+fn write_object<'v, I: ExactSizeIterator<Item = (&'v str, &'v Value)>>(
+    php: &mut Vec<u8>,
+    class: &str,
+    properties: I,
+) {
+    let class: String = php_class(class);
+    php.extend_from_slice(
+        format!("O:{}:\"{class}\":{}:{{", class.len(), properties.len()).as_bytes(),
+    );
+
+    for (name, value) in properties {
+        write_string(php, name.as_bytes());
+        write_value(php, value);
+    }
+
+    php.push(b'}');
+}
+
+fn write_value(php: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => php.extend_from_slice(b"N;"),
+        Value::Bool(bool) => php.extend_from_slice(if *bool { b"b:1;" } else { b"b:0;" }),
+        Value::Number(number) => {
+            if number.is_i64() {
+                php.extend_from_slice(format!("i:{number};").as_bytes());
+            } else if number.is_u64() {
+                write_string(php, number.to_string().as_bytes());
+            } else {
+                let float: f64 = number.as_f64().unwrap_or_default();
+                php.extend_from_slice(format!("d:{float};").as_bytes());
+            }
+        }
+        Value::String(string) => write_string(
+            php,
+            string
+                .strip_prefix("__symbol__")
+                .unwrap_or(string)
+                .as_bytes(),
+        ),
+        Value::Array(array) => {
+            php.extend_from_slice(format!("a:{}:{{", array.len()).as_bytes());
+
+            for (index, element) in array.iter().enumerate() {
+                php.extend_from_slice(format!("i:{index};").as_bytes());
+                write_value(php, element);
+            }
+
+            php.push(b'}');
+        }
+        Value::Object(object) => {
+            if let Some(bytes) = bytes_of(value) {
+                write_string(php, &bytes);
+                return;
+            }
+
+            if is_hash(value) {
+                php.extend_from_slice(format!("a:{}:{{", object.len()).as_bytes());
+
+                for (key, value) in object {
+                    write_key(php, key);
+                    write_value(php, value);
+                }
+
+                php.push(b'}');
+                return;
+            }
+
+            let class: &str = value["__class"].as_str().unwrap_or_default();
+            let user_defined: Option<Vec<u8>> =
+                value["__userDefined"].as_array().and_then(|data| {
+                    data.iter()
+                        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                        .collect()
+                });
+
+            match value["__type"].as_str() {
+                Some("bigint") => {
+                    write_string(php, value["value"].as_str().unwrap_or_default().as_bytes())
+                }
+                Some("regexp") => {
+                    let expression: &str = value["expression"].as_str().unwrap_or_default();
+                    let flags: String = value["flags"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .replace('m', "s");
+
+                    write_string(
+                        php,
+                        format!("/{}/{flags}", expression.replace('/', "\\/")).as_bytes(),
+                    );
+                }
+                Some("object")
+                    if object.keys().all(|key| {
+                        key == "__class" || key == "__type" || key.starts_with("__symbol__@")
+                    }) =>
+                {
+                    write_object(
+                        php,
+                        class,
+                        object
+                            .iter()
+                            .filter_map(|(key, value)| {
+                                Some((key.strip_prefix("__symbol__@")?, value))
+                            })
+                            .collect::<Vec<_>>()
+                            .into_iter(),
+                    )
+                }
+                Some("object") if object.len() == 3 && user_defined.is_some() => {
+                    let class: String = php_class(class);
+                    let data: Vec<u8> = user_defined.unwrap_or_default();
+
+                    php.extend_from_slice(
+                        format!("C:{}:\"{class}\":{}:{{", class.len(), data.len()).as_bytes(),
+                    );
+                    php.extend_from_slice(&data);
+                    php.push(b'}');
+                }
+                Some("struct") if value["__members"].is_object() => write_object(
+                    php,
+                    class,
+                    value["__members"]
+                        .as_object()
+                        .into_iter()
+                        .flatten()
+                        .map(|(key, value)| (key.strip_prefix("__symbol__").unwrap_or(key), value))
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                ),
+                _ => {
+                    php.extend_from_slice(format!("a:{}:{{", object.len()).as_bytes());
+
+                    for (key, value) in object {
+                        write_string(php, key.as_bytes());
+
+                        match (key.as_str(), value) {
+                            ("__class", Value::String(class)) => {
+                                write_string(php, class.as_bytes())
+                            }
+                            _ => write_value(php, value),
+                        }
+                    }
+
+                    php.push(b'}');
+                }
+            }
+        }
+    }
+}
+
+/// Converts the Value to PHP's `serialize()` format. See the module documentation for the mapping.
+/// # Example
+/// ```rust
+/// use marshal_rs::php::{from_php, to_php};
+/// use serde_json::json;
+///
+/// let value = json!({ "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword" });
+/// let php: Vec<u8> = to_php(&value);
+///
+/// assert_eq!(php, br#"O:8:"RPG\Item":1:{s:4:"name";s:5:"Sword";}"#);
+/// assert_eq!(from_php(&php).unwrap(), value);
+/// ```
+pub fn to_php(value: &Value) -> Vec<u8> {
+    let mut php: Vec<u8> = Vec::new();
+    write_value(&mut php, value);
+    php
+}
+
+enum Key {
+    Integer(i64),
+    String(String),
+}
+
+struct Unserializer<'a> {
+    php: &'a [u8],
+    position: usize,
+    depth: usize,
+    /// Values, that can be referenced with `r:` and `R:`, if the input has references.
+    values: Option<Vec<Value>>,
+}
+
+impl<'a> Unserializer<'a> {
+    fn error(&self, message: &str) -> ValueError {
+        ValueError {
+            message: format!("{message} at PHP position {}", self.position),
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), ValueError> {
+        if self.php.get(self.position) != Some(&expected) {
+            return Err(self.error(&format!("Expected '{}'", expected as char)));
+        }
+
+        self.position += 1;
+        Ok(())
+    }
+
+    fn read(&mut self, amount: usize) -> Result<&'a [u8], ValueError> {
+        let end: usize = self
+            .position
+            .checked_add(amount)
+            .filter(|&end| end <= self.php.len())
+            .ok_or_else(|| self.error("Unexpected end of input"))?;
+
+        let bytes: &[u8] = &self.php[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Reads the token until the terminator, skipping the terminator.
+    fn token(&mut self, terminator: u8) -> Result<&'a str, ValueError> {
+        let length: usize = self.php[self.position..]
+            .iter()
+            .position(|&byte| byte == terminator)
+            .ok_or_else(|| self.error(&format!("Expected '{}'", terminator as char)))?;
+
+        let token: &[u8] = self.read(length)?;
+        self.position += 1;
+
+        std::str::from_utf8(token).map_err(|_| self.error("Invalid token"))
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, terminator: u8) -> Result<T, ValueError> {
+        let position: usize = self.position;
+        let token: &str = self.token(terminator)?;
+
+        token.parse().map_err(|_| ValueError {
+            message: format!("Invalid number {token} at PHP position {position}"),
+        })
+    }
+
+    /// Reads `length:"bytes"` part of strings and class names.
+    fn quoted(&mut self) -> Result<&'a [u8], ValueError> {
+        let length: usize = self.number(b':')?;
+        self.expect(b'"')?;
+        let bytes: &[u8] = self.read(length)?;
+        self.expect(b'"')?;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], ValueError> {
+        self.expect(b':')?;
+        let bytes: &[u8] = self.quoted()?;
+        self.expect(b';')?;
+        Ok(bytes)
+    }
+
+    fn class(&mut self) -> Result<String, ValueError> {
+        self.expect(b':')?;
+        let class: &[u8] = self.quoted()?;
+        self.expect(b':')?;
+
+        let class: &str =
+            std::str::from_utf8(class).map_err(|_| self.error("Invalid class name"))?;
+        Ok(to_symbol(&class.replace('\\', "::")))
+    }
+
+    fn key(&mut self) -> Result<Key, ValueError> {
+        let tag: u8 = self.read(1)?[0];
+
+        match tag {
+            b'i' => {
+                self.expect(b':')?;
+                Ok(Key::Integer(self.number(b';')?))
+            }
+            b's' => Ok(Key::String(
+                String::from_utf8_lossy(self.string()?).into_owned(),
+            )),
+            _ => Err(self.error("Expected integer or string key")),
+        }
+    }
+
+    /// Reads the count of entries and the opening brace. Rejects counts, that can't fit in the rest of the input.
+    fn count(&mut self) -> Result<usize, ValueError> {
+        let count: usize = self.number(b':')?;
+        self.expect(b'{')?;
+
+        if count > (self.php.len() - self.position) / 4 {
+            return Err(self.error("Too many entries"));
+        }
+
+        Ok(count)
+    }
+
+    fn entries(&mut self, count: usize) -> Result<Vec<(Key, Value)>, ValueError> {
+        let mut entries: Vec<(Key, Value)> = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let key: Key = self.key()?;
+            let value: Value = self.value()?;
+            entries.push((key, value));
+        }
+
+        self.expect(b'}')?;
+        Ok(entries)
+    }
+
+    fn value(&mut self) -> Result<Value, ValueError> {
+        let tag: u8 = self.read(1)?[0];
+
+        let slot: Option<usize> = match &mut self.values {
+            Some(values) if tag != b'R' => {
+                values.push(Value::Null);
+                Some(values.len() - 1)
+            }
+            _ => None,
+        };
+
+        if matches!(tag, b'a' | b'O') {
+            self.depth += 1;
+
+            if self.depth > MAX_DEPTH {
+                return Err(self.error(&format!("Nesting is deeper than {MAX_DEPTH} levels")));
+            }
+        }
+
+        let value: Value = match tag {
+            b'N' => {
+                self.expect(b';')?;
+                Value::Null
+            }
+            b'b' => {
+                self.expect(b':')?;
+                (self.number::<u8>(b';')? != 0).into()
+            }
+            b'i' => {
+                self.expect(b':')?;
+                self.number::<i64>(b';')?.into()
+            }
+            b'd' => {
+                self.expect(b':')?;
+                let float: f64 = match self.token(b';')? {
+                    "INF" => f64::INFINITY,
+                    "-INF" => f64::NEG_INFINITY,
+                    "NAN" => f64::NAN,
+                    token => token
+                        .parse()
+                        .map_err(|_| self.error(&format!("Invalid float {token}")))?,
+                };
+
+                json!(float)
+            }
+            b's' => {
+                let bytes: &[u8] = self.string()?;
+
+                match std::str::from_utf8(bytes) {
+                    Ok(string) => string.into(),
+                    Err(_) => json!({ "__type": "bytes", "data": bytes }),
+                }
+            }
+            b'a' => {
+                self.expect(b':')?;
+                let count: usize = self.count()?;
+                let entries: Vec<(Key, Value)> = self.entries(count)?;
+                array_value(entries)
+            }
+            b'O' => {
+                let class: String = self.class()?;
+                let count: usize = self.count()?;
+                let mut object: Map<String, Value> = Map::new();
+                object.insert("__class".to_string(), class.into());
+                object.insert("__type".to_string(), "object".into());
+
+                for (key, value) in self.entries(count)? {
+                    let name: String = match key {
+                        Key::Integer(integer) => integer.to_string(),
+                        // Protected and private properties are prefixed with `\0*\0` and `\0Class\0`.
+                        Key::String(name) => {
+                            name.rsplit('\0').next().unwrap_or_default().to_string()
+                        }
+                    };
+
+                    object.insert(to_symbol(&format!("@{name}")), value);
+                }
+
+                Value::Object(object)
+            }
+            b'C' => {
+                let class: String = self.class()?;
+                let length: usize = self.number(b':')?;
+                self.expect(b'{')?;
+                let data: &[u8] = self.read(length)?;
+                self.expect(b'}')?;
+
+                json!({ "__class": class, "__type": "object", "__userDefined": data })
+            }
+            b'r' | b'R' => {
+                self.expect(b':')?;
+                let index: usize = self.number(b';')?;
+
+                index
+                    .checked_sub(1)
+                    .and_then(|index| self.values.as_ref()?.get(index))
+                    .cloned()
+                    .ok_or_else(|| self.error("Invalid reference"))?
+            }
+            _ => return Err(self.error(&format!("Unsupported type '{}'", tag as char))),
+        };
+
+        if matches!(tag, b'a' | b'O') {
+            self.depth -= 1;
+        }
+
+        if let (Some(slot), Some(values)) = (slot, &mut self.values) {
+            values[slot] = value.clone();
+        }
+
+        Ok(value)
+    }
+}
+
+/// Converts entries of PHP array to an Array, if its keys are `0`, `1`, ..., or to a Hash otherwise.
+fn array_value(entries: Vec<(Key, Value)>) -> Value {
+    let is_list: bool = entries
+        .iter()
+        .enumerate()
+        .all(|(index, (key, _))| matches!(key, Key::Integer(integer) if *integer == index as i64));
+
+    if is_list {
+        return Value::Array(entries.into_iter().map(|(_, value)| value).collect());
+    }
+
+    Value::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| match key {
+                Key::Integer(integer) => (format!("__integer__{integer}"), value),
+                Key::String(key) => (key, value),
+            })
+            .collect(),
+    )
+}
+
+/// Converts data in PHP's `serialize()` format, written by `to_php()` or by PHP, to a Value. See the module documentation for the mapping.
+///
+/// Returns an Err when the data is malformed, or has unsupported types, like enums.
+pub fn from_php(php: &[u8]) -> Result<Value, ValueError> {
+    let has_references: bool = php.windows(2).any(|bytes| bytes == b"r:" || bytes == b"R:");
+
+    let mut unserializer: Unserializer = Unserializer {
+        php,
+        position: 0,
+        depth: 0,
+        values: if has_references {
+            Some(Vec::new())
+        } else {
+            None
+        },
+    };
+
+    let value: Value = unserializer.value()?;
+
+    if unserializer.position != php.len() {
+        return Err(unserializer.error("Unexpected trailing bytes"));
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,899 @@
+//! Conversion of loaded JSON values to Python pickle (protocol 3) and back, so data can be carried over to Python without running Ruby.
+//!
+//! | Ruby object                  | Python                                           |
+//! | ---------------------------- | ------------------------------------------------ |
+//! | `nil`, `true`, `false`       | `None`, `True`, `False`                          |
+//! | Integer, Big Integer, Float  | `int`, `float`                                   |
+//! | String, Symbol               | `str`                                            |
+//! | Binary string                | `bytes`                                          |
+//! | Regexp                       | `re.Pattern`, with `re.I`, `re.X` and `re.S` flags |
+//! | Array                        | `list`                                           |
+//! | Hash                         | `dict`, with Array keys as `tuple`               |
+//! | Object with instance vars    | `dict`, like `{"__class__": "Name", "@ivar": value}` |
+//! | Struct                       | `dict`, like `{"__struct__": "Name", "member": value}` |
+//!
+//! Python has no symbols, so they're written as `str`, and read back as strings.
+//! Other objects, like ones with `_dump` data, are written as `dict`s with their JSON keys, so they survive the round trip.
+//!
+//! `from_pickle()` reads pickles of protocols 2 to 5, written by `to_pickle()` or by Python, with the mapping above.
+//! Instances of Python classes are read as objects, with the class name and instance variables from their `__dict__`.
+//! Pickles can run arbitrary code when loaded in Python, but `from_pickle()` only understands builtin callables, that Python uses for regular expressions, bytes and sets, and returns an Err for others.
+//!
+//! Requires `bigint` feature. Not available with `sonic` feature enabled.
+
+use crate::value::{bytes_of, hash_key, is_hash, key_value, to_symbol, ValueError};
+use num_bigint::BigInt;
+use serde_json::{json, Map, Value};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, str::FromStr};
+
+const PROTOCOL: u8 = 3;
+/// Maximum nesting of containers, read by `from_pickle()`. Also stops recursive containers.
+const MAX_DEPTH: usize = 64;
+
+const MARK: u8 = b'(';
+const STOP: u8 = b'.';
+const POP: u8 = b'0';
+const POP_MARK: u8 = b'1';
+const DUP: u8 = b'2';
+const BINFLOAT: u8 = b'G';
+const BININT: u8 = b'J';
+const BININT1: u8 = b'K';
+const BININT2: u8 = b'M';
+const NONE: u8 = b'N';
+const REDUCE: u8 = b'R';
+const BINSTRING: u8 = b'T';
+const SHORT_BINSTRING: u8 = b'U';
+const BINUNICODE: u8 = b'X';
+const APPEND: u8 = b'a';
+const BUILD: u8 = b'b';
+const GLOBAL: u8 = b'c';
+const DICT: u8 = b'd';
+const EMPTY_DICT: u8 = b'}';
+const APPENDS: u8 = b'e';
+const BINGET: u8 = b'h';
+const LONG_BINGET: u8 = b'j';
+const LIST: u8 = b'l';
+const EMPTY_LIST: u8 = b']';
+const BINPUT: u8 = b'q';
+const LONG_BINPUT: u8 = b'r';
+const SETITEM: u8 = b's';
+const TUPLE: u8 = b't';
+const EMPTY_TUPLE: u8 = b')';
+const SETITEMS: u8 = b'u';
+const BINBYTES: u8 = b'B';
+const SHORT_BINBYTES: u8 = b'C';
+const PROTO: u8 = 0x80;
+const NEWOBJ: u8 = 0x81;
+const TUPLE1: u8 = 0x85;
+const TUPLE2: u8 = 0x86;
+const TUPLE3: u8 = 0x87;
+const NEWTRUE: u8 = 0x88;
+const NEWFALSE: u8 = 0x89;
+const LONG1: u8 = 0x8a;
+const LONG4: u8 = 0x8b;
+const SHORT_BINUNICODE: u8 = 0x8c;
+const BINUNICODE8: u8 = 0x8d;
+const BINBYTES8: u8 = 0x8e;
+const EMPTY_SET: u8 = 0x8f;
+const ADDITEMS: u8 = 0x90;
+const FROZENSET: u8 = 0x91;
+const NEWOBJ_EX: u8 = 0x92;
+const STACK_GLOBAL: u8 = 0x93;
+const MEMOIZE: u8 = 0x94;
+const FRAME: u8 = 0x95;
+const BYTEARRAY8: u8 = 0x96;
+
+/// Flags of Python's `re` module, matching Ruby's Regexp flags.
+const RE_IGNORECASE: i64 = 2;
+const RE_DOTALL: i64 = 16;
+const RE_VERBOSE: i64 = 64;
+
+fn write_len(pickle: &mut Vec<u8>, short: u8, long: u8, bytes: &[u8]) {
+    if let Ok(len) = u8::try_from(bytes.len()) {
+        pickle.push(short);
+        pickle.push(len);
+    } else {
+        pickle.push(long);
+        pickle.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    }
+
+    pickle.extend_from_slice(bytes);
+}
+
+fn write_str(pickle: &mut Vec<u8>, string: &str) {
+    pickle.push(BINUNICODE);
+    pickle.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    pickle.extend_from_slice(string.as_bytes());
+}
+
+/// Writes an integer with the shortest opcode. Integers out of `i32` range are written as little-endian two's complement bytes.
+fn write_int(pickle: &mut Vec<u8>, integer: i128) {
+    if let Ok(integer) = u8::try_from(integer) {
+        pickle.extend_from_slice(&[BININT1, integer]);
+    } else if let Ok(integer) = u16::try_from(integer) {
+        pickle.push(BININT2);
+        pickle.extend_from_slice(&integer.to_le_bytes());
+    } else if let Ok(integer) = i32::try_from(integer) {
+        pickle.push(BININT);
+        pickle.extend_from_slice(&integer.to_le_bytes());
+    } else {
+        let mut bytes: Vec<u8> = integer.to_le_bytes().to_vec();
+
+        while bytes.len() > 1 {
+            let last: u8 = bytes[bytes.len() - 1];
+            let sign: u8 = bytes[bytes.len() - 2] & 0x80;
+
+            if (last == 0 && sign == 0) || (last == 0xff && sign != 0) {
+                bytes.pop();
+            } else {
+                break;
+            }
+        }
+
+        write_len(pickle, LONG1, LONG4, &bytes);
+    }
+}
+
+fn write_list(pickle: &mut Vec<u8>, array: &[Value]) {
+    pickle.push(EMPTY_LIST);
+
+    if !array.is_empty() {
+        pickle.push(MARK);
+
+        for element in array {
+            write_value(pickle, element);
+        }
+
+        pickle.push(APPENDS);
+    }
+}
+
+/// Writes an Array as a tuple, which, unlike a list, can be a key of Python's dict.
+fn write_tuple(pickle: &mut Vec<u8>, array: &[Value]) {
+    pickle.push(MARK);
+
+    for element in array {
+        match element {
+            Value::Array(array) => write_tuple(pickle, array),
+            _ => write_value(pickle, element),
+        }
+    }
+
+    pickle.push(TUPLE);
+}
+
+fn write_dict<'v, I: IntoIterator<Item = (&'v str, &'v Value)>>(pickle: &mut Vec<u8>, entries: I) {
+    pickle.push(EMPTY_DICT);
+
+    let mut entries = entries.into_iter().peekable();
+
+    if entries.peek().is_some() {
+        pickle.push(MARK);
+
+        for (key, value) in entries {
+            write_str(pickle, key);
+            write_value(pickle, value);
+        }
+
+        pickle.push(SETITEMS);
+    }
+}
+
+fn write_hash(pickle: &mut Vec<u8>, hash: &Map<String, Value>) {
+    pickle.push(EMPTY_DICT);
+
+    if !hash.is_empty() {
+        pickle.push(MARK);
+
+        for (key, value) in hash {
+            match key_value(key) {
+                Value::Array(array) => write_tuple(pickle, &array),
+                Value::Object(_) => write_str(pickle, key),
+                key => write_value(pickle, &key),
+            }
+
+            write_value(pickle, value);
+        }
+
+        pickle.push(SETITEMS);
+    }
+}
+
+/// Returns whether all keys of the object, except for its class and type, are instance variables.
+fn has_only_ivars(object: &Map<String, Value>) -> bool {
+    object
+        .keys()
+        .all(|key| key == "__class" || key == "__type" || key.starts_with("__symbol__@"))
+}
+
+fn write_value(pickle: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => pickle.push(NONE),
+        Value::Bool(bool) => pickle.push(if *bool { NEWTRUE } else { NEWFALSE }),
+        Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                write_int(pickle, integer as i128);
+            } else if let Some(integer) = number.as_u64() {
+                write_int(pickle, integer as i128);
+            } else {
+                pickle.push(BINFLOAT);
+                pickle.extend_from_slice(&number.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(string) => {
+            write_str(pickle, string.strip_prefix("__symbol__").unwrap_or(string))
+        }
+        Value::Array(array) => write_list(pickle, array),
+        Value::Object(object) => {
+            if let Some(bytes) = bytes_of(value) {
+                write_len(pickle, SHORT_BINBYTES, BINBYTES, &bytes);
+                return;
+            }
+
+            if is_hash(value) {
+                write_hash(pickle, object);
+                return;
+            }
+
+            let class: &str = value["__class"].as_str().unwrap_or_default();
+
+            match value["__type"].as_str() {
+                Some("bigint") => {
+                    let bigint: BigInt = value["value"]
+                        .as_str()
+                        .and_then(|value| BigInt::from_str(value).ok())
+                        .unwrap_or_default();
+
+                    write_len(pickle, LONG1, LONG4, &bigint.to_signed_bytes_le());
+                }
+                Some("regexp") => {
+                    let flags: &str = value["flags"].as_str().unwrap_or_default();
+                    let mut re_flags: i64 = 0;
+
+                    for (flag, re_flag) in
+                        [('i', RE_IGNORECASE), ('x', RE_VERBOSE), ('m', RE_DOTALL)]
+                    {
+                        if flags.contains(flag) {
+                            re_flags |= re_flag;
+                        }
+                    }
+
+                    pickle.push(GLOBAL);
+                    pickle.extend_from_slice(b"re\n_compile\n");
+                    write_str(pickle, value["expression"].as_str().unwrap_or_default());
+                    write_int(pickle, re_flags as i128);
+                    pickle.extend_from_slice(&[TUPLE2, REDUCE]);
+                }
+                Some("object") if has_only_ivars(object) => write_dict(
+                    pickle,
+                    std::iter::once(("__class__", &json!(class.trim_start_matches("__symbol__"))))
+                        .chain(object.iter().filter_map(|(key, value)| {
+                            Some((key.strip_prefix("__symbol__")?, value))
+                        }))
+                        .collect::<Vec<_>>(),
+                ),
+                Some("struct") if value["__members"].is_object() => write_dict(
+                    pickle,
+                    std::iter::once(("__struct__", &json!(class.trim_start_matches("__symbol__"))))
+                        .chain(value["__members"].as_object().into_iter().flatten().map(
+                            |(key, value)| (key.strip_prefix("__symbol__").unwrap_or(key), value),
+                        ))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => {
+                    pickle.push(EMPTY_DICT);
+                    pickle.push(MARK);
+
+                    for (key, value) in object {
+                        write_str(pickle, key);
+
+                        match (key.as_str(), value) {
+                            ("__class", Value::String(class)) => write_str(pickle, class),
+                            _ => write_value(pickle, value),
+                        }
+                    }
+
+                    pickle.push(SETITEMS);
+                }
+            }
+        }
+    }
+}
+
+/// Converts the Value to Python pickle of protocol 3. See the module documentation for the mapping.
+/// # Example
+/// ```rust
+/// use marshal_rs::pickle::{from_pickle, to_pickle};
+/// use serde_json::json;
+///
+/// let pickle: Vec<u8> = to_pickle(&json!({ "a": [1, null] }));
+///
+/// assert_eq!(pickle, b"\x80\x03}(X\x01\x00\x00\x00a](K\x01Neu.");
+/// assert_eq!(from_pickle(&pickle).unwrap(), json!({ "a": [1, null] }));
+/// ```
+pub fn to_pickle(value: &Value) -> Vec<u8> {
+    let mut pickle: Vec<u8> = vec![PROTO, PROTOCOL];
+    write_value(&mut pickle, value);
+    pickle.push(STOP);
+    pickle
+}
+
+/// Python object on the stack of the unpickler. Containers are shared, because memoized containers are filled after they're memoized.
+enum PyObject {
+    Value(Value),
+    List(Vec<PyRef>),
+    Tuple(Vec<PyRef>),
+    Dict(Vec<(PyRef, PyRef)>),
+    Global(String, String),
+    Instance(String, Option<PyRef>),
+}
+
+type PyRef = Rc<RefCell<PyObject>>;
+/// Containers, that are being converted, to detect recursive ones.
+type Ancestors = Vec<*const RefCell<PyObject>>;
+
+fn py(object: PyObject) -> PyRef {
+    Rc::new(RefCell::new(object))
+}
+
+struct Unpickler<'a> {
+    pickle: &'a [u8],
+    position: usize,
+    stack: Vec<PyRef>,
+    marks: Vec<usize>,
+    memo: HashMap<u32, PyRef>,
+}
+
+impl<'a> Unpickler<'a> {
+    fn error(&self, message: &str) -> ValueError {
+        ValueError {
+            message: format!("{message} at pickle position {}", self.position),
+        }
+    }
+
+    fn read(&mut self, amount: usize) -> Result<&'a [u8], ValueError> {
+        let end: usize = self
+            .position
+            .checked_add(amount)
+            .filter(|&end| end <= self.pickle.len())
+            .ok_or_else(|| self.error("Unexpected end of pickle"))?;
+
+        let bytes: &[u8] = &self.pickle[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, ValueError> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ValueError> {
+        let bytes: &[u8] = self.read(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, ValueError> {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(self.read(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_chunk(&mut self, length: u64) -> Result<&'a [u8], ValueError> {
+        let length: usize = usize::try_from(length).map_err(|_| self.error("Chunk is too long"))?;
+        self.read(length)
+    }
+
+    fn read_line(&mut self) -> Result<String, ValueError> {
+        let length: usize = self.pickle[self.position..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| self.error("Unterminated line"))?;
+        let line: &[u8] = self.read(length + 1)?;
+
+        String::from_utf8(line[..length].to_vec()).map_err(|_| self.error("Invalid UTF-8 line"))
+    }
+
+    fn read_str(&mut self, length: u64) -> Result<PyRef, ValueError> {
+        let bytes: &[u8] = self.read_chunk(length)?;
+        let string: &str =
+            std::str::from_utf8(bytes).map_err(|_| self.error("Invalid UTF-8 string"))?;
+
+        Ok(py(PyObject::Value(string.into())))
+    }
+
+    fn read_bytes(&mut self, length: u64) -> Result<PyRef, ValueError> {
+        let bytes: &[u8] = self.read_chunk(length)?;
+        Ok(py(PyObject::Value(
+            json!({ "__type": "bytes", "data": bytes }),
+        )))
+    }
+
+    fn read_long(&mut self, length: u64) -> Result<PyRef, ValueError> {
+        let bigint: BigInt = BigInt::from_signed_bytes_le(self.read_chunk(length)?);
+
+        let value: Value = if let Ok(integer) = i64::try_from(&bigint) {
+            integer.into()
+        } else if let Ok(integer) = u64::try_from(&bigint) {
+            integer.into()
+        } else {
+            json!({ "__type": "bigint", "value": bigint.to_string() })
+        };
+
+        Ok(py(PyObject::Value(value)))
+    }
+
+    fn pop(&mut self) -> Result<PyRef, ValueError> {
+        self.stack
+            .pop()
+            .ok_or_else(|| self.error("Stack underflow"))
+    }
+
+    fn top(&self) -> Result<PyRef, ValueError> {
+        self.stack
+            .last()
+            .cloned()
+            .ok_or_else(|| self.error("Stack underflow"))
+    }
+
+    fn pop_mark(&mut self) -> Result<Vec<PyRef>, ValueError> {
+        let mark: usize = self
+            .marks
+            .pop()
+            .filter(|&mark| mark <= self.stack.len())
+            .ok_or_else(|| self.error("Missing mark"))?;
+
+        Ok(self.stack.split_off(mark))
+    }
+
+    fn memoize(&mut self, index: u32) -> Result<(), ValueError> {
+        let top: PyRef = self.top()?;
+        self.memo.insert(index, top);
+        Ok(())
+    }
+
+    fn get(&mut self, index: u32) -> Result<(), ValueError> {
+        let object: PyRef = self
+            .memo
+            .get(&index)
+            .cloned()
+            .ok_or_else(|| self.error("Missing memo entry"))?;
+
+        self.stack.push(object);
+        Ok(())
+    }
+
+    fn extend(&mut self, items: Vec<PyRef>) -> Result<(), ValueError> {
+        match &mut *self.top()?.borrow_mut() {
+            PyObject::List(list) => list.extend(items),
+            _ => return Err(self.error("Appending to a non-list")),
+        }
+
+        Ok(())
+    }
+
+    fn set_items(&mut self, items: Vec<PyRef>) -> Result<(), ValueError> {
+        if items.len() % 2 != 0 {
+            return Err(self.error("Odd number of dict items"));
+        }
+
+        let mut items = items.into_iter();
+
+        match &mut *self.top()?.borrow_mut() {
+            PyObject::Dict(dict) => {
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    dict.push((key, value));
+                }
+            }
+            _ => return Err(self.error("Setting items of a non-dict")),
+        }
+
+        Ok(())
+    }
+
+    fn global(&self, object: &PyRef) -> Result<(String, String), ValueError> {
+        match &*object.borrow() {
+            PyObject::Global(module, name) => Ok((module.clone(), name.clone())),
+            _ => Err(self.error("Calling a non-global")),
+        }
+    }
+
+    /// Calls builtin callables, that Python uses to pickle regular expressions, bytes and sets.
+    fn reduce(&self, callable: &PyRef, args: &PyRef) -> Result<PyRef, ValueError> {
+        let (module, name) = self.global(callable)?;
+
+        let args: Vec<Value> = match &*args.borrow() {
+            PyObject::Tuple(args) => args
+                .iter()
+                .map(|arg| into_value(arg, &mut Vec::new()))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(self.error("Arguments aren't a tuple")),
+        };
+
+        let value: Value = match (module.as_str(), name.as_str(), args.as_slice()) {
+            ("re", "_compile" | "compile", [expression, flags @ ..]) => {
+                let expression: String = match bytes_of(expression) {
+                    Some(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                    None => expression.as_str().unwrap_or_default().to_string(),
+                };
+                let re_flags: i64 = flags.first().and_then(Value::as_i64).unwrap_or_default();
+                let mut flags: String = String::new();
+
+                for (flag, re_flag) in [('i', RE_IGNORECASE), ('x', RE_VERBOSE), ('m', RE_DOTALL)] {
+                    if re_flags & re_flag != 0 {
+                        flags.push(flag);
+                    }
+                }
+
+                json!({ "__type": "regexp", "expression": expression, "flags": flags })
+            }
+            ("_codecs", "encode", [Value::String(string), ..]) => {
+                let bytes: Vec<u8> = string.chars().map(|char| char as u8).collect();
+                json!({ "__type": "bytes", "data": bytes })
+            }
+            ("builtins" | "__builtin__", "bytes" | "bytearray", _) => {
+                let bytes: Vec<u8> = match args.first() {
+                    Some(Value::String(string)) => string.chars().map(|char| char as u8).collect(),
+                    Some(bytes) => bytes_of(bytes).unwrap_or_default(),
+                    None => Vec::new(),
+                };
+
+                json!({ "__type": "bytes", "data": bytes })
+            }
+            ("builtins" | "__builtin__", "set" | "frozenset", _) => Value::Array(
+                args.first()
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            _ => {
+                return Err(self.error(&format!("Unsupported callable {module}.{name}")));
+            }
+        };
+
+        Ok(py(PyObject::Value(value)))
+    }
+
+    fn run(&mut self) -> Result<PyRef, ValueError> {
+        loop {
+            let opcode: u8 = self.read_byte()?;
+
+            let object: PyRef = match opcode {
+                STOP => return self.pop(),
+                PROTO => {
+                    if self.read_byte()? > 5 {
+                        return Err(self.error("Unsupported pickle protocol"));
+                    }
+
+                    continue;
+                }
+                FRAME => {
+                    self.read_u64()?;
+                    continue;
+                }
+                MARK => {
+                    self.marks.push(self.stack.len());
+                    continue;
+                }
+                POP => {
+                    self.pop()?;
+                    continue;
+                }
+                POP_MARK => {
+                    self.pop_mark()?;
+                    continue;
+                }
+                DUP => self.top()?,
+                NONE => py(PyObject::Value(Value::Null)),
+                NEWTRUE => py(PyObject::Value(true.into())),
+                NEWFALSE => py(PyObject::Value(false.into())),
+                BININT => {
+                    let integer: i32 = self.read_u32()? as i32;
+                    py(PyObject::Value(integer.into()))
+                }
+                BININT1 => py(PyObject::Value(self.read_byte()?.into())),
+                BININT2 => {
+                    let bytes: &[u8] = self.read(2)?;
+                    py(PyObject::Value(
+                        u16::from_le_bytes([bytes[0], bytes[1]]).into(),
+                    ))
+                }
+                LONG1 => {
+                    let length: u8 = self.read_byte()?;
+                    self.read_long(length as u64)?
+                }
+                LONG4 => {
+                    let length: u32 = self.read_u32()?;
+                    self.read_long(length as u64)?
+                }
+                BINFLOAT => {
+                    let mut bytes: [u8; 8] = [0; 8];
+                    bytes.copy_from_slice(self.read(8)?);
+                    py(PyObject::Value(json!(f64::from_be_bytes(bytes))))
+                }
+                SHORT_BINUNICODE => {
+                    let length: u8 = self.read_byte()?;
+                    self.read_str(length as u64)?
+                }
+                BINUNICODE => {
+                    let length: u32 = self.read_u32()?;
+                    self.read_str(length as u64)?
+                }
+                BINUNICODE8 => {
+                    let length: u64 = self.read_u64()?;
+                    self.read_str(length)?
+                }
+                SHORT_BINBYTES | SHORT_BINSTRING => {
+                    let length: u8 = self.read_byte()?;
+                    self.read_bytes(length as u64)?
+                }
+                BINBYTES | BINSTRING => {
+                    let length: u32 = self.read_u32()?;
+                    self.read_bytes(length as u64)?
+                }
+                BINBYTES8 | BYTEARRAY8 => {
+                    let length: u64 = self.read_u64()?;
+                    self.read_bytes(length)?
+                }
+                EMPTY_LIST | EMPTY_SET => py(PyObject::List(Vec::new())),
+                LIST => py(PyObject::List(self.pop_mark()?)),
+                FROZENSET => py(PyObject::List(self.pop_mark()?)),
+                APPEND => {
+                    let item: PyRef = self.pop()?;
+                    self.extend(vec![item])?;
+                    continue;
+                }
+                APPENDS | ADDITEMS => {
+                    let items: Vec<PyRef> = self.pop_mark()?;
+                    self.extend(items)?;
+                    continue;
+                }
+                EMPTY_TUPLE => py(PyObject::Tuple(Vec::new())),
+                TUPLE => py(PyObject::Tuple(self.pop_mark()?)),
+                TUPLE1 | TUPLE2 | TUPLE3 => {
+                    let length: usize = (opcode - TUPLE1 + 1) as usize;
+
+                    if self.stack.len() < length {
+                        return Err(self.error("Stack underflow"));
+                    }
+
+                    let items: Vec<PyRef> = self.stack.split_off(self.stack.len() - length);
+                    py(PyObject::Tuple(items))
+                }
+                EMPTY_DICT => py(PyObject::Dict(Vec::new())),
+                DICT => {
+                    let items: Vec<PyRef> = self.pop_mark()?;
+                    self.stack.push(py(PyObject::Dict(Vec::new())));
+                    self.set_items(items)?;
+                    continue;
+                }
+                SETITEM => {
+                    let value: PyRef = self.pop()?;
+                    let key: PyRef = self.pop()?;
+                    self.set_items(vec![key, value])?;
+                    continue;
+                }
+                SETITEMS => {
+                    let items: Vec<PyRef> = self.pop_mark()?;
+                    self.set_items(items)?;
+                    continue;
+                }
+                BINPUT => {
+                    let index: u8 = self.read_byte()?;
+                    self.memoize(index as u32)?;
+                    continue;
+                }
+                LONG_BINPUT => {
+                    let index: u32 = self.read_u32()?;
+                    self.memoize(index)?;
+                    continue;
+                }
+                MEMOIZE => {
+                    self.memoize(self.memo.len() as u32)?;
+                    continue;
+                }
+                BINGET => {
+                    let index: u8 = self.read_byte()?;
+                    self.get(index as u32)?;
+                    continue;
+                }
+                LONG_BINGET => {
+                    let index: u32 = self.read_u32()?;
+                    self.get(index)?;
+                    continue;
+                }
+                GLOBAL => {
+                    let module: String = self.read_line()?;
+                    let name: String = self.read_line()?;
+                    py(PyObject::Global(module, name))
+                }
+                STACK_GLOBAL => {
+                    let name: Value = into_value(&self.pop()?, &mut Vec::new())?;
+                    let module: Value = into_value(&self.pop()?, &mut Vec::new())?;
+
+                    match (module, name) {
+                        (Value::String(module), Value::String(name)) => {
+                            py(PyObject::Global(module, name))
+                        }
+                        _ => return Err(self.error("Global name isn't a string")),
+                    }
+                }
+                REDUCE => {
+                    let args: PyRef = self.pop()?;
+                    let callable: PyRef = self.pop()?;
+                    self.reduce(&callable, &args)?
+                }
+                NEWOBJ | NEWOBJ_EX => {
+                    if opcode == NEWOBJ_EX {
+                        self.pop()?;
+                    }
+
+                    self.pop()?;
+                    let class: PyRef = self.pop()?;
+                    let (_, name) = self.global(&class)?;
+                    py(PyObject::Instance(name.replace('.', "::"), None))
+                }
+                BUILD => {
+                    let state: PyRef = self.pop()?;
+
+                    match &mut *self.top()?.borrow_mut() {
+                        PyObject::Instance(_, instance_state) => *instance_state = Some(state),
+                        _ => return Err(self.error("Building a non-instance")),
+                    }
+
+                    continue;
+                }
+                _ => return Err(self.error(&format!("Unsupported opcode {opcode:#04x}"))),
+            };
+
+            self.stack.push(object);
+        }
+    }
+}
+
+fn depth_error() -> ValueError {
+    ValueError {
+        message: format!("Pickle is nested deeper than {MAX_DEPTH} levels, or is recursive"),
+    }
+}
+
+/// Converts a Python object to a Ruby object with the class and instance variables.
+fn object_value(class: &str, entries: Vec<(String, Value)>) -> Value {
+    let mut object: Map<String, Value> = Map::new();
+    object.insert("__class".to_string(), to_symbol(class).into());
+    object.insert("__type".to_string(), "object".into());
+
+    for (key, value) in entries {
+        let key: String = if key.starts_with('@') {
+            to_symbol(&key)
+        } else {
+            to_symbol(&format!("@{key}"))
+        };
+
+        object.insert(key, value);
+    }
+
+    Value::Object(object)
+}
+
+fn dict_entries(
+    dict: &[(PyRef, PyRef)],
+    ancestors: &mut Ancestors,
+) -> Result<Vec<(Value, Value)>, ValueError> {
+    dict.iter()
+        .map(|(key, value)| Ok((into_value(key, ancestors)?, into_value(value, ancestors)?)))
+        .collect()
+}
+
+fn string_entries(entries: Vec<(Value, Value)>) -> Vec<(String, Value)> {
+    entries
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.as_str()?.to_string(), value)))
+        .collect()
+}
+
+fn into_value(object: &PyRef, ancestors: &mut Ancestors) -> Result<Value, ValueError> {
+    let pointer: *const RefCell<PyObject> = Rc::as_ptr(object);
+
+    if ancestors.len() >= MAX_DEPTH || ancestors.contains(&pointer) {
+        return Err(depth_error());
+    }
+
+    ancestors.push(pointer);
+    let value: Value = object_into_value(&object.borrow(), ancestors)?;
+    ancestors.pop();
+
+    Ok(value)
+}
+
+fn object_into_value(object: &PyObject, ancestors: &mut Ancestors) -> Result<Value, ValueError> {
+    Ok(match object {
+        PyObject::Value(value) => value.clone(),
+        PyObject::List(items) | PyObject::Tuple(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| into_value(item, ancestors))
+                .collect::<Result<_, _>>()?,
+        ),
+        PyObject::Global(module, name) => {
+            return Err(ValueError {
+                message: format!("Unexpected global {module}.{name} in pickle"),
+            })
+        }
+        PyObject::Instance(class, state) => {
+            let entries: Vec<(String, Value)> = match state {
+                Some(state) => match &*state.borrow() {
+                    PyObject::Dict(dict) => string_entries(dict_entries(dict, ancestors)?),
+                    PyObject::Tuple(items) => match items.first().map(|item| item.borrow()) {
+                        Some(item) => match &*item {
+                            PyObject::Dict(dict) => string_entries(dict_entries(dict, ancestors)?),
+                            _ => Vec::new(),
+                        },
+                        None => Vec::new(),
+                    },
+                    _ => Vec::new(),
+                },
+                None => Vec::new(),
+            };
+
+            object_value(class, entries)
+        }
+        PyObject::Dict(dict) => {
+            let entries: Vec<(Value, Value)> = dict_entries(dict, ancestors)?;
+            let tag = |name: &str| {
+                entries
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .and_then(|(_, value)| value.as_str().map(str::to_string))
+            };
+
+            if let Some(class) = tag("__class__") {
+                object_value(
+                    &class,
+                    string_entries(entries)
+                        .into_iter()
+                        .filter(|(key, _)| key != "__class__")
+                        .collect(),
+                )
+            } else if let Some(class) = tag("__struct__") {
+                let members: Map<String, Value> = string_entries(entries)
+                    .into_iter()
+                    .filter(|(key, _)| key != "__struct__")
+                    .map(|(key, value)| (to_symbol(&key), value))
+                    .collect();
+
+                json!({ "__class": to_symbol(&class), "__type": "struct", "__members": members })
+            } else if tag("__type").is_some() {
+                Value::Object(string_entries(entries).into_iter().collect())
+            } else {
+                let mut hash: Map<String, Value> = Map::new();
+
+                for (key, value) in entries {
+                    hash.insert(hash_key(&key), value);
+                }
+
+                Value::Object(hash)
+            }
+        }
+    })
+}
+
+/// Converts Python pickle of protocols 2 to 5 to a Value. See the module documentation for the mapping.
+///
+/// Returns an Err when pickle is malformed, or uses callables, other than builtins for regular expressions, bytes and sets.
+pub fn from_pickle(pickle: &[u8]) -> Result<Value, ValueError> {
+    let mut unpickler: Unpickler = Unpickler {
+        pickle,
+        position: 0,
+        stack: Vec::new(),
+        marks: Vec::new(),
+        memo: HashMap::new(),
+    };
+
+    let object: PyRef = unpickler.run()?;
+
+    if unpickler.position != pickle.len() {
+        return Err(unpickler.error("Unexpected trailing bytes"));
+    }
+
+    into_value(&object, &mut Vec::new())
+}
@@ -0,0 +1,439 @@
+//! Direct conversion of Marshal data to plain JSON, for consumers, that only read the data, and never dump it back.
+//!
+//! `load_json()` converts structures while parsing them, without building the Value tree of `load()` first, and drops everything, that `dump()` needs to restore the data:
+//!
+//! | Ruby object                     | Plain JSON                                                              |
+//! | ------------------------------- | ----------------------------------------------------------------------- |
+//! | `nil`, `true`, `false`, Integer | `null`, `true`, `false`, number                                         |
+//! | Big Integer                     | number, or decimal string, if it doesn't fit in 64 bits                 |
+//! | Float                           | number, or by `NonFiniteFloatPolicy` for infinities and NaN             |
+//! | String, Symbol, Regexp          | string                                                                  |
+//! | String without encoding         | string with invalid UTF-8 replaced, or Array of bytes                   |
+//! | Array                           | Array                                                                   |
+//! | Hash                            | object with keys as strings, or as JSON text for non-string keys        |
+//! | Object, Struct                  | object of instance variables or members, optionally with the class      |
+//! | Class, Module                   | string with the name                                                    |
+//! | `_dump`, `marshal_dump` data    | the dumped data                                                         |
+//!
+//! Links to objects are replaced with copies of them, and links to objects, which contain the links, are replaced with `null`.
+//! # Example
+//! ```rust
+//! use marshal_rs::{plain::{load_json, PlainJsonOptions}, dump};
+//! use serde_json::json;
+//!
+//! let bytes: Vec<u8> = dump(
+//!     json!({ "__class": "__symbol__Point", "__type": "object", "__symbol__@x": 1, "__symbol__@tag": "__symbol__origin" }),
+//!     None,
+//! );
+//!
+//! let options = PlainJsonOptions { class_key: Some("class".to_string()), ..Default::default() };
+//!
+//! assert_eq!(load_json(&bytes, options).unwrap(), json!({ "class": "Point", "x": 1, "tag": "origin" }));
+//! ```
+//!
+//! Available with `serde` feature enabled.
+
+use crate::{
+    load::{scan_links, LoadError, NonFiniteFloatPolicy},
+    raw::{Constants, Reader},
+};
+#[cfg(feature = "encodings")]
+use encoding_rs::Encoding;
+use serde_json::{json, Map, Number, Value};
+
+/// Options of `load_json()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlainJsonOptions {
+    /// Key, that classes of objects and structs are written to. If None, classes are omitted.
+    pub class_key: Option<String>,
+    /// Prefix, that replaces "@" of instance variables. Defaults to an empty string, which removes it.
+    pub instance_var_prefix: String,
+    /// Whether strings without encoding, and `_dump` data are written as Arrays of bytes, instead of UTF-8 strings with invalid sequences replaced.
+    pub binary_as_bytes: bool,
+    /// Representation of NaN and infinite Floats, like `LoaderBuilder::non_finite_float_policy()`. Defaults to `null`.
+    pub non_finite_float_policy: NonFiniteFloatPolicy,
+}
+
+struct Converter<'a> {
+    reader: Reader<'a>,
+    options: PlainJsonOptions,
+    symbols: Vec<String>,
+    /// Converted objects, that are referenced by links. Others, and objects, that are still being converted, are None.
+    objects: Vec<Option<Value>>,
+    /// Whether each object is referenced by a link. If None, the document couldn't be scanned, and all objects are stored.
+    linked: Option<Vec<bool>>,
+}
+
+impl<'a> Converter<'a> {
+    fn length(&mut self) -> Result<usize, LoadError> {
+        self.reader.read_length()
+    }
+
+    /// Reserves a place for the object in the objects table, in the same order, as the Loader does.
+    fn register(&mut self) -> usize {
+        self.objects.push(None);
+        self.objects.len() - 1
+    }
+
+    /// Stores the converted object, if it's referenced by a link.
+    fn store(&mut self, index: usize, value: Value) -> Value {
+        let linked: bool = self
+            .linked
+            .as_ref()
+            .map_or(true, |linked| linked.get(index).copied().unwrap_or(false));
+
+        if linked {
+            self.objects[index] = Some(value.clone());
+        }
+
+        value
+    }
+
+    fn binary(&self, bytes: &[u8]) -> Value {
+        if self.options.binary_as_bytes {
+            bytes.into()
+        } else {
+            String::from_utf8_lossy(bytes).into()
+        }
+    }
+
+    /// Represents the NaN or infinite Float by the policy. Other Floats, that can't be parsed, are `null`.
+    fn non_finite_float(&self, string: &str, position: usize) -> Result<Value, LoadError> {
+        let name: &str = match string {
+            "nan" => "NaN",
+            "inf" => "Infinity",
+            "-inf" => "-Infinity",
+            _ => return Ok(Value::Null),
+        };
+
+        Ok(match self.options.non_finite_float_policy {
+            NonFiniteFloatPolicy::Null => Value::Null,
+            NonFiniteFloatPolicy::String => name.into(),
+            NonFiniteFloatPolicy::Tagged => json!({ "__type": "float", "value": string }),
+            NonFiniteFloatPolicy::Error => {
+                return Err(LoadError {
+                    message: format!("Float {string} can't be represented at position {position}."),
+                    ..Default::default()
+                })
+            }
+        })
+    }
+
+    fn symbol(&mut self) -> Result<String, LoadError> {
+        let position: usize = self.reader.position();
+
+        match self.value()? {
+            Value::String(symbol) => Ok(symbol),
+            _ => Err(LoadError {
+                message: format!("Expected a symbol at position {position}."),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Reads the instance variables of a string, returning the name of its encoding, if there's one.
+    fn encoding(&mut self) -> Result<Option<String>, LoadError> {
+        let mut encoding: Option<String> = None;
+
+        for _ in 0..self.length()? {
+            let name: String = self.symbol()?;
+            let value: Value = self.value()?;
+
+            match (name.as_str(), value) {
+                ("E", Value::Bool(_)) => encoding = Some("UTF-8".to_string()),
+                ("encoding", Value::String(label)) => encoding = Some(label),
+                _ => {}
+            }
+        }
+
+        Ok(encoding)
+    }
+
+    /// Reads the instance variables or members into the object, replacing "@" prefixes of their names, if `instance_vars` is set.
+    fn fields(
+        &mut self,
+        object: &mut Map<String, Value>,
+        instance_vars: bool,
+    ) -> Result<(), LoadError> {
+        for _ in 0..self.length()? {
+            let mut name: String = self.symbol()?;
+
+            if instance_vars && name.starts_with('@') {
+                name.replace_range(..1, &self.options.instance_var_prefix);
+            }
+
+            let value: Value = self.value()?;
+            object.insert(name, value);
+        }
+
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, LoadError> {
+        let position: usize = self.reader.position();
+        let structure: Constants = self.reader.read_type()?;
+
+        Ok(match structure {
+            Constants::Nil => Value::Null,
+            Constants::True => Value::Bool(true),
+            Constants::False => Value::Bool(false),
+            Constants::Fixnum => self.reader.read_int()?.into(),
+            Constants::Symbol => {
+                let symbol: String =
+                    String::from_utf8_lossy(self.reader.read_chunk()?).into_owned();
+                self.symbols.push(symbol.clone());
+                symbol.into()
+            }
+            Constants::Symlink | Constants::Link => {
+                let symbol: bool = structure == Constants::Symlink;
+                let index: i32 = self.reader.read_int()?;
+                let index: Option<usize> = usize::try_from(index).ok();
+
+                let value: Option<Value> = if symbol {
+                    index
+                        .and_then(|index| self.symbols.get(index))
+                        .map(|symbol| symbol.as_str().into())
+                } else {
+                    index
+                        .and_then(|index| self.objects.get(index))
+                        .map(|object| object.clone().unwrap_or_default())
+                };
+
+                value.ok_or_else(|| LoadError {
+                    message: format!(
+                        "Invalid link {} at position {position}.",
+                        index.unwrap_or(0)
+                    ),
+                    ..Default::default()
+                })?
+            }
+            Constants::InstanceVar => {
+                let string_position: usize = self.reader.position();
+
+                if self.reader.read_type()? != Constants::String {
+                    self.reader.set_position(string_position);
+                    let value: Value = self.value()?;
+
+                    // Instance variables of other structures, like encodings of Regexps, are dropped
+                    for _ in 0..self.length()? {
+                        self.value()?;
+                        self.value()?;
+                    }
+
+                    return Ok(value);
+                }
+
+                let index: usize = self.register();
+                let bytes: &[u8] = self.reader.read_chunk()?;
+                let encoding: Option<String> = self.encoding()?;
+
+                let value: Value = match encoding {
+                    Some(encoding) => decode(bytes, &encoding).into(),
+                    None => self.binary(bytes),
+                };
+
+                self.store(index, value)
+            }
+            Constants::Extended => {
+                self.symbol()?;
+                self.value()?
+            }
+            Constants::Array => {
+                let index: usize = self.register();
+                let length: usize = self.length()?;
+                let mut array: Vec<Value> = Vec::new();
+
+                for _ in 0..length {
+                    array.push(self.value()?);
+                }
+
+                self.store(index, array.into())
+            }
+            Constants::Hash | Constants::HashDefault => {
+                let index: usize = self.register();
+                let mut hash: Map<String, Value> = Map::new();
+
+                for _ in 0..self.length()? {
+                    let key: String = match self.value()? {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+
+                    let value: Value = self.value()?;
+                    hash.insert(key, value);
+                }
+
+                // Default values aren't data
+                if structure == Constants::HashDefault {
+                    self.value()?;
+                }
+
+                self.store(index, hash.into())
+            }
+            Constants::Object | Constants::Struct => {
+                let class: String = self.symbol()?;
+                let index: usize = self.register();
+                let mut object: Map<String, Value> = Map::new();
+
+                if let Some(class_key) = &self.options.class_key {
+                    object.insert(class_key.clone(), class.into());
+                }
+
+                self.fields(&mut object, structure == Constants::Object)?;
+                self.store(index, object.into())
+            }
+            Constants::Float => {
+                let index: usize = self.register();
+                let string: String =
+                    String::from_utf8_lossy(self.reader.read_chunk()?).into_owned();
+                let value: Value = match parse_float(&string) {
+                    Some(number) => Value::Number(number),
+                    None => self.non_finite_float(&string, position)?,
+                };
+
+                self.store(index, value)
+            }
+            Constants::Bignum => {
+                let index: usize = self.register();
+                let negative: bool = self.reader.read_byte()? == Constants::Negative;
+                let length: usize = self.length()?;
+                let bytes: &[u8] = self.reader.read_bytes(length * 2)?;
+
+                self.store(index, bignum(bytes, negative))
+            }
+            Constants::String => {
+                let index: usize = self.register();
+                let bytes: &[u8] = self.reader.read_chunk()?;
+                let value: Value = self.binary(bytes);
+
+                self.store(index, value)
+            }
+            Constants::Regexp => {
+                let index: usize = self.register();
+                let source: Value = String::from_utf8_lossy(self.reader.read_chunk()?).into();
+                self.reader.read_byte()?;
+
+                self.store(index, source)
+            }
+            Constants::Class | Constants::Module | Constants::ModuleOld => {
+                let index: usize = self.register();
+                let name: Value = String::from_utf8_lossy(self.reader.read_chunk()?).into();
+
+                self.store(index, name)
+            }
+            Constants::Data | Constants::UserClass | Constants::UserMarshal => {
+                self.symbol()?;
+                let index: usize = self.register();
+                let value: Value = self.value()?;
+
+                self.store(index, value)
+            }
+            Constants::UserDefined => {
+                self.symbol()?;
+                let index: usize = self.register();
+                let bytes: &[u8] = self.reader.read_chunk()?;
+                let value: Value = self.binary(bytes);
+
+                self.store(index, value)
+            }
+            _ => {
+                return Err(LoadError {
+                    message: format!("Unexpected {structure:?} at position {position}."),
+                    ..Default::default()
+                })
+            }
+        })
+    }
+}
+
+/// Decodes the string from its encoding, replacing invalid sequences. Strings in unknown encodings are decoded as UTF-8.
+fn decode(bytes: &[u8], encoding: &str) -> String {
+    // encoding_rs treats US-ASCII label as windows-1252, so it's decoded as UTF-8
+    #[cfg(feature = "encodings")]
+    if !encoding.eq_ignore_ascii_case("US-ASCII") {
+        if let Some(encoding) = Encoding::for_label(encoding.as_bytes()) {
+            return encoding.decode_without_bom_handling(bytes).0.into_owned();
+        }
+    }
+
+    #[cfg(not(feature = "encodings"))]
+    let _ = encoding;
+
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Parses the Float, like Ruby writes it. Returns None for infinities and NaN, which JSON can't represent.
+fn parse_float(string: &str) -> Option<Number> {
+    let float: f64 = match string.parse::<f64>() {
+        Ok(float) => float,
+        // Old Ruby versions wrote the mantissa after the number, separated by a null byte
+        Err(_) => string
+            .split('\0')
+            .next()
+            .and_then(|number| number.parse::<f64>().ok())?,
+    };
+
+    Number::from_f64(float)
+}
+
+/// Converts the little-endian magnitude of the Big Integer to a number, or to a decimal string, if it doesn't fit in 64 bits.
+fn bignum(bytes: &[u8], negative: bool) -> Value {
+    let length: usize = bytes
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+
+    if length <= 8 {
+        let mut buffer: [u8; 8] = [0; 8];
+        buffer[..length].copy_from_slice(&bytes[..length]);
+        let magnitude: u64 = u64::from_le_bytes(buffer);
+
+        if !negative {
+            return magnitude.into();
+        }
+
+        if magnitude <= i64::MAX as u64 + 1 {
+            return (magnitude as i64).wrapping_neg().into();
+        }
+    }
+
+    let mut magnitude: Vec<u8> = bytes[..length].to_vec();
+    let mut digits: Vec<u8> = Vec::new();
+
+    // Divides the magnitude by 10, collecting remainders as digits
+    while magnitude.iter().any(|&byte| byte != 0) {
+        let mut remainder: u16 = 0;
+
+        for byte in magnitude.iter_mut().rev() {
+            let current: u16 = (remainder << 8) | *byte as u16;
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+
+        digits.push(b'0' + remainder as u8);
+    }
+
+    if negative {
+        digits.push(b'-');
+    }
+
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default().into()
+}
+
+/// Converts the Marshal data straight to plain JSON, without building the Value tree of `load()`. The data can't be dumped back from the result.
+///
+/// Symbols are written as plain strings, objects as maps of their instance variables, and other structures by the table of `plain` module.
+/// Returns an Err, if the data is not a valid Marshal document. Bytes after the document are ignored.
+pub fn load_json(buffer: &[u8], options: PlainJsonOptions) -> Result<Value, LoadError> {
+    let mut converter: Converter = Converter {
+        reader: Reader::new(buffer),
+        options,
+        symbols: Vec::new(),
+        objects: Vec::new(),
+        linked: scan_links(buffer),
+    };
+
+    converter.reader.read_version()?;
+    converter.value()
+}
@@ -0,0 +1,283 @@
+//! Pool of internal tables of Loaders and Dumpers, and of output buffers, for batch processing of many files.
+//!
+//! Loaders and Dumpers keep their tables across calls, but each new instance allocates them anew.
+//! Instances, built with a pool, take tables from it at the start of each call, and return them at the end, so short-lived instances reuse capacity of previous ones.
+//! Dumped bytes can be returned to the pool with `TablePool::recycle()` after they're written, so following dumps reuse their capacity too.
+//!
+//! `TablePool` is meant to be used by Loaders and Dumpers of a single thread. Services, that load and dump values on many threads,
+//! should use `Pool`, which hands out whole Loader and Dumper instances to any thread, and takes them back, when they're dropped.
+//! # Example
+//! ```rust
+//! use marshal_rs::{pool::TablePool, Dumper, Loader};
+//! use serde_json::json;
+//!
+//! let pool = TablePool::new();
+//!
+//! for value in [json!([1, 2]), json!({ "a": "b" })] {
+//!     let bytes: Vec<u8> = Dumper::builder().pool(&pool).build().dump(value.clone(), None);
+//!     assert_eq!(Loader::builder().pool(&pool).build().load(&bytes, None, None).unwrap(), value);
+//!
+//!     pool.recycle(bytes);
+//! }
+//! ```
+
+use crate::{dump::DumperTable, load::SymbolTable, Dumper, DumperBuilder, Loader, LoaderBuilder};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard},
+    thread::{self, ThreadId},
+};
+
+/// Locks the mutex, ignoring poisoning: stored items are only pushed and popped, so they can't be left in an inconsistent state.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Pool of tables and buffers, that are shared by Loaders and Dumpers of a thread.
+#[derive(Default)]
+pub struct TablePool {
+    loader_tables: Mutex<Vec<SymbolTable>>,
+    dumper_tables: Mutex<Vec<(DumperTable, DumperTable)>>,
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl std::fmt::Debug for TablePool {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("TablePool")
+            .field("loader_tables", &lock(&self.loader_tables).len())
+            .field("dumper_tables", &lock(&self.dumper_tables).len())
+            .field("buffers", &lock(&self.buffers).len())
+            .finish()
+    }
+}
+
+impl TablePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the buffer with dumped bytes to the pool, so its capacity is reused by following dumps.
+    pub fn recycle(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        lock(&self.buffers).push(buffer);
+    }
+
+    /// Returns the number of tables and buffers, that are currently stored in the pool.
+    pub fn len(&self) -> usize {
+        lock(&self.loader_tables).len()
+            + lock(&self.dumper_tables).len()
+            + lock(&self.buffers).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all stored tables and buffers, freeing their memory.
+    pub fn clear(&self) {
+        lock(&self.loader_tables).clear();
+        lock(&self.dumper_tables).clear();
+        lock(&self.buffers).clear();
+    }
+
+    pub(crate) fn take_loader_table(&self) -> SymbolTable {
+        lock(&self.loader_tables).pop().unwrap_or_default()
+    }
+
+    pub(crate) fn give_loader_table(&self, mut symbols: SymbolTable) {
+        symbols.clear();
+        lock(&self.loader_tables).push(symbols);
+    }
+
+    pub(crate) fn take_dumper_tables(&self) -> (DumperTable, DumperTable) {
+        lock(&self.dumper_tables).pop().unwrap_or_default()
+    }
+
+    pub(crate) fn give_dumper_tables(&self, mut symbols: DumperTable, mut objects: DumperTable) {
+        symbols.clear();
+        objects.clear();
+        lock(&self.dumper_tables).push((symbols, objects));
+    }
+
+    pub(crate) fn take_buffer(&self) -> Option<Vec<u8>> {
+        lock(&self.buffers).pop()
+    }
+}
+
+/// Thread-safe pool of Loaders and Dumpers, for services, that load and dump values on many threads, like a Loader per request.
+///
+/// `loader()` and `dumper()` hand out instances, built with the pool's builders, and return them to the pool, when they're dropped.
+/// Returned instances keep their tables and output buffers warm, and are handed out again preferably to the thread, that used them last.
+/// Settings, changed with setters of handed out instances, stay changed, when they're returned to the pool.
+/// # Example
+/// ```rust
+/// use marshal_rs::{pool::Pool, Loader};
+/// use serde_json::json;
+///
+/// let pool = Pool::new(Loader::builder().max_depth(64), Default::default());
+///
+/// std::thread::scope(|scope| {
+///     for number in 0..4 {
+///         let pool = &pool;
+///
+///         scope.spawn(move || {
+///             let bytes: Vec<u8> = pool.dumper().dump(json!([number]), None);
+///             assert_eq!(pool.loader().load(&bytes, None, None).unwrap(), json!([number]));
+///
+///             pool.recycle(bytes);
+///         });
+///     }
+/// });
+/// ```
+pub struct Pool<'a> {
+    loader_builder: LoaderBuilder<'a>,
+    dumper_builder: DumperBuilder<'a>,
+    loaders: Mutex<Vec<(ThreadId, Loader<'a>)>>,
+    dumpers: Mutex<Vec<(ThreadId, Dumper<'a>)>>,
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl<'a> std::fmt::Debug for Pool<'a> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("Pool")
+            .field("loaders", &lock(&self.loaders).len())
+            .field("dumpers", &lock(&self.dumpers).len())
+            .field("buffers", &lock(&self.buffers).len())
+            .finish()
+    }
+}
+
+impl<'a> Default for Pool<'a> {
+    fn default() -> Self {
+        Self::new(LoaderBuilder::new(), DumperBuilder::new())
+    }
+}
+
+/// Takes the item, that was last used by the current thread, or the most recently returned one.
+fn take_item<T>(items: &Mutex<Vec<(ThreadId, T)>>) -> Option<T> {
+    let mut items = lock(items);
+    let current: ThreadId = thread::current().id();
+
+    match items.iter().rposition(|(thread, _)| *thread == current) {
+        Some(index) => Some(items.remove(index).1),
+        None => items.pop().map(|(_, item)| item),
+    }
+}
+
+impl<'a> Pool<'a> {
+    /// Creates an empty pool, that builds new Loaders and Dumpers with the builders, when no returned instances are available.
+    pub fn new(loader_builder: LoaderBuilder<'a>, dumper_builder: DumperBuilder<'a>) -> Self {
+        Self {
+            loader_builder,
+            dumper_builder,
+            loaders: Mutex::default(),
+            dumpers: Mutex::default(),
+            buffers: Mutex::default(),
+        }
+    }
+
+    /// Hands out a Loader, that's returned to the pool, when it's dropped.
+    pub fn loader(&self) -> PooledLoader<'_, 'a> {
+        PooledLoader {
+            pool: self,
+            loader: Some(take_item(&self.loaders).unwrap_or_else(|| self.loader_builder.build())),
+        }
+    }
+
+    /// Hands out a Dumper, that's returned to the pool, when it's dropped.
+    pub fn dumper(&self) -> PooledDumper<'_, 'a> {
+        let mut dumper: Dumper<'a> =
+            take_item(&self.dumpers).unwrap_or_else(|| self.dumper_builder.build());
+
+        if dumper.buffer.capacity() == 0 {
+            if let Some(buffer) = lock(&self.buffers).pop() {
+                dumper.buffer = buffer;
+            }
+        }
+
+        PooledDumper {
+            pool: self,
+            dumper: Some(dumper),
+        }
+    }
+
+    /// Returns the buffer with dumped bytes to the pool, so its capacity is reused by following dumps.
+    pub fn recycle(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        lock(&self.buffers).push(buffer);
+    }
+
+    /// Returns the number of Loaders, Dumpers and buffers, that are currently stored in the pool.
+    pub fn len(&self) -> usize {
+        lock(&self.loaders).len() + lock(&self.dumpers).len() + lock(&self.buffers).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all stored Loaders, Dumpers and buffers, freeing their memory. Handed out instances are still returned to the pool.
+    pub fn clear(&self) {
+        lock(&self.loaders).clear();
+        lock(&self.dumpers).clear();
+        lock(&self.buffers).clear();
+    }
+}
+
+/// Loader, handed out by `Pool::loader()`. Dereferences to `Loader`, and returns it to the pool, when it's dropped.
+pub struct PooledLoader<'p, 'a> {
+    pool: &'p Pool<'a>,
+    loader: Option<Loader<'a>>,
+}
+
+impl<'p, 'a> Deref for PooledLoader<'p, 'a> {
+    type Target = Loader<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.loader.as_ref().unwrap()
+    }
+}
+
+impl<'p, 'a> DerefMut for PooledLoader<'p, 'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.loader.as_mut().unwrap()
+    }
+}
+
+impl<'p, 'a> Drop for PooledLoader<'p, 'a> {
+    fn drop(&mut self) {
+        if let Some(loader) = self.loader.take() {
+            lock(&self.pool.loaders).push((thread::current().id(), loader));
+        }
+    }
+}
+
+/// Dumper, handed out by `Pool::dumper()`. Dereferences to `Dumper`, and returns it to the pool, when it's dropped.
+pub struct PooledDumper<'p, 'a> {
+    pool: &'p Pool<'a>,
+    dumper: Option<Dumper<'a>>,
+}
+
+impl<'p, 'a> Deref for PooledDumper<'p, 'a> {
+    type Target = Dumper<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.dumper.as_ref().unwrap()
+    }
+}
+
+impl<'p, 'a> DerefMut for PooledDumper<'p, 'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.dumper.as_mut().unwrap()
+    }
+}
+
+impl<'p, 'a> Drop for PooledDumper<'p, 'a> {
+    fn drop(&mut self) {
+        if let Some(dumper) = self.dumper.take() {
+            lock(&self.pool.dumpers).push((thread::current().id(), dumper));
+        }
+    }
+}
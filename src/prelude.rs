@@ -0,0 +1,14 @@
+//! Re-exports of commonly used types and traits.
+//!
+//! ```rust
+//! use marshal_rs::prelude::*;
+//! ```
+
+pub use crate::dump::{dump, Dumper, DumperBuilder};
+pub use crate::load::{
+    load, DuplicateKeyPolicy, LoadError, Loader, LoaderBuilder, Preset, StringMode,
+};
+#[cfg(not(feature = "sonic"))]
+pub use crate::typed::{FromValue, IntoValue};
+#[cfg(not(feature = "sonic"))]
+pub use crate::value::{Path, PathSegment, ValueError, ValueExt};
@@ -0,0 +1,419 @@
+//! Bindings for Python, exposing `loads()` and `dumps()` through PyO3, so Marshal files can be read in notebooks without installing Ruby.
+//!
+//! Loaded values are converted to native Python objects:
+//!
+//! | Ruby object                              | Python object                                  |
+//! | ---------------------------------------- | ---------------------------------------------- |
+//! | `nil`, `true`, `false`                   | `None`, `True`, `False`                        |
+//! | Integer, Big Integer, Float              | `int`, `float`                                 |
+//! | String                                   | `str`, or `bytes`, if it's not decoded         |
+//! | Symbol                                   | `Symbol`, holding its name                     |
+//! | Array, Hash                              | `list`, `dict`                                 |
+//! | Objects, structs, classes, modules, Regexps | `RubyObject`, holding the class name, `kind` and `attributes` |
+//!
+//! Hash keys, that can't be Python dict keys, like Arrays, are kept as strings, that `ValueExt` uses for them.
+//! `dumps()` converts such objects back, and also accepts tuples as Arrays and `bytearray`s as binary strings.
+//!
+//! To build a package, depend on `marshal-rs` with `python` feature from a `cdylib` crate, call `register()` from its `#[pymodule]`, and build it with `maturin`.
+//!
+//! Requires `python` feature. Not available with `sonic` feature enabled.
+
+use crate::{
+    dump::Dumper,
+    load::Loader,
+    value::{bytes_of, hash_key, key_value, to_symbol},
+    StringMode, ValueExt, EXTENDS_SYMBOL,
+};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+    IntoPyObjectExt,
+};
+use serde_json::{json, Map, Value};
+
+/// Ruby's Symbol, like `:name`.
+#[pyclass(module = "marshal_rs", frozen, eq, hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    #[pyo3(get)]
+    pub name: String,
+}
+
+#[pymethods]
+impl Symbol {
+    #[new]
+    fn new(name: String) -> Self {
+        Symbol { name }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Symbol({:?})", self.name)
+    }
+}
+
+/// Ruby object, that has no native Python counterpart.
+///
+/// `kind` is one of:
+/// - `"object"`, `"struct"`: `attributes` hold instance variables with "@" or struct members.
+/// - `"user_defined"`: `data` holds bytes, produced by `_dump`, and `attributes` hold instance variables.
+/// - `"user_marshal"`, `"data"`, `"wrapped"`: `data` holds the object, produced by `marshal_dump` or `_dump_data`, or the wrapped String, Regexp, Array or Hash of the subclass.
+/// - `"class"`, `"module"`: `class_name` is the name of the class or module.
+/// - `"regexp"`: `data` holds the expression, and `attributes` hold its `flags`, like `"im"`.
+#[pyclass(module = "marshal_rs", get_all, set_all)]
+pub struct RubyObject {
+    pub class_name: String,
+    pub kind: String,
+    pub attributes: Py<PyDict>,
+    pub data: PyObject,
+    /// Names of modules, that the object is extended with.
+    pub extends: Vec<String>,
+}
+
+#[pymethods]
+impl RubyObject {
+    #[new]
+    #[pyo3(signature = (class_name, kind = None, attributes = None, data = None, extends = Vec::new()))]
+    fn new(
+        py: Python<'_>,
+        class_name: String,
+        kind: Option<String>,
+        attributes: Option<Py<PyDict>>,
+        data: Option<PyObject>,
+        extends: Vec<String>,
+    ) -> Self {
+        RubyObject {
+            class_name,
+            kind: kind.unwrap_or_else(|| "object".to_string()),
+            attributes: attributes.unwrap_or_else(|| PyDict::new(py).unbind()),
+            data: data.unwrap_or_else(|| py.None()),
+            extends,
+        }
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "RubyObject({:?}, kind={:?}, attributes={})",
+            self.class_name,
+            self.kind,
+            self.attributes.bind(py).repr()?
+        ))
+    }
+}
+
+/// Strips the symbol prefix from keys of instance variables and struct members, and converts their values.
+fn attributes_to_python<'py>(
+    py: Python<'py>,
+    entries: &Map<String, Value>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let attributes: Bound<PyDict> = PyDict::new(py);
+
+    for (key, entry) in entries {
+        if let Some(name) = key.strip_prefix("__symbol__") {
+            attributes.set_item(name, to_python(py, entry)?)?;
+        }
+    }
+
+    Ok(attributes)
+}
+
+fn ruby_object(py: Python<'_>, value: &Value, kind: &str) -> PyResult<RubyObject> {
+    let mut object: RubyObject = RubyObject::new(
+        py,
+        value.class_name().unwrap_or_default().to_string(),
+        Some(kind.to_string()),
+        None,
+        None,
+        value[EXTENDS_SYMBOL]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(|module| {
+                module
+                    .strip_prefix("__symbol__")
+                    .unwrap_or(module)
+                    .to_string()
+            })
+            .collect(),
+    );
+
+    match kind {
+        "class" | "module" => {
+            object.class_name = value["__class"].as_str().unwrap_or_default().to_string();
+        }
+        "regexp" => {
+            object.class_name = "Regexp".to_string();
+            object.data = to_python(py, &value["expression"])?;
+            object
+                .attributes
+                .bind(py)
+                .set_item("flags", value["flags"].as_str().unwrap_or_default())?;
+        }
+        "struct" => {
+            if let Some(members) = value["__members"].as_object() {
+                object.attributes = attributes_to_python(py, members)?.unbind();
+            }
+        }
+        _ => {
+            for (key, kind) in [
+                ("__userDefined", "user_defined"),
+                ("__userMarshal", "user_marshal"),
+                ("__data", "data"),
+                ("__wrapped", "wrapped"),
+            ] {
+                if let Some(data) = value.get(key) {
+                    object.kind = kind.to_string();
+                    object.data = if key == "__userDefined" {
+                        let bytes: Vec<u8> = serde_json::from_value(data.clone())
+                            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+                        PyBytes::new(py, &bytes).into_any().unbind()
+                    } else {
+                        to_python(py, data)?
+                    };
+                }
+            }
+
+            object.attributes = attributes_to_python(py, value.as_object().unwrap())?.unbind();
+        }
+    }
+
+    Ok(object)
+}
+
+/// Converts the loaded Value to a Python object.
+pub fn to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(bool) => bool.into_py_any(py)?,
+        Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                integer.into_py_any(py)?
+            } else if let Some(integer) = number.as_u64() {
+                integer.into_py_any(py)?
+            } else {
+                number.as_f64().unwrap_or_default().into_py_any(py)?
+            }
+        }
+        Value::String(string) => match string.strip_prefix("__symbol__") {
+            Some(name) => Symbol::new(name.to_string()).into_py_any(py)?,
+            None => string.into_py_any(py)?,
+        },
+        Value::Array(array) => {
+            let list: Bound<PyList> = PyList::empty(py);
+
+            for element in array {
+                list.append(to_python(py, element)?)?;
+            }
+
+            list.into_any().unbind()
+        }
+        Value::Object(object) => match value["__type"].as_str() {
+            Some("bytes") => PyBytes::new(py, &bytes_of(value).unwrap_or_default())
+                .into_any()
+                .unbind(),
+            Some("bigint") => py
+                .get_type::<PyInt>()
+                .call1((value["value"].as_str().unwrap_or("0"),))?
+                .unbind(),
+            Some(kind @ ("object" | "struct" | "class" | "module" | "regexp")) => {
+                ruby_object(py, value, kind)?.into_py_any(py)?
+            }
+            _ => {
+                let dict: Bound<PyDict> = PyDict::new(py);
+
+                for (key, entry) in object {
+                    let mut dict_key: PyObject = to_python(py, &key_value(key))?;
+
+                    // Lists and dicts are unhashable
+                    if dict_key.bind(py).hash().is_err() {
+                        dict_key = key.into_py_any(py)?;
+                    }
+
+                    dict.set_item(dict_key, to_python(py, entry)?)?;
+                }
+
+                dict.into_any().unbind()
+            }
+        },
+    })
+}
+
+fn attributes_from_python(attributes: &Bound<PyDict>) -> PyResult<Map<String, Value>> {
+    attributes
+        .iter()
+        .map(|(name, attribute)| {
+            Ok((
+                to_symbol(&name.extract::<String>()?),
+                from_python(&attribute)?,
+            ))
+        })
+        .collect()
+}
+
+fn ruby_object_from_python(object: &RubyObject, py: Python<'_>) -> PyResult<Value> {
+    let attributes: &Bound<PyDict> = object.attributes.bind(py);
+    let data: &Bound<PyAny> = object.data.bind(py);
+
+    let mut value: Value = match object.kind.as_str() {
+        "class" | "module" => {
+            return Ok(json!({ "__class": object.class_name, "__type": object.kind }));
+        }
+        "regexp" => {
+            let flags: String = match attributes.get_item("flags")? {
+                Some(flags) => flags.extract()?,
+                None => String::new(),
+            };
+
+            return Ok(
+                json!({ "__type": "regexp", "expression": data.extract::<String>()?, "flags": flags }),
+            );
+        }
+        "struct" => json!({
+            "__class": to_symbol(&object.class_name),
+            "__type": "struct",
+            "__members": attributes_from_python(attributes)?,
+        }),
+        kind => {
+            let key: Option<&str> = match kind {
+                "object" => None,
+                "user_defined" => Some("__userDefined"),
+                "user_marshal" => Some("__userMarshal"),
+                "data" => Some("__data"),
+                "wrapped" => Some("__wrapped"),
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown kind of RubyObject: {kind}"
+                    )))
+                }
+            };
+
+            let mut value: Value =
+                json!({ "__class": to_symbol(&object.class_name), "__type": "object" });
+
+            if let Some(key) = key {
+                value[key] = if key == "__userDefined" {
+                    json!(data.extract::<Vec<u8>>()?)
+                } else {
+                    from_python(data)?
+                };
+            }
+
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(attributes_from_python(attributes)?);
+            value
+        }
+    };
+
+    if !object.extends.is_empty() {
+        value[EXTENDS_SYMBOL] = object
+            .extends
+            .iter()
+            .map(|module| to_symbol(module))
+            .collect();
+    }
+
+    Ok(value)
+}
+
+/// Converts the Python object to a Value, that can be dumped.
+pub fn from_python(object: &Bound<PyAny>) -> PyResult<Value> {
+    Ok(if object.is_none() {
+        Value::Null
+    } else if object.is_instance_of::<PyBool>() {
+        Value::Bool(object.extract()?)
+    } else if object.is_instance_of::<PyInt>() {
+        if let Ok(integer) = object.extract::<i64>() {
+            json!(integer)
+        } else if let Ok(integer) = object.extract::<u64>() {
+            json!(integer)
+        } else {
+            json!({ "__type": "bigint", "value": object.str()?.to_string() })
+        }
+    } else if object.is_instance_of::<PyFloat>() {
+        json!(object.extract::<f64>()?)
+    } else if let Ok(string) = object.downcast::<PyString>() {
+        Value::String(string.to_string())
+    } else if object.is_instance_of::<PyBytes>() || object.is_instance_of::<PyByteArray>() {
+        json!({ "__type": "bytes", "data": object.extract::<Vec<u8>>()? })
+    } else if let Ok(symbol) = object.downcast::<Symbol>() {
+        Value::String(to_symbol(&symbol.get().name))
+    } else if let Ok(ruby_object) = object.downcast::<RubyObject>() {
+        ruby_object_from_python(&ruby_object.borrow(), object.py())?
+    } else if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        Value::Array(
+            object
+                .try_iter()?
+                .map(|element| from_python(&element?))
+                .collect::<PyResult<_>>()?,
+        )
+    } else if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut hash: Map<String, Value> = Map::new();
+
+        for (key, entry) in dict {
+            hash.insert(hash_key(&from_python(&key)?), from_python(&entry)?);
+        }
+
+        Value::Object(hash)
+    } else {
+        return Err(PyTypeError::new_err(format!(
+            "Objects of type {} can't be dumped",
+            object.get_type().name()?
+        )));
+    })
+}
+
+/// Loads Marshal data to a Python object.
+///
+/// `string_mode` is either `"utf8"` or `"binary"`.
+#[pyfunction]
+#[pyo3(signature = (data, string_mode = None))]
+pub fn loads(py: Python<'_>, data: &[u8], string_mode: Option<&str>) -> PyResult<PyObject> {
+    let string_mode: Option<StringMode> = match string_mode {
+        None => None,
+        Some("utf8") => Some(StringMode::UTF8),
+        Some("binary") => Some(StringMode::Binary),
+        Some(mode) => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown string mode {mode}, expected `utf8` or `binary`"
+            )))
+        }
+    };
+
+    let value: Value = Loader::new()
+        .load(data, string_mode, None)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    to_python(py, &value)
+}
+
+/// Dumps the Python object to Marshal data.
+#[pyfunction]
+pub fn dumps<'py>(py: Python<'py>, object: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let value: Value = from_python(object)?;
+    let bytes: Vec<u8> = Dumper::new()
+        .try_dump(value, None)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    Ok(PyBytes::new(py, &bytes))
+}
+
+/// Adds `loads()`, `dumps()`, `Symbol` and `RubyObject` to the module. Call it from the `#[pymodule]` function of the extension.
+/// # Example
+/// ```rust ignore
+/// use pyo3::prelude::*;
+///
+/// #[pymodule]
+/// fn marshal(module: &Bound<PyModule>) -> PyResult<()> {
+///     marshal_rs::python::register(module)
+/// }
+/// ```
+pub fn register(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(loads, module)?)?;
+    module.add_function(wrap_pyfunction!(dumps, module)?)?;
+    module.add_class::<Symbol>()?;
+    module.add_class::<RubyObject>()?;
+    Ok(())
+}
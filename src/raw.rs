@@ -0,0 +1,360 @@
+//! Low-level building blocks of the Marshal format.
+//!
+//! Exposes type tags, the version header and the packed integer format, which Marshal uses both for Fixnums and for lengths of strings, arrays, hashes and other structures.
+//! These are useful for building tools, which work with Marshal byte streams directly, like disassemblers.
+//! # Example
+//! ```rust
+//! use marshal_rs::raw::{write_int, Constants, Reader, VERSION_HEADER};
+//!
+//! // [300]
+//! let mut bytes: Vec<u8> = VERSION_HEADER.to_vec();
+//! bytes.push(Constants::Array as u8);
+//! write_int(&mut bytes, 1);
+//! bytes.push(Constants::Fixnum as u8);
+//! write_int(&mut bytes, 300);
+//!
+//! let mut reader = Reader::new(&bytes);
+//! reader.read_version().unwrap();
+//!
+//! assert_eq!(reader.read_type().unwrap(), Constants::Array);
+//! assert_eq!(reader.read_int().unwrap(), 1);
+//! assert_eq!(reader.read_type().unwrap(), Constants::Fixnum);
+//! assert_eq!(reader.read_int().unwrap(), 300);
+//! assert!(reader.is_empty());
+//! ```
+
+use crate::load::{LoadError, LoadErrorKind};
+
+/// Type tags of Marshal structures, along with Bignum signs and Regexp flags.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Constants {
+    True = 84,         // 'T'
+    False = 70,        // 'F'
+    Nil = 48,          // '0'
+    Fixnum = 105,      // 'i'
+    Symbol = 58,       // ':'
+    Symlink = 59,      // ';'
+    Link = 64,         // '@'
+    InstanceVar = 73,  // 'I'
+    Extended = 101,    // 'e'
+    Array = 91,        // '['
+    Bignum = 108,      // 'l'
+    Class = 99,        // 'c'
+    Module = 109,      // 'm'
+    ModuleOld = 77,    // 'M'
+    Data = 100,        // 'd'
+    Float = 102,       // 'f'
+    Hash = 123,        // '{'
+    HashDefault = 125, // '}'
+    Object = 111,      // 'o'
+    Regexp = 47,       // '/'
+    String = 34,       // '"'
+    Struct = 83,       // 'S'
+    UserClass = 67,    // 'C'
+    UserDefined = 117, // 'u'
+    UserMarshal = 85,  // 'U'
+    Positive = 43,     // '+'
+    Negative = 45,     // '-'
+
+    // Regular expression flags
+    RegexpIgnore = 1,
+    RegexpExtended = 2,
+    RegexpMultiline = 4,
+}
+
+impl TryFrom<u8> for Constants {
+    type Error = LoadError;
+
+    /// Converts a byte to the type tag of a structure. Bignum signs and Regexp flags are not type tags, and aren't converted.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        use Constants::*;
+
+        Ok(match byte {
+            b'T' => True,
+            b'F' => False,
+            b'0' => Nil,
+            b'i' => Fixnum,
+            b':' => Symbol,
+            b';' => Symlink,
+            b'@' => Link,
+            b'I' => InstanceVar,
+            b'e' => Extended,
+            b'[' => Array,
+            b'l' => Bignum,
+            b'c' => Class,
+            b'm' => Module,
+            b'M' => ModuleOld,
+            b'd' => Data,
+            b'f' => Float,
+            b'{' => Hash,
+            b'}' => HashDefault,
+            b'o' => Object,
+            b'/' => Regexp,
+            b'"' => String,
+            b'S' => Struct,
+            b'C' => UserClass,
+            b'u' => UserDefined,
+            b'U' => UserMarshal,
+            _ => {
+                return Err(LoadError {
+                    message: format!("Unknown structure type {byte}."),
+                    ..Default::default()
+                })
+            }
+        })
+    }
+}
+
+impl std::ops::BitAnd<Constants> for u8 {
+    type Output = u8;
+
+    fn bitand(self, rhs: Constants) -> Self::Output {
+        self & (rhs as u8)
+    }
+}
+
+impl PartialEq<Constants> for u8 {
+    fn eq(&self, other: &Constants) -> bool {
+        *self == *other as u8
+    }
+}
+
+/// The latest and probably final version of Ruby Marshal is 4.8.
+pub const MARSHAL_VERSION: u16 = 0x0408;
+
+/// Two bytes, which every Marshal byte stream starts with.
+pub const VERSION_HEADER: [u8; 2] = MARSHAL_VERSION.to_be_bytes();
+
+/// Version of Marshal format, which byte streams start with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MarshalVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl MarshalVersion {
+    /// Version 4.8, the only one, that Ruby writes and `marshal-rs` reads.
+    pub const CURRENT: Self = Self {
+        major: VERSION_HEADER[0],
+        minor: VERSION_HEADER[1],
+    };
+
+    /// Returns the version from the first two bytes of the buffer, whether it's supported or not. Returns None, if the buffer is shorter.
+    pub fn detect(buffer: &[u8]) -> Option<Self> {
+        match buffer {
+            [major, minor, ..] => Some(Self {
+                major: *major,
+                minor: *minor,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MarshalVersion {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Returns an Err, if the byte stream doesn't start with 4.8 version header. The error includes the version, that was found.
+pub fn check_version(buffer: &[u8]) -> Result<(), LoadError> {
+    match MarshalVersion::detect(buffer) {
+        Some(MarshalVersion::CURRENT) => Ok(()),
+        Some(version) => Err(LoadError {
+            message: format!(
+                "Incompatible Marshal file format or version: found {version}, expected {}.",
+                MarshalVersion::CURRENT
+            ),
+            ..Default::default()
+        }),
+        None => Err(LoadError {
+            message: "Marshal data is too short. Wasn't even able to read starting version bytes."
+                .to_string(),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Returns the error of a length field, that's negative, or larger than the data can hold.
+pub(crate) fn invalid_length(length: i32, position: usize) -> LoadError {
+    LoadError {
+        message: format!("Invalid length {length} at position {position}."),
+        kind: LoadErrorKind::InvalidLength {
+            value: length,
+            offset: position,
+        },
+    }
+}
+
+/// Returns the length of the packed integer in bytes.
+pub fn int_size(number: i32) -> usize {
+    match number {
+        -123..=122 => 1,
+        -256..=255 => 2,
+        -65536..=65535 => 3,
+        -16777216..=16777215 => 4,
+        _ => 5,
+    }
+}
+
+/// Packs the integer into a stack buffer, returning the buffer and the length of the packed integer in it.
+///
+/// Values in `-123..=122` are packed into a single byte. Others are written as a signed length byte, followed by up to 4 little-endian bytes.
+/// Useful for writing packed integers to `io::Write` streams without intermediate allocations.
+pub fn encode_int(number: i32) -> ([u8; 5], usize) {
+    let mut bytes: [u8; 5] = [0; 5];
+
+    match number {
+        0 => (bytes, 1),
+        1..=122 => {
+            bytes[0] = number as u8 + 5;
+            (bytes, 1)
+        }
+        -123..=-1 => {
+            bytes[0] = (number - 5) as u8;
+            (bytes, 1)
+        }
+        _ => {
+            let length: usize = int_size(number) - 1;
+            bytes[0] = if number < 0 {
+                (length as u8).wrapping_neg()
+            } else {
+                length as u8
+            };
+            bytes[1..=length].copy_from_slice(&number.to_le_bytes()[..length]);
+            (bytes, length + 1)
+        }
+    }
+}
+
+/// Appends the packed integer to the buffer, like `encode_int()` packs it.
+pub fn write_int(buffer: &mut Vec<u8>, number: i32) {
+    let (bytes, length) = encode_int(number);
+    buffer.extend_from_slice(&bytes[..length]);
+}
+
+/// Reads the packed integer from the start of the buffer. Returns the integer and its length in bytes.
+pub fn read_int(buffer: &[u8]) -> Result<(i32, usize), LoadError> {
+    let mut reader: Reader = Reader::new(buffer);
+    let number: i32 = reader.read_int()?;
+    Ok((number, reader.position()))
+}
+
+/// Cursor over a Marshal byte stream.
+pub struct Reader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+        }
+    }
+
+    /// Returns the position of the next byte to read.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Returns whether all bytes were read.
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.buffer.len()
+    }
+
+    fn too_short(&self) -> LoadError {
+        LoadError {
+            message: format!(
+                "Marshal data is too short. Last position: {}",
+                self.position
+            ),
+            ..Default::default()
+        }
+    }
+
+    pub fn read_byte(&mut self) -> Result<u8, LoadError> {
+        let byte: u8 = *self
+            .buffer
+            .get(self.position)
+            .ok_or_else(|| self.too_short())?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bytes(&mut self, amount: usize) -> Result<&'a [u8], LoadError> {
+        let bytes: &[u8] = self
+            .position
+            .checked_add(amount)
+            .and_then(|end| self.buffer.get(self.position..end))
+            .ok_or_else(|| self.too_short())?;
+        self.position += amount;
+        Ok(bytes)
+    }
+
+    /// Reads the version header, returning an Err, if it's not 4.8.
+    pub fn read_version(&mut self) -> Result<(), LoadError> {
+        check_version(&self.buffer[self.position.min(self.buffer.len())..])?;
+        self.position += 2;
+        Ok(())
+    }
+
+    /// Reads the type tag of a structure.
+    pub fn read_type(&mut self) -> Result<Constants, LoadError> {
+        let position: usize = self.position;
+
+        Constants::try_from(self.read_byte()?).map_err(|err| LoadError {
+            message: format!("{} Position: {position}", err.message.trim_end_matches('.')),
+            ..Default::default()
+        })
+    }
+
+    /// Reads the packed integer.
+    pub fn read_int(&mut self) -> Result<i32, LoadError> {
+        let length: i8 = self.read_byte()? as i8;
+
+        Ok(match length {
+            0 => 0,
+            // These values mark the length of the integer in bytes
+            -4..=4 => {
+                let bytes: &[u8] = self.read_bytes(length.unsigned_abs() as usize)?;
+                let mut buffer: [u8; 4] = [if length < 0 { 255u8 } else { 0u8 }; 4];
+                buffer[..bytes.len()].copy_from_slice(bytes);
+                i32::from_le_bytes(buffer)
+            }
+            // Otherwise the integer is packed into the length byte
+            5..=127 => (length - 5) as i32,
+            _ => (length + 5) as i32,
+        })
+    }
+
+    /// Reads the packed integer as a number of elements, entries or 16-bit words of a structure.
+    ///
+    /// Returns an Err, if the number is negative, or larger than the number of remaining bytes, as each of them takes at least one byte.
+    pub fn read_length(&mut self) -> Result<usize, LoadError> {
+        let position: usize = self.position;
+        let length: i32 = self.read_int()?;
+
+        match usize::try_from(length) {
+            Ok(count) if count <= self.buffer.len().saturating_sub(self.position) => Ok(count),
+            _ => Err(invalid_length(length, position)),
+        }
+    }
+
+    /// Reads the bytes, prefixed with their length, like contents of strings and symbols.
+    pub fn read_chunk(&mut self) -> Result<&'a [u8], LoadError> {
+        let position: usize = self.position;
+        let length: i32 = self.read_int()?;
+        let length: usize =
+            usize::try_from(length).map_err(|_| invalid_length(length, position))?;
+
+        self.read_bytes(length)
+    }
+}
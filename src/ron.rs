@@ -0,0 +1,615 @@
+//! Conversion of loaded JSON values to Rusty Object Notation (RON) and back, which is easier to edit by hand than JSON.
+//!
+//! | Ruby object                  | RON                                            |
+//! | ---------------------------- | ---------------------------------------------- |
+//! | `nil`                        | `()`                                           |
+//! | Symbol                       | `Symbol("name")`                               |
+//! | Binary string                | `Bytes([1, 2, 3])`                             |
+//! | Big Integer                  | `BigInt("36893488147419103232")`               |
+//! | Regexp                       | `Regexp("expression", "flags")`                |
+//! | Class, Module                | `Class("Name")`, `Module("Name")`              |
+//! | Object with instance vars    | `Object(class: "Name", ivar: value, ...)`      |
+//! | Struct                       | `Struct(class: "Name", member: value, ...)`    |
+//! | Hash                         | `{ key: value, ... }`                          |
+//!
+//! Instance variables and members are written as struct fields, so their names must be valid identifiers, and can't be `class`.
+//! Objects, that don't satisfy this, and other objects, like ones with `_dump` data, are written as `Raw({ "__key": value, ... })` with their JSON keys.
+//! Modules, written with old `M` type, are written as `OldModule("Name")`.
+//!
+//! `from_ron()` also accepts `//` and `/* */` comments, and trailing commas.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::value::{bytes_of, hash_key, is_hash, key_value, ValueError};
+use serde_json::{json, Map, Number, Value};
+
+const INDENT: &str = "    ";
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .map_or(false, |char| char.is_ascii_alphabetic() || char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+fn write_string(ron: &mut String, string: &str) {
+    ron.push('"');
+
+    for char in string.chars() {
+        match char {
+            '"' => *ron += "\\\"",
+            '\\' => *ron += "\\\\",
+            '\n' => *ron += "\\n",
+            '\r' => *ron += "\\r",
+            '\t' => *ron += "\\t",
+            char if char.is_control() => *ron += &format!("\\u{:04x}", char as u32),
+            _ => ron.push(char),
+        }
+    }
+
+    ron.push('"');
+}
+
+fn write_indent(ron: &mut String, depth: usize) {
+    for _ in 0..depth {
+        *ron += INDENT;
+    }
+}
+
+/// Writes entries, one per line, wrapped with `open` and `close`.
+fn write_entries<F: FnMut(&mut String, usize)>(
+    ron: &mut String,
+    (open, close): (&str, &str),
+    count: usize,
+    depth: usize,
+    mut write_entry: F,
+) {
+    *ron += open;
+
+    if count == 0 {
+        *ron += close;
+        return;
+    }
+
+    for index in 0..count {
+        ron.push('\n');
+        write_indent(ron, depth + 1);
+        write_entry(ron, index);
+        ron.push(',');
+    }
+
+    ron.push('\n');
+    write_indent(ron, depth);
+    *ron += close;
+}
+
+/// Returns fields of an object with plain keys, if all keys are symbols, that start with `prefix`, and are valid identifiers.
+fn plain_fields<'a>(
+    object: &'a Map<String, Value>,
+    prefix: &str,
+) -> Option<Vec<(&'a str, &'a Value)>> {
+    object
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "__class" | "__type"))
+        .map(|(key, entry)| {
+            let name: &str = key.strip_prefix("__symbol__")?.strip_prefix(prefix)?;
+            (is_identifier(name) && name != "class").then_some((name, entry))
+        })
+        .collect()
+}
+
+fn write_fields(
+    ron: &mut String,
+    variant: &str,
+    class: &str,
+    fields: &[(&str, &Value)],
+    depth: usize,
+) {
+    *ron += variant;
+    *ron += "(\n";
+    write_indent(ron, depth + 1);
+    *ron += "class: ";
+    write_string(ron, class);
+    ron.push(',');
+
+    for (name, entry) in fields {
+        ron.push('\n');
+        write_indent(ron, depth + 1);
+        *ron += name;
+        *ron += ": ";
+        write_value(ron, entry, depth + 1);
+        ron.push(',');
+    }
+
+    ron.push('\n');
+    write_indent(ron, depth);
+    ron.push(')');
+}
+
+fn write_map(ron: &mut String, object: &Map<String, Value>, depth: usize, raw: bool) {
+    let keys: Vec<&String> = object.keys().collect();
+
+    write_entries(ron, ("{", "}"), keys.len(), depth, |ron, index| {
+        let key: &str = keys[index];
+
+        if raw {
+            write_string(ron, key);
+        } else {
+            write_value(ron, &key_value(key), depth + 1);
+        }
+
+        *ron += ": ";
+        write_value(ron, &object[key], depth + 1);
+    });
+}
+
+/// Writes the object in one of the special forms, returning false if it doesn't fit any.
+fn write_object(
+    ron: &mut String,
+    value: &Value,
+    object: &Map<String, Value>,
+    depth: usize,
+) -> bool {
+    let class: Option<&str> = object.get("__class").and_then(Value::as_str);
+    let class_name: Option<&str> = class.and_then(|class| class.strip_prefix("__symbol__"));
+
+    match object.get("__type").and_then(Value::as_str) {
+        Some("bytes") => {
+            let bytes: Vec<u8> = match bytes_of(value) {
+                Some(bytes) => bytes,
+                None => return false,
+            };
+
+            let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+            *ron += &format!("Bytes([{}])", bytes.join(", "));
+        }
+        Some("bigint") => match object.get("value").and_then(Value::as_str) {
+            Some(digits) => {
+                *ron += "BigInt(";
+                write_string(ron, digits);
+                ron.push(')');
+            }
+            None => return false,
+        },
+        Some("regexp") => match (
+            object.get("expression").and_then(Value::as_str),
+            object.get("flags").and_then(Value::as_str),
+        ) {
+            (Some(expression), Some(flags)) => {
+                *ron += "Regexp(";
+                write_string(ron, expression);
+                *ron += ", ";
+                write_string(ron, flags);
+                ron.push(')');
+            }
+            _ => return false,
+        },
+        Some(type_ @ ("class" | "module")) => match class {
+            Some(class) => {
+                *ron += match (type_, object.get("__old") == Some(&Value::Bool(true))) {
+                    ("class", _) => "Class(",
+                    (_, false) => "Module(",
+                    (_, true) => "OldModule(",
+                };
+                write_string(ron, class);
+                ron.push(')');
+            }
+            None => return false,
+        },
+        Some("object") => match class_name.zip(plain_fields(object, "@")) {
+            Some((class, fields)) => write_fields(ron, "Object", class, &fields, depth),
+            None => return false,
+        },
+        Some("struct") if object.len() == 3 => {
+            let members = object
+                .get("__members")
+                .and_then(Value::as_object)
+                .and_then(|members| plain_fields(members, ""));
+
+            match class_name.zip(members) {
+                Some((class, fields)) => write_fields(ron, "Struct", class, &fields, depth),
+                None => return false,
+            }
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+fn write_value(ron: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Null => *ron += "()",
+        Value::Bool(bool) => *ron += if *bool { "true" } else { "false" },
+        Value::Number(number) => {
+            let number: String = number.to_string();
+            let is_float: bool = value.is_f64() && !number.contains(['.', 'e', 'E']);
+
+            *ron += &number;
+
+            if is_float {
+                *ron += ".0";
+            }
+        }
+        Value::String(string) => match string.strip_prefix("__symbol__") {
+            Some(symbol) => {
+                *ron += "Symbol(";
+                write_string(ron, symbol);
+                ron.push(')');
+            }
+            None => write_string(ron, string),
+        },
+        Value::Array(array) => write_entries(ron, ("[", "]"), array.len(), depth, |ron, index| {
+            write_value(ron, &array[index], depth + 1)
+        }),
+        Value::Object(object) => {
+            if is_hash(value) {
+                write_map(ron, object, depth, false);
+            } else if !write_object(ron, value, object, depth) {
+                *ron += "Raw(";
+                write_map(ron, object, depth, true);
+                ron.push(')');
+            }
+        }
+    }
+}
+
+/// Converts the Value to pretty-printed RON.
+/// # Example
+/// ```rust
+/// use marshal_rs::ron::to_ron;
+/// use serde_json::json;
+///
+/// let value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@kind": "__symbol__weapon" });
+///
+/// assert_eq!(to_ron(&value), "Object(\n    class: \"Item\",\n    name: \"Sword\",\n    kind: Symbol(\"weapon\"),\n)");
+/// ```
+pub fn to_ron(value: &Value) -> String {
+    let mut ron: String = String::new();
+    write_value(&mut ron, value, 0);
+    ron
+}
+
+struct Parser<'a> {
+    ron: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> ValueError {
+        let before: &str = &self.ron[..self.position];
+        let line: usize = before.matches('\n').count() + 1;
+        let column: usize = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+        ValueError {
+            message: format!("{message} at RON line {line}, column {column}"),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.ron[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ValueError> {
+        loop {
+            let rest: &str = self.rest();
+            let trimmed: &str = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                match trimmed.find("*/") {
+                    Some(end) => self.position += end + 2,
+                    None => return Err(self.error("Unterminated comment")),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Consumes `char` after optional whitespace, returning whether it was present.
+    fn eat(&mut self, char: char) -> Result<bool, ValueError> {
+        self.skip_whitespace()?;
+
+        if self.peek() == Some(char) {
+            self.position += char.len_utf8();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, char: char) -> Result<(), ValueError> {
+        if self.eat(char)? {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{char}`")))
+        }
+    }
+
+    /// Parses a comma-separated list until `close`, allowing a trailing comma.
+    fn list<F: FnMut(&mut Self) -> Result<(), ValueError>>(
+        &mut self,
+        close: char,
+        mut parse_entry: F,
+    ) -> Result<(), ValueError> {
+        loop {
+            if self.eat(close)? {
+                return Ok(());
+            }
+
+            parse_entry(self)?;
+
+            if !self.eat(',')? {
+                return self.expect(close);
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, ValueError> {
+        self.skip_whitespace()?;
+
+        let rest: &str = self.rest();
+        let length: usize = rest
+            .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+            .unwrap_or(rest.len());
+
+        if !is_identifier(&rest[..length]) {
+            return Err(self.error("Expected an identifier"));
+        }
+
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn string(&mut self) -> Result<String, ValueError> {
+        self.skip_whitespace()?;
+
+        if self.peek() != Some('"') {
+            return Err(self.error("Expected a string"));
+        }
+
+        self.position += 1;
+        let mut string: String = String::new();
+
+        loop {
+            let mut chars = self.rest().chars();
+            let char: char = chars
+                .next()
+                .ok_or_else(|| self.error("Unterminated string"))?;
+            self.position += char.len_utf8();
+
+            match char {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escape: char = chars
+                        .next()
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    self.position += escape.len_utf8();
+
+                    string.push(match escape {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        '0' => '\0',
+                        '"' | '\\' | '\'' | '/' => escape,
+                        'u' => {
+                            let code: Option<char> = self
+                                .rest()
+                                .get(..4)
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32);
+
+                            self.position += 4;
+                            code.ok_or_else(|| self.error("Invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("Invalid escape")),
+                    });
+                }
+                _ => string.push(char),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ValueError> {
+        let rest: &str = self.rest();
+        let length: usize = rest
+            .char_indices()
+            .find(|&(index, char)| {
+                !(char.is_ascii_digit()
+                    || matches!(char, '.' | 'e' | 'E' | '_')
+                    || (matches!(char, '+' | '-')
+                        && (index == 0 || rest[..index].ends_with(['e', 'E']))))
+            })
+            .map_or(rest.len(), |(index, _)| index);
+
+        let literal: String = rest[..length].replace('_', "");
+        self.position += length;
+
+        if !literal.contains(['.', 'e', 'E']) {
+            if let Ok(integer) = literal.parse::<i64>() {
+                return Ok(integer.into());
+            }
+
+            if let Ok(integer) = literal.parse::<u64>() {
+                return Ok(integer.into());
+            }
+        }
+
+        literal
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("Invalid number {literal}")))
+    }
+
+    fn value(&mut self) -> Result<Value, ValueError> {
+        self.skip_whitespace()?;
+
+        match self.peek() {
+            Some('"') => return self.string().map(Value::String),
+            Some('[') => {
+                self.position += 1;
+                let mut array: Vec<Value> = Vec::new();
+
+                self.list(']', |parser| {
+                    array.push(parser.value()?);
+                    Ok(())
+                })?;
+
+                return Ok(Value::Array(array));
+            }
+            Some('{') => {
+                self.position += 1;
+                return self.map(false).map(Value::Object);
+            }
+            Some('(') => {
+                self.position += 1;
+                self.expect(')')?;
+                return Ok(Value::Null);
+            }
+            Some(char) if char.is_ascii_digit() || matches!(char, '-' | '+' | '.') => {
+                return self.number()
+            }
+            None => return Err(self.error("Unexpected end of RON")),
+            _ => {}
+        }
+
+        let start: usize = self.position;
+        let identifier: &str = self.identifier()?;
+
+        match identifier {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+
+        self.expect('(')?;
+
+        let value: Value = match identifier {
+            "Symbol" => Value::String(format!("__symbol__{}", self.string()?)),
+            "Bytes" => {
+                self.expect('[')?;
+                let mut bytes: Vec<u8> = Vec::new();
+
+                self.list(']', |parser| {
+                    let byte: Option<u8> = parser
+                        .number()?
+                        .as_u64()
+                        .and_then(|byte| u8::try_from(byte).ok());
+
+                    bytes.push(byte.ok_or_else(|| parser.error("Expected a byte"))?);
+                    Ok(())
+                })?;
+
+                json!({ "__type": "bytes", "data": bytes })
+            }
+            "BigInt" => json!({ "__type": "bigint", "value": self.string()? }),
+            "Regexp" => {
+                let expression: String = self.string()?;
+                self.expect(',')?;
+                let flags: String = self.string()?;
+                json!({ "__type": "regexp", "expression": expression, "flags": flags })
+            }
+            "Class" => json!({ "__class": self.string()?, "__type": "class" }),
+            "Module" => json!({ "__class": self.string()?, "__type": "module", "__old": false }),
+            "OldModule" => json!({ "__class": self.string()?, "__type": "module", "__old": true }),
+            "Object" | "Struct" => return self.fields(identifier),
+            "Raw" => {
+                self.expect('{')?;
+                Value::Object(self.map(true)?)
+            }
+            _ => {
+                self.position = start;
+                return Err(self.error(&format!("Unknown variant {identifier}")));
+            }
+        };
+
+        self.eat(',')?;
+        self.expect(')')?;
+        Ok(value)
+    }
+
+    /// Parses entries of a map after its opening brace. Keys of raw maps are kept as is, and keys of Hashes are converted with `hash_key()`.
+    fn map(&mut self, raw: bool) -> Result<Map<String, Value>, ValueError> {
+        let mut object: Map<String, Value> = Map::new();
+
+        self.list('}', |parser| {
+            let key: String = if raw {
+                parser.string()?
+            } else {
+                hash_key(&parser.value()?)
+            };
+
+            parser.expect(':')?;
+            object.insert(key, parser.value()?);
+            Ok(())
+        })?;
+
+        Ok(object)
+    }
+
+    /// Parses fields of `Object` or `Struct` variant after its opening parenthesis.
+    fn fields(&mut self, variant: &str) -> Result<Value, ValueError> {
+        let mut class: Option<String> = None;
+        let mut fields: Map<String, Value> = Map::new();
+
+        self.list(')', |parser| {
+            let name: &str = parser.identifier()?;
+            parser.expect(':')?;
+
+            if name == "class" {
+                class = Some(parser.string()?);
+            } else {
+                let prefix: &str = if variant == "Object" { "@" } else { "" };
+                fields.insert(format!("__symbol__{prefix}{name}"), parser.value()?);
+            }
+
+            Ok(())
+        })?;
+
+        let class: String = class.ok_or_else(|| self.error(&format!("{variant} without class")))?;
+        let mut object: Map<String, Value> = Map::new();
+        object.insert(
+            "__class".to_string(),
+            Value::String(format!("__symbol__{class}")),
+        );
+
+        if variant == "Object" {
+            object.insert("__type".to_string(), "object".into());
+            object.extend(fields);
+        } else {
+            object.insert("__type".to_string(), "struct".into());
+            object.insert("__members".to_string(), Value::Object(fields));
+        }
+
+        Ok(Value::Object(object))
+    }
+}
+
+/// Converts RON, written by `to_ron()`, to a Value, that can be passed to `dump()`.
+///
+/// Returns an Err when RON is malformed, or contains unknown variants.
+pub fn from_ron(ron: &str) -> Result<Value, ValueError> {
+    let mut parser: Parser = Parser { ron, position: 0 };
+    let value: Value = parser.value()?;
+
+    parser.skip_whitespace()?;
+
+    if parser.position != ron.len() {
+        return Err(parser.error("Unexpected trailing characters"));
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,311 @@
+//! Typed structures of RPG Maker VX Ace (RGSS3) data, with `FromValue` and `IntoValue` implementations.
+//!
+//! Structures list all instance variables of their classes, so data survives the conversion to them and back.
+//! Data of `Table`, `Color` and `Tone`, which are dumped with `_dump`, and parameters of event commands are kept as Values.
+//!
+//! Requires `rpg` feature. Not available with `sonic` feature enabled.
+//! # Example
+//! ```rust
+//! use marshal_rs::{rpg::MapInfo, FromValue};
+//! use serde_json::json;
+//!
+//! let value = json!({
+//!     "__class": "__symbol__RPG::MapInfo", "__type": "object",
+//!     "__symbol__@name": "Town", "__symbol__@parent_id": 0, "__symbol__@order": 1,
+//!     "__symbol__@expanded": false, "__symbol__@scroll_x": 0, "__symbol__@scroll_y": 0
+//! });
+//!
+//! assert_eq!(MapInfo::from_value(&value).unwrap().name, "Town");
+//! ```
+
+use crate::{impl_from_value, impl_into_value};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+macro_rules! rpg_struct {
+    ($(#[$attr:meta])* $name:ident, $class:expr, { $($(#[$field_attr:meta])* $field:ident: $type:ty = $ivar:expr),* $(,)? }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct $name {
+            $($(#[$field_attr])* pub $field: $type,)*
+        }
+
+        impl_from_value!($name, $class, { $($field: $ivar),* });
+        impl_into_value!($name, $class, { $($field: $ivar),* });
+    };
+}
+
+macro_rules! audio_file {
+    ($(#[$attr:meta])* $name:ident, $class:expr) => {
+        rpg_struct!($(#[$attr])* $name, $class, {
+            name: String = "@name",
+            volume: i32 = "@volume",
+            pitch: i32 = "@pitch",
+        });
+    };
+}
+
+audio_file!(
+    /// Background music.
+    Bgm,
+    "RPG::BGM"
+);
+audio_file!(
+    /// Background sound.
+    Bgs,
+    "RPG::BGS"
+);
+audio_file!(
+    /// Music effect.
+    Me,
+    "RPG::ME"
+);
+audio_file!(
+    /// Sound effect.
+    Se,
+    "RPG::SE"
+);
+
+rpg_struct!(
+    /// Command of an event page. Parameters depend on the command's code.
+    EventCommand, "RPG::EventCommand", {
+        code: i32 = "@code",
+        indent: i32 = "@indent",
+        parameters: Vec<Value> = "@parameters",
+    }
+);
+
+rpg_struct!(
+    /// Command of a move route.
+    MoveCommand, "RPG::MoveCommand", {
+        code: i32 = "@code",
+        parameters: Vec<Value> = "@parameters",
+    }
+);
+
+rpg_struct!(
+    MoveRoute, "RPG::MoveRoute", {
+        repeat: bool = "@repeat",
+        skippable: bool = "@skippable",
+        wait: bool = "@wait",
+        list: Vec<MoveCommand> = "@list",
+    }
+);
+
+rpg_struct!(
+    /// Conditions, under which an event page is active.
+    EventPageCondition, "RPG::Event::Page::Condition", {
+        switch1_valid: bool = "@switch1_valid",
+        switch2_valid: bool = "@switch2_valid",
+        variable_valid: bool = "@variable_valid",
+        self_switch_valid: bool = "@self_switch_valid",
+        item_valid: bool = "@item_valid",
+        actor_valid: bool = "@actor_valid",
+        switch1_id: i32 = "@switch1_id",
+        switch2_id: i32 = "@switch2_id",
+        variable_id: i32 = "@variable_id",
+        variable_value: i32 = "@variable_value",
+        self_switch_ch: String = "@self_switch_ch",
+        item_id: i32 = "@item_id",
+        actor_id: i32 = "@actor_id",
+    }
+);
+
+rpg_struct!(
+    EventPageGraphic, "RPG::Event::Page::Graphic", {
+        tile_id: i32 = "@tile_id",
+        character_name: String = "@character_name",
+        character_index: i32 = "@character_index",
+        direction: i32 = "@direction",
+        pattern: i32 = "@pattern",
+    }
+);
+
+rpg_struct!(
+    EventPage, "RPG::Event::Page", {
+        condition: EventPageCondition = "@condition",
+        graphic: EventPageGraphic = "@graphic",
+        move_type: i32 = "@move_type",
+        move_speed: i32 = "@move_speed",
+        move_frequency: i32 = "@move_frequency",
+        move_route: MoveRoute = "@move_route",
+        walk_anime: bool = "@walk_anime",
+        step_anime: bool = "@step_anime",
+        direction_fix: bool = "@direction_fix",
+        through: bool = "@through",
+        priority_type: i32 = "@priority_type",
+        trigger: i32 = "@trigger",
+        list: Vec<EventCommand> = "@list",
+    }
+);
+
+rpg_struct!(
+    /// Event on a map.
+    Event, "RPG::Event", {
+        id: i32 = "@id",
+        name: String = "@name",
+        x: i32 = "@x",
+        y: i32 = "@y",
+        pages: Vec<EventPage> = "@pages",
+    }
+);
+
+rpg_struct!(
+    MapEncounter, "RPG::Map::Encounter", {
+        troop_id: i32 = "@troop_id",
+        weight: i32 = "@weight",
+        region_set: Vec<i32> = "@region_set",
+    }
+);
+
+rpg_struct!(
+    /// Map of `MapXXX.rvdata2` files.
+    Map, "RPG::Map", {
+        display_name: String = "@display_name",
+        tileset_id: i32 = "@tileset_id",
+        width: i32 = "@width",
+        height: i32 = "@height",
+        scroll_type: i32 = "@scroll_type",
+        specify_battleback: bool = "@specify_battleback",
+        battleback1_name: String = "@battleback1_name",
+        battleback2_name: String = "@battleback2_name",
+        autoplay_bgm: bool = "@autoplay_bgm",
+        bgm: Bgm = "@bgm",
+        autoplay_bgs: bool = "@autoplay_bgs",
+        bgs: Bgs = "@bgs",
+        disable_dashing: bool = "@disable_dashing",
+        encounter_list: Vec<MapEncounter> = "@encounter_list",
+        encounter_step: i32 = "@encounter_step",
+        parallax_name: String = "@parallax_name",
+        parallax_loop_x: bool = "@parallax_loop_x",
+        parallax_loop_y: bool = "@parallax_loop_y",
+        parallax_sx: i32 = "@parallax_sx",
+        parallax_sy: i32 = "@parallax_sy",
+        parallax_show: bool = "@parallax_show",
+        note: String = "@note",
+        /// Tile data, dumped as `Table`.
+        data: Value = "@data",
+        /// Events by their IDs.
+        events: BTreeMap<i32, Event> = "@events",
+    }
+);
+
+rpg_struct!(
+    /// Entry of `MapInfos.rvdata2`, which is a Hash of map IDs to map infos.
+    MapInfo, "RPG::MapInfo", {
+        name: String = "@name",
+        parent_id: i32 = "@parent_id",
+        order: i32 = "@order",
+        expanded: bool = "@expanded",
+        scroll_x: i32 = "@scroll_x",
+        scroll_y: i32 = "@scroll_y",
+    }
+);
+
+rpg_struct!(
+    /// Trait of an actor, class, item or state.
+    Feature, "RPG::BaseItem::Feature", {
+        code: i32 = "@code",
+        data_id: i32 = "@data_id",
+        value: f64 = "@value",
+    }
+);
+
+rpg_struct!(
+    /// Entry of `Actors.rvdata2`, which is an Array, that starts with `nil`.
+    Actor, "RPG::Actor", {
+        id: i32 = "@id",
+        name: String = "@name",
+        icon_index: i32 = "@icon_index",
+        description: String = "@description",
+        features: Vec<Feature> = "@features",
+        note: String = "@note",
+        nickname: String = "@nickname",
+        class_id: i32 = "@class_id",
+        initial_level: i32 = "@initial_level",
+        max_level: i32 = "@max_level",
+        character_name: String = "@character_name",
+        character_index: i32 = "@character_index",
+        face_name: String = "@face_name",
+        face_index: i32 = "@face_index",
+        equips: Vec<i32> = "@equips",
+    }
+);
+
+rpg_struct!(
+    SystemVehicle, "RPG::System::Vehicle", {
+        character_name: String = "@character_name",
+        character_index: i32 = "@character_index",
+        bgm: Bgm = "@bgm",
+        start_map_id: i32 = "@start_map_id",
+        start_x: i32 = "@start_x",
+        start_y: i32 = "@start_y",
+    }
+);
+
+rpg_struct!(
+    /// Names of parameters, equipment types and commands.
+    SystemTerms, "RPG::System::Terms", {
+        basic: Vec<String> = "@basic",
+        params: Vec<String> = "@params",
+        etypes: Vec<String> = "@etypes",
+        commands: Vec<String> = "@commands",
+    }
+);
+
+rpg_struct!(
+    /// Actor of battle tests.
+    SystemTestBattler, "RPG::System::TestBattler", {
+        actor_id: i32 = "@actor_id",
+        level: i32 = "@level",
+        equips: Vec<i32> = "@equips",
+    }
+);
+
+rpg_struct!(
+    /// Contents of `System.rvdata2`.
+    System, "RPG::System", {
+        game_title: String = "@game_title",
+        version_id: i32 = "@version_id",
+        japanese: bool = "@japanese",
+        party_members: Vec<i32> = "@party_members",
+        currency_unit: String = "@currency_unit",
+        elements: Vec<Option<String>> = "@elements",
+        skill_types: Vec<Option<String>> = "@skill_types",
+        weapon_types: Vec<Option<String>> = "@weapon_types",
+        armor_types: Vec<Option<String>> = "@armor_types",
+        switches: Vec<Option<String>> = "@switches",
+        variables: Vec<Option<String>> = "@variables",
+        boat: SystemVehicle = "@boat",
+        ship: SystemVehicle = "@ship",
+        airship: SystemVehicle = "@airship",
+        title1_name: String = "@title1_name",
+        title2_name: String = "@title2_name",
+        opt_draw_title: bool = "@opt_draw_title",
+        opt_use_midi: bool = "@opt_use_midi",
+        opt_transparent: bool = "@opt_transparent",
+        opt_followers: bool = "@opt_followers",
+        opt_slip_death: bool = "@opt_slip_death",
+        opt_floor_death: bool = "@opt_floor_death",
+        opt_display_tp: bool = "@opt_display_tp",
+        opt_extra_exp: bool = "@opt_extra_exp",
+        /// Dumped as `Tone`.
+        window_tone: Value = "@window_tone",
+        title_bgm: Bgm = "@title_bgm",
+        battle_bgm: Bgm = "@battle_bgm",
+        battle_end_me: Me = "@battle_end_me",
+        gameover_me: Me = "@gameover_me",
+        sounds: Vec<Se> = "@sounds",
+        test_battlers: Vec<SystemTestBattler> = "@test_battlers",
+        test_troop_id: i32 = "@test_troop_id",
+        start_map_id: i32 = "@start_map_id",
+        start_x: i32 = "@start_x",
+        start_y: i32 = "@start_y",
+        terms: SystemTerms = "@terms",
+        battleback1_name: String = "@battleback1_name",
+        battleback2_name: String = "@battleback2_name",
+        battler_name: String = "@battler_name",
+        battler_hue: i32 = "@battler_hue",
+        edit_map_id: i32 = "@edit_map_id",
+    }
+);
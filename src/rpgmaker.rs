@@ -0,0 +1,215 @@
+//! Detection and loading of RPG Maker XP, VX and VX Ace data and save files.
+//!
+//! Data files (`.rxdata`, `.rvdata`, `.rvdata2`) hold a single Marshal document, while save files hold several consecutive documents.
+//! Engines are told apart by file extensions, or, when loading bytes, by data itself:
+//! VX Ace is written by Ruby 1.9+, that marks strings' encodings, and XP and VX are told apart by classes, that only exist in one of them.
+//!
+//! Not available with `sonic` feature enabled.
+//! # Example
+//! ```rust
+//! use marshal_rs::{dump, rpgmaker::{detect, Engine, Format}};
+//! use serde_json::json;
+//!
+//! let bytes = dump(json!([null, { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Eric" }]), None);
+//!
+//! assert_eq!(detect(&bytes), Some(Format { engine: Engine::VxAce, save: false }));
+//! ```
+
+use crate::{
+    load::{LoadError, Loader, Preset},
+    raw::VERSION_HEADER,
+    value::{is_leaf_object, METADATA_KEYS},
+};
+use serde_json::Value;
+use std::path::Path;
+
+/// Classes, that only RPG Maker XP has.
+const XP_CLASSES: [&str; 2] = ["RPG::AudioFile", "RPG::Tileset"];
+/// Classes, that only RPG Maker VX has, among the engines, that don't mark strings' encodings.
+const VX_CLASSES: [&str; 5] = ["RPG::BGM", "RPG::BGS", "RPG::ME", "RPG::SE", "RPG::Area"];
+
+/// Version of RPG Maker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Engine {
+    Xp,
+    Vx,
+    VxAce,
+}
+
+impl Engine {
+    /// Returns the extension of the engine's files, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Engine::Xp => "rxdata",
+            Engine::Vx => "rvdata",
+            Engine::VxAce => "rvdata2",
+        }
+    }
+
+    /// Returns the engine, whose files have the extension. Comparison is case-insensitive.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        [Engine::Xp, Engine::Vx, Engine::VxAce]
+            .into_iter()
+            .find(|engine| extension.eq_ignore_ascii_case(engine.extension()))
+    }
+
+    /// Returns the loader preset, that decodes the engine's strings.
+    pub fn preset(self) -> Preset {
+        match self {
+            Engine::Xp => Preset::RpgMakerXp,
+            Engine::Vx => Preset::RpgMakerVx,
+            Engine::VxAce => Preset::RpgMakerVxAce,
+        }
+    }
+
+    /// Returns a Loader, configured with the engine's preset.
+    pub fn loader<'a>(self) -> Loader<'a> {
+        Loader::builder().preset(self.preset()).build()
+    }
+}
+
+/// Format of RPG Maker file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Format {
+    pub engine: Engine,
+    /// Whether the file is a save file, that holds multiple Marshal documents.
+    pub save: bool,
+}
+
+#[derive(Default)]
+struct Hints {
+    encoded_strings: bool,
+    xp_classes: bool,
+    vx_classes: bool,
+}
+
+impl Hints {
+    fn scan(&mut self, value: &Value) {
+        match value {
+            Value::String(string) if !string.starts_with("__symbol__") => {
+                self.encoded_strings = true
+            }
+            Value::Array(array) => array.iter().for_each(|element| self.scan(element)),
+            Value::Object(object) => {
+                if let Some(class) = object
+                    .get("__class")
+                    .and_then(Value::as_str)
+                    .and_then(|class| class.strip_prefix("__symbol__"))
+                {
+                    self.xp_classes |= XP_CLASSES.contains(&class);
+                    self.vx_classes |= VX_CLASSES.contains(&class);
+                }
+
+                if is_leaf_object(value) {
+                    return;
+                }
+
+                for (key, entry) in object {
+                    if !METADATA_KEYS.contains(&key.as_str()) {
+                        self.scan(entry);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn engine(&self) -> Option<Engine> {
+        if self.encoded_strings {
+            Some(Engine::VxAce)
+        } else if self.vx_classes {
+            Some(Engine::Vx)
+        } else if self.xp_classes {
+            Some(Engine::Xp)
+        } else {
+            None
+        }
+    }
+}
+
+/// Loads all consecutive Marshal documents of the bytes with the loader.
+fn load_documents<'a>(bytes: &'a [u8], loader: &mut Loader<'a>) -> Result<Vec<Value>, LoadError> {
+    let mut documents: Vec<Value> = Vec::new();
+    let mut position: usize = 0;
+
+    while position < bytes.len() {
+        let (value, length) = loader.load_with_length(&bytes[position..], None, None)?;
+        documents.push(value);
+        position += length;
+    }
+
+    Ok(documents)
+}
+
+/// Detects the format by the data, returning None if the bytes aren't Marshal data, or if the data doesn't reveal the engine.
+///
+/// Data without strings and engine-specific classes, like `Scripts.rxdata`, can't be recognized; use `detect_file()`, which also considers the extension.
+pub fn detect(bytes: &[u8]) -> Option<Format> {
+    let documents: Vec<Value> = load_documents(bytes, &mut Loader::new()).ok()?;
+    let mut hints: Hints = Hints::default();
+    documents.iter().for_each(|document| hints.scan(document));
+
+    Some(Format {
+        engine: hints.engine()?,
+        save: documents.len() > 1,
+    })
+}
+
+fn detect_with_extension(path: &Path, bytes: &[u8]) -> Option<Format> {
+    let engine: Option<Engine> = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Engine::from_extension);
+
+    let engine: Engine = match engine {
+        Some(engine) => engine,
+        None => return detect(bytes),
+    };
+
+    if !bytes.starts_with(&VERSION_HEADER) {
+        return None;
+    }
+
+    let save: bool = Loader::new()
+        .load_with_length(bytes, None, None)
+        .map_or(false, |(_, length)| length < bytes.len());
+
+    Some(Format { engine, save })
+}
+
+/// Detects the format of the file by its extension, falling back to detection by data for unknown extensions.
+///
+/// Returns an Err when the file can't be read.
+pub fn detect_file<P: AsRef<Path>>(path: P) -> std::io::Result<Option<Format>> {
+    let path: &Path = path.as_ref();
+    let bytes: Vec<u8> = std::fs::read(path)?;
+    Ok(detect_with_extension(path, &bytes))
+}
+
+/// Loads a data file of the engine, decoding strings with the engine's preset.
+pub fn load(bytes: &[u8], engine: Engine) -> Result<Value, LoadError> {
+    engine.loader().load(bytes, None, None)
+}
+
+/// Loads all documents of a save file of the engine, decoding strings with the engine's preset.
+pub fn load_save(bytes: &[u8], engine: Engine) -> Result<Vec<Value>, LoadError> {
+    load_documents(bytes, &mut engine.loader())
+}
+
+/// Detects the format of the file with `detect_file()`, and loads its documents. Data files produce a single document.
+///
+/// Returns an Err when the file can't be read, its format can't be detected, or its data is invalid.
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<(Format, Vec<Value>), LoadError> {
+    let path: &Path = path.as_ref();
+    let bytes: Vec<u8> = std::fs::read(path).map_err(|err| LoadError {
+        message: format!("Failed to read {}: {err}", path.display()),
+        ..Default::default()
+    })?;
+
+    let format: Format = detect_with_extension(path, &bytes).ok_or_else(|| LoadError {
+        message: format!("Unknown RPG Maker format of {}", path.display()),
+        ..Default::default()
+    })?;
+
+    Ok((format, load_save(&bytes, format.engine)?))
+}
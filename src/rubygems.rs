@@ -0,0 +1,437 @@
+//! Reading of RubyGems index files, like `specs.4.8.gz`, `latest_specs.4.8.gz` and `prerelease_specs.4.8.gz`.
+//!
+//! Index files are Marshal Arrays of `[name, Gem::Version, platform]` tuples, optionally compressed with gzip:
+//!
+//! | Element    | Dumped as                                                                 | Read as                    |
+//! | ---------- | ------------------------------------------------------------------------- | -------------------------- |
+//! | `name`     | String                                                                    | `String`                   |
+//! | `version`  | Gem::Version, dumped with `marshal_dump`, or with `@version` before that  | Version `String`           |
+//! | `platform` | String, like `"ruby"`, or Gem::Platform object                            | `String`, like `"x86-mingw32"` |
+//!
+//! RubyGems classes, that are found in index files and in `quick/Marshal.4.8/*.gemspec.rz` files, are mapped to typed structures,
+//! which implement `FromValue` and `IntoValue`:
+//!
+//! | Ruby class           | Dumped as                                                        | Structure          |
+//! | -------------------- | ---------------------------------------------------------------- | ------------------ |
+//! | `Gem::Version`       | `marshal_dump` of `[version]`                                    | `GemVersion`       |
+//! | `Gem::Requirement`   | `marshal_dump` of `[[[operator, Gem::Version], ...]]`            | `GemRequirement`   |
+//! | `Gem::Dependency`    | Object with `@name`, `@requirement`, `@type` and `@prerelease`   | `GemDependency`    |
+//! | `Gem::Specification` | `_dump` of Marshal data of the Array of its attributes           | `GemSpecification` |
+//!
+//! Requires `rubygems` feature. Not available with `sonic` feature enabled.
+
+use crate::{
+    dump::dump,
+    impl_from_value, impl_into_value,
+    load::Loader,
+    typed::{mismatch, FromValue, IntoValue},
+    value::{bytes_of, to_symbol, user_defined_of, ValueError},
+    StringMode, ValueExt,
+};
+use miniz_oxide::inflate::decompress_to_vec;
+use serde_json::{json, Value};
+use std::{fmt, fs, path::Path};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_HEADER_LENGTH: usize = 10;
+const GZIP_TRAILER_LENGTH: usize = 8;
+const FHCRC: u8 = 1 << 1;
+const FEXTRA: u8 = 1 << 2;
+const FNAME: u8 = 1 << 3;
+const FCOMMENT: u8 = 1 << 4;
+
+/// Single gem release of an index file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IndexEntry {
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+}
+
+fn index_error(message: String) -> ValueError {
+    ValueError { message }
+}
+
+/// Decompresses a single-member gzip stream, verifying its checksum and size.
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, ValueError> {
+    let truncated = || index_error("Truncated gzip stream.".to_string());
+
+    if bytes.len() < GZIP_HEADER_LENGTH + GZIP_TRAILER_LENGTH {
+        return Err(truncated());
+    }
+
+    if bytes[2] != 8 {
+        return Err(index_error(
+            "Unsupported gzip compression method.".to_string(),
+        ));
+    }
+
+    let flags: u8 = bytes[3];
+    let mut position: usize = GZIP_HEADER_LENGTH;
+
+    if flags & FEXTRA != 0 {
+        let length: &[u8] = bytes.get(position..position + 2).ok_or_else(truncated)?;
+        position += 2 + usize::from(u16::from_le_bytes([length[0], length[1]]));
+    }
+
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let terminator: usize = bytes
+                .get(position..)
+                .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+                .ok_or_else(truncated)?;
+            position += terminator + 1;
+        }
+    }
+
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+
+    let trailer: usize = bytes.len() - GZIP_TRAILER_LENGTH;
+    let compressed: &[u8] = bytes.get(position..trailer).ok_or_else(truncated)?;
+    let data: Vec<u8> = decompress_to_vec(compressed)
+        .map_err(|err| index_error(format!("Invalid gzip stream: {err}.")))?;
+
+    let checksum: u32 = u32::from_le_bytes(bytes[trailer..trailer + 4].try_into().unwrap());
+    let size: u32 = u32::from_le_bytes(bytes[trailer + 4..].try_into().unwrap());
+
+    if crc32fast::hash(&data) != checksum || data.len() as u32 != size {
+        return Err(index_error("Gzip stream checksum mismatch.".to_string()));
+    }
+
+    Ok(data)
+}
+
+/// Returns the string or binary string as `String`.
+fn string(value: &Value) -> Option<String> {
+    match value.as_str() {
+        Some(string) => Some(string.to_string()),
+        None => bytes_of(value).map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+/// Joins Gem::Platform's `@cpu`, `@os` and `@version` like `Gem::Platform#to_s`.
+fn platform_string(value: &Value) -> Option<String> {
+    if !value.is_instance_of("Gem::Platform") {
+        return string(value);
+    }
+
+    let parts: Vec<String> = ["@cpu", "@os", "@version"]
+        .iter()
+        .filter_map(|name| string(&value[to_symbol(name)]))
+        .collect();
+
+    Some(parts.join("-"))
+}
+
+fn entry(tuple: &Value) -> Option<IndexEntry> {
+    match tuple.as_array()?.as_slice() {
+        [name, version, platform] => Some(IndexEntry {
+            name: string(name)?,
+            version: GemVersion::from_value(version).ok()?.version,
+            platform: platform_string(platform)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Parses the contents of an index file, that are either gzip-compressed or not. See the module documentation for the mapping.
+///
+/// Returns an Err, if the data is not a valid gzip stream or Marshal data, or is not an Array of `[name, Gem::Version, platform]` tuples.
+/// # Example
+/// ```rust
+/// use marshal_rs::{dump, rubygems::{parse_index, IndexEntry}};
+/// use serde_json::json;
+///
+/// let index = dump(json!([["rake", { "__class": "__symbol__Gem::Version", "__type": "object", "__userMarshal": ["13.2.1"] }, "ruby"]]), None);
+///
+/// assert_eq!(
+///     parse_index(&index).unwrap(),
+///     [IndexEntry { name: "rake".to_string(), version: "13.2.1".to_string(), platform: "ruby".to_string() }]
+/// );
+/// ```
+pub fn parse_index(bytes: &[u8]) -> Result<Vec<IndexEntry>, ValueError> {
+    let data: Vec<u8>;
+    let marshal: &[u8] = if bytes.starts_with(&GZIP_MAGIC) {
+        data = gunzip(bytes)?;
+        &data
+    } else {
+        bytes
+    };
+
+    let value: Value = Loader::new()
+        .load(marshal, Some(StringMode::UTF8), None)
+        .map_err(|err| index_error(err.to_string()))?;
+    let tuples: &Vec<Value> = value
+        .as_array()
+        .ok_or_else(|| index_error("Index is not an Array.".to_string()))?;
+
+    tuples
+        .iter()
+        .enumerate()
+        .map(|(index, tuple)| {
+            entry(tuple).ok_or_else(|| {
+                index_error(format!(
+                    "Entry {index} is not a [name, Gem::Version, platform] tuple."
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Reads the index file at `path`. See `parse_index()`.
+pub fn read_index<P: AsRef<Path>>(path: P) -> Result<Vec<IndexEntry>, ValueError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(|err| index_error(err.to_string()))?;
+    parse_index(&bytes)
+}
+
+/// Version of a gem, like `"1.2.3"` or `"2.0.0.pre"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GemVersion {
+    pub version: String,
+}
+
+impl fmt::Display for GemVersion {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.version)
+    }
+}
+
+impl FromValue for GemVersion {
+    /// Converts Gem::Version, dumped with `marshal_dump`, or, as RubyGems 1.3 and earlier did, as an object with `@version`.
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("Gem::Version") {
+            return Err(mismatch("Gem::Version", value));
+        }
+
+        string(&value["__userMarshal"][0])
+            .or_else(|| string(&value[to_symbol("@version")]))
+            .map(|version| GemVersion { version })
+            .ok_or_else(|| index_error("Gem::Version has no version string".to_string()))
+    }
+}
+
+impl IntoValue for GemVersion {
+    fn into_value(self) -> Value {
+        json!({ "__class": to_symbol("Gem::Version"), "__type": "object", "__userMarshal": [self.version] })
+    }
+}
+
+/// Version requirement of a gem, like `~> 1.0, >= 1.0.2`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GemRequirement {
+    /// Pairs of operators, like `">="` or `"~>"`, and versions.
+    pub requirements: Vec<(String, GemVersion)>,
+}
+
+impl fmt::Display for GemRequirement {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (operator, version)) in self.requirements.iter().enumerate() {
+            if index > 0 {
+                formatter.write_str(", ")?;
+            }
+
+            write!(formatter, "{operator} {version}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromValue for GemRequirement {
+    /// Converts Gem::Requirement, dumped with `marshal_dump`, or, as RubyGems 1.3 and earlier did, as an object with `@requirements`.
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("Gem::Requirement") {
+            return Err(mismatch("Gem::Requirement", value));
+        }
+
+        let requirements: &Value = match value.get("__userMarshal") {
+            Some(dumped) => &dumped[0],
+            None => &value[to_symbol("@requirements")],
+        };
+
+        let requirements: Vec<Vec<Value>> = FromValue::from_value(requirements)?;
+
+        Ok(GemRequirement {
+            requirements: requirements
+                .iter()
+                .map(|pair| match pair.as_slice() {
+                    [operator, version] => Ok((
+                        String::from_value(operator)?,
+                        GemVersion::from_value(version)?,
+                    )),
+                    _ => Err(index_error(
+                        "Gem::Requirement's requirement is not an [operator, Gem::Version] pair"
+                            .to_string(),
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl IntoValue for GemRequirement {
+    fn into_value(self) -> Value {
+        let requirements: Vec<Value> = self
+            .requirements
+            .into_iter()
+            .map(|(operator, version)| json!([operator, version.into_value()]))
+            .collect();
+
+        json!({ "__class": to_symbol("Gem::Requirement"), "__type": "object", "__userMarshal": [requirements] })
+    }
+}
+
+/// Dependency of a gem.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GemDependency {
+    pub name: String,
+    pub requirement: GemRequirement,
+    /// Dependency type symbol: `"__symbol__runtime"` or `"__symbol__development"`.
+    pub kind: String,
+    pub prerelease: Option<bool>,
+}
+
+impl_from_value!(GemDependency, "Gem::Dependency", {
+    name: "@name",
+    requirement: "@requirement",
+    kind: "@type",
+    prerelease: "@prerelease",
+});
+impl_into_value!(GemDependency, "Gem::Dependency", {
+    name: "@name",
+    requirement: "@requirement",
+    kind: "@type",
+    prerelease: "@prerelease",
+});
+
+/// Specification of a gem release, as RubyGems serves it in `quick/Marshal.4.8/*.gemspec.rz` files.
+///
+/// Fields, that Ruby doesn't restrict to a single type, are kept as Values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GemSpecification {
+    pub rubygems_version: String,
+    pub specification_version: i32,
+    pub name: String,
+    pub version: GemVersion,
+    /// Time, see `chrono::RubyTime`.
+    pub date: Value,
+    pub summary: String,
+    pub required_ruby_version: GemRequirement,
+    pub required_rubygems_version: GemRequirement,
+    /// Platform string of specifications, written by RubyGems 0.9 and earlier, or `nil`.
+    pub original_platform: Value,
+    pub dependencies: Vec<GemDependency>,
+    /// Always an empty string, written in place of the removed `rubyforge_project` attribute.
+    pub rubyforge_project: Value,
+    /// String or an Array of strings.
+    pub email: Value,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    /// Always `true`, written in place of the removed `has_rdoc` attribute.
+    pub has_rdoc: Value,
+    /// Platform string, like `"ruby"`, or Gem::Platform object.
+    pub platform: Value,
+    /// Absent in specifications, written by RubyGems 1.3 and earlier.
+    pub licenses: Option<Vec<String>>,
+    /// Hash of metadata strings. Absent in specifications, written by RubyGems 1.8 and earlier.
+    pub metadata: Option<Value>,
+}
+
+/// Number of attributes, that all dumped specifications have.
+const SPECIFICATION_ATTRIBUTES: usize = 17;
+
+fn attribute<T: FromValue>(
+    attributes: &[Value],
+    index: usize,
+    name: &str,
+) -> Result<T, ValueError> {
+    T::from_value(attributes.get(index).unwrap_or(&Value::Null)).map_err(|err| ValueError {
+        message: format!("{name}: {}", err.message),
+    })
+}
+
+impl FromValue for GemSpecification {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if !value.is_instance_of("Gem::Specification") {
+            return Err(mismatch("Gem::Specification", value));
+        }
+
+        let dumped: Vec<u8> = user_defined_of(value)
+            .ok_or_else(|| index_error("Gem::Specification has no dumped data".to_string()))?;
+        let attributes: Value = Loader::new()
+            .load(&dumped, Some(StringMode::UTF8), None)
+            .map_err(|err| index_error(format!("Gem::Specification: {err}")))?;
+        let attributes: &[Value] = attributes
+            .as_array()
+            .filter(|attributes| attributes.len() >= SPECIFICATION_ATTRIBUTES)
+            .ok_or_else(|| {
+                index_error(format!(
+                    "Gem::Specification's dumped data is not an Array of at least {SPECIFICATION_ATTRIBUTES} attributes"
+                ))
+            })?;
+
+        Ok(GemSpecification {
+            rubygems_version: attribute(attributes, 0, "rubygems_version")?,
+            specification_version: attribute(attributes, 1, "specification_version")?,
+            name: attribute(attributes, 2, "name")?,
+            version: attribute(attributes, 3, "version")?,
+            date: attributes[4].clone(),
+            summary: attribute(attributes, 5, "summary")?,
+            required_ruby_version: attribute(attributes, 6, "required_ruby_version")?,
+            required_rubygems_version: attribute(attributes, 7, "required_rubygems_version")?,
+            original_platform: attributes[8].clone(),
+            dependencies: attribute(attributes, 9, "dependencies")?,
+            rubyforge_project: attributes[10].clone(),
+            email: attributes[11].clone(),
+            authors: attribute(attributes, 12, "authors")?,
+            description: attribute(attributes, 13, "description")?,
+            homepage: attribute(attributes, 14, "homepage")?,
+            has_rdoc: attributes[15].clone(),
+            platform: attributes[16].clone(),
+            licenses: attribute(attributes, 17, "licenses")?,
+            metadata: attributes.get(18).cloned(),
+        })
+    }
+}
+
+impl IntoValue for GemSpecification {
+    /// Converts the specification to the object, that RubyGems dumps. Absent `licenses` and `metadata` are omitted from the dumped attributes.
+    fn into_value(self) -> Value {
+        let mut attributes: Vec<Value> = vec![
+            self.rubygems_version.into_value(),
+            self.specification_version.into_value(),
+            self.name.into_value(),
+            self.version.into_value(),
+            self.date,
+            self.summary.into_value(),
+            self.required_ruby_version.into_value(),
+            self.required_rubygems_version.into_value(),
+            self.original_platform,
+            self.dependencies.into_value(),
+            self.rubyforge_project,
+            self.email,
+            self.authors.into_value(),
+            self.description.into_value(),
+            self.homepage.into_value(),
+            self.has_rdoc,
+            self.platform,
+        ];
+
+        if self.licenses.is_some() || self.metadata.is_some() {
+            attributes.push(self.licenses.into_value());
+        }
+
+        if let Some(metadata) = self.metadata {
+            attributes.push(metadata);
+        }
+
+        json!({
+            "__class": to_symbol("Gem::Specification"),
+            "__type": "object",
+            "__userDefined": dump(Value::Array(attributes), None),
+        })
+    }
+}
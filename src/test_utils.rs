@@ -0,0 +1,332 @@
+//! Utilities for testing applications against their own Marshal data.
+//!
+//! Golden fixtures are JSON files, stored next to Marshal files with `.json` appended to their names.
+//! Missing fixtures are created on the first run. To update existing fixtures, set `MARSHAL_RS_UPDATE_GOLDEN` environment variable.
+//!
+//! When Ruby is installed, `ruby_cross_check()` compares marshal-rs against Ruby's own `Marshal` on the same data.
+//!
+//! Requires `test-utils` feature. Not available with `sonic` feature enabled.
+
+use crate::{dump, load};
+use serde_json::Value;
+use std::{
+    ffi::OsString,
+    fs::{read, read_dir, read_to_string, write},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Environment variable, which enables overwriting of existing golden fixtures.
+pub const UPDATE_GOLDEN_VAR: &str = "MARSHAL_RS_UPDATE_GOLDEN";
+
+/// Asserts that Marshal data loads, and that dumping and loading the result again produces the same Value.
+///
+/// Bytes aren't compared, as `dump()` doesn't reproduce object links and string encodings exactly. Returns the loaded Value.
+/// # Example
+/// ```rust
+/// use marshal_rs::test_utils::assert_roundtrip;
+///
+/// let value = assert_roundtrip(b"\x04\x08[\x07i\x06I\"\x06a\x06:\x06ET");
+/// assert_eq!(value, serde_json::json!([1, "a"]));
+/// ```
+#[track_caller]
+pub fn assert_roundtrip(bytes: &[u8]) -> Value {
+    let value: Value = match load(bytes, None, None) {
+        Ok(value) => value,
+        Err(err) => panic!("Failed to load Marshal data: {err}"),
+    };
+
+    assert_value_roundtrip(&value);
+    value
+}
+
+/// Asserts that dumping the Value and loading the result produces the same Value.
+/// # Example
+/// ```rust
+/// use marshal_rs::test_utils::assert_value_roundtrip;
+/// use serde_json::json;
+///
+/// assert_value_roundtrip(&json!({ "__class": "__symbol__Point", "__type": "object", "__symbol__@x": 1 }));
+/// ```
+#[track_caller]
+pub fn assert_value_roundtrip(value: &Value) {
+    let bytes: Vec<u8> = dump(value.clone(), None);
+
+    match load(&bytes, None, None) {
+        Ok(loaded) => assert_eq!(loaded, *value, "Value changed after dumping and loading"),
+        Err(err) => panic!("Failed to load dumped Marshal data: {err}"),
+    }
+}
+
+/// Returns the path of the golden fixture of a Marshal file.
+pub fn golden_path(path: &Path) -> PathBuf {
+    let mut golden: OsString = path.as_os_str().to_owned();
+    golden.push(".json");
+    PathBuf::from(golden)
+}
+
+/// Returns paths of Marshal files in the directory, skipping golden fixtures and subdirectories, sorted by name.
+///
+/// Panics, if the directory can't be read.
+#[track_caller]
+pub fn golden_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let dir: &Path = dir.as_ref();
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => panic!("Failed to read directory {}: {err}", dir.display()),
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().map_or(true, |ext| ext != "json"))
+        .collect();
+
+    files.sort();
+    files
+}
+
+/// Asserts that the Marshal file round-trips, and that it loads into the Value, stored in its golden fixture.
+///
+/// If the fixture doesn't exist, or `MARSHAL_RS_UPDATE_GOLDEN` environment variable is set, writes the loaded Value to it instead of comparing.
+#[track_caller]
+pub fn assert_golden(path: impl AsRef<Path>) {
+    let path: &Path = path.as_ref();
+    let bytes: Vec<u8> = match read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => panic!("Failed to read {}: {err}", path.display()),
+    };
+
+    let value: Value = match load(&bytes, None, None) {
+        Ok(value) => value,
+        Err(err) => panic!("Failed to load {}: {err}", path.display()),
+    };
+
+    assert_value_roundtrip(&value);
+
+    let golden: PathBuf = golden_path(path);
+
+    if !golden.exists() || std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        let json: String = serde_json::to_string_pretty(&value).unwrap();
+
+        if let Err(err) = write(&golden, json + "\n") {
+            panic!("Failed to write {}: {err}", golden.display());
+        }
+
+        return;
+    }
+
+    let expected: Value = match read_to_string(&golden)
+        .map_err(|err| err.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|err| err.to_string()))
+    {
+        Ok(expected) => expected,
+        Err(err) => panic!("Failed to read {}: {err}", golden.display()),
+    };
+
+    assert_eq!(
+        value,
+        expected,
+        "{} doesn't match its golden fixture. Set {UPDATE_GOLDEN_VAR} environment variable to update it.",
+        path.display()
+    );
+}
+
+/// Runs `assert_golden()` on every Marshal file in the directory.
+/// # Example
+/// ```rust,no_run
+/// use marshal_rs::test_utils::assert_golden_dir;
+///
+/// assert_golden_dir("tests/fixtures");
+/// ```
+#[track_caller]
+pub fn assert_golden_dir(dir: impl AsRef<Path>) {
+    for path in golden_files(dir) {
+        assert_golden(path);
+    }
+}
+
+/// Environment variable, which overrides the Ruby binary used by `ruby_cross_check()`. Defaults to `ruby`.
+pub const RUBY_VAR: &str = "MARSHAL_RS_RUBY";
+
+/// Reads the input and marshal-rs output, prefixed with the input's length, from stdin.
+/// Writes the input, dumped back by Ruby, to stdout, and exits with code 2, if marshal-rs output can't be loaded.
+const RUBY_SCRIPT: &str = r#"
+data = STDIN.binmode.read
+length = data.unpack1("N")
+input = Marshal.load(data[4, length])
+STDOUT.binmode.write(Marshal.dump(input))
+
+begin
+  Marshal.load(data[4 + length..])
+rescue Exception => e
+  STDERR.write(e.message)
+  exit 2
+end
+"#;
+
+/// Difference between behavior of marshal-rs and Ruby.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// Data dumped by marshal-rs differs from data dumped by Ruby, starting from the offset.
+    Bytes {
+        offset: usize,
+        ruby: Vec<u8>,
+        rust: Vec<u8>,
+    },
+    /// Data dumped by Ruby loads into a different Value, than the input.
+    Value { ruby: Value, rust: Value },
+    /// Ruby failed to load data dumped by marshal-rs.
+    RubyRejected(String),
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Divergence::Bytes { offset, .. } => {
+                write!(
+                    formatter,
+                    "Dumped bytes differ from Ruby's at offset {offset}"
+                )
+            }
+            Divergence::Value { ruby, rust } => {
+                write!(
+                    formatter,
+                    "Ruby's dump loads into {ruby}, instead of {rust}"
+                )
+            }
+            Divergence::RubyRejected(message) => {
+                write!(formatter, "Ruby failed to load dumped data: {message}")
+            }
+        }
+    }
+}
+
+/// Error, returned when the cross-check can't be performed.
+#[derive(Debug)]
+pub struct CrossCheckError {
+    message: String,
+}
+
+impl std::fmt::Display for CrossCheckError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CrossCheckError {}
+
+fn ruby_binary() -> OsString {
+    std::env::var_os(RUBY_VAR).unwrap_or_else(|| "ruby".into())
+}
+
+/// Returns whether the Ruby binary can be executed.
+pub fn ruby_available() -> bool {
+    Command::new(ruby_binary())
+        .arg("-v")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(false, |status| status.success())
+}
+
+/// Loads and dumps Marshal data with both marshal-rs and Ruby's `Marshal`, and returns the differences.
+///
+/// Checks that:
+/// * marshal-rs dumps the loaded data into the same bytes as Ruby.
+/// * Data, dumped by Ruby, loads into the same Value as the input.
+/// * Ruby loads data, dumped by marshal-rs.
+///
+/// Classes and modules of objects in the data must exist in plain Ruby, or Ruby will fail to load it.
+///
+/// Returns an Err when Ruby can't be executed, or when the input can't be loaded by marshal-rs or Ruby.
+pub fn ruby_cross_check(bytes: &[u8]) -> Result<Vec<Divergence>, CrossCheckError> {
+    let value: Value = load(bytes, None, None).map_err(|err| CrossCheckError {
+        message: format!("Failed to load Marshal data: {err}"),
+    })?;
+    let rust: Vec<u8> = dump(value.clone(), None);
+
+    let mut child = Command::new(ruby_binary())
+        .args(["-e", RUBY_SCRIPT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| CrossCheckError {
+            message: format!("Failed to execute Ruby: {err}"),
+        })?;
+
+    let mut input: Vec<u8> = Vec::with_capacity(4 + bytes.len() + rust.len());
+    input.extend((bytes.len() as u32).to_be_bytes());
+    input.extend(bytes);
+    input.extend(&rust);
+
+    // Ruby might exit before reading everything, in which case its exit status reports the error
+    let _ = child.stdin.take().unwrap().write_all(&input);
+
+    let output = child.wait_with_output().map_err(|err| CrossCheckError {
+        message: format!("Failed to execute Ruby: {err}"),
+    })?;
+    let stderr: String = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+    let mut divergences: Vec<Divergence> = Vec::new();
+
+    match output.status.code() {
+        Some(0) => {}
+        Some(2) => divergences.push(Divergence::RubyRejected(stderr)),
+        _ => {
+            return Err(CrossCheckError {
+                message: format!("Ruby failed to load Marshal data: {stderr}"),
+            })
+        }
+    }
+
+    let ruby: Vec<u8> = output.stdout;
+
+    if ruby != rust {
+        let offset: usize = ruby
+            .iter()
+            .zip(&rust)
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| ruby.len().min(rust.len()));
+
+        divergences.push(Divergence::Bytes {
+            offset,
+            ruby: ruby.clone(),
+            rust,
+        });
+    }
+
+    match load(&ruby, None, None) {
+        Ok(loaded) if loaded == value => {}
+        Ok(loaded) => divergences.push(Divergence::Value {
+            ruby: loaded,
+            rust: value,
+        }),
+        Err(err) => {
+            return Err(CrossCheckError {
+                message: format!("Failed to load Marshal data, dumped by Ruby: {err}"),
+            })
+        }
+    }
+
+    Ok(divergences)
+}
+
+/// Asserts that `ruby_cross_check()` finds no divergences. Skips the check, if Ruby isn't available.
+#[track_caller]
+pub fn assert_ruby_cross_check(bytes: &[u8]) {
+    if !ruby_available() {
+        eprintln!("Ruby is not available, skipping cross-check");
+        return;
+    }
+
+    match ruby_cross_check(bytes) {
+        Ok(divergences) if divergences.is_empty() => {}
+        Ok(divergences) => {
+            let messages: Vec<String> = divergences.iter().map(ToString::to_string).collect();
+            panic!("Diverged from Ruby:\n{}", messages.join("\n"));
+        }
+        Err(err) => panic!("{err}"),
+    }
+}
@@ -0,0 +1,431 @@
+//! Generator of random, structurally valid Marshal documents, for seeding fuzzers and benchmarking the Loader without real data files.
+//!
+//! Documents are written directly as bytes, just like Ruby writes them: repeated symbols are written as symbol links,
+//! strings and Regexps carry their encodings, and objects may be referenced multiple times with object links.
+//! Links only reference objects, that are completely written before them, so documents never contain cycles.
+//!
+//! Generation is deterministic: the same options and seed always produce the same documents.
+//! # Example
+//! ```rust
+//! use marshal_rs::{testgen::{Generator, GeneratorOptions}, Loader};
+//!
+//! let mut generator = Generator::new(GeneratorOptions { max_depth: 4, seed: 42, ..Default::default() });
+//!
+//! for _ in 0..10 {
+//!     let bytes: Vec<u8> = generator.generate();
+//!     assert!(Loader::new().load(&bytes, None, None).is_ok());
+//! }
+//! ```
+
+use crate::raw::{write_int, Constants, VERSION_HEADER};
+
+const CLASSES: [&str; 6] = [
+    "Game_Actor",
+    "Game_Event",
+    "RPG::Map",
+    "RPG::Item",
+    "Table",
+    "Color",
+];
+const NAMES: [&str; 8] = ["id", "name", "x", "y", "list", "data", "note", "params"];
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+/// Relative weights of structure types. Types with zero weight aren't generated.
+///
+/// Containers (arrays, hashes, objects and structs) are replaced with leaves at the maximum depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeWeights {
+    pub nil: u32,
+    pub boolean: u32,
+    pub fixnum: u32,
+    pub bignum: u32,
+    pub float: u32,
+    pub string: u32,
+    pub symbol: u32,
+    pub regexp: u32,
+    pub array: u32,
+    pub hash: u32,
+    pub object: u32,
+    pub structure: u32,
+    /// Objects, dumped with `_dump`.
+    pub user_defined: u32,
+}
+
+impl Default for TypeWeights {
+    fn default() -> Self {
+        Self {
+            nil: 2,
+            boolean: 2,
+            fixnum: 6,
+            bignum: 1,
+            float: 2,
+            string: 6,
+            symbol: 3,
+            regexp: 1,
+            array: 3,
+            hash: 2,
+            object: 3,
+            structure: 1,
+            user_defined: 1,
+        }
+    }
+}
+
+/// Options of `Generator`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorOptions {
+    /// Maximum nesting depth of containers. Defaults to 6.
+    pub max_depth: usize,
+    /// Maximum number of elements of arrays and hashes, and of instance variables of objects. Defaults to 8.
+    pub max_length: usize,
+    /// Maximum length of strings and `_dump` data in bytes. Defaults to 32.
+    pub max_string_length: usize,
+    pub weights: TypeWeights,
+    /// Probability from 0 to 1, that a value is written as a link to one of previously written objects, if there are any. Defaults to 0.05.
+    pub link_density: f64,
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 6,
+            max_length: 8,
+            max_string_length: 32,
+            weights: TypeWeights::default(),
+            link_density: 0.05,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Nil,
+    Boolean,
+    Fixnum,
+    Bignum,
+    Float,
+    String,
+    Symbol,
+    Regexp,
+    Array,
+    Hash,
+    Object,
+    Struct,
+    UserDefined,
+}
+
+impl Kind {
+    fn is_container(self) -> bool {
+        matches!(self, Kind::Array | Kind::Hash | Kind::Object | Kind::Struct)
+    }
+}
+
+/// Generator of random Marshal documents.
+pub struct Generator {
+    options: GeneratorOptions,
+    /// State of SplitMix64.
+    state: u64,
+    buffer: Vec<u8>,
+    symbols: Vec<String>,
+    /// Number of registered objects, and whether each of them is completely written.
+    objects: Vec<bool>,
+}
+
+impl Generator {
+    pub fn new(options: GeneratorOptions) -> Self {
+        Self {
+            options,
+            state: options.seed,
+            buffer: Vec::new(),
+            symbols: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    /// Generates the next document.
+    pub fn generate(&mut self) -> Vec<u8> {
+        self.symbols.clear();
+        self.objects.clear();
+        self.buffer.extend(VERSION_HEADER);
+        self.write_value(0);
+
+        std::mem::take(&mut self.buffer)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut value: u64 = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// Returns a random number in `0..bound`, or 0, if the bound is 0.
+    fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    fn choose_kind(&mut self, leaf: bool) -> Kind {
+        let weights: TypeWeights = self.options.weights;
+        let kinds: [(Kind, u32); 13] = [
+            (Kind::Nil, weights.nil),
+            (Kind::Boolean, weights.boolean),
+            (Kind::Fixnum, weights.fixnum),
+            (Kind::Bignum, weights.bignum),
+            (Kind::Float, weights.float),
+            (Kind::String, weights.string),
+            (Kind::Symbol, weights.symbol),
+            (Kind::Regexp, weights.regexp),
+            (Kind::Array, weights.array),
+            (Kind::Hash, weights.hash),
+            (Kind::Object, weights.object),
+            (Kind::Struct, weights.structure),
+            (Kind::UserDefined, weights.user_defined),
+        ];
+
+        let weight = |(kind, weight): &(Kind, u32)| {
+            if leaf && kind.is_container() {
+                0
+            } else {
+                *weight as usize
+            }
+        };
+
+        let mut roll: usize = self.below(kinds.iter().map(weight).sum());
+
+        for entry in &kinds {
+            if roll < weight(entry) {
+                return entry.0;
+            }
+
+            roll -= weight(entry);
+        }
+
+        Kind::Nil
+    }
+
+    fn write_number(&mut self, number: i32) {
+        write_int(&mut self.buffer, number);
+    }
+
+    fn write_chunk(&mut self, bytes: &[u8]) {
+        self.write_number(bytes.len() as i32);
+        self.buffer.extend(bytes);
+    }
+
+    fn random_string(&mut self) -> Vec<u8> {
+        let length: usize = self.below(self.options.max_string_length + 1);
+        (0..length)
+            .map(|_| ALPHABET[self.below(ALPHABET.len())])
+            .collect()
+    }
+
+    fn write_symbol(&mut self, name: &str) {
+        match self.symbols.iter().position(|symbol| symbol == name) {
+            Some(index) => {
+                self.buffer.push(Constants::Symlink as u8);
+                self.write_number(index as i32);
+            }
+            None => {
+                self.buffer.push(Constants::Symbol as u8);
+                self.write_chunk(name.as_bytes());
+                self.symbols.push(name.to_string());
+            }
+        }
+    }
+
+    /// Registers an object in the object table, and returns its index.
+    fn register(&mut self) -> usize {
+        self.objects.push(false);
+        self.objects.len() - 1
+    }
+
+    fn write_link(&mut self) -> bool {
+        let completed: usize = self.objects.iter().filter(|&&completed| completed).count();
+
+        if completed == 0 || !self.chance(self.options.link_density) {
+            return false;
+        }
+
+        let nth: usize = self.below(completed);
+        let index: usize = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, &completed)| completed)
+            .nth(nth)
+            .unwrap()
+            .0;
+
+        self.buffer.push(Constants::Link as u8);
+        self.write_number(index as i32);
+        true
+    }
+
+    /// Writes the number of entries and calls `write_entry` for each of them.
+    fn write_entries<F: FnMut(&mut Self, usize)>(&mut self, length: usize, mut write_entry: F) {
+        self.write_number(length as i32);
+
+        for index in 0..length {
+            write_entry(self, index);
+        }
+    }
+
+    fn write_value(&mut self, depth: usize) {
+        if self.write_link() {
+            return;
+        }
+
+        let kind: Kind = self.choose_kind(depth >= self.options.max_depth);
+
+        match kind {
+            Kind::Nil => self.buffer.push(Constants::Nil as u8),
+            Kind::Boolean => {
+                let value: bool = self.chance(0.5);
+
+                self.buffer.push(if value {
+                    Constants::True
+                } else {
+                    Constants::False
+                } as u8);
+            }
+            Kind::Fixnum => {
+                // Mostly small numbers, that are packed in fewer bytes, like in real data
+                let bits: u32 = [7, 15, 30][self.below(3)];
+                let magnitude: i32 = self.below(1 << bits) as i32;
+                let negative: bool = self.chance(0.3);
+
+                self.buffer.push(Constants::Fixnum as u8);
+                self.write_number(if negative { -magnitude } else { magnitude });
+            }
+            Kind::Bignum => {
+                let index: usize = self.register();
+                let words: usize = 2 + self.below(3);
+                let mut bytes: Vec<u8> = (0..words * 2).map(|_| self.next_u64() as u8).collect();
+                // Most significant byte is non-zero, so the number isn't shorter, than its word count says
+                *bytes.last_mut().unwrap() |= 1;
+                let negative: bool = self.chance(0.5);
+
+                self.buffer.push(Constants::Bignum as u8);
+                self.buffer.push(if negative {
+                    Constants::Negative
+                } else {
+                    Constants::Positive
+                } as u8);
+                self.write_number(words as i32);
+                self.buffer.extend(bytes);
+                self.objects[index] = true;
+            }
+            Kind::Float => {
+                let index: usize = self.register();
+                let float: f64 = (self.below(2_000_001) as f64 - 1_000_000.0) / 64.0;
+
+                self.buffer.push(Constants::Float as u8);
+                self.write_chunk(float.to_string().as_bytes());
+                self.objects[index] = true;
+            }
+            Kind::String | Kind::Regexp => {
+                let index: usize = self.register();
+                let string: Vec<u8> = self.random_string();
+
+                self.buffer.push(Constants::InstanceVar as u8);
+
+                if let Kind::String = kind {
+                    self.buffer.push(Constants::String as u8);
+                    self.write_chunk(&string);
+                } else {
+                    self.buffer.push(Constants::Regexp as u8);
+                    self.write_chunk(&string);
+                    let options: u8 = self.below(8) as u8;
+                    self.buffer.push(options);
+                }
+
+                self.write_number(1);
+                self.write_symbol("E");
+                self.buffer.push(Constants::True as u8);
+                self.objects[index] = true;
+            }
+            Kind::Symbol => {
+                let name: &str = NAMES[self.below(NAMES.len())];
+                self.write_symbol(name);
+            }
+            Kind::Array => {
+                let index: usize = self.register();
+                let length: usize = self.below(self.options.max_length + 1);
+
+                self.buffer.push(Constants::Array as u8);
+                self.write_entries(length, |generator, _| generator.write_value(depth + 1));
+                self.objects[index] = true;
+            }
+            Kind::Hash => {
+                let index: usize = self.register();
+                let length: usize = self.below(self.options.max_length + 1);
+
+                self.buffer.push(Constants::Hash as u8);
+                self.write_entries(length, |generator, entry| {
+                    // Keys are distinct, so loaded Hashes keep all entries
+                    if generator.chance(0.5) {
+                        generator.buffer.push(Constants::Fixnum as u8);
+                        generator.write_number(entry as i32);
+                    } else {
+                        generator.write_symbol(&format!("key{entry}"));
+                    }
+
+                    generator.write_value(depth + 1);
+                });
+                self.objects[index] = true;
+            }
+            Kind::Object | Kind::Struct => {
+                let class: &str = CLASSES[self.below(CLASSES.len())];
+                let length: usize = self.below(self.options.max_length.min(NAMES.len()) + 1);
+
+                self.buffer.push(if let Kind::Object = kind {
+                    Constants::Object
+                } else {
+                    Constants::Struct
+                } as u8);
+                self.write_symbol(class);
+
+                let index: usize = self.register();
+
+                self.write_entries(length, |generator, entry| {
+                    if let Kind::Object = kind {
+                        generator.write_symbol(&format!("@{}", NAMES[entry]));
+                    } else {
+                        generator.write_symbol(NAMES[entry]);
+                    }
+
+                    generator.write_value(depth + 1);
+                });
+                self.objects[index] = true;
+            }
+            Kind::UserDefined => {
+                let class: &str = CLASSES[self.below(CLASSES.len())];
+                let data: Vec<u8> = self.random_string();
+
+                self.buffer.push(Constants::UserDefined as u8);
+                self.write_symbol(class);
+
+                let index: usize = self.register();
+                self.write_chunk(&data);
+                self.objects[index] = true;
+            }
+        }
+    }
+}
+
+/// Generates a single random document with the options.
+pub fn generate(options: GeneratorOptions) -> Vec<u8> {
+    Generator::new(options).generate()
+}
@@ -0,0 +1,155 @@
+//! Change tracking of loaded JSON values, for editors, that persist only modified data and show unsaved changes.
+//!
+//! Not available with `sonic` feature enabled.
+//! # Example
+//! ```rust
+//! use marshal_rs::{tracked::Tracked, Path};
+//! use serde_json::json;
+//!
+//! let mut actor = Tracked::new(json!({
+//!     "__class": "__symbol__RPG::Actor",
+//!     "__type": "object",
+//!     "__symbol__@name": "Ralph",
+//!     "__symbol__@level": 1,
+//! }));
+//!
+//! assert!(!actor.is_dirty());
+//!
+//! let level = Path::from_pointer("/__symbol__@level").unwrap();
+//! *actor.get_mut(&level).unwrap() = json!(2);
+//!
+//! assert!(actor.is_dirty());
+//! assert_eq!(actor.dirty_paths(), [level]);
+//!
+//! actor.mark_clean();
+//! assert!(!actor.is_dirty());
+//! ```
+
+use crate::value::{resolve_name, AtKey, Path, PathSegment, ValueError};
+use serde_json::Value;
+
+/// Returns whether the path is the ancestor of the other path, or equal to it.
+fn contains(ancestor: &Path, path: &Path) -> bool {
+    path.segments().starts_with(ancestor.segments())
+}
+
+/// Value, that records paths of subtrees, which were mutated through its accessors.
+///
+/// Only paths, passed to mutating accessors, are recorded, as the Value can't tell, whether the returned reference was actually written.
+/// Keys of the paths are recorded, as they're stored in the Value, so `@name` is recorded as `__symbol__@name`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tracked {
+    value: Value,
+    dirty: Vec<Path>,
+}
+
+impl Tracked {
+    /// Wraps the Value without any modified paths.
+    pub fn new(value: Value) -> Self {
+        Self {
+            value,
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Returns the tracked Value.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the tracked Value, dropping the record of modified paths.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+
+    /// Records the path as modified. Paths inside of an already modified subtree aren't recorded, and recording a subtree replaces records of paths inside of it.
+    pub fn mark_dirty(&mut self, path: &Path) {
+        if self.dirty.iter().any(|dirty| contains(dirty, path)) {
+            return;
+        }
+
+        self.dirty.retain(|dirty| !contains(path, dirty));
+        self.dirty.push(path.clone());
+    }
+
+    /// Forgets all modified paths, for example after the Value was saved.
+    pub fn mark_clean(&mut self) {
+        self.dirty.clear();
+    }
+
+    /// Returns whether any path was modified since the creation, or the last `mark_clean()`.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Returns whether the subtree at the path was modified: the path itself, any path inside of it, or any subtree, that includes it.
+    ///
+    /// Keys of the path must be passed, as they're stored in the Value.
+    pub fn is_path_dirty(&self, path: &Path) -> bool {
+        self.dirty
+            .iter()
+            .any(|dirty| contains(dirty, path) || contains(path, dirty))
+    }
+
+    /// Returns roots of modified subtrees, in order of their first modification.
+    pub fn dirty_paths(&self) -> &[Path] {
+        &self.dirty
+    }
+
+    /// Returns the path with keys, as they're stored in the Value, for example `__symbol__@name` for `@name`. Returns None, when the path doesn't exist.
+    fn resolve(&self, path: &Path) -> Option<Path> {
+        let mut resolved: Path = Path::new();
+        let mut value: &Value = &self.value;
+
+        for segment in path.segments() {
+            let segment: PathSegment = match segment {
+                PathSegment::Key(key) => PathSegment::Key(resolve_name(value, key)?.1),
+                PathSegment::Index(index) => PathSegment::Index(*index),
+            };
+
+            value = segment.lookup(value)?;
+            resolved.push(segment);
+        }
+
+        Some(resolved)
+    }
+
+    /// Returns a mutable reference to the Value at the path, resolving its segments like `ValueExt::at_mut()`, and records the path as modified.
+    ///
+    /// Returns None, leaving the record unchanged, when the path doesn't exist.
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Value> {
+        let path: Path = self.resolve(path)?;
+        self.mark_dirty(&path);
+
+        let mut value: &mut Value = &mut self.value;
+
+        for segment in path.segments() {
+            value = segment.lookup_mut(value)?;
+        }
+
+        Some(value)
+    }
+
+    /// Replaces the Value at the path, and returns the previous one.
+    ///
+    /// Returns an Err, when the path doesn't exist.
+    pub fn replace(&mut self, path: &Path, value: Value) -> Result<Value, ValueError> {
+        let target: &mut Value = self.get_mut(path).ok_or_else(|| ValueError {
+            message: format!("Path {path} doesn't exist."),
+        })?;
+
+        Ok(std::mem::replace(target, value))
+    }
+
+    /// Calls `f` with a mutable reference to the whole Value, and records the root as modified.
+    pub fn modify<R, F: FnOnce(&mut Value) -> R>(&mut self, f: F) -> R {
+        self.mark_dirty(&Path::new());
+        f(&mut self.value)
+    }
+}
+
+impl From<Value> for Tracked {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,383 @@
+//! Extraction of user-visible strings for translation, and application of translated strings.
+//!
+//! Strings are addressed by their paths, displayed as JSON pointers, so translation tables stay valid as long as the data's structure doesn't change.
+//! Tables can be exchanged as CSV files with `path,class,source,translation` columns, or as gettext PO files, where paths are stored as message contexts.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{
+    dump, load,
+    load::LoadError,
+    value::{visit_strings, Path, ValueError},
+};
+use serde_json::Value;
+
+/// A translatable string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranslationEntry {
+    pub path: Path,
+    /// Class of the nearest object, enclosing the string.
+    pub class: Option<String>,
+    pub source: String,
+    /// Translated string. Entries with empty translations are skipped, when translations are applied.
+    pub translation: String,
+}
+
+/// Options of `extract()`.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// If not empty, only strings inside instances of these classes (the nearest enclosing object) are extracted.
+    pub classes: Vec<String>,
+    /// Whether empty strings should be extracted.
+    pub include_empty: bool,
+}
+
+/// Result of `apply()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// Number of replaced strings.
+    pub applied: usize,
+    /// Paths, that don't point to strings.
+    pub missing: Vec<Path>,
+    /// Paths of strings, that don't match entries' source strings anymore. These are left unchanged.
+    pub changed: Vec<Path>,
+}
+
+/// Extracts strings from the Value, leaving their translations empty. Symbols and Hash keys are not extracted.
+/// # Example
+/// ```rust
+/// use marshal_rs::translate::{extract, ExtractOptions};
+/// use serde_json::json;
+///
+/// let value = json!([{ "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword" }, "other"]);
+/// let options = ExtractOptions { classes: vec!["RPG::Item".to_string()], ..Default::default() };
+///
+/// let entries = extract(&value, &options);
+///
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!(entries[0].path.to_string(), "/0/__symbol__@name");
+/// assert_eq!(entries[0].source, "Sword");
+/// ```
+pub fn extract(value: &Value, options: &ExtractOptions) -> Vec<TranslationEntry> {
+    let mut entries: Vec<TranslationEntry> = Vec::new();
+
+    visit_strings(value, &mut Path::new(), None, &mut |path, class, string| {
+        let in_class: bool = options.classes.is_empty()
+            || class.map_or(false, |class| {
+                options.classes.iter().any(|name| name == class)
+            });
+
+        if in_class && (options.include_empty || !string.is_empty()) {
+            entries.push(TranslationEntry {
+                path: path.clone(),
+                class: class.map(str::to_string),
+                source: string.to_string(),
+                translation: String::new(),
+            });
+        }
+    });
+
+    entries
+}
+
+/// Replaces strings with translations of the entries.
+///
+/// Strings are only replaced, if they still match the entries' source strings.
+pub fn apply(value: &mut Value, entries: &[TranslationEntry]) -> ApplyReport {
+    let mut report: ApplyReport = ApplyReport::default();
+
+    for entry in entries {
+        if entry.translation.is_empty() {
+            continue;
+        }
+
+        match value.pointer_mut(&entry.path.to_string()) {
+            Some(Value::String(string)) if !string.starts_with("__symbol__") => {
+                if *string == entry.source {
+                    *string = entry.translation.clone();
+                    report.applied += 1;
+                } else {
+                    report.changed.push(entry.path.clone());
+                }
+            }
+            _ => report.missing.push(entry.path.clone()),
+        }
+    }
+
+    report
+}
+
+/// Loads Marshal data, applies translations to it, and dumps it back.
+pub fn apply_to_bytes(
+    bytes: &[u8],
+    entries: &[TranslationEntry],
+) -> Result<(Vec<u8>, ApplyReport), LoadError> {
+    let mut value: Value = load(bytes, None, None)?;
+    let report: ApplyReport = apply(&mut value, entries);
+    Ok((dump(value, None), report))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Serializes entries to CSV with `path,class,source,translation` header.
+pub fn to_csv(entries: &[TranslationEntry]) -> String {
+    let mut csv: String = String::from("path,class,source,translation\n");
+
+    for entry in entries {
+        let fields: [String; 4] = [
+            csv_field(&entry.path.to_string()),
+            csv_field(entry.class.as_deref().unwrap_or("")),
+            csv_field(&entry.source),
+            csv_field(&entry.translation),
+        ];
+
+        csv += &fields.join(",");
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Splits CSV into records of fields.
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>, ValueError> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field: String = String::new();
+    let mut quoted: bool = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if quoted {
+            match char {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(char),
+            }
+
+            continue;
+        }
+
+        match char {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(char),
+        }
+    }
+
+    if quoted {
+        return Err(ValueError {
+            message: "Unterminated quoted field in CSV".to_string(),
+        });
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Parses entries from CSV, produced by `to_csv()`.
+///
+/// Returns an Err when CSV is malformed, or when a record doesn't have 4 fields.
+pub fn from_csv(csv: &str) -> Result<Vec<TranslationEntry>, ValueError> {
+    let mut entries: Vec<TranslationEntry> = Vec::new();
+
+    for (number, record) in parse_csv(csv)?.into_iter().enumerate().skip(1) {
+        let [path, class, source, translation]: [String; 4] =
+            record.try_into().map_err(|_| ValueError {
+                message: format!("CSV record {number} must have 4 fields"),
+            })?;
+
+        entries.push(TranslationEntry {
+            path: Path::from_pointer(&path)?,
+            class: if class.is_empty() { None } else { Some(class) },
+            source,
+            translation,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn po_string(string: &str) -> String {
+    let mut escaped: String = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+
+    for char in string.chars() {
+        match char {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => escaped += "\\r",
+            '\t' => escaped += "\\t",
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Serializes entries to gettext PO. Paths are stored as message contexts, and classes as extracted comments.
+pub fn to_po(entries: &[TranslationEntry]) -> String {
+    let mut po: String =
+        String::from("msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for entry in entries {
+        po.push('\n');
+
+        if let Some(class) = &entry.class {
+            po += &format!("#. {class}\n");
+        }
+
+        po += &format!(
+            "msgctxt {}\nmsgid {}\nmsgstr {}\n",
+            po_string(&entry.path.to_string()),
+            po_string(&entry.source),
+            po_string(&entry.translation)
+        );
+    }
+
+    po
+}
+
+fn parse_po_string(line: &str, number: usize) -> Result<String, ValueError> {
+    let error = || ValueError {
+        message: format!("Malformed string at PO line {number}"),
+    };
+
+    let inner: &str = line
+        .strip_prefix('"')
+        .and_then(|line| line.strip_suffix('"'))
+        .ok_or_else(error)?;
+
+    let mut string: String = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            string.push(char);
+            continue;
+        }
+
+        string.push(match chars.next().ok_or_else(error)? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            char @ ('"' | '\\') => char,
+            _ => return Err(error()),
+        });
+    }
+
+    Ok(string)
+}
+
+/// Parses entries from gettext PO, produced by `to_po()`. Messages without contexts, like the header, are skipped.
+///
+/// Returns an Err when PO is malformed.
+pub fn from_po(po: &str) -> Result<Vec<TranslationEntry>, ValueError> {
+    #[derive(PartialEq)]
+    enum Field {
+        None,
+        Context,
+        Id,
+        Str,
+    }
+
+    let mut entries: Vec<TranslationEntry> = Vec::new();
+    let mut entry: TranslationEntry = TranslationEntry::default();
+    let mut context: Option<String> = None;
+    let mut comment: Option<String> = None;
+    let mut field: Field = Field::None;
+
+    let mut finish =
+        |entry: &mut TranslationEntry, context: &mut Option<String>| -> Result<(), ValueError> {
+            let entry: TranslationEntry = std::mem::take(entry);
+
+            if let Some(context) = context.take() {
+                entries.push(TranslationEntry {
+                    path: Path::from_pointer(&context)?,
+                    ..entry
+                });
+            }
+
+            Ok(())
+        };
+
+    for (index, line) in po.lines().enumerate() {
+        let line: &str = line.trim();
+        let number: usize = index + 1;
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // Comments precede the message they belong to, so they're kept until the message starts
+        if let Some(class) = line.strip_prefix("#.") {
+            comment = Some(class.trim().to_string());
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("msgctxt ") {
+            finish(&mut entry, &mut context)?;
+            entry.class = comment.take();
+
+            context = Some(parse_po_string(rest.trim(), number)?);
+            field = Field::Context;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            // Messages without contexts start with msgid
+            if field != Field::Context {
+                finish(&mut entry, &mut context)?;
+                entry.class = comment.take();
+            }
+
+            entry.source = parse_po_string(rest.trim(), number)?;
+            field = Field::Id;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            entry.translation = parse_po_string(rest.trim(), number)?;
+            field = Field::Str;
+        } else if line.starts_with('"') {
+            let continuation: String = parse_po_string(line, number)?;
+
+            match field {
+                Field::Context => context
+                    .get_or_insert_with(String::new)
+                    .push_str(&continuation),
+                Field::Id => entry.source += &continuation,
+                Field::Str => entry.translation += &continuation,
+                Field::None => {
+                    return Err(ValueError {
+                        message: format!("Unexpected string at PO line {number}"),
+                    })
+                }
+            }
+        } else {
+            return Err(ValueError {
+                message: format!("Unknown keyword at PO line {number}"),
+            });
+        }
+    }
+
+    finish(&mut entry, &mut context)?;
+    Ok(entries)
+}
@@ -0,0 +1,391 @@
+//! Conversions between loaded JSON values and Rust types.
+//!
+//! Structs, mapped to Ruby classes, implement `FromValue` and `IntoValue` with `impl_from_value!` and `impl_into_value!` macros,
+//! or with `FromValue` and `IntoValue` derives of `marshal-rs-macros` crate, which expand to these macros.
+//!
+//! With `path-to-error` feature, `from_value()` and `from_str()` deserialize serde types, reporting where in the data the deserialization failed.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{
+    inspect::ruby_class,
+    value::{to_symbol, ValueError},
+    ValueExt,
+};
+#[cfg(feature = "path-to-error")]
+use serde::Deserialize;
+use serde_json::json;
+#[doc(hidden)]
+pub use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Conversion from a loaded Value.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, ValueError>;
+}
+
+/// Conversion into a Value, that can be passed to `dump()`.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+pub(crate) fn mismatch(expected: &str, value: &Value) -> ValueError {
+    ValueError {
+        message: format!("Expected {expected}, found {}", ruby_class(value)),
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        value.as_bool().ok_or_else(|| mismatch("Boolean", value))
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        value
+            .as_i64()
+            .and_then(|integer| i32::try_from(integer).ok())
+            .ok_or_else(|| mismatch("32-bit Integer", value))
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        value.as_i64().ok_or_else(|| mismatch("Integer", value))
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        value.as_f64().ok_or_else(|| mismatch("Float", value))
+    }
+}
+
+/// Symbols are converted with their `__symbol__` prefix, so they're dumped back as symbols.
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| mismatch("String", value))
+    }
+}
+
+/// `nil` is converted to `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_value(value).map(Some)
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let array: &Vec<Value> = value.as_array().ok_or_else(|| mismatch("Array", value))?;
+
+        array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| {
+                T::from_value(element).map_err(|err| ValueError {
+                    message: format!("[{index}]: {}", err.message),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Hashes with Integer keys, like events of RPG Maker maps.
+impl<T: FromValue> FromValue for BTreeMap<i32, T> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        let object = value
+            .as_object()
+            .filter(|_| value.get("__type").is_none())
+            .ok_or_else(|| mismatch("Hash", value))?;
+
+        object
+            .iter()
+            .map(|(key, entry)| {
+                let integer: i32 = key
+                    .strip_prefix("__integer__")
+                    .and_then(|integer| integer.parse().ok())
+                    .ok_or_else(|| ValueError {
+                        message: format!("Expected Integer key, found {key}"),
+                    })?;
+
+                let entry: T = T::from_value(entry).map_err(|err| ValueError {
+                    message: format!("[{integer}]: {}", err.message),
+                })?;
+
+                Ok((integer, entry))
+            })
+            .collect()
+    }
+}
+
+impl<T: FromValue> FromValue for Box<T> {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        T::from_value(value).map(Box::new)
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl IntoValue for i32 {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Null, IntoValue::into_value)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Box<T> {
+    fn into_value(self) -> Value {
+        (*self).into_value()
+    }
+}
+
+impl<T: IntoValue> IntoValue for BTreeMap<i32, T> {
+    fn into_value(self) -> Value {
+        Value::Object(
+            self.into_iter()
+                .map(|(key, entry)| (format!("__integer__{key}"), entry.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::Array(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+/// Returns an Err, if the Value is not an instance of the class.
+#[doc(hidden)]
+pub fn expect_class(value: &Value, class: &str) -> Result<(), ValueError> {
+    if value.is_instance_of(class) {
+        Ok(())
+    } else {
+        Err(mismatch(class, value))
+    }
+}
+
+/// Converts the instance variable of the object. Missing instance variables are treated as `nil`.
+#[doc(hidden)]
+pub fn field<T: FromValue>(object: &Value, name: &str) -> Result<T, ValueError> {
+    let value: &Value = object.get(to_symbol(name)).unwrap_or(&Value::Null);
+
+    T::from_value(value).map_err(|err| ValueError {
+        message: format!("{name}: {}", err.message),
+    })
+}
+
+#[doc(hidden)]
+pub fn new_object(class: &str) -> Value {
+    json!({ "__class": to_symbol(class), "__type": "object" })
+}
+
+#[doc(hidden)]
+pub fn set_field<T: IntoValue>(object: &mut Value, name: &str, value: T) {
+    if let Some(object) = object.as_object_mut() {
+        object.insert(to_symbol(name), value.into_value());
+    }
+}
+
+/// Formats the path to the failed value like Ruby accessors, without `__symbol__` prefixes: `@actors[3].@name`.
+#[cfg(feature = "path-to-error")]
+fn path_error(path: &serde_path_to_error::Path, err: impl std::fmt::Display) -> ValueError {
+    use serde_path_to_error::Segment;
+
+    let mut location: String = String::new();
+
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => location += &format!("[{index}]"),
+            Segment::Map { key } => {
+                if !location.is_empty() {
+                    location.push('.');
+                }
+
+                location += key.strip_prefix("__symbol__").unwrap_or(key);
+            }
+            Segment::Enum { variant } => {
+                if !location.is_empty() {
+                    location.push('.');
+                }
+
+                location += variant;
+            }
+            Segment::Unknown => location += "?",
+        }
+    }
+
+    ValueError {
+        message: if location.is_empty() {
+            err.to_string()
+        } else {
+            format!("at {location}: {err}")
+        },
+    }
+}
+
+/// Deserializes a serde type from the Value, like `serde_json::from_value()`, but reports the path to the value, that failed to deserialize.
+///
+/// Requires `path-to-error` feature.
+/// # Example
+/// ```rust
+/// use marshal_rs::typed::from_value;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Actor {
+///     #[serde(rename = "__symbol__@name")]
+///     name: String,
+/// }
+///
+/// let value = json!([{ "__symbol__@name": "Ralph" }, { "__symbol__@name": [] }]);
+/// let err = from_value::<Vec<Actor>>(&value).unwrap_err();
+///
+/// assert_eq!(err.to_string(), "at [1].@name: invalid type: sequence, expected a string");
+/// ```
+#[cfg(feature = "path-to-error")]
+pub fn from_value<'a, T: Deserialize<'a>>(value: &'a Value) -> Result<T, ValueError> {
+    serde_path_to_error::deserialize(value).map_err(|err| path_error(err.path(), err.inner()))
+}
+
+/// Deserializes a serde type from JSON, like `serde_json::from_str()`, but reports the path to the value, that failed to deserialize.
+///
+/// Requires `path-to-error` feature.
+#[cfg(feature = "path-to-error")]
+pub fn from_str<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, ValueError> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+
+    let value: T = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|err| path_error(err.path(), err.inner()))?;
+
+    deserializer.end().map_err(|err| ValueError {
+        message: err.to_string(),
+    })?;
+
+    Ok(value)
+}
+
+/// Implements `FromValue` for a struct with named fields, mapped to a Ruby class and its instance variables.
+/// # Example
+/// ```rust
+/// use marshal_rs::{impl_from_value, FromValue};
+/// use serde_json::json;
+///
+/// struct Actor {
+///     name: String,
+///     level: i32,
+/// }
+///
+/// impl_from_value!(Actor, "RPG::Actor", { name: "@name", level: "@level" });
+///
+/// let value = json!({ "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Ralph", "__symbol__@level": 1 });
+/// let actor = Actor::from_value(&value).unwrap();
+///
+/// assert_eq!(actor.name, "Ralph");
+/// assert_eq!(actor.level, 1);
+/// ```
+#[macro_export]
+macro_rules! impl_from_value {
+    ($name:ident, $class:expr, { $($field:ident: $ivar:expr),* $(,)? }) => {
+        impl $crate::FromValue for $name {
+            fn from_value(
+                value: &$crate::typed::Value,
+            ) -> ::core::result::Result<Self, $crate::ValueError> {
+                $crate::typed::expect_class(value, $class)?;
+
+                ::core::result::Result::Ok(Self {
+                    $($field: $crate::typed::field(value, $ivar)?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Implements `IntoValue` for a struct with named fields, mapped to a Ruby class and its instance variables.
+/// # Example
+/// ```rust
+/// use marshal_rs::{impl_into_value, IntoValue};
+/// use serde_json::json;
+///
+/// struct Actor {
+///     name: String,
+///     level: i32,
+/// }
+///
+/// impl_into_value!(Actor, "RPG::Actor", { name: "@name", level: "@level" });
+///
+/// let actor = Actor { name: "Ralph".to_string(), level: 1 };
+///
+/// assert_eq!(
+///     actor.into_value(),
+///     json!({ "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Ralph", "__symbol__@level": 1 })
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_into_value {
+    ($name:ident, $class:expr, { $($field:ident: $ivar:expr),* $(,)? }) => {
+        impl $crate::IntoValue for $name {
+            fn into_value(self) -> $crate::typed::Value {
+                #[allow(unused_mut)]
+                let mut object: $crate::typed::Value = $crate::typed::new_object($class);
+                $($crate::typed::set_field(&mut object, $ivar, self.$field);)*
+                object
+            }
+        }
+    };
+}
@@ -0,0 +1,2410 @@
+//! Utilities for inspecting and modifying loaded JSON values.
+//!
+//! Not available with `sonic` feature enabled.
+
+use crate::{
+    inspect::{estimate, ruby_class},
+    Dumper, DEFAULT_SYMBOL, EXTENDS_SYMBOL, SYMBOL_KEYS_SYMBOL,
+};
+#[cfg(feature = "bson")]
+use bson::Bson;
+#[cfg(feature = "encodings")]
+use encoding_rs::{Encoding, UTF_8};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "regex")]
+use regex::{Regex, Replacer};
+use serde_json::{from_str, json, to_string, Map, Number, Value};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+};
+
+#[derive(Debug)]
+pub struct ValueError {
+    pub(crate) message: String,
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Prefixes, that `load()` adds to non-string Hash keys.
+const HASH_KEY_PREFIXES: [&str; 7] = [
+    "__symbol__",
+    "__nil__",
+    "__boolean__",
+    "__integer__",
+    "__float__",
+    "__array__",
+    "__object__",
+];
+
+/// Keys of serialized objects, that hold metadata instead of Ruby values.
+pub(crate) const METADATA_KEYS: [&str; 6] = [
+    "__class",
+    "__type",
+    "__old",
+    "__userDefined",
+    EXTENDS_SYMBOL,
+    SYMBOL_KEYS_SYMBOL,
+];
+
+/// A single step in a `Path`: either object key or array index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Location of a Value inside of a tree.
+///
+/// Displayed as a JSON pointer, for example `/__symbol__@events/3/__symbol__@name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the segments of the path, from the root to the value.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Formats the path with dot-separated keys and bracketed indices, for example `__symbol__@events[3].__symbol__@name`.
+    ///
+    /// Dots, brackets and backslashes in keys are escaped with a backslash.
+    pub fn to_dotted(&self) -> String {
+        let mut dotted: String = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => {
+                    if !dotted.is_empty() {
+                        dotted.push('.');
+                    }
+
+                    for char in key.chars() {
+                        if matches!(char, '.' | '[' | '\\') {
+                            dotted.push('\\');
+                        }
+
+                        dotted.push(char);
+                    }
+                }
+                PathSegment::Index(index) => dotted += &format!("[{index}]"),
+            }
+        }
+
+        dotted
+    }
+
+    /// Parses the path, formatted with `to_dotted()`.
+    ///
+    /// Returns an Err when the path is malformed.
+    pub fn from_dotted(dotted: &str) -> Result<Self, ValueError> {
+        let error = || ValueError {
+            message: format!("Malformed path: {dotted}"),
+        };
+
+        let mut path: Path = Path::new();
+        let mut chars = dotted.chars().peekable();
+
+        while let Some(&char) = chars.peek() {
+            if char == '[' {
+                chars.next();
+
+                let index: String = chars.by_ref().take_while(|&char| char != ']').collect();
+                path.push(PathSegment::Index(index.parse().map_err(|_| error())?));
+
+                if !matches!(chars.peek(), None | Some('.' | '[')) {
+                    return Err(error());
+                }
+            } else {
+                if char == '.' {
+                    if path.segments.is_empty() {
+                        return Err(error());
+                    }
+
+                    chars.next();
+                }
+
+                let mut key: String = String::new();
+
+                while let Some(&char) = chars.peek() {
+                    match char {
+                        '.' | '[' => break,
+                        '\\' => {
+                            chars.next();
+                            key.push(chars.next().ok_or_else(error)?);
+                        }
+                        _ => {
+                            chars.next();
+                            key.push(char);
+                        }
+                    }
+                }
+
+                path.push(PathSegment::Key(key));
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Parses the path, formatted as a JSON pointer. Segments, consisting of digits, are parsed as array indices.
+    ///
+    /// Returns an Err when the pointer is not empty and doesn't start with `/`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{Path, PathSegment};
+    ///
+    /// let path = Path::from_pointer("/__symbol__@events/3/a~1b").unwrap();
+    ///
+    /// assert_eq!(path.segments()[1], PathSegment::Index(3));
+    /// assert_eq!(path.segments()[2], PathSegment::Key("a/b".to_string()));
+    /// assert_eq!(path.to_string(), "/__symbol__@events/3/a~1b");
+    /// ```
+    pub fn from_pointer(pointer: &str) -> Result<Self, ValueError> {
+        let mut path: Path = Path::new();
+
+        if pointer.is_empty() {
+            return Ok(path);
+        }
+
+        let pointer: &str = pointer.strip_prefix('/').ok_or_else(|| ValueError {
+            message: format!("Malformed JSON pointer: {pointer}"),
+        })?;
+
+        for segment in pointer.split('/') {
+            let is_index: bool = !segment.is_empty()
+                && segment.bytes().all(|byte| byte.is_ascii_digit())
+                && (segment == "0" || !segment.starts_with('0'));
+
+            path.push(match segment.parse::<usize>() {
+                Ok(index) if is_index => PathSegment::Index(index),
+                _ => PathSegment::Key(segment.replace("~1", "/").replace("~0", "~")),
+            });
+        }
+
+        Ok(path)
+    }
+
+    pub(crate) fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.segments.pop();
+    }
+}
+
+impl std::fmt::Display for Path {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => {
+                    write!(formatter, "/{}", key.replace('~', "~0").replace('/', "~1"))?
+                }
+                PathSegment::Index(index) => write!(formatter, "/{index}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Group of structurally equal values, found by `ValueExt::find_duplicates()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// Paths of the equal values.
+    pub paths: Vec<Path>,
+    /// Estimated size of a single value, serialized to Marshal.
+    pub size: usize,
+    /// Estimated number of bytes, that would be saved by replacing all values but one with object links.
+    pub savings: usize,
+}
+
+/// Kind of Ruby value, that a Value represents, as counted by `ValueExt::count_by_kind()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValueKind {
+    Nil,
+    Boolean,
+    /// Fixnums and Big Integers.
+    Integer,
+    Float,
+    /// Regular and binary strings.
+    String,
+    Symbol,
+    Regexp,
+    Array,
+    Hash,
+    /// Objects, including ones dumped with `_dump` and `marshal_dump`, and instances of String, Regexp, Array and Hash subclasses.
+    Object,
+    Struct,
+    Class,
+    Module,
+}
+
+/// Key of `ValueExt::at()` and `ValueExt::at_mut()`.
+///
+/// Strings look up keys of objects and Hashes, falling back to the symbol of the same name, so instance variables are accessed as `"@name"`,
+/// and struct members as `"name"`. Integers index arrays, and look up Integer keys of Hashes.
+pub trait AtKey {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+/// Returns the key of the object, that the name refers to: the name itself, or its symbol, or the member of the struct.
+pub(crate) fn resolve_name<'v>(value: &'v Value, name: &str) -> Option<(&'v Value, String)> {
+    let object: &Value = if value["__type"] == "struct" {
+        &value["__members"]
+    } else {
+        value
+    };
+    let map: &Map<String, Value> = object.as_object()?;
+
+    if map.contains_key(name) {
+        return Some((object, name.to_string()));
+    }
+
+    let symbol: String = to_symbol(name);
+    map.contains_key(&symbol).then_some((object, symbol))
+}
+
+impl AtKey for str {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        let (object, key) = resolve_name(value, self)?;
+        object.get(key)
+    }
+
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        let key: String = resolve_name(value, self)?.1;
+
+        if value["__type"] == "struct" {
+            value["__members"].get_mut(key)
+        } else {
+            value.get_mut(key)
+        }
+    }
+}
+
+impl AtKey for String {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.as_str().lookup(value)
+    }
+
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_str().lookup_mut(value)
+    }
+}
+
+impl AtKey for usize {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match value {
+            Value::Array(array) => array.get(*self),
+            _ => value.get_key(&json!(self)),
+        }
+    }
+
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        match value {
+            Value::Array(array) => array.get_mut(*self),
+            _ => value.get_key_mut(&json!(self)),
+        }
+    }
+}
+
+impl AtKey for PathSegment {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match self {
+            PathSegment::Key(key) => key.lookup(value),
+            PathSegment::Index(index) => index.lookup(value),
+        }
+    }
+
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        match self {
+            PathSegment::Key(key) => key.lookup_mut(value),
+            PathSegment::Index(index) => index.lookup_mut(value),
+        }
+    }
+}
+
+impl<T: AtKey + ?Sized> AtKey for &T {
+    fn lookup<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).lookup(value)
+    }
+
+    fn lookup_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).lookup_mut(value)
+    }
+}
+
+/// Options of `ValueExt::replace_matching()`.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceOptions {
+    /// If not empty, only strings inside instances of these classes (the nearest enclosing object) are processed.
+    pub classes: Vec<String>,
+    /// If set, only strings under this path are processed.
+    pub path_prefix: Option<Path>,
+    /// Whether symbols should be processed along with strings.
+    pub include_symbols: bool,
+}
+
+/// Result of `ValueExt::replace_matching()`.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceReport {
+    /// Total number of replaced matches.
+    pub count: usize,
+    /// Paths of changed strings.
+    pub paths: Vec<Path>,
+}
+
+#[cfg(feature = "regex")]
+fn replace_in<R: Replacer>(
+    value: &mut Value,
+    path: &mut Path,
+    class: Option<&str>,
+    regex: &Regex,
+    replacement: &mut R,
+    options: &ReplaceOptions,
+    report: &mut ReplaceReport,
+) {
+    if let Value::String(string) = value {
+        let (prefix, text): (&str, &str) = match string.strip_prefix("__symbol__") {
+            Some(symbol) if options.include_symbols => ("__symbol__", symbol),
+            Some(_) => return,
+            None => ("", string),
+        };
+
+        let in_class: bool = options.classes.is_empty()
+            || class.map_or(false, |class| {
+                options.classes.iter().any(|name| name == class)
+            });
+        let in_path: bool = options.path_prefix.as_ref().map_or(true, |prefix| {
+            path.segments().starts_with(prefix.segments())
+        });
+
+        if in_class && in_path {
+            let count: usize = regex.find_iter(text).count();
+
+            if count > 0 {
+                *string = prefix.to_string() + &regex.replace_all(text, replacement.by_ref());
+                report.count += count;
+                report.paths.push(path.clone());
+            }
+        }
+
+        return;
+    }
+
+    if is_leaf_object(value) {
+        return;
+    }
+
+    let class: Option<String> = value.class_name().or(class).map(str::to_string);
+
+    match value {
+        Value::Array(array) => {
+            for (index, element) in array.iter_mut().enumerate() {
+                path.push(PathSegment::Index(index));
+                replace_in(
+                    element,
+                    path,
+                    class.as_deref(),
+                    regex,
+                    replacement,
+                    options,
+                    report,
+                );
+                path.pop();
+            }
+        }
+        Value::Object(object) => {
+            for (key, entry) in object.iter_mut() {
+                if METADATA_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                path.push(PathSegment::Key(key.to_owned()));
+                replace_in(
+                    entry,
+                    path,
+                    class.as_deref(),
+                    regex,
+                    replacement,
+                    options,
+                    report,
+                );
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Result of `ValueExt::transcode_strings()`.
+#[cfg(feature = "encodings")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranscodeReport {
+    /// Number of converted strings.
+    pub converted: usize,
+    /// Paths of strings, that are invalid in source encoding or can't be represented in target encoding. These are left unchanged.
+    pub failed: Vec<Path>,
+}
+
+/// Returns the bytes of serialized binary string.
+pub(crate) fn bytes_of(value: &Value) -> Option<Vec<u8>> {
+    if value["__type"] != "bytes" {
+        return None;
+    }
+
+    byte_array(&value["data"])
+}
+
+/// Returns the `_dump` payload of the object.
+#[cfg(any(feature = "chrono", feature = "decimal", feature = "rubygems"))]
+pub(crate) fn user_defined_of(value: &Value) -> Option<Vec<u8>> {
+    byte_array(&value["__userDefined"])
+}
+
+fn byte_array(value: &Value) -> Option<Vec<u8>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect()
+}
+
+/// Hashes the number, consistently with its equality: integers and floats are never equal, and zero floats are equal regardless of their sign.
+fn hash_number<H: Hasher>(number: &Number, state: &mut H) {
+    if let Some(unsigned) = number.as_u64() {
+        state.write_u8(0);
+        state.write_u64(unsigned);
+    } else if let Some(signed) = number.as_i64() {
+        state.write_u8(1);
+        state.write_i64(signed);
+    } else {
+        let float: f64 = number.as_f64().unwrap_or_default();
+        state.write_u8(2);
+        state.write_u64(if float == 0.0 { 0 } else { float.to_bits() });
+    }
+}
+
+/// Returns the hash of an object's entry, that's combined with hashes of other entries by addition, so the order of entries doesn't matter.
+fn hash_entry(key: &str, entry_hash: impl FnOnce(&mut DefaultHasher)) -> u64 {
+    let mut hasher: DefaultHasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    entry_hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes the Value structurally, consistently with its equality, without allocating.
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    match value {
+        Value::Null => state.write_u8(0),
+        Value::Bool(bool) => {
+            state.write_u8(1);
+            bool.hash(state);
+        }
+        Value::Number(number) => {
+            state.write_u8(2);
+            hash_number(number, state);
+        }
+        Value::String(string) => {
+            state.write_u8(3);
+            string.hash(state);
+        }
+        Value::Array(array) => {
+            state.write_u8(4);
+            state.write_usize(array.len());
+
+            for element in array {
+                hash_value(element, state);
+            }
+        }
+        Value::Object(object) => {
+            let combined: u64 = object.iter().fold(0, |combined, (key, entry)| {
+                combined.wrapping_add(hash_entry(key, |hasher| hash_value(entry, hasher)))
+            });
+
+            state.write_u8(5);
+            state.write_usize(object.len());
+            state.write_u64(combined);
+        }
+    }
+}
+
+/// Returns a structural hash of the Value, consistent with its equality, and records hashes of its shareable nested values.
+fn hash_values<'a>(
+    value: &'a Value,
+    path: &mut Path,
+    hashes: &mut Vec<(u64, Path, &'a Value)>,
+) -> u64 {
+    let mut hasher: DefaultHasher = DefaultHasher::new();
+
+    match value {
+        Value::Array(array) => {
+            hasher.write_u8(4);
+            hasher.write_usize(array.len());
+
+            for (index, element) in array.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                hash_values(element, path, hashes).hash(&mut hasher);
+                path.pop();
+            }
+        }
+        Value::Object(object) => {
+            let mut combined: u64 = 0;
+
+            for (key, entry) in object {
+                path.push(PathSegment::Key(key.to_owned()));
+
+                let entry_hash: u64 = hash_values(entry, path, hashes);
+                combined = combined.wrapping_add(hash_entry(key, |hasher| entry_hash.hash(hasher)));
+
+                path.pop();
+            }
+
+            hasher.write_u8(5);
+            hasher.write_usize(object.len());
+            hasher.write_u64(combined);
+        }
+        _ => hash_value(value, &mut hasher),
+    }
+
+    let hash: u64 = hasher.finish();
+
+    // Only strings and containers are stored in Marshal object table and can be linked
+    let shareable: bool = match value {
+        Value::String(string) => !string.starts_with("__symbol__"),
+        Value::Array(_) | Value::Object(_) => true,
+        _ => false,
+    };
+
+    if shareable {
+        hashes.push((hash, path.clone(), value));
+    }
+
+    hash
+}
+
+/// Returns whether the Value is a serialized object, that holds no nested Ruby values (bytes, bigint, regexp, class or module).
+pub(crate) fn is_leaf_object(value: &Value) -> bool {
+    matches!(
+        value["__type"].as_str(),
+        Some("bytes" | "string" | "bigint" | "regexp" | "class" | "module")
+    )
+}
+
+/// Calls `f` for each Ruby value, directly nested in the Value: elements of arrays, values of Hashes, instance variables and wrapped values of objects, and members of structs.
+fn for_each_child<'v, F: FnMut(&'v Value)>(value: &'v Value, mut f: F) {
+    if is_leaf_object(value) {
+        return;
+    }
+
+    match value {
+        Value::Array(array) => array.iter().for_each(f),
+        Value::Object(_) if value["__type"] == "struct" => {
+            if let Some(members) = value["__members"].as_object() {
+                members.values().for_each(f);
+            }
+        }
+        Value::Object(object) => {
+            for (key, entry) in object {
+                if !METADATA_KEYS.contains(&key.as_str()) {
+                    f(entry);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns entries of the Hash, instance variables of the object or of its wrapped value, or members of the struct, or None, if the Value is not one of them.
+fn entries(value: &Value) -> Option<Vec<(&String, &Value)>> {
+    let object: &Map<String, Value> = value.as_object()?;
+
+    match value["__type"].as_str() {
+        None => Some(
+            object
+                .iter()
+                .filter(|(key, _)| !METADATA_KEYS.contains(&key.as_str()) && *key != DEFAULT_SYMBOL)
+                .collect(),
+        ),
+        Some("object") => match object.get("__wrapped") {
+            Some(wrapped) => entries(wrapped),
+            None => Some(
+                object
+                    .iter()
+                    .filter(|(key, _)| {
+                        !METADATA_KEYS.contains(&key.as_str())
+                            && !matches!(key.as_str(), "__data" | "__userMarshal")
+                    })
+                    .collect(),
+            ),
+        },
+        Some("struct") => Some(value["__members"].as_object()?.iter().collect()),
+        Some(_) => None,
+    }
+}
+
+/// Calls `f` for the Value and each of its nested values, skipping metadata of serialized objects.
+///
+/// Nested values are visited after `f` is called for their parent.
+pub(crate) fn visit_mut<F: FnMut(&Path, &mut Value)>(
+    value: &mut Value,
+    path: &mut Path,
+    f: &mut F,
+) {
+    f(path, value);
+
+    if is_leaf_object(value) {
+        return;
+    }
+
+    match value {
+        Value::Array(array) => {
+            for (index, element) in array.iter_mut().enumerate() {
+                path.push(PathSegment::Index(index));
+                visit_mut(element, path, f);
+                path.pop();
+            }
+        }
+        Value::Object(object) => {
+            for (key, entry) in object.iter_mut() {
+                if METADATA_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                path.push(PathSegment::Key(key.to_owned()));
+                visit_mut(entry, path, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Like `visit_mut()`, but visits elements of arrays and entries of objects on rayon's thread pool, in no particular order.
+#[cfg(feature = "rayon")]
+fn par_visit_mut<F: Fn(&Path, &mut Value) + Sync>(value: &mut Value, path: &Path, f: &F) {
+    f(path, value);
+
+    if is_leaf_object(value) {
+        return;
+    }
+
+    let children: Vec<(PathSegment, &mut Value)> = match value {
+        Value::Array(array) => array
+            .iter_mut()
+            .enumerate()
+            .map(|(index, element)| (PathSegment::Index(index), element))
+            .collect(),
+        Value::Object(object) => object
+            .iter_mut()
+            .filter(|(key, _)| !METADATA_KEYS.contains(&key.as_str()))
+            .map(|(key, entry)| (PathSegment::Key(key.to_owned()), entry))
+            .collect(),
+        _ => return,
+    };
+
+    children.into_par_iter().for_each(|(segment, child)| {
+        let mut path: Path = path.clone();
+        path.push(segment);
+        par_visit_mut(child, &path, f);
+    });
+}
+
+/// Immutable counterpart of `par_visit_mut()`.
+#[cfg(feature = "rayon")]
+fn par_visit<F: Fn(&Path, &Value) + Sync>(value: &Value, path: &Path, f: &F) {
+    f(path, value);
+
+    if is_leaf_object(value) {
+        return;
+    }
+
+    let children: Vec<(PathSegment, &Value)> = match value {
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (PathSegment::Index(index), element))
+            .collect(),
+        Value::Object(object) => object
+            .iter()
+            .filter(|(key, _)| !METADATA_KEYS.contains(&key.as_str()))
+            .map(|(key, entry)| (PathSegment::Key(key.to_owned()), entry))
+            .collect(),
+        _ => return,
+    };
+
+    children.into_par_iter().for_each(|(segment, child)| {
+        let mut path: Path = path.clone();
+        path.push(segment);
+        par_visit(child, &path, f);
+    });
+}
+
+/// Calls `f` for each string in the Value, except symbols, along with the class of the nearest enclosing object.
+pub(crate) fn visit_strings<F: FnMut(&Path, Option<&str>, &str)>(
+    value: &Value,
+    path: &mut Path,
+    class: Option<&str>,
+    f: &mut F,
+) {
+    if let Value::String(string) = value {
+        if !string.starts_with("__symbol__") {
+            f(path, class, string);
+        }
+
+        return;
+    }
+
+    if is_leaf_object(value) {
+        return;
+    }
+
+    let class: Option<&str> = value.class_name().or(class);
+
+    match value {
+        Value::Array(array) => {
+            for (index, element) in array.iter().enumerate() {
+                path.push(PathSegment::Index(index));
+                visit_strings(element, path, class, f);
+                path.pop();
+            }
+        }
+        Value::Object(object) => {
+            for (key, entry) in object {
+                if METADATA_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                path.push(PathSegment::Key(key.to_owned()));
+                visit_strings(entry, path, class, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn retain_children<F: FnMut(&Path, &Value) -> bool>(value: &mut Value, path: &mut Path, f: &mut F) {
+    if is_leaf_object(value) {
+        return;
+    }
+
+    match value {
+        Value::Array(array) => {
+            for (index, element) in array.iter_mut().enumerate() {
+                path.push(PathSegment::Index(index));
+                retain_children(element, path, f);
+                path.pop();
+            }
+
+            let mut index: usize = 0;
+
+            array.retain(|element| {
+                path.push(PathSegment::Index(index));
+                let retain: bool = f(path, element);
+                path.pop();
+
+                index += 1;
+                retain
+            });
+        }
+        Value::Object(object) => {
+            for (key, entry) in object.iter_mut() {
+                if METADATA_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                path.push(PathSegment::Key(key.to_owned()));
+                retain_children(entry, path, f);
+                path.pop();
+            }
+
+            object.retain(|key, entry| {
+                if METADATA_KEYS.contains(&key.as_str()) {
+                    return true;
+                }
+
+                path.push(PathSegment::Key(key.to_owned()));
+                let retain: bool = f(path, entry);
+                path.pop();
+
+                retain
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Returns whether the Value is a serialized Ruby Hash.
+pub(crate) fn is_hash(value: &Value) -> bool {
+    value
+        .as_object()
+        .map_or(false, |object| !object.contains_key("__type"))
+}
+
+/// Orders metadata keys of serialized objects first, in the order of `METADATA_KEYS`, and other keys by their bytes.
+fn key_order(key: &str) -> (usize, &str) {
+    let metadata: usize = METADATA_KEYS
+        .iter()
+        .position(|&metadata| metadata == key)
+        .unwrap_or(METADATA_KEYS.len());
+
+    (metadata, key)
+}
+
+fn sort_keys(value: &mut Value) {
+    match value {
+        Value::Array(array) => array.iter_mut().for_each(sort_keys),
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(object).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| key_order(a).cmp(&key_order(b)));
+
+            for (_, entry) in &mut entries {
+                sort_keys(entry);
+            }
+
+            *object = entries.into_iter().collect();
+        }
+        _ => {}
+    }
+}
+
+/// Rewrites the text of `__integer__`, `__float__`, `__array__` and `__object__` Hash key the way `load()` writes it, so keys of equal Ruby values are equal strings.
+fn canonical_key(key: String) -> String {
+    if let Some(integer) = key.strip_prefix("__integer__") {
+        if let Ok(integer) = integer.parse::<i64>() {
+            return format!("__integer__{integer}");
+        }
+    } else if let Some(float) = key.strip_prefix("__float__") {
+        if let Some(float) = float.parse::<f64>().ok().and_then(Number::from_f64) {
+            return format!("__float__{float}");
+        }
+    } else {
+        for prefix in ["__array__", "__object__"] {
+            if let Some(Ok(mut nested)) = key.strip_prefix(prefix).map(from_str::<Value>) {
+                nested.canonicalize();
+                return format!("{prefix}{nested}");
+            }
+        }
+    }
+
+    key
+}
+
+fn canonicalize_keys(value: &mut Value) {
+    if is_leaf_object(value) {
+        return;
+    }
+
+    match value {
+        Value::Array(array) => array.iter_mut().for_each(canonicalize_keys),
+        Value::Object(object) => {
+            let is_hash: bool = !object.contains_key("__type");
+
+            for (_, entry) in object.iter_mut() {
+                canonicalize_keys(entry);
+            }
+
+            if is_hash {
+                *object = std::mem::take(object)
+                    .into_iter()
+                    .map(|(key, entry)| (canonical_key(key), entry))
+                    .collect();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn coerce_keys(value: &mut Value) {
+    if is_leaf_object(value) {
+        return;
+    }
+
+    match value {
+        Value::Array(array) => array.iter_mut().for_each(coerce_keys),
+        Value::Object(object) => {
+            let is_hash: bool = !object.contains_key("__type");
+
+            for (_, entry) in object.iter_mut() {
+                coerce_keys(entry);
+            }
+
+            if is_hash {
+                *object = std::mem::take(object)
+                    .into_iter()
+                    .map(|(key, entry)| {
+                        let key: String = match key.as_str() {
+                            "__nil__null" => "nil".to_string(),
+                            _ => HASH_KEY_PREFIXES
+                                .iter()
+                                .find_map(|prefix| key.strip_prefix(prefix))
+                                .map_or(key.clone(), str::to_string),
+                        };
+
+                        (key, entry)
+                    })
+                    .collect();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn flatten_into(value: &Value, path: &mut Path, flat: &mut Map<String, Value>) {
+    let children: Vec<(PathSegment, &Value)> = match value {
+        Value::Array(array) if !array.is_empty() => array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (PathSegment::Index(index), element))
+            .collect(),
+        Value::Object(object) if !object.is_empty() && !is_leaf_object(value) => object
+            .iter()
+            .map(|(key, entry)| (PathSegment::Key(key.to_owned()), entry))
+            .collect(),
+        _ => {
+            flat.insert(path.to_dotted(), value.clone());
+            return;
+        }
+    };
+
+    for (segment, child) in children {
+        path.push(segment);
+        flatten_into(child, path, flat);
+        path.pop();
+    }
+}
+
+/// Converts a Value to the key, under which it's stored in serialized Hash object.
+pub(crate) fn hash_key(key: &Value) -> String {
+    match key {
+        Value::Null => "__nil__null".to_string(),
+        Value::Bool(boolean) => format!("__boolean__{boolean}"),
+        Value::String(string) => string.to_owned(),
+        Value::Number(number) => {
+            if number.is_f64() {
+                "__float__".to_string() + &to_string(number).unwrap()
+            } else {
+                "__integer__".to_string() + &to_string(number).unwrap()
+            }
+        }
+        Value::Array(_) => "__array__".to_string() + &to_string(key).unwrap(),
+        Value::Object(_) => "__object__".to_string() + &to_string(key).unwrap(),
+    }
+}
+
+/// Converts the key of serialized Hash object back to the Value, it was made from. Inverse of `hash_key()`.
+pub(crate) fn key_value(key: &str) -> Value {
+    match key {
+        "__nil__null" => return Value::Null,
+        "__boolean__true" => return Value::Bool(true),
+        "__boolean__false" => return Value::Bool(false),
+        _ => {}
+    }
+
+    if let Some(integer) = key.strip_prefix("__integer__") {
+        if let Ok(integer) = from_str::<Value>(integer) {
+            return integer;
+        }
+    } else if let Some(float) = key.strip_prefix("__float__") {
+        if let Ok(float) = from_str::<Value>(float) {
+            return float;
+        }
+    } else if let Some(json) = key
+        .strip_prefix("__array__")
+        .or_else(|| key.strip_prefix("__object__"))
+    {
+        if let Ok(value) = from_str::<Value>(json) {
+            return value;
+        }
+    }
+
+    Value::String(key.to_string())
+}
+
+/// Returns whether the Value is serialized object or struct, which class and extensions can be set directly.
+fn is_classed(value: &Value) -> bool {
+    matches!(value["__type"].as_str(), Some("object") | Some("struct"))
+}
+
+/// Returns whether the Value is an instance of the core class, that can be subclassed and serialized with `__wrapped` key.
+fn is_wrappable(value: &Value) -> bool {
+    match value {
+        Value::String(string) => !string.starts_with("__symbol__"),
+        Value::Array(_) => true,
+        Value::Object(_) => {
+            is_hash(value)
+                || matches!(
+                    value["__type"].as_str(),
+                    Some("bytes") | Some("string") | Some("regexp")
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Prefixes the name with `__symbol__`, if it's not prefixed already.
+pub(crate) fn to_symbol(name: &str) -> String {
+    if name.starts_with("__symbol__") {
+        name.to_string()
+    } else {
+        "__symbol__".to_string() + name
+    }
+}
+
+/// Extension methods for `serde_json::Value`, produced by `load()`.
+pub trait ValueExt {
+    /// `null` Value.
+    const NULL: Value = Value::Null;
+    /// `true` Value.
+    const TRUE: Value = Value::Bool(true);
+    /// `false` Value.
+    const FALSE: Value = Value::Bool(false);
+
+    /// Returns an empty array Value.
+    fn array_empty() -> Value {
+        json!([])
+    }
+
+    /// Returns an empty object Value.
+    fn object_empty() -> Value {
+        json!({})
+    }
+
+    /// Returns a mutable reference to the instance variable of Ruby object, inserting the result of `default` if it's absent.
+    ///
+    /// `name` may be passed either with or without `__symbol__` prefix.
+    ///
+    /// Returns an Err when Value is not a serialized Ruby object.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// let mut object = json!({ "__class": "__symbol__Item", "__type": "object" });
+    ///
+    /// let tags: &mut Value = object.get_or_insert_with("@tags", Value::array_empty).unwrap();
+    /// tags.as_array_mut().unwrap().push(json!("new"));
+    ///
+    /// assert_eq!(object["__symbol__@tags"], json!(["new"]));
+    /// ```
+    fn get_or_insert_with<F: FnOnce() -> Value>(
+        &mut self,
+        name: &str,
+        default: F,
+    ) -> Result<&mut Value, ValueError>;
+
+    /// Returns a mutable reference to the value of Ruby Hash under `key`, inserting the result of `default` if it's absent.
+    ///
+    /// `key` is converted to the Hash key the same way `load()` does it.
+    ///
+    /// Returns an Err when Value is not a serialized Ruby Hash.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// let mut hash = json!({});
+    /// *hash.get_or_insert_with_key(&json!(1), || json!(0)).unwrap() = json!(5);
+    ///
+    /// assert_eq!(hash, json!({ "__integer__1": 5 }));
+    /// ```
+    fn get_or_insert_with_key<F: FnOnce() -> Value>(
+        &mut self,
+        key: &Value,
+        default: F,
+    ) -> Result<&mut Value, ValueError>;
+
+    /// Returns the class name of serialized Ruby object, struct, class or module, without `__symbol__` prefix.
+    fn class_name(&self) -> Option<&str>;
+
+    /// Returns whether the Value is a serialized instance of exactly `class`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let event = json!({ "__class": "__symbol__RPG::Event", "__type": "object" });
+    ///
+    /// assert!(event.is_instance_of("RPG::Event"));
+    /// assert!(event.class_matches(|class| class.starts_with("RPG::")));
+    /// ```
+    fn is_instance_of(&self, class: &str) -> bool;
+
+    /// Returns whether the Value has a class, for which `predicate` returns true.
+    fn class_matches<F: FnOnce(&str) -> bool>(&self, predicate: F) -> bool;
+
+    /// Returns whether the Value is an instance of `class`, that subclasses String, Regexp, Array or Hash (serialized with `__wrapped` key).
+    fn is_subclass_value(&self, class: &str) -> bool;
+
+    /// Returns the Value with its class set to `class`, which may be passed either with or without `__symbol__` prefix.
+    ///
+    /// Classes of serialized objects and structs are replaced. Strings, Regexps, Arrays and Hashes are wrapped into instances of `class`, which is their subclass.
+    /// Other values are returned unchanged, as Marshal can't store their classes.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let name = json!("text").with_class("Name").with_extension("Comparable");
+    ///
+    /// assert_eq!(
+    ///     name,
+    ///     json!({
+    ///         "__class": "__symbol__Name",
+    ///         "__type": "object",
+    ///         "__wrapped": "text",
+    ///         "__ruby_extends__": ["__symbol__Comparable"],
+    ///     })
+    /// );
+    /// ```
+    fn with_class(self, class: &str) -> Value;
+
+    /// Returns serialized object or struct, extended with `module`, which may be passed either with or without `__symbol__` prefix. Other values are returned unchanged.
+    fn with_extension(self, module: &str) -> Value;
+
+    /// Returns the Value wrapped into an instance of its own class, like `with_class()` wraps it into a subclass, if `user_class` is true,
+    /// or the wrapped value with the class and extensions discarded, if it's false.
+    ///
+    /// Values, that can't be wrapped, or are already (un)wrapped, are returned unchanged.
+    fn with_user_class(self, user_class: bool) -> Value;
+
+    /// Returns the string Value marked to be dumped in `encoding` (a Ruby or WHATWG encoding name) instead of UTF-8, overriding how the Dumper writes strings.
+    ///
+    /// `"ASCII-8BIT"` or `"BINARY"` dump the string as raw bytes without encoding. Other encodings require `encodings` feature, except `"UTF-8"` and `"US-ASCII"`.
+    /// Strings, that can't be encoded without losses, are dumped with UTF-8 encoding. Values other than strings are returned unchanged.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{dump, ValueExt};
+    /// use serde_json::json;
+    ///
+    /// let value = json!(["text", json!("script").with_string_encoding("ASCII-8BIT")]);
+    ///
+    /// assert_eq!(value[1], json!({ "__type": "string", "value": "script", "encoding": "ASCII-8BIT" }));
+    /// assert_eq!(dump(value, None), b"\x04\x08[\x07I\"\x09text\x06:\x06ET\"\x0bscript");
+    /// ```
+    fn with_string_encoding(self, encoding: &str) -> Value;
+
+    /// Returns a reference to the value of Ruby Hash under `key`, or None if Value is not a Hash or the key is absent.
+    ///
+    /// `key` is converted to the Hash key the same way `load()` does it. Array elements can be accessed with `get()`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let hash = json!({ "__integer__1": "one", "__symbol__two": 2 });
+    ///
+    /// assert_eq!(hash.get_key(&json!(1)), Some(&json!("one")));
+    /// assert_eq!(hash.get_key(&json!("__symbol__two")), Some(&json!(2)));
+    /// assert_eq!(hash.get_key(&json!(3)), None);
+    /// ```
+    fn get_key(&self, key: &Value) -> Option<&Value>;
+
+    /// Returns a mutable reference to the value of Ruby Hash under `key`, or None if Value is not a Hash or the key is absent.
+    fn get_key_mut(&mut self, key: &Value) -> Option<&mut Value>;
+
+    /// Returns the nested value under `key`, or `null`, if the Value has no such key, so lookups can be chained without checks.
+    ///
+    /// Unlike indexing, names of instance variables and symbols don't need `__symbol__` prefixes, struct members are found in `__members`,
+    /// and integers look up Integer keys of Hashes. See `AtKey`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let map = json!({
+    ///     "__class": "__symbol__RPG::Map",
+    ///     "__type": "object",
+    ///     "__symbol__@events": { "__integer__3": { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss" } },
+    /// });
+    ///
+    /// assert_eq!(map.at("@events").at(3).at("@name"), "Boss");
+    /// assert!(map.at("@events").at(4).at("@name").is_null());
+    /// assert!(map.at(0).at("@name").is_null());
+    /// ```
+    fn at<K: AtKey>(&self, key: K) -> &Value;
+
+    /// Returns a mutable reference to the nested value under `key`, or None, if the Value has no such key. Keys are resolved like `at()` does.
+    fn at_mut<K: AtKey>(&mut self, key: K) -> Option<&mut Value>;
+
+    /// Returns the kind of Ruby value, that the Value represents.
+    fn kind(&self) -> ValueKind;
+
+    /// Returns the number of values on the longest path from the Value to a nested value without children, including both, so scalars have depth 1.
+    ///
+    /// Marshal nests more structures than that, like instance variables of strings and class names of objects, so `LoaderBuilder::max_depth()` has to be higher.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// assert_eq!(json!(1).depth(), 1);
+    /// assert_eq!(json!([[1], []]).depth(), 3);
+    /// ```
+    fn depth(&self) -> usize;
+
+    /// Returns the number of values in the tree, including the Value itself. Metadata of serialized objects, such as classes, isn't counted.
+    fn count_nodes(&self) -> usize;
+
+    /// Returns the number of values of each kind in the tree, including the Value itself.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{ValueExt, ValueKind};
+    /// use serde_json::json;
+    ///
+    /// let value = json!([{ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@tags": [] }, "__symbol__a"]);
+    /// let counts = value.count_by_kind();
+    ///
+    /// assert_eq!(counts[&ValueKind::Array], 2);
+    /// assert_eq!(counts[&ValueKind::Object], 1);
+    /// assert_eq!(counts[&ValueKind::String], 1);
+    /// assert_eq!(counts.values().sum::<usize>(), value.count_nodes());
+    /// ```
+    fn count_by_kind(&self) -> BTreeMap<ValueKind, usize>;
+
+    /// Returns the number of elements of the array, entries of the Hash, instance variables of the object, members of the struct, characters of the string or symbol, or bytes of the binary string. Returns 0 for other values.
+    ///
+    /// Objects of user classes, like subclasses of Hash, are measured by their wrapped values. Default values of Hashes aren't counted.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// assert_eq!(json!([1, 2]).len(), 2);
+    /// assert_eq!(json!({ "__symbol__a": 1, "__ruby_default__": 0 }).len(), 1);
+    /// assert_eq!(json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" }).len(), 1);
+    /// assert_eq!(json!("ドラゴン").len(), 4);
+    /// assert_eq!(json!({ "__type": "bytes", "data": [1, 2, 3] }).len(), 3);
+    /// assert_eq!(json!(42).len(), 0);
+    /// ```
+    fn len(&self) -> usize;
+
+    /// Returns whether `len()` is 0.
+    fn is_empty(&self) -> bool;
+
+    /// Returns keys of the Hash, names of instance variables of the object, or names of members of the struct, as they're stored, with prefixes. Returns an empty Vec for other values.
+    fn keys(&self) -> Vec<&str>;
+
+    /// Returns elements of the array, values of the Hash, instance variables of the object, or members of the struct. Returns an empty Vec for other values, including strings.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1, "__symbol__y": 2 } });
+    ///
+    /// assert_eq!(value.keys(), ["__symbol__x", "__symbol__y"]);
+    /// assert_eq!(value.values(), [&json!(1), &json!(2)]);
+    /// ```
+    fn values(&self) -> Vec<&Value>;
+
+    /// Collects key-value pairs into a Ruby Hash, converting keys like `get_key()` does. Later entries overwrite earlier ones with the same key.
+    ///
+    /// Arrays and objects (`String` keys) are collected with `serde_json`'s own `FromIterator` implementations, as `Value` is foreign to this crate.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// let hash = Value::hash_from_entries((1..=2).map(|id| (json!(id), json!(id * 10)))).unwrap();
+    ///
+    /// assert_eq!(hash, json!({ "__integer__1": 10, "__integer__2": 20 }));
+    /// ```
+    fn hash_from_entries<I: IntoIterator<Item = (Value, Value)>>(
+        entries: I,
+    ) -> Result<Value, ValueError> {
+        let mut hash: Value = Self::object_empty();
+        hash.extend_entries(entries)?;
+        Ok(hash)
+    }
+
+    /// Appends the elements to an array Value.
+    ///
+    /// Returns an Err when Value is not an array.
+    fn extend_elements<I: IntoIterator<Item = Value>>(
+        &mut self,
+        elements: I,
+    ) -> Result<(), ValueError>;
+
+    /// Inserts key-value pairs into a Ruby Hash or serialized Ruby object, converting keys like `get_key()` does, and overwriting existing entries.
+    ///
+    /// Returns an Err when Value is not a JSON object.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut hash = json!({ "__symbol__a": 1 });
+    /// hash.extend_entries([(json!("__symbol__b"), json!(2)), (json!([1]), json!(3))]).unwrap();
+    ///
+    /// assert_eq!(hash, json!({ "__symbol__a": 1, "__symbol__b": 2, "__array__[1]": 3 }));
+    /// assert!(json!([]).extend_entries([(json!(null), json!(4))]).is_err());
+    /// ```
+    fn extend_entries<I: IntoIterator<Item = (Value, Value)>>(
+        &mut self,
+        entries: I,
+    ) -> Result<(), ValueError>;
+
+    /// Recursively removes all nested values, for which `predicate` returns false.
+    ///
+    /// Values are visited depth-first, so `predicate` receives each value after its own nested values were already filtered.
+    /// Metadata keys of serialized objects (such as `__class` and `__type`) are never passed to `predicate`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut value = json!([1, "two", [3, "four"]]);
+    /// value.retain_recursive(|_, value| !value.is_string());
+    ///
+    /// assert_eq!(value, json!([1, [3]]));
+    /// ```
+    fn retain_recursive<F: FnMut(&Path, &Value) -> bool>(&mut self, predicate: F);
+
+    /// Converts serialized Ruby object to a Ruby Hash, keyed by symbols of its instance variables' names without "@".
+    ///
+    /// Returns an Err when Value is not a plain serialized Ruby object.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let object = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" });
+    ///
+    /// assert_eq!(object.object_into_hash().unwrap(), json!({ "__symbol__name": "Sword" }));
+    /// ```
+    fn object_into_hash(self) -> Result<Value, ValueError>;
+
+    /// Converts a Ruby Hash, keyed by strings or symbols, to serialized Ruby object of `class`, with "@"-prefixed instance variables.
+    ///
+    /// Returns an Err when:
+    /// * Value is not a serialized Ruby Hash.
+    /// * Hash has a default value.
+    /// * Hash has keys other than strings and symbols.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let hash = json!({ "__symbol__name": "Sword" });
+    ///
+    /// assert_eq!(
+    ///     hash.hash_into_object("Item").unwrap(),
+    ///     json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" })
+    /// );
+    /// ```
+    fn hash_into_object(self, class: &str) -> Result<Value, ValueError>;
+
+    /// Flattens the Value to an object, mapping dotted paths (see `Path::to_dotted()`) to leaf values.
+    ///
+    /// Leaf values are scalars, empty arrays and objects, and serialized objects without nested Ruby values, such as bytes.
+    /// Metadata keys of serialized objects are flattened as well, so `unflatten()` can restore the original Value.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// let value = json!({ "a": [1, { "b": true }] });
+    /// let flat = value.flatten();
+    ///
+    /// assert_eq!(Value::from(flat.clone()), json!({ "a[0]": 1, "a[1].b": true }));
+    /// assert_eq!(Value::unflatten(&flat).unwrap(), value);
+    /// ```
+    fn flatten(&self) -> Map<String, Value>;
+
+    /// Builds a Value back from the object produced by `flatten()`.
+    ///
+    /// Missing array elements are filled with `null`.
+    ///
+    /// Returns an Err when paths are malformed or contradict each other.
+    fn unflatten(flat: &Map<String, Value>) -> Result<Value, ValueError>;
+
+    /// Finds groups of structurally equal nested strings, arrays and objects, which are at least `min_size` bytes in estimated serialized size.
+    ///
+    /// Groups are sorted by estimated savings, from the largest. Values nested in other duplicates are not reported separately.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "a": [1, 2, 3], "b": [1, 2, 3] });
+    /// let duplicates = value.find_duplicates(4);
+    ///
+    /// assert_eq!(duplicates.len(), 1);
+    /// assert_eq!(duplicates[0].paths[0].to_string(), "/a");
+    /// assert_eq!(duplicates[0].paths[1].to_string(), "/b");
+    /// ```
+    fn find_duplicates(&self, min_size: usize) -> Vec<DuplicateGroup>;
+
+    /// Recursively converts keys of all Ruby Hashes to plain strings, removing type prefixes `load()` adds to them.
+    ///
+    /// If several keys become equal, the last one is kept.
+    fn coerce_keys_to_strings(&mut self);
+
+    /// Calls `f` for every string in the tree, along with its path.
+    ///
+    /// If `include_symbols` is true, `f` is called for symbols as well, receiving their names without `__symbol__` prefix.
+    /// Hash keys, instance variables' names and binary strings (`{ "__type": "bytes" }` objects) are not visited.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "sword" });
+    /// value.walk_strings_mut(false, |_, string| *string = string.to_uppercase());
+    ///
+    /// assert_eq!(value["__symbol__@name"], json!("SWORD"));
+    /// ```
+    fn walk_strings_mut<F: FnMut(&Path, &mut String)>(&mut self, include_symbols: bool, f: F);
+
+    /// Calls `f` for the Value and each of its nested values, except metadata of serialized objects, along with their paths.
+    ///
+    /// Nested values of each array and object are visited in parallel on rayon's global thread pool, so `f` is called in no particular order,
+    /// but always after it's called for the parent.
+    ///
+    /// Requires `rayon` feature.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let value = json!([1, [2, 3], { "__class": "__symbol__Item", "__type": "object", "__symbol__@id": 4 }]);
+    /// let numbers = AtomicUsize::new(0);
+    ///
+    /// value.par_walk(|_, value| {
+    ///     if value.is_number() {
+    ///         numbers.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(numbers.into_inner(), 4);
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn par_walk<F: Fn(&Path, &Value) + Sync>(&self, f: F);
+
+    /// Parallel `walk_strings_mut()`: calls `f` for every string in the tree, visiting nested values of each array and object on rayon's global thread pool.
+    ///
+    /// Requires `rayon` feature.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut value = json!([{ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "sword" }, "shield"]);
+    /// value.par_map_strings(false, |_, string| *string = string.to_uppercase());
+    ///
+    /// assert_eq!(value[0]["__symbol__@name"], json!("SWORD"));
+    /// assert_eq!(value[1], json!("SHIELD"));
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn par_map_strings<F: Fn(&Path, &mut String) + Sync>(&mut self, include_symbols: bool, f: F);
+
+    /// Replaces all matches of `regex` in strings of the tree with `replacement`, which may reference capture groups like `Regex::replace_all()`.
+    ///
+    /// Returns a report with the number of replaced matches and paths of changed strings.
+    ///
+    /// Requires `regex` feature.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{ReplaceOptions, ValueExt};
+    /// use regex::Regex;
+    /// use serde_json::json;
+    ///
+    /// let mut value = json!(["Potion x2", "Ether x10"]);
+    /// let report = value.replace_matching(&Regex::new(r"x(\d+)").unwrap(), "×$1", &ReplaceOptions::default());
+    ///
+    /// assert_eq!(value, json!(["Potion ×2", "Ether ×10"]));
+    /// assert_eq!(report.count, 2);
+    /// ```
+    #[cfg(feature = "regex")]
+    fn replace_matching<R: Replacer>(
+        &mut self,
+        regex: &Regex,
+        replacement: R,
+        options: &ReplaceOptions,
+    ) -> ReplaceReport;
+
+    /// Converts binary strings (`{ "__type": "bytes" }` objects) from `from` encoding to `to` encoding.
+    ///
+    /// Requires `encodings` feature.
+    ///
+    /// If `to` is UTF-8, converted strings become regular strings, otherwise they stay binary. Regular strings are always UTF-8 and are not changed.
+    /// # Example
+    /// ```rust
+    /// use encoding_rs::{SHIFT_JIS, UTF_8};
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// // "テスト" in Shift_JIS
+    /// let mut value = json!([{ "__type": "bytes", "data": [131, 101, 131, 88, 131, 103] }]);
+    /// let report = value.transcode_strings(SHIFT_JIS, UTF_8);
+    ///
+    /// assert_eq!(value, json!(["テスト"]));
+    /// assert_eq!(report.converted, 1);
+    /// ```
+    #[cfg(feature = "encodings")]
+    fn transcode_strings(
+        &mut self,
+        from: &'static Encoding,
+        to: &'static Encoding,
+    ) -> TranscodeReport;
+
+    /// Decodes binary strings (`{ "__type": "bytes" }` objects) in `encoding` to regular strings.
+    ///
+    /// Shorthand for `transcode_strings(encoding, UTF_8)`. Requires `encodings` feature.
+    #[cfg(feature = "encodings")]
+    fn decode_bytes(&mut self, encoding: &'static Encoding) -> TranscodeReport;
+
+    /// Recursively removes all nested `null` values.
+    fn prune_nulls(&mut self);
+
+    /// Recursively removes all nested empty arrays, Hashes and strings, including ones that became empty after pruning.
+    fn prune_empty(&mut self);
+
+    /// Recursively sorts keys of all Hashes and serialized objects: metadata keys, like `__class` and `__type`, go first in a fixed order, and other keys are sorted by their bytes.
+    ///
+    /// Dumping the sorted Value writes Hash entries and instance variables in the sorted order.
+    fn sort_keys_recursive(&mut self);
+
+    /// Brings the Value to a canonical form, so Values of equal Ruby data are equal, and serialize to the same JSON, regardless of the order of keys.
+    ///
+    /// Sorts keys like `sort_keys_recursive()`, and rewrites numbers and nested values in `__integer__`, `__float__`, `__array__` and `__object__` Hash keys the way `load()` writes them.
+    /// Useful for snapshots and diffs, that shouldn't depend on the order of Hash entries.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut a = json!({ "x": [{ "__symbol__@b": 2, "__type": "object", "__class": "__symbol__A", "__symbol__@a": 1 }], "__float__1.50": null });
+    /// let mut b = json!({ "__float__1.5": null, "x": [{ "__class": "__symbol__A", "__type": "object", "__symbol__@a": 1, "__symbol__@b": 2 }] });
+    ///
+    /// a.canonicalize();
+    /// b.canonicalize();
+    ///
+    /// assert_eq!(a.to_string(), b.to_string());
+    /// assert_eq!(
+    ///     a.to_string(),
+    ///     r#"{"__float__1.5":null,"x":[{"__class":"__symbol__A","__type":"object","__symbol__@a":1,"__symbol__@b":2}]}"#
+    /// );
+    /// ```
+    fn canonicalize(&mut self);
+
+    /// Returns all strings in the tree, except symbols, along with their paths, which are displayed as JSON pointers.
+    ///
+    /// External editors can change strings in the table, and pass it back to `apply_string_table()`.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::json;
+    ///
+    /// let mut value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "sword" });
+    /// let mut table = value.string_table();
+    ///
+    /// assert_eq!(table[0].0.to_string(), "/__symbol__@name");
+    ///
+    /// table[0].1 = "axe".to_string();
+    /// value.apply_string_table(table).unwrap();
+    ///
+    /// assert_eq!(value["__symbol__@name"], json!("axe"));
+    /// ```
+    fn string_table(&self) -> Vec<(Path, String)>;
+
+    /// Replaces strings at paths of the table with its strings. Returns the number of changed strings.
+    ///
+    /// Returns an Err, leaving the Value unchanged, when any of the paths doesn't point to a string, or points to a symbol.
+    fn apply_string_table<I: IntoIterator<Item = (Path, String)>>(
+        &mut self,
+        table: I,
+    ) -> Result<usize, ValueError>;
+
+    /// Returns the exact number of bytes, that `dump()` produces for the Value, without producing them.
+    ///
+    /// Repeated symbols are counted as symbol links. See `Dumper::measure()` to count with an instance variable prefix.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::{dump, ValueExt};
+    /// use serde_json::json;
+    ///
+    /// let value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" });
+    ///
+    /// assert_eq!(value.estimate_marshal_size(), dump(value, None).len());
+    /// ```
+    fn estimate_marshal_size(&self) -> usize;
+
+    /// Returns a compact overview of the Value for debugging. See `inspect::summarize()`.
+    fn summarize(&self, max_depth: usize, max_items: usize) -> String;
+
+    /// Converts the Value to pretty-printed RON. See `ron` module for the mapping.
+    fn to_ron(&self) -> String;
+
+    /// Converts RON, written by `to_ron()`, to a Value.
+    fn from_ron(ron: &str) -> Result<Value, ValueError>;
+
+    /// Converts the Value to Python pickle of protocol 3. See `pickle` module for the mapping.
+    #[cfg(feature = "bigint")]
+    fn to_pickle(&self) -> Vec<u8>;
+
+    /// Converts Python pickle, written by `to_pickle()` or by Python, to a Value.
+    #[cfg(feature = "bigint")]
+    fn from_pickle(pickle: &[u8]) -> Result<Value, ValueError>;
+
+    /// Converts the Value to PHP's `serialize()` format. See `php` module for the mapping.
+    fn to_php(&self) -> Vec<u8>;
+
+    /// Converts data in PHP's `serialize()` format, written by `to_php()` or by PHP, to a Value.
+    fn from_php(php: &[u8]) -> Result<Value, ValueError>;
+
+    /// Converts the Value to BSON, with symbols as BSON symbols. See `bson` module for the mapping, and `bson::to_bson()` for options.
+    ///
+    /// Requires `bson` feature.
+    #[cfg(feature = "bson")]
+    fn to_bson(&self) -> Bson;
+
+    /// Converts BSON, written by `to_bson()` or by MongoDB, to a Value.
+    ///
+    /// Requires `bson` feature.
+    #[cfg(feature = "bson")]
+    fn from_bson(bson: Bson) -> Value;
+
+    /// Converts the Value to YAML with tags of Ruby's Psych library. See `yaml` module for the mapping.
+    ///
+    /// Requires `yaml` feature.
+    #[cfg(feature = "yaml")]
+    fn to_yaml(&self) -> Result<String, ValueError>;
+
+    /// Converts YAML, written by `to_yaml()` or by Psych, to a Value.
+    ///
+    /// Requires `yaml` feature.
+    /// # Example
+    /// ```rust
+    /// use marshal_rs::ValueExt;
+    /// use serde_json::{json, Value};
+    ///
+    /// let value = Value::from_yaml("!ruby/object:Item\nname: Sword\ntags:\n- :sharp\n").unwrap();
+    ///
+    /// assert_eq!(
+    ///     value,
+    ///     json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@tags": ["__symbol__sharp"] })
+    /// );
+    /// ```
+    #[cfg(feature = "yaml")]
+    fn from_yaml(yaml: &str) -> Result<Value, ValueError>;
+}
+
+impl ValueExt for Value {
+    fn get_or_insert_with<F: FnOnce() -> Value>(
+        &mut self,
+        name: &str,
+        default: F,
+    ) -> Result<&mut Value, ValueError> {
+        if self["__type"] != "object" {
+            return Err(ValueError {
+                message: format!("Can't get instance variable {name} of non-object value."),
+            });
+        }
+
+        Ok(self
+            .as_object_mut()
+            .unwrap()
+            .entry(to_symbol(name))
+            .or_insert_with(default))
+    }
+
+    fn get_or_insert_with_key<F: FnOnce() -> Value>(
+        &mut self,
+        key: &Value,
+        default: F,
+    ) -> Result<&mut Value, ValueError> {
+        let object = match self.as_object_mut() {
+            Some(object) if !object.contains_key("__type") => object,
+            _ => {
+                return Err(ValueError {
+                    message: "Can't get key of non-hash value.".to_string(),
+                })
+            }
+        };
+
+        Ok(object.entry(hash_key(key)).or_insert_with(default))
+    }
+
+    fn class_name(&self) -> Option<&str> {
+        if is_hash(self) {
+            return None;
+        }
+
+        let class: &str = self.get("__class")?.as_str()?;
+        Some(class.strip_prefix("__symbol__").unwrap_or(class))
+    }
+
+    fn is_instance_of(&self, class: &str) -> bool {
+        self.class_name() == Some(class)
+    }
+
+    fn class_matches<F: FnOnce(&str) -> bool>(&self, predicate: F) -> bool {
+        self.class_name().map_or(false, predicate)
+    }
+
+    fn is_subclass_value(&self, class: &str) -> bool {
+        self.get("__wrapped").is_some() && self.is_instance_of(class)
+    }
+
+    fn with_class(mut self, class: &str) -> Value {
+        if is_classed(&self) {
+            self["__class"] = to_symbol(class).into();
+            self
+        } else if is_wrappable(&self) {
+            json!({ "__class": to_symbol(class), "__type": "object", "__wrapped": self })
+        } else {
+            self
+        }
+    }
+
+    fn with_extension(mut self, module: &str) -> Value {
+        if !is_classed(&self) {
+            return self;
+        }
+
+        match self[EXTENDS_SYMBOL].as_array_mut() {
+            Some(modules) => modules.push(to_symbol(module).into()),
+            None => self[EXTENDS_SYMBOL] = json!([to_symbol(module)]),
+        }
+
+        self
+    }
+
+    fn with_string_encoding(self, encoding: &str) -> Value {
+        match self {
+            Value::String(string) if !string.starts_with("__symbol__") => {
+                json!({ "__type": "string", "value": string, "encoding": encoding })
+            }
+            Value::Object(_) if self["__type"] == "string" => {
+                let mut marked: Value = self;
+                marked["encoding"] = encoding.into();
+                marked
+            }
+            _ => self,
+        }
+    }
+
+    fn with_user_class(mut self, user_class: bool) -> Value {
+        if !user_class {
+            return match self.get_mut("__wrapped") {
+                Some(wrapped) => wrapped.take(),
+                None => self,
+            };
+        }
+
+        if is_wrappable(&self) {
+            let class: String = ruby_class(&self);
+            self.with_class(&class)
+        } else {
+            self
+        }
+    }
+
+    fn get_key(&self, key: &Value) -> Option<&Value> {
+        if !is_hash(self) {
+            return None;
+        }
+
+        self.as_object().unwrap().get(&hash_key(key))
+    }
+
+    fn get_key_mut(&mut self, key: &Value) -> Option<&mut Value> {
+        if !is_hash(self) {
+            return None;
+        }
+
+        self.as_object_mut().unwrap().get_mut(&hash_key(key))
+    }
+
+    fn kind(&self) -> ValueKind {
+        match self {
+            Value::Null => ValueKind::Nil,
+            Value::Bool(_) => ValueKind::Boolean,
+            Value::Number(number) => {
+                if number.is_f64() {
+                    ValueKind::Float
+                } else {
+                    ValueKind::Integer
+                }
+            }
+            Value::String(string) => {
+                if string.starts_with("__symbol__") {
+                    ValueKind::Symbol
+                } else {
+                    ValueKind::String
+                }
+            }
+            Value::Array(_) => ValueKind::Array,
+            Value::Object(_) => match self["__type"].as_str() {
+                None => ValueKind::Hash,
+                Some("bytes" | "string") => ValueKind::String,
+                Some("bigint") => ValueKind::Integer,
+                Some("float") => ValueKind::Float,
+                Some("regexp") => ValueKind::Regexp,
+                Some("struct") => ValueKind::Struct,
+                Some("class") => ValueKind::Class,
+                Some("module") => ValueKind::Module,
+                Some(_) => ValueKind::Object,
+            },
+        }
+    }
+
+    fn depth(&self) -> usize {
+        let mut depth: usize = 0;
+        for_each_child(self, |child| depth = depth.max(child.depth()));
+        depth + 1
+    }
+
+    fn count_nodes(&self) -> usize {
+        let mut count: usize = 1;
+        for_each_child(self, |child| count += child.count_nodes());
+        count
+    }
+
+    fn count_by_kind(&self) -> BTreeMap<ValueKind, usize> {
+        fn count(value: &Value, counts: &mut BTreeMap<ValueKind, usize>) {
+            *counts.entry(value.kind()).or_default() += 1;
+            for_each_child(value, |child| count(child, counts));
+        }
+
+        let mut counts: BTreeMap<ValueKind, usize> = BTreeMap::new();
+        count(self, &mut counts);
+        counts
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Value::Array(array) => array.len(),
+            Value::String(string) => string
+                .strip_prefix("__symbol__")
+                .unwrap_or(string)
+                .chars()
+                .count(),
+            Value::Object(object) => match self["__type"].as_str() {
+                Some("string") => self["value"]
+                    .as_str()
+                    .map_or(0, |string| string.chars().count()),
+                Some("bytes") => self["data"].as_array().map_or(0, Vec::len),
+                Some("object") if object.contains_key("__wrapped") => self["__wrapped"].len(),
+                _ => entries(self).map_or(0, |entries| entries.len()),
+            },
+            _ => 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        entries(self).map_or_else(Vec::new, |entries| {
+            entries.into_iter().map(|(key, _)| key.as_str()).collect()
+        })
+    }
+
+    fn values(&self) -> Vec<&Value> {
+        match self {
+            Value::Array(array) => array.iter().collect(),
+            _ => entries(self).map_or_else(Vec::new, |entries| {
+                entries.into_iter().map(|(_, value)| value).collect()
+            }),
+        }
+    }
+
+    fn at<K: AtKey>(&self, key: K) -> &Value {
+        key.lookup(self).unwrap_or(&Value::Null)
+    }
+
+    fn at_mut<K: AtKey>(&mut self, key: K) -> Option<&mut Value> {
+        key.lookup_mut(self)
+    }
+
+    fn extend_elements<I: IntoIterator<Item = Value>>(
+        &mut self,
+        elements: I,
+    ) -> Result<(), ValueError> {
+        match self.as_array_mut() {
+            Some(array) => {
+                array.extend(elements);
+                Ok(())
+            }
+            None => Err(ValueError {
+                message: "Only arrays can be extended with elements.".to_string(),
+            }),
+        }
+    }
+
+    fn extend_entries<I: IntoIterator<Item = (Value, Value)>>(
+        &mut self,
+        entries: I,
+    ) -> Result<(), ValueError> {
+        let object: &mut Map<String, Value> = match self.as_object_mut() {
+            Some(object) => object,
+            None => {
+                return Err(ValueError {
+                    message: "Only Hashes and objects can be extended with entries.".to_string(),
+                })
+            }
+        };
+
+        for (key, value) in entries {
+            object.insert(hash_key(&key), value);
+        }
+
+        Ok(())
+    }
+
+    fn retain_recursive<F: FnMut(&Path, &Value) -> bool>(&mut self, mut predicate: F) {
+        retain_children(self, &mut Path::new(), &mut predicate);
+    }
+
+    fn object_into_hash(self) -> Result<Value, ValueError> {
+        let object = match self {
+            Value::Object(object)
+                if object
+                    .get("__type")
+                    .map_or(false, |type_| type_ == "object")
+                    && ["__data", "__wrapped", "__userDefined", "__userMarshal"]
+                        .iter()
+                        .all(|key| !object.contains_key(*key)) =>
+            {
+                object
+            }
+            _ => {
+                return Err(ValueError {
+                    message: "Only plain objects can be converted to Hash.".to_string(),
+                })
+            }
+        };
+
+        Ok(object
+            .into_iter()
+            .filter(|(key, _)| !METADATA_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| {
+                let name: &str = key.strip_prefix("__symbol__").unwrap_or(&key);
+                (to_symbol(name.strip_prefix('@').unwrap_or(name)), value)
+            })
+            .collect())
+    }
+
+    fn hash_into_object(self, class: &str) -> Result<Value, ValueError> {
+        if !is_hash(&self) {
+            return Err(ValueError {
+                message: "Only Hashes can be converted to object.".to_string(),
+            });
+        }
+
+        let mut object = json!({ "__class": to_symbol(class), "__type": "object" });
+
+        for (key, value) in self.as_object().unwrap() {
+            if key == DEFAULT_SYMBOL {
+                return Err(ValueError {
+                    message: "Hash with default value can't be converted to object.".to_string(),
+                });
+            }
+
+            let name: &str = if let Some(name) = key.strip_prefix("__symbol__") {
+                name
+            } else if HASH_KEY_PREFIXES
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+            {
+                return Err(ValueError {
+                    message: format!("Hash key {key} is not a string or a symbol."),
+                });
+            } else {
+                key
+            };
+
+            object[to_symbol(&("@".to_string() + name))] = value.clone();
+        }
+
+        Ok(object)
+    }
+
+    fn flatten(&self) -> Map<String, Value> {
+        let mut flat: Map<String, Value> = Map::new();
+        flatten_into(self, &mut Path::new(), &mut flat);
+        flat
+    }
+
+    fn unflatten(flat: &Map<String, Value>) -> Result<Value, ValueError> {
+        let mut root: Value = Value::Null;
+
+        for (dotted, leaf) in flat {
+            let path: Path = Path::from_dotted(dotted)?;
+            let mut current: &mut Value = &mut root;
+
+            for segment in path.segments() {
+                let conflict = || ValueError {
+                    message: format!("Path {dotted} conflicts with other paths."),
+                };
+
+                current = match segment {
+                    PathSegment::Key(key) => {
+                        if current.is_null() {
+                            *current = json!({});
+                        }
+
+                        current
+                            .as_object_mut()
+                            .ok_or_else(conflict)?
+                            .entry(key.to_owned())
+                            .or_insert(Value::Null)
+                    }
+                    PathSegment::Index(index) => {
+                        if current.is_null() {
+                            *current = json!([]);
+                        }
+
+                        let array: &mut Vec<Value> = current.as_array_mut().ok_or_else(conflict)?;
+
+                        if array.len() <= *index {
+                            array.resize(index + 1, Value::Null);
+                        }
+
+                        &mut array[*index]
+                    }
+                };
+            }
+
+            if !current.is_null() {
+                return Err(ValueError {
+                    message: format!("Path {dotted} conflicts with other paths."),
+                });
+            }
+
+            *current = leaf.clone();
+        }
+
+        Ok(root)
+    }
+
+    fn find_duplicates(&self, min_size: usize) -> Vec<DuplicateGroup> {
+        let mut hashes: Vec<(u64, Path, &Value)> = Vec::new();
+        hash_values(self, &mut Path::new(), &mut hashes);
+
+        let mut buckets: HashMap<u64, Vec<(Path, &Value)>> = HashMap::new();
+
+        for (hash, path, value) in hashes {
+            buckets.entry(hash).or_default().push((path, value));
+        }
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+
+        for (_, mut bucket) in buckets {
+            // Split the bucket in case of hash collisions
+            while bucket.len() > 1 {
+                let value: &Value = bucket[0].1;
+                let (equal, rest): (Vec<_>, Vec<_>) =
+                    bucket.into_iter().partition(|(_, other)| *other == value);
+                bucket = rest;
+
+                if equal.len() < 2 {
+                    continue;
+                }
+
+                let size: usize = estimate(value, None).0;
+
+                if size >= min_size {
+                    groups.push(DuplicateGroup {
+                        savings: (equal.len() - 1) * size.saturating_sub(2),
+                        paths: equal.into_iter().map(|(path, _)| path).collect(),
+                        size,
+                    });
+                }
+            }
+        }
+
+        groups.sort_by_key(|group| (Reverse(group.savings), group.paths[0].to_string()));
+
+        let mut reported: Vec<Path> = Vec::new();
+
+        groups.retain(|group| {
+            let nested: bool = group.paths.iter().all(|path| {
+                reported
+                    .iter()
+                    .any(|reported| path.segments().starts_with(reported.segments()))
+            });
+
+            if !nested {
+                reported.extend(group.paths.iter().cloned());
+            }
+
+            !nested
+        });
+
+        for group in &mut groups {
+            group.paths.sort_by_key(|path| path.to_string());
+        }
+
+        groups
+    }
+
+    fn coerce_keys_to_strings(&mut self) {
+        coerce_keys(self);
+    }
+
+    fn walk_strings_mut<F: FnMut(&Path, &mut String)>(&mut self, include_symbols: bool, mut f: F) {
+        visit_mut(self, &mut Path::new(), &mut |path, value| {
+            if let Value::String(string) = value {
+                if let Some(symbol) = string.strip_prefix("__symbol__") {
+                    if include_symbols {
+                        let mut symbol: String = symbol.to_string();
+                        f(path, &mut symbol);
+                        *string = "__symbol__".to_string() + &symbol;
+                    }
+                } else {
+                    f(path, string);
+                }
+            }
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_walk<F: Fn(&Path, &Value) + Sync>(&self, f: F) {
+        par_visit(self, &Path::new(), &f);
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_map_strings<F: Fn(&Path, &mut String) + Sync>(&mut self, include_symbols: bool, f: F) {
+        par_visit_mut(self, &Path::new(), &|path, value| {
+            if let Value::String(string) = value {
+                if let Some(symbol) = string.strip_prefix("__symbol__") {
+                    if include_symbols {
+                        let mut symbol: String = symbol.to_string();
+                        f(path, &mut symbol);
+                        *string = "__symbol__".to_string() + &symbol;
+                    }
+                } else {
+                    f(path, string);
+                }
+            }
+        });
+    }
+
+    #[cfg(feature = "regex")]
+    fn replace_matching<R: Replacer>(
+        &mut self,
+        regex: &Regex,
+        mut replacement: R,
+        options: &ReplaceOptions,
+    ) -> ReplaceReport {
+        let mut report: ReplaceReport = ReplaceReport::default();
+
+        replace_in(
+            self,
+            &mut Path::new(),
+            None,
+            regex,
+            &mut replacement,
+            options,
+            &mut report,
+        );
+
+        report
+    }
+
+    #[cfg(feature = "encodings")]
+    fn transcode_strings(
+        &mut self,
+        from: &'static Encoding,
+        to: &'static Encoding,
+    ) -> TranscodeReport {
+        let mut report: TranscodeReport = TranscodeReport::default();
+
+        visit_mut(self, &mut Path::new(), &mut |path, value| {
+            let bytes: Vec<u8> = if let Some(bytes) = bytes_of(value) {
+                bytes
+            } else {
+                return;
+            };
+
+            let decoded = if let Some(decoded) =
+                from.decode_without_bom_handling_and_without_replacement(&bytes)
+            {
+                decoded
+            } else {
+                report.failed.push(path.clone());
+                return;
+            };
+
+            if to == UTF_8 {
+                *value = decoded.into_owned().into();
+            } else {
+                let (encoded, _, unmappable) = to.encode(&decoded);
+
+                // encoding_rs can't encode to UTF-16, and falls back to UTF-8
+                if unmappable || to.output_encoding() != to {
+                    report.failed.push(path.clone());
+                    return;
+                }
+
+                value["data"] = encoded.into_owned().into();
+
+                if value.get("encoding").is_some() {
+                    value["encoding"] = to.name().into();
+                }
+            }
+
+            report.converted += 1;
+        });
+
+        report
+    }
+
+    #[cfg(feature = "encodings")]
+    fn decode_bytes(&mut self, encoding: &'static Encoding) -> TranscodeReport {
+        self.transcode_strings(encoding, UTF_8)
+    }
+
+    fn prune_nulls(&mut self) {
+        self.retain_recursive(|_, value| !value.is_null());
+    }
+
+    fn prune_empty(&mut self) {
+        self.retain_recursive(|_, value| match value {
+            Value::Array(array) => !array.is_empty(),
+            Value::Object(object) => object.contains_key("__type") || !object.is_empty(),
+            Value::String(string) => !string.is_empty(),
+            _ => true,
+        });
+    }
+
+    fn sort_keys_recursive(&mut self) {
+        sort_keys(self);
+    }
+
+    fn canonicalize(&mut self) {
+        canonicalize_keys(self);
+        sort_keys(self);
+    }
+
+    fn string_table(&self) -> Vec<(Path, String)> {
+        let mut table: Vec<(Path, String)> = Vec::new();
+
+        visit_strings(self, &mut Path::new(), None, &mut |path, _, string| {
+            table.push((path.clone(), string.to_string()))
+        });
+
+        table
+    }
+
+    fn apply_string_table<I: IntoIterator<Item = (Path, String)>>(
+        &mut self,
+        table: I,
+    ) -> Result<usize, ValueError> {
+        let table: Vec<(String, String)> = table
+            .into_iter()
+            .map(|(path, string)| (path.to_string(), string))
+            .collect();
+
+        // All paths are validated first, so the Value isn't left partially changed
+        for (pointer, _) in &table {
+            match self.pointer(pointer) {
+                Some(Value::String(string)) if !string.starts_with("__symbol__") => {}
+                Some(_) => {
+                    return Err(ValueError {
+                        message: format!("Value at {pointer} is not a string"),
+                    })
+                }
+                None => {
+                    return Err(ValueError {
+                        message: format!("No value at {pointer}"),
+                    })
+                }
+            }
+        }
+
+        let mut changed: usize = 0;
+
+        for (pointer, new) in table {
+            if let Some(Value::String(string)) = self.pointer_mut(&pointer) {
+                if *string != new {
+                    *string = new;
+                    changed += 1;
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    fn estimate_marshal_size(&self) -> usize {
+        Dumper::new().measure(self.clone(), None)
+    }
+
+    fn summarize(&self, max_depth: usize, max_items: usize) -> String {
+        crate::inspect::summarize(self, max_depth, max_items)
+    }
+
+    fn to_ron(&self) -> String {
+        crate::ron::to_ron(self)
+    }
+
+    fn from_ron(ron: &str) -> Result<Value, ValueError> {
+        crate::ron::from_ron(ron)
+    }
+
+    #[cfg(feature = "bigint")]
+    fn to_pickle(&self) -> Vec<u8> {
+        crate::pickle::to_pickle(self)
+    }
+
+    #[cfg(feature = "bigint")]
+    fn from_pickle(pickle: &[u8]) -> Result<Value, ValueError> {
+        crate::pickle::from_pickle(pickle)
+    }
+
+    fn to_php(&self) -> Vec<u8> {
+        crate::php::to_php(self)
+    }
+
+    fn from_php(php: &[u8]) -> Result<Value, ValueError> {
+        crate::php::from_php(php)
+    }
+
+    #[cfg(feature = "bson")]
+    fn to_bson(&self) -> Bson {
+        crate::bson::to_bson(self, crate::bson::BsonOptions::default())
+    }
+
+    #[cfg(feature = "bson")]
+    fn from_bson(bson: Bson) -> Value {
+        crate::bson::from_bson(bson)
+    }
+
+    #[cfg(feature = "yaml")]
+    fn to_yaml(&self) -> Result<String, ValueError> {
+        crate::yaml::to_yaml(self)
+    }
+
+    #[cfg(feature = "yaml")]
+    fn from_yaml(yaml: &str) -> Result<Value, ValueError> {
+        crate::yaml::from_yaml(yaml)
+    }
+}
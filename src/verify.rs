@@ -0,0 +1,470 @@
+//! Verification of byte-identical round-trips of Marshal data.
+//!
+//! `roundtrip_report()` loads the data, dumps it back, and compares the structures of both byte streams, explaining each place where they diverge.
+//! # Example
+//! ```rust
+//! use marshal_rs::verify::roundtrip_report;
+//!
+//! // [1.0, 1.0], where the second Float is a link to the first one
+//! let original: &[u8] = b"\x04\x08[\x07f\x061@\x06";
+//! let report = roundtrip_report(original).unwrap();
+//!
+//! assert!(!report.is_identical());
+//! assert_eq!(report.divergences[0].offset, 7);
+//! assert_eq!(report.divergences[0].reason, "object link expanded");
+//! ```
+
+use crate::{
+    dump, load,
+    load::LoadError,
+    raw::{Constants, Reader},
+};
+
+/// Place, where the dumped byte stream diverges from the original one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Position of the structure in the original byte stream.
+    pub offset: usize,
+    /// Position of the corresponding structure in the dumped byte stream.
+    pub dumped_offset: usize,
+    /// Type tag of the structure in the original byte stream. None for bytes after the end of the original document.
+    pub token: Option<Constants>,
+    /// Explanation of the divergence, like "float reformatted" or "ivar @name dropped".
+    pub reason: String,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} at {} (dumped at {}): {}",
+            self.token.map_or_else(
+                || "Trailing bytes".to_string(),
+                |token| format!("{token:?}")
+            ),
+            self.offset,
+            self.dumped_offset,
+            self.reason
+        )
+    }
+}
+
+/// Result of `roundtrip_report()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Bytes, that the loaded value was dumped to.
+    pub dumped: Vec<u8>,
+    /// Divergences in order of their positions in the original byte stream. Empty, if the round-trip is byte-identical.
+    pub divergences: Vec<Divergence>,
+}
+
+impl Report {
+    /// Returns whether the dumped bytes are identical to the original ones.
+    pub fn is_identical(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Structure of a Marshal byte stream, with symbol links resolved to their symbols.
+struct Node<'a> {
+    structure: Constants,
+    offset: usize,
+    /// Fixnum, index of a link, sign of a Bignum or flags of a Regexp.
+    number: i32,
+    /// Contents of strings, symbols, floats, Bignums, Regexps, classes and `_dump` data.
+    bytes: &'a [u8],
+    children: Vec<Node<'a>>,
+}
+
+struct Parser<'a> {
+    reader: Reader<'a>,
+    symbols: Vec<&'a [u8]>,
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the document, returning its root structure and the position of its end.
+    fn parse(buffer: &'a [u8]) -> Result<(Node<'a>, usize), LoadError> {
+        let mut parser: Parser = Parser {
+            reader: Reader::new(buffer),
+            symbols: Vec::new(),
+        };
+
+        parser.reader.read_version()?;
+        let node: Node = parser.node()?;
+        Ok((node, parser.reader.position()))
+    }
+
+    fn count(&mut self) -> Result<usize, LoadError> {
+        self.reader.read_length()
+    }
+
+    fn nodes(&mut self, amount: usize, node: &mut Node<'a>) -> Result<(), LoadError> {
+        for _ in 0..amount {
+            node.children.push(self.node()?);
+        }
+
+        Ok(())
+    }
+
+    fn node(&mut self) -> Result<Node<'a>, LoadError> {
+        let offset: usize = self.reader.position();
+        let structure: Constants = self.reader.read_type()?;
+
+        let mut node: Node = Node {
+            structure,
+            offset,
+            number: 0,
+            bytes: &[],
+            children: Vec::new(),
+        };
+
+        match structure {
+            Constants::True | Constants::False | Constants::Nil => {}
+            Constants::Fixnum | Constants::Link => node.number = self.reader.read_int()?,
+            Constants::Symbol => {
+                node.bytes = self.reader.read_chunk()?;
+                self.symbols.push(node.bytes);
+            }
+            Constants::Symlink => {
+                node.number = self.reader.read_int()?;
+                node.bytes = usize::try_from(node.number)
+                    .ok()
+                    .and_then(|index| self.symbols.get(index))
+                    .ok_or_else(|| LoadError {
+                        message: format!("Invalid symbol link at position {offset}."),
+                        ..Default::default()
+                    })?;
+            }
+            Constants::Class
+            | Constants::Module
+            | Constants::ModuleOld
+            | Constants::Float
+            | Constants::String => node.bytes = self.reader.read_chunk()?,
+            Constants::Regexp => {
+                node.bytes = self.reader.read_chunk()?;
+                node.number = self.reader.read_byte()? as i32;
+            }
+            Constants::Bignum => {
+                node.number = self.reader.read_byte()? as i32;
+                let length: usize = self.count()?;
+                node.bytes = self.reader.read_bytes(length * 2)?;
+            }
+            Constants::Array => {
+                let length: usize = self.count()?;
+                self.nodes(length, &mut node)?;
+            }
+            Constants::Hash => {
+                let length: usize = self.count()?;
+                self.nodes(length * 2, &mut node)?;
+            }
+            Constants::HashDefault => {
+                let length: usize = self.count()?;
+                self.nodes(length * 2 + 1, &mut node)?;
+            }
+            Constants::InstanceVar | Constants::Object | Constants::Struct => {
+                self.nodes(1, &mut node)?;
+                let length: usize = self.count()?;
+                self.nodes(length * 2, &mut node)?;
+            }
+            Constants::Extended
+            | Constants::UserClass
+            | Constants::UserMarshal
+            | Constants::Data => self.nodes(2, &mut node)?,
+            Constants::UserDefined => {
+                self.nodes(1, &mut node)?;
+                node.bytes = self.reader.read_chunk()?;
+            }
+            _ => {
+                return Err(LoadError {
+                    message: format!("Unexpected {structure:?} at position {offset}."),
+                    ..Default::default()
+                })
+            }
+        }
+
+        Ok(node)
+    }
+}
+
+/// Returns the name of the symbol, that may be wrapped with instance variables.
+fn symbol_name(node: &Node) -> String {
+    match node.structure {
+        Constants::Symbol | Constants::Symlink => String::from_utf8_lossy(node.bytes).into_owned(),
+        Constants::InstanceVar => symbol_name(&node.children[0]),
+        _ => String::new(),
+    }
+}
+
+fn is_symbol(node: &Node) -> bool {
+    matches!(node.structure, Constants::Symbol | Constants::Symlink)
+}
+
+struct Comparer {
+    divergences: Vec<Divergence>,
+}
+
+impl Comparer {
+    fn diverge(&mut self, original: &Node, dumped: &Node, reason: impl Into<String>) {
+        self.divergences.push(Divergence {
+            offset: original.offset,
+            dumped_offset: dumped.offset,
+            token: Some(original.structure),
+            reason: reason.into(),
+        });
+    }
+
+    fn compare(&mut self, original: &Node, dumped: &Node) {
+        if is_symbol(original) && is_symbol(dumped) {
+            if original.bytes != dumped.bytes {
+                self.diverge(original, dumped, "symbol changed");
+            } else if original.structure != dumped.structure {
+                self.diverge(original, dumped, "symbol table order changed");
+            } else if original.number != dumped.number {
+                self.diverge(original, dumped, "symbol link index changed");
+            }
+
+            return;
+        }
+
+        if original.structure != dumped.structure {
+            self.compare_different(original, dumped);
+            return;
+        }
+
+        match original.structure {
+            Constants::Fixnum if original.number != dumped.number => {
+                self.diverge(original, dumped, "integer changed")
+            }
+            Constants::Link if original.number != dumped.number => {
+                self.diverge(original, dumped, "object link index changed")
+            }
+            Constants::Float if original.bytes != dumped.bytes => {
+                let parse = |bytes: &[u8]| std::str::from_utf8(bytes).ok()?.parse::<f64>().ok();
+
+                match (parse(original.bytes), parse(dumped.bytes)) {
+                    (Some(original_float), Some(dumped_float))
+                        if original_float == dumped_float =>
+                    {
+                        self.diverge(original, dumped, "float reformatted")
+                    }
+                    _ => self.diverge(original, dumped, "float changed"),
+                }
+            }
+            Constants::String if original.bytes != dumped.bytes => {
+                self.diverge(original, dumped, "string changed")
+            }
+            Constants::Regexp
+                if original.bytes != dumped.bytes || original.number != dumped.number =>
+            {
+                self.diverge(original, dumped, "regexp changed")
+            }
+            Constants::Bignum
+                if original.number != dumped.number || original.bytes != dumped.bytes =>
+            {
+                let magnitude = |bytes: &[u8]| {
+                    let length: usize = bytes
+                        .iter()
+                        .rposition(|&byte| byte != 0)
+                        .map_or(0, |index| index + 1);
+                    bytes[..length].to_vec()
+                };
+
+                if original.number == dumped.number
+                    && magnitude(original.bytes) == magnitude(dumped.bytes)
+                {
+                    self.diverge(original, dumped, "bignum padding changed")
+                } else {
+                    self.diverge(original, dumped, "integer changed")
+                }
+            }
+            Constants::Class | Constants::Module | Constants::ModuleOld
+                if original.bytes != dumped.bytes =>
+            {
+                self.diverge(original, dumped, "class name changed")
+            }
+            Constants::Array => self.compare_sequences(original, dumped, "array length changed"),
+            Constants::Hash | Constants::HashDefault => {
+                self.compare_sequences(original, dumped, "hash size changed")
+            }
+            Constants::InstanceVar | Constants::Object | Constants::Struct => {
+                self.compare(&original.children[0], &dumped.children[0]);
+                self.compare_instance_vars(original, dumped);
+            }
+            Constants::UserDefined => {
+                self.compare(&original.children[0], &dumped.children[0]);
+
+                if original.bytes != dumped.bytes {
+                    self.diverge(original, dumped, "_dump data changed");
+                }
+            }
+            Constants::Extended
+            | Constants::UserClass
+            | Constants::UserMarshal
+            | Constants::Data => {
+                self.compare(&original.children[0], &dumped.children[0]);
+                self.compare(&original.children[1], &dumped.children[1]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Compares structures with different type tags.
+    fn compare_different(&mut self, original: &Node, dumped: &Node) {
+        match (original.structure, dumped.structure) {
+            (Constants::Link, _) => self.diverge(original, dumped, "object link expanded"),
+            (Constants::Fixnum, Constants::Bignum) | (Constants::Bignum, Constants::Fixnum) => {
+                self.diverge(original, dumped, "integer size changed")
+            }
+            (Constants::InstanceVar, _) => {
+                self.compare(&original.children[0], dumped);
+
+                for pair in original.children[1..].chunks(2) {
+                    let name: String = symbol_name(&pair[0]);
+                    self.diverge(original, dumped, format!("ivar {name} dropped"));
+                }
+            }
+            (_, Constants::InstanceVar) => {
+                self.compare(original, &dumped.children[0]);
+
+                for pair in dumped.children[1..].chunks(2) {
+                    let name: String = symbol_name(&pair[0]);
+                    self.diverge(original, dumped, format!("ivar {name} added"));
+                }
+            }
+            (Constants::Extended, _) => {
+                self.diverge(original, dumped, "extension dropped");
+                self.compare(&original.children[1], dumped);
+            }
+            (Constants::UserClass, _) => {
+                self.diverge(original, dumped, "user class dropped");
+                self.compare(&original.children[1], dumped);
+            }
+            (Constants::Hash, Constants::HashDefault) => {
+                self.diverge(original, dumped, "hash default added")
+            }
+            (Constants::HashDefault, Constants::Hash) => {
+                self.diverge(original, dumped, "hash default dropped")
+            }
+            (original_structure, dumped_structure) => self.diverge(
+                original,
+                dumped,
+                format!("type changed from {original_structure:?} to {dumped_structure:?}"),
+            ),
+        }
+    }
+
+    /// Compares children in order, reporting `reason`, if their counts differ.
+    fn compare_sequences(&mut self, original: &Node, dumped: &Node, reason: &str) {
+        if original.children.len() != dumped.children.len() {
+            self.diverge(original, dumped, reason);
+        }
+
+        for (original_child, dumped_child) in original.children.iter().zip(&dumped.children) {
+            self.compare(original_child, dumped_child);
+        }
+    }
+
+    /// Compares instance variables of objects, structs and wrapped values by their names.
+    fn compare_instance_vars(&mut self, original: &Node, dumped: &Node) {
+        let original_vars: Vec<(String, &Node)> = original.children[1..]
+            .chunks(2)
+            .map(|pair| (symbol_name(&pair[0]), &pair[1]))
+            .collect();
+        let dumped_vars: Vec<(String, &Node)> = dumped.children[1..]
+            .chunks(2)
+            .map(|pair| (symbol_name(&pair[0]), &pair[1]))
+            .collect();
+
+        let common_names: Vec<&String> = original_vars
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| {
+                dumped_vars
+                    .iter()
+                    .any(|(dumped_name, _)| dumped_name == *name)
+            })
+            .collect();
+        let dumped_common_names: Vec<&String> = dumped_vars
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| common_names.contains(name))
+            .collect();
+
+        if common_names != dumped_common_names {
+            self.diverge(original, dumped, "ivars reordered");
+        }
+
+        for (index, (name, original_value)) in original_vars.iter().enumerate() {
+            match dumped_vars
+                .iter()
+                .position(|(dumped_name, _)| dumped_name == name)
+            {
+                Some(dumped_index) => {
+                    let original_pair: &[Node] = &original.children[1 + index * 2..];
+                    let dumped_pair: &[Node] = &dumped.children[1 + dumped_index * 2..];
+
+                    self.compare(&original_pair[0], &dumped_pair[0]);
+                    self.compare(original_value, dumped_vars[dumped_index].1);
+                }
+                None => self.diverge(original, dumped, format!("ivar {name} dropped")),
+            }
+        }
+
+        for (name, _) in &dumped_vars {
+            if !original_vars
+                .iter()
+                .any(|(original_name, _)| original_name == name)
+            {
+                self.diverge(original, dumped, format!("ivar {name} added"));
+            }
+        }
+    }
+}
+
+/// Loads the Marshal data with default options, dumps it back, and explains each divergence between the original and the dumped byte streams.
+///
+/// Divergences are found by comparing the structures of both byte streams, so a single changed structure is reported once, even if it shifts all following bytes.
+/// Returns an Err, if the data can't be loaded.
+/// # Example
+/// ```rust
+/// use marshal_rs::verify::roundtrip_report;
+///
+/// // Float 1.5, written in exponential notation
+/// let report = roundtrip_report(b"\x04\x08f\x0a1.5e0").unwrap();
+///
+/// assert_eq!(report.dumped, b"\x04\x08f\x081.5");
+/// assert_eq!(report.divergences[0].reason, "float reformatted");
+/// ```
+pub fn roundtrip_report(original: &[u8]) -> Result<Report, LoadError> {
+    let value = load(original, None, None)?;
+    let dumped: Vec<u8> = dump(value, None);
+
+    if dumped == original {
+        return Ok(Report {
+            dumped,
+            divergences: Vec::new(),
+        });
+    }
+
+    let mut comparer: Comparer = Comparer {
+        divergences: Vec::new(),
+    };
+
+    let (original_root, original_end) = Parser::parse(original)?;
+    let (dumped_root, dumped_end) = Parser::parse(&dumped)?;
+    comparer.compare(&original_root, &dumped_root);
+
+    if original_end < original.len() {
+        comparer.divergences.push(Divergence {
+            offset: original_end,
+            dumped_offset: dumped_end,
+            token: None,
+            reason: format!("{} trailing bytes dropped", original.len() - original_end),
+        });
+    }
+
+    Ok(Report {
+        dumped,
+        divergences: comparer.divergences,
+    })
+}
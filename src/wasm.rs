@@ -0,0 +1,82 @@
+//! Bindings for JavaScript, exposing `load()` and `dump()` through `wasm-bindgen`, so Marshal data can be edited entirely in a browser.
+//!
+//! Exported functions take Marshal data as `Uint8Array`, and produce either plain JavaScript objects (`load`, `dump`), or JSON strings (`loadJson`, `dumpJson`).
+//! Objects are converted with `JSON.parse()` and `JSON.stringify()`, so integers outside of JavaScript's safe range lose precision; use JSON strings to preserve them.
+//!
+//! To build a package, depend on `marshal-rs` with `wasm` feature from a `cdylib` crate, and build it with `wasm-pack`.
+//!
+//! Requires `wasm` feature. Not available with `sonic` feature enabled.
+
+use crate::{dump::Dumper, load::Loader, StringMode};
+use js_sys::JSON;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+fn string_mode(string_mode: Option<String>) -> Result<Option<StringMode>, JsError> {
+    Ok(match string_mode.as_deref() {
+        None => None,
+        Some("utf8") => Some(StringMode::UTF8),
+        Some("binary") => Some(StringMode::Binary),
+        Some(mode) => {
+            return Err(JsError::new(&format!(
+                "Unknown string mode {mode}, expected `utf8` or `binary`"
+            )))
+        }
+    })
+}
+
+fn load_value(
+    bytes: &[u8],
+    mode: Option<String>,
+    instance_var_prefix: Option<String>,
+) -> Result<Value, JsError> {
+    Loader::new()
+        .load(bytes, string_mode(mode)?, instance_var_prefix.as_deref())
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Loads Marshal data to a JavaScript object.
+///
+/// `string_mode` is either `"utf8"` or `"binary"`.
+#[wasm_bindgen]
+pub fn load(
+    bytes: &[u8],
+    string_mode: Option<String>,
+    instance_var_prefix: Option<String>,
+) -> Result<JsValue, JsError> {
+    let json: String = load_json(bytes, string_mode, instance_var_prefix)?;
+    JSON::parse(&json).map_err(|_| JsError::new("Failed to parse loaded JSON"))
+}
+
+/// Loads Marshal data to a JSON string.
+///
+/// `string_mode` is either `"utf8"` or `"binary"`.
+#[wasm_bindgen(js_name = loadJson)]
+pub fn load_json(
+    bytes: &[u8],
+    string_mode: Option<String>,
+    instance_var_prefix: Option<String>,
+) -> Result<String, JsError> {
+    let value: Value = load_value(bytes, string_mode, instance_var_prefix)?;
+    serde_json::to_string(&value).map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Dumps a JavaScript object, produced by `load`, to Marshal data.
+#[wasm_bindgen]
+pub fn dump(value: &JsValue, instance_var_prefix: Option<String>) -> Result<Vec<u8>, JsError> {
+    let json: String = JSON::stringify(value)
+        .ok()
+        .and_then(|json| json.as_string())
+        .ok_or_else(|| JsError::new("Value can't be converted to JSON"))?;
+
+    dump_json(&json, instance_var_prefix)
+}
+
+/// Dumps a JSON string, produced by `loadJson`, to Marshal data.
+#[wasm_bindgen(js_name = dumpJson)]
+pub fn dump_json(json: &str, instance_var_prefix: Option<String>) -> Result<Vec<u8>, JsError> {
+    let value: Value = serde_json::from_str(json).map_err(|err| JsError::new(&err.to_string()))?;
+    Dumper::new()
+        .try_dump(value, instance_var_prefix.as_deref())
+        .map_err(|err| JsError::new(&err.to_string()))
+}
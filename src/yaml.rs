@@ -0,0 +1,348 @@
+//! Conversion of loaded JSON values to YAML and back, using tags of Ruby's Psych library.
+//!
+//! | Ruby object                  | YAML                                   |
+//! | ---------------------------- | -------------------------------------- |
+//! | Symbol                       | `!ruby/symbol name`                    |
+//! | Binary string                | `!binary base64`                       |
+//! | Regexp                       | `!ruby/regexp /expression/flags`       |
+//! | Object with instance vars    | `!ruby/object:Class` mapping           |
+//! | Struct                       | `!ruby/struct:Class` mapping           |
+//! | Class, Module                | `!ruby/class 'Name'`, `!ruby/module 'Name'` |
+//! | Big Integer                  | `!marshal-rs/bigint 'digits'`          |
+//!
+//! Other objects, like ones with `_dump` data, are written as plain mappings with their JSON keys, so they survive the round trip, but aren't understood by Psych.
+//! Big Integers use a non-Psych tag, because YAML parser can't read integers larger than 64 bits.
+//!
+//! Symbol keys of Hashes are written as `:name`, like Psych writes them.
+//! When reading YAML, plain strings starting with `:` are read as symbols, like Psych does. Strings, written by `to_yaml()`, that start with `:` are tagged with `!ruby/string`,
+//! except for Hash keys, which are read back as symbols.
+//!
+//! Requires `yaml` feature. Not available with `sonic` feature enabled.
+
+use crate::value::{bytes_of, hash_key, is_hash, key_value, ValueError};
+use serde_json::{json, Map, Value};
+use serde_yaml::{
+    value::{Tag, TaggedValue},
+    Mapping, Value as Yaml,
+};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded: String = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group: u32 = chunk.iter().enumerate().fold(0, |group, (index, byte)| {
+            group | (*byte as u32) << (16 - index * 8)
+        });
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(group >> (18 - index * 6)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut group: u32 = 0;
+    let mut bits: u32 = 0;
+
+    for byte in encoded.bytes() {
+        let sextet: u8 = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ if byte.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+
+        group = group << 6 | sextet as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+fn tagged(tag: &str, value: Yaml) -> Yaml {
+    Yaml::Tagged(Box::new(TaggedValue {
+        tag: Tag::new(tag),
+        value,
+    }))
+}
+
+/// Converts a mapping of symbol keys to a mapping of plain keys, if all keys are symbols, that start with `prefix`.
+fn plain_keys(object: &Map<String, Value>, prefix: &str) -> Option<Mapping> {
+    let mut mapping: Mapping = Mapping::new();
+
+    for (key, entry) in object {
+        if matches!(key.as_str(), "__class" | "__type") {
+            continue;
+        }
+
+        let name: &str = key.strip_prefix("__symbol__")?.strip_prefix(prefix)?;
+        mapping.insert(Yaml::String(name.to_string()), to_yaml_value(entry));
+    }
+
+    Some(mapping)
+}
+
+fn generic_mapping(object: &Map<String, Value>) -> Yaml {
+    Yaml::Mapping(
+        object
+            .iter()
+            .map(|(key, entry)| {
+                // Tagged keys of nested mappings can't be emitted, so symbol keys are written like Psych writes them
+                let key: Yaml = match key.strip_prefix("__symbol__") {
+                    Some(symbol) => Yaml::String(format!(":{symbol}")),
+                    None => to_yaml_value(&key_value(key)),
+                };
+
+                (key, to_yaml_value(entry))
+            })
+            .collect(),
+    )
+}
+
+fn to_yaml_value(value: &Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(bool) => Yaml::Bool(*bool),
+        Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                integer.into()
+            } else if let Some(integer) = number.as_u64() {
+                integer.into()
+            } else {
+                number.as_f64().unwrap_or_default().into()
+            }
+        }
+        Value::String(string) => {
+            if let Some(symbol) = string.strip_prefix("__symbol__") {
+                tagged("ruby/symbol", symbol.into())
+            } else if string.starts_with(':') {
+                tagged("ruby/string", string.as_str().into())
+            } else {
+                string.as_str().into()
+            }
+        }
+        Value::Array(array) => Yaml::Sequence(array.iter().map(to_yaml_value).collect()),
+        Value::Object(object) => {
+            if is_hash(value) {
+                return generic_mapping(object);
+            }
+
+            let class: Option<&str> = object.get("__class").and_then(Value::as_str);
+            let converted: Option<Yaml> = match object.get("__type").and_then(Value::as_str) {
+                Some("bytes") => {
+                    bytes_of(value).map(|bytes| tagged("binary", base64_encode(&bytes).into()))
+                }
+                Some("bigint") => object
+                    .get("value")
+                    .and_then(Value::as_str)
+                    .map(|digits| tagged("marshal-rs/bigint", digits.into())),
+                Some("regexp") => match (
+                    object.get("expression").and_then(Value::as_str),
+                    object.get("flags").and_then(Value::as_str),
+                ) {
+                    (Some(expression), Some(flags)) => Some(tagged(
+                        "ruby/regexp",
+                        format!("/{expression}/{flags}").into(),
+                    )),
+                    _ => None,
+                },
+                Some("class") => class.map(|class| tagged("ruby/class", class.into())),
+                Some("module") if object.get("__old") != Some(&Value::Bool(true)) => {
+                    class.map(|class| tagged("ruby/module", class.into()))
+                }
+                Some("object") => class
+                    .and_then(|class| class.strip_prefix("__symbol__"))
+                    .zip(plain_keys(object, "@"))
+                    .map(|(class, mapping)| {
+                        tagged(&format!("ruby/object:{class}"), Yaml::Mapping(mapping))
+                    }),
+                Some("struct") if object.len() == 3 => class
+                    .and_then(|class| class.strip_prefix("__symbol__"))
+                    .zip(object.get("__members").and_then(Value::as_object))
+                    .and_then(|(class, members)| Some((class, plain_keys(members, "")?)))
+                    .map(|(class, mapping)| {
+                        tagged(&format!("ruby/struct:{class}"), Yaml::Mapping(mapping))
+                    }),
+                _ => None,
+            };
+
+            converted.unwrap_or_else(|| generic_mapping(object))
+        }
+    }
+}
+
+fn unexpected(tag: &Tag, value: &Yaml) -> ValueError {
+    ValueError {
+        message: format!("Unexpected YAML value {value:?} with tag {tag}"),
+    }
+}
+
+fn tagged_object(class: &str, type_: &str) -> Map<String, Value> {
+    let mut object: Map<String, Value> = Map::new();
+    object.insert(
+        "__class".to_string(),
+        Value::String(format!("__symbol__{class}")),
+    );
+    object.insert("__type".to_string(), type_.into());
+    object
+}
+
+/// Converts a mapping with plain keys to entries of an object, prefixing keys with `__symbol__` and `prefix`.
+fn insert_plain_keys(
+    object: &mut Map<String, Value>,
+    mapping: &Mapping,
+    prefix: &str,
+) -> Result<(), ValueError> {
+    for (key, entry) in mapping {
+        let name: &str = key.as_str().ok_or_else(|| ValueError {
+            message: format!("Expected a string key in YAML, found {key:?}"),
+        })?;
+
+        object.insert(format!("__symbol__{prefix}{name}"), from_yaml_value(entry)?);
+    }
+
+    Ok(())
+}
+
+fn from_tagged(tagged: &TaggedValue) -> Result<Value, ValueError> {
+    let TaggedValue { tag, value } = tagged;
+    let tag_name: String = tag.to_string();
+    let tag_name: &str = tag_name.trim_start_matches('!');
+
+    if let Some(class) = tag_name.strip_prefix("ruby/object:") {
+        let mapping: &Mapping = value.as_mapping().ok_or_else(|| unexpected(tag, value))?;
+        let mut object: Map<String, Value> = tagged_object(class, "object");
+        insert_plain_keys(&mut object, mapping, "@")?;
+        return Ok(Value::Object(object));
+    }
+
+    if let Some(class) = tag_name.strip_prefix("ruby/struct:") {
+        let mapping: &Mapping = value.as_mapping().ok_or_else(|| unexpected(tag, value))?;
+        let mut object: Map<String, Value> = tagged_object(class, "struct");
+        let mut members: Map<String, Value> = Map::new();
+        insert_plain_keys(&mut members, mapping, "")?;
+        object.insert("__members".to_string(), Value::Object(members));
+        return Ok(Value::Object(object));
+    }
+
+    let string: &str = match (tag_name, value) {
+        ("ruby/hash" | "ruby/array", _) => return from_yaml_value(value),
+        (_, Yaml::String(string)) => string,
+        _ => return Err(unexpected(tag, value)),
+    };
+
+    Ok(match tag_name {
+        "ruby/symbol" | "ruby/sym" => Value::String(format!("__symbol__{string}")),
+        "ruby/string" => Value::String(string.to_string()),
+        "binary" => {
+            let bytes: Vec<u8> = base64_decode(string).ok_or_else(|| unexpected(tag, value))?;
+            json!({ "__type": "bytes", "data": bytes })
+        }
+        "ruby/regexp" => {
+            let (expression, flags) = string
+                .strip_prefix('/')
+                .and_then(|string| string.rsplit_once('/'))
+                .ok_or_else(|| unexpected(tag, value))?;
+
+            json!({ "__type": "regexp", "expression": expression, "flags": flags })
+        }
+        "ruby/class" => json!({ "__class": string, "__type": "class" }),
+        "ruby/module" => json!({ "__class": string, "__type": "module", "__old": false }),
+        "marshal-rs/bigint" => json!({ "__type": "bigint", "value": string }),
+        _ => {
+            return Err(ValueError {
+                message: format!("Unsupported YAML tag {tag}"),
+            })
+        }
+    })
+}
+
+fn from_yaml_value(yaml: &Yaml) -> Result<Value, ValueError> {
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(bool) => Value::Bool(*bool),
+        Yaml::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                integer.into()
+            } else if let Some(integer) = number.as_u64() {
+                integer.into()
+            } else {
+                let float: f64 = number.as_f64().unwrap_or_default();
+
+                if !float.is_finite() {
+                    return Err(ValueError {
+                        message: format!("Non-finite float {float} can't be stored in JSON"),
+                    });
+                }
+
+                float.into()
+            }
+        }
+        Yaml::String(string) => match string.strip_prefix(':') {
+            Some(symbol) if !symbol.is_empty() => Value::String(format!("__symbol__{symbol}")),
+            _ => Value::String(string.clone()),
+        },
+        Yaml::Sequence(sequence) => Value::Array(
+            sequence
+                .iter()
+                .map(from_yaml_value)
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Mapping(mapping) => {
+            let mut object: Map<String, Value> = Map::new();
+
+            for (key, entry) in mapping {
+                object.insert(hash_key(&from_yaml_value(key)?), from_yaml_value(entry)?);
+            }
+
+            Value::Object(object)
+        }
+        Yaml::Tagged(tagged) => from_tagged(tagged)?,
+    })
+}
+
+/// Converts the Value to YAML with Psych tags.
+/// # Example
+/// ```rust
+/// use marshal_rs::yaml::to_yaml;
+/// use serde_json::json;
+///
+/// let value = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@kind": "__symbol__weapon" });
+///
+/// assert_eq!(to_yaml(&value).unwrap(), "!ruby/object:Item\nname: Sword\nkind: !ruby/symbol weapon\n");
+/// ```
+pub fn to_yaml(value: &Value) -> Result<String, ValueError> {
+    serde_yaml::to_string(&to_yaml_value(value)).map_err(|err| ValueError {
+        message: err.to_string(),
+    })
+}
+
+/// Converts YAML, written by `to_yaml()` or by Psych, to a Value, that can be passed to `dump()`.
+///
+/// Returns an Err when YAML is malformed, or contains tags and values, that can't be represented in a Value.
+pub fn from_yaml(yaml: &str) -> Result<Value, ValueError> {
+    let yaml: Yaml = serde_yaml::from_str(yaml).map_err(|err| ValueError {
+        message: err.to_string(),
+    })?;
+
+    from_yaml_value(&yaml)
+}
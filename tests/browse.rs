@@ -0,0 +1,79 @@
+#![cfg(all(feature = "tui", not(feature = "sonic")))]
+use marshal_rs::inspect::Browser;
+use ratatui::{
+    backend::TestBackend,
+    crossterm::event::{KeyCode, KeyEvent},
+    Terminal,
+};
+use serde_json::json;
+
+fn screen(browser: &mut Browser, terminal: &mut Terminal<TestBackend>) -> Vec<String> {
+    terminal.draw(|frame| browser.render(frame)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let width: usize = buffer.area.width as usize;
+
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+fn press(browser: &mut Browser, keys: &[KeyCode]) {
+    for key in keys {
+        assert!(browser.handle_key(KeyEvent::from(*key)));
+    }
+}
+
+#[test]
+fn browse_tree() {
+    let value = json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@events": {
+            "__integer__1": { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss" }
+        },
+        "__symbol__@data": { "__class": "__symbol__Table", "__type": "object", "__userDefined": [1, 2, 3] }
+    });
+    let mut terminal: Terminal<TestBackend> = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    let mut browser: Browser = Browser::new(&value);
+
+    let lines: Vec<String> = screen(&mut browser, &mut terminal);
+    assert_eq!(lines[0], "▾ RPG::Map (2 ivars)");
+    assert_eq!(lines[1], "  ▸ @events: Hash (1 entries)");
+    assert_eq!(lines[2], "    @data: Table (_dump, 3 bytes)");
+
+    // Expanding the Hash shows its entry
+    press(&mut browser, &[KeyCode::Down, KeyCode::Right]);
+    let lines: Vec<String> = screen(&mut browser, &mut terminal);
+    assert_eq!(lines[1], "  ▾ @events: Hash (1 entries)");
+    assert_eq!(lines[2], "    ▸ 1: RPG::Event (1 ivars)");
+    assert!(lines.contains(&"Class: Hash  Type: hash".to_string()));
+
+    // Selecting the dumped object shows its flags
+    press(&mut browser, &[KeyCode::End]);
+    let lines: Vec<String> = screen(&mut browser, &mut terminal);
+    assert!(lines.contains(&"Path: @data".to_string()));
+    assert!(lines.contains(&"Flags: _dump (3 bytes)".to_string()));
+
+    // Searching expands collapsed parents of the match
+    press(&mut browser, &[KeyCode::Left, KeyCode::Char('/')]);
+    press(
+        &mut browser,
+        &"boss".chars().map(KeyCode::Char).collect::<Vec<_>>(),
+    );
+    press(&mut browser, &[KeyCode::Enter]);
+    let lines: Vec<String> = screen(&mut browser, &mut terminal);
+    assert_eq!(lines[3], "        @name: \"Boss\"");
+    assert!(lines.contains(&"Path: @events › 1 › @name".to_string()));
+    assert_eq!(lines[11], "/boss");
+
+    assert!(!browser.handle_key(KeyEvent::from(KeyCode::Char('q'))));
+}
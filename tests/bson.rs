@@ -0,0 +1,67 @@
+#![cfg(all(feature = "bson", not(feature = "sonic")))]
+use bson::{doc, oid::ObjectId, Bson};
+use marshal_rs::{
+    bson::{from_bson, to_bson, BsonOptions},
+    ValueExt,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn bson_roundtrip() {
+    let value = json!([
+        null, true, 1, -2147483649i64, 1.5, "text", "__symbol__sym",
+        { "__type": "bytes", "data": [0, 255] },
+        { "__type": "bigint", "value": "36893488147419103232" },
+        { "__type": "regexp", "expression": "a+b", "flags": "ixm" },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@kind": "__symbol__weapon" },
+        { "__integer__1": "one", "__symbol__key": [] },
+    ]);
+
+    let bson: Bson = value.to_bson();
+
+    assert_eq!(
+        bson.as_array().unwrap()[2..10],
+        [
+            Bson::Int32(1),
+            Bson::Int64(-2147483649),
+            Bson::Double(1.5),
+            Bson::String("text".to_string()),
+            Bson::Symbol("sym".to_string()),
+            Bson::Binary(bson::Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: vec![0, 255]
+            }),
+            Bson::Document(doc! { "__type": "bigint", "value": "36893488147419103232" }),
+            Bson::RegularExpression(bson::Regex {
+                pattern: "a+b".to_string(),
+                options: "isx".to_string()
+            }),
+        ]
+    );
+    assert_eq!(Value::from_bson(bson), value);
+}
+
+#[test]
+fn bson_options() {
+    let value = json!({ "__symbol__key": "__symbol__sym", "big": 18446744073709551615u64 });
+    let options = BsonOptions {
+        symbols_as_strings: true,
+    };
+
+    assert_eq!(
+        to_bson(&value, options),
+        Bson::Document(
+            doc! { "__symbol__key": "sym", "big": { "__type": "bigint", "value": "18446744073709551615" } }
+        )
+    );
+}
+
+#[test]
+fn bson_from_mongo() {
+    let id = ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+
+    assert_eq!(
+        from_bson(Bson::Document(doc! { "_id": id, "n": 1i64 })),
+        json!({ "_id": { "$oid": "507f1f77bcf86cd799439011" }, "n": 1 })
+    );
+}
@@ -0,0 +1,96 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    builtins::{OpenStruct, RubyRange, RubySet},
+    dump, load, FromValue, IntoValue, LoaderBuilder,
+};
+use serde_json::{json, Map, Value};
+
+#[test]
+fn set_roundtrip() {
+    // Marshal.dump(Set[1, :a, [2]])
+    let marshal: &[u8] = b"\x04\x08o:\x08Set\x06:\x0a@hash}\x08i\x06T:\x06aT[\x06i\x07TF";
+    let value: Value = load(marshal, None, None).unwrap();
+    let set: RubySet = RubySet::from_value(&value).unwrap();
+
+    assert_eq!(set.elements, [json!(1), json!("__symbol__a"), json!([2])]);
+    assert_eq!(set.default, false);
+    assert_eq!(dump(set.into_value(), None), marshal);
+
+    let set: RubySet = RubySet::from(vec![json!("a"), json!(1.5)]);
+
+    assert_eq!(
+        set.clone().into_value(),
+        json!({
+            "__class": "__symbol__Set",
+            "__type": "object",
+            "__symbol__@hash": { "a": true, "__float__1.5": true, "__ruby_default__": false },
+        })
+    );
+    assert_eq!(RubySet::from_value(&set.clone().into_value()).unwrap(), set);
+}
+
+#[test]
+fn open_struct_roundtrip() {
+    // Marshal.dump(OpenStruct.new(a: 1, b: "x"))
+    let marshal: &[u8] = b"\x04\x08U:\x0fOpenStruct{\x07:\x06ai\x06:\x06bI\"\x06x\x06:\x06ET";
+    let value: Value = load(marshal, None, None).unwrap();
+    let open_struct: OpenStruct = OpenStruct::from_value(&value).unwrap();
+
+    let mut table: Map<String, Value> = Map::new();
+    table.insert("a".to_string(), json!(1));
+    table.insert("b".to_string(), json!("x"));
+
+    assert_eq!(open_struct.table, table);
+    assert_eq!(dump(open_struct.into_value(), None), marshal);
+
+    // Ruby 1.8 dumped OpenStruct's table as an instance variable
+    let legacy: Value = json!({
+        "__class": "__symbol__OpenStruct",
+        "__type": "object",
+        "__symbol__@table": { "__symbol__a": 1, "__symbol__b": "x" },
+    });
+
+    assert_eq!(OpenStruct::from_value(&legacy).unwrap().table, table);
+}
+
+#[test]
+fn range_roundtrip() {
+    // Marshal.dump(1...10)
+    let marshal: &[u8] = b"\x04\x08o:\x0aRange\x08:\x09exclT:\x0abegini\x06:\x08endi\x0f";
+    let value: Value = LoaderBuilder::new()
+        .strict(true)
+        .build()
+        .load(marshal, None, None)
+        .unwrap();
+    let range: RubyRange = RubyRange::from_value(&value).unwrap();
+
+    assert_eq!(
+        range,
+        RubyRange {
+            begin: json!(1),
+            end: json!(10),
+            exclusive: true,
+        }
+    );
+    assert_eq!(dump(range.into_value(), None), marshal);
+
+    // Range's instance variables have no "@" prefixes to replace
+    let value: Value = load(marshal, None, Some("")).unwrap();
+
+    assert_eq!(value["__symbol__begin"], 1);
+    assert_eq!(dump(value, Some("")), marshal);
+}
+
+#[test]
+fn builtins_errors() {
+    assert_eq!(
+        RubySet::from_value(&json!([1])).unwrap_err().to_string(),
+        "Expected Set, found Array"
+    );
+    assert_eq!(
+        OpenStruct::from_value(&json!({ "__class": "__symbol__OpenStruct", "__type": "object", "__userMarshal": { "a": 1 } }))
+            .unwrap_err()
+            .to_string(),
+        "OpenStruct field name a is not a symbol"
+    );
+}
@@ -0,0 +1,178 @@
+#![cfg(all(feature = "chrono", not(feature = "sonic")))]
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use marshal_rs::{
+    chrono::{RubyDate, RubyDateTime, RubyTime, ITALY},
+    dump, load, FromValue, IntoValue,
+};
+use serde_json::{json, Value};
+
+// Marshal.dump(Time.new(2024, 1, 2, 3, 4, 5, "+09:00"))
+const FIXED_OFFSET_TIME: &[u8] = &[
+    0x04, 0x08, 0x49, 0x75, 0x3a, 0x09, 0x54, 0x69, 0x6d, 0x65, 0x0d, 0x32, 0x00, 0x1f, 0x80, 0x00,
+    0x00, 0x50, 0x10, 0x06, 0x3a, 0x0b, 0x6f, 0x66, 0x66, 0x73, 0x65, 0x74, 0x69, 0x02, 0x90, 0x7e,
+];
+
+#[test]
+fn time_from_ruby() {
+    let value: Value = load(FIXED_OFFSET_TIME, None, None).unwrap();
+
+    assert_eq!(value["__symbol__offset"], 32400);
+
+    let datetime: DateTime<FixedOffset> = DateTime::from_value(&value).unwrap();
+
+    assert_eq!(
+        datetime,
+        DateTime::parse_from_rfc3339("2024-01-02T03:04:05+09:00").unwrap()
+    );
+    assert_eq!(datetime.offset().local_minus_utc(), 32400);
+
+    assert_eq!(dump(value.clone(), None), FIXED_OFFSET_TIME);
+    assert_eq!(dump(datetime.into_value(), None), FIXED_OFFSET_TIME);
+}
+
+#[test]
+fn time_roundtrip() {
+    let utc: DateTime<Utc> = Utc
+        .with_ymd_and_hms(1999, 12, 31, 23, 59, 58)
+        .unwrap()
+        .with_timezone(&Utc)
+        + chrono::Duration::nanoseconds(123_456_789);
+
+    let value: Value = utc.into_value();
+
+    assert_eq!(value["__symbol__nano_num"], 789);
+    assert_eq!(value["__symbol__nano_den"], 1);
+    assert_eq!(
+        value["__symbol__submicro"],
+        json!({ "__type": "bytes", "data": [0x78, 0x90] })
+    );
+    assert_eq!(value["__symbol__zone"], "UTC");
+    assert!(value.get("__symbol__offset").is_none());
+
+    let loaded: Value = load(&dump(value, None), None, None).unwrap();
+    let time: RubyTime = RubyTime::from_value(&loaded).unwrap();
+
+    assert!(time.utc);
+    assert_eq!(time.zone.as_deref(), Some("UTC"));
+    assert_eq!(time.datetime, utc);
+
+    let jst: RubyTime = RubyTime {
+        datetime: FixedOffset::east_opt(32400)
+            .unwrap()
+            .with_ymd_and_hms(3000, 6, 7, 8, 9, 10)
+            .unwrap(),
+        utc: false,
+        zone: Some("JST".to_string()),
+    };
+
+    assert_eq!(
+        RubyTime::from_value(&jst.clone().into_value()).unwrap(),
+        jst
+    );
+
+    let ancient: DateTime<FixedOffset> =
+        DateTime::parse_from_rfc3339("1800-01-01T00:00:00-05:00").unwrap();
+    let value: Value = ancient.into_value();
+
+    assert_eq!(value["__symbol__year"], 1800);
+    assert_eq!(
+        DateTime::<FixedOffset>::from_value(&value).unwrap(),
+        ancient
+    );
+}
+
+#[test]
+fn time_legacy_format() {
+    // Ruby 1.8 dumped seconds and microseconds since the epoch
+    let value: Value = json!({
+        "__class": "__symbol__Time",
+        "__type": "object",
+        "__userDefined": [0x00, 0xca, 0x9a, 0x3b, 0x20, 0xa1, 0x07, 0x00],
+    });
+
+    assert_eq!(
+        DateTime::<Utc>::from_value(&value).unwrap(),
+        Utc.timestamp_opt(1_000_000_000, 500_000_000).unwrap()
+    );
+}
+
+#[test]
+fn time_errors() {
+    let message = |value: Value| RubyTime::from_value(&value).unwrap_err().to_string();
+
+    assert_eq!(message(json!(1)), "Expected Time, found Integer");
+    assert_eq!(
+        message(
+            json!({ "__class": "__symbol__Time", "__type": "object", "__userDefined": [1, 2, 3] })
+        ),
+        "Invalid Time: expected 8 bytes of dumped data"
+    );
+    assert_eq!(
+        message(json!({
+            "__class": "__symbol__Time",
+            "__type": "object",
+            "__userDefined": [0x32, 0x00, 0x1f, 0x80, 0x00, 0x00, 0x50, 0x10],
+            "__symbol__offset": "+09:00",
+        })),
+        "Invalid Time: offset is not an Integer"
+    );
+}
+
+#[test]
+fn date_from_ruby() {
+    // Marshal.dump(Date.new(2000, 1, 1))
+    let marshal: &[u8] = b"\x04\x08U:\x09Date[\x0bi\x00i\x03Yh%i\x00i\x00i\x00f\x0c2299161";
+    let value: Value = load(marshal, None, None).unwrap();
+    let date: RubyDate = RubyDate::from_value(&value).unwrap();
+
+    assert_eq!(date.date, NaiveDate::from_ymd_opt(2000, 1, 1).unwrap());
+    assert_eq!(date.start, ITALY);
+    assert_eq!(dump(date.into_value(), None), marshal);
+    assert_eq!(
+        dump(
+            NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().into_value(),
+            None
+        ),
+        marshal
+    );
+}
+
+#[test]
+fn date_time_roundtrip() {
+    // DateTime.new(2001, 2, 3, 4, 5, 6.5, "+7")
+    let value: Value = json!({
+        "__class": "__symbol__DateTime",
+        "__type": "object",
+        "__userMarshal": [0, 2451943, 75906, 500_000_000, 25200, 2299161.0],
+    });
+    let datetime: RubyDateTime = RubyDateTime::from_value(&value).unwrap();
+
+    assert_eq!(
+        datetime.datetime,
+        DateTime::parse_from_rfc3339("2001-02-03T04:05:06.5+07:00").unwrap()
+    );
+    assert_eq!(datetime.into_value(), value);
+
+    // Ruby 1.8 dumped astronomical Julian day and the offset in days as Rationals
+    let rational = |numerator: i64, denominator: i64| json!({ "__class": "__symbol__Rational", "__type": "object", "__userMarshal": [numerator, denominator] });
+    let legacy: Value = json!({
+        "__class": "__symbol__DateTime",
+        "__type": "object",
+        "__userMarshal": [rational(2451943 * 172800 - 86400 + 151813, 172800), rational(7, 24), 2299161.0],
+    });
+
+    assert_eq!(
+        RubyDateTime::from_value(&legacy).unwrap().datetime,
+        DateTime::parse_from_rfc3339("2001-02-03T04:05:06.5+07:00").unwrap()
+    );
+
+    let message = |value: Value| RubyDate::from_value(&value).unwrap_err().to_string();
+
+    assert_eq!(message(value), "Expected Date, found DateTime");
+    assert_eq!(
+        message(
+            json!({ "__class": "__symbol__Date", "__type": "object", "__userMarshal": [1, 0, 0, 0, 0, 2299161.0] })
+        ),
+        "Invalid Date: Julian day periods are not supported"
+    );
+}
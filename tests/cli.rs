@@ -0,0 +1,87 @@
+#![cfg(all(feature = "cli", not(feature = "sonic")))]
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+// [RPG::Item { @name: "Sword" }, nil]
+const ITEMS: &[u8] = b"\x04\x08[\x07o:\x0eRPG::Item\x06:\x0a@nameI\"\x0aSword\x06:\x06ET0";
+
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_marshal-rs"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The command may exit before reading the input, for example, on invalid arguments
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn json_roundtrip() {
+    let json = run(&["to-json"], ITEMS);
+
+    assert!(json.status.success());
+    assert_eq!(
+        String::from_utf8(json.stdout.clone()).unwrap(),
+        "[{\"__class\":\"__symbol__RPG::Item\",\"__type\":\"object\",\"__symbol__@name\":\"Sword\"},null]\n"
+    );
+
+    let marshal = run(&["from-json", "-"], &json.stdout);
+
+    assert!(marshal.status.success());
+    assert_eq!(marshal.stdout, ITEMS);
+}
+
+#[test]
+fn inspect_and_validate() {
+    let outline = run(&["inspect", "--depth", "1"], ITEMS);
+
+    assert_eq!(
+        String::from_utf8(outline.stdout).unwrap(),
+        "Array (2)\n  [0]: RPG::Item\n  [1]: nil\n"
+    );
+
+    assert!(run(&["validate"], ITEMS).status.success());
+
+    let invalid = run(&["validate"], b"\x04\x09");
+
+    assert_eq!(invalid.status.code(), Some(1));
+    assert!(String::from_utf8(invalid.stdout)
+        .unwrap()
+        .starts_with("FAILED -"));
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(run(&["unknown"], b"").status.code(), Some(2));
+    assert_eq!(
+        run(&["to-json", "--string-mode", "x"], b"").status.code(),
+        Some(2)
+    );
+    assert_eq!(run(&["diff", "-"], b"").status.code(), Some(2));
+    assert!(run(&["--help"], b"").status.success());
+}
+
+#[test]
+fn filters() {
+    let names = run(&["get", ".[] | .@name"], ITEMS);
+
+    assert_eq!(
+        String::from_utf8(names.stdout).unwrap(),
+        "\"Sword\"\nnull\n"
+    );
+
+    let items = run(&["select", "class == \"RPG::Item\"", "-"], ITEMS);
+
+    assert_eq!(
+        String::from_utf8(items.stdout).unwrap(),
+        "{\"__class\":\"__symbol__RPG::Item\",\"__type\":\"object\",\"__symbol__@name\":\"Sword\"}\n"
+    );
+
+    assert_eq!(run(&["get", ".["], ITEMS).status.code(), Some(2));
+}
@@ -0,0 +1,32 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{load::StringMode, InstanceVarPolicy, MarshalCodec};
+use serde_json::json;
+
+#[test]
+fn symmetric_options() {
+    let mut codec = MarshalCodec::builder()
+        .instance_var_prefix("$")
+        // Prefixes, set for a single direction, are ignored
+        .loader(|loader| {
+            loader
+                .instance_var_prefix("%")
+                .string_mode(StringMode::Binary)
+        })
+        .dumper(|dumper| dumper.instance_var_policy(InstanceVarPolicy::Error))
+        .build();
+
+    let item = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__$count": 3 });
+    let bytes: Vec<u8> = codec.try_dump(item.clone()).unwrap();
+
+    assert_eq!(bytes, marshal_rs::dump(item.clone(), Some("$")));
+    assert_eq!(codec.load(&bytes).unwrap(), item);
+
+    // Options of a single direction are applied
+    assert_eq!(
+        codec.load(b"\x04\x08I\"\x06a\x06:\x06ET").unwrap(),
+        json!({ "__type": "bytes", "data": [97] })
+    );
+    assert!(codec
+        .try_dump(json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__count": 3 }))
+        .is_err());
+}
@@ -0,0 +1,126 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    convert::{
+        dir_to_json, json_file_to_marshal_file, json_to_dir, marshal_file_to_json_file,
+        ConvertOptions,
+    },
+    dump,
+};
+use serde_json::json;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+#[test]
+fn dir_roundtrip() {
+    let root = std::env::temp_dir().join(format!("marshal-rs-convert-{}", std::process::id()));
+    let (data, json_dir, restored) = (root.join("Data"), root.join("Json"), root.join("Restored"));
+    std::fs::create_dir_all(data.join("Maps")).unwrap();
+
+    let actors = dump(
+        json!([null, { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Eric" }]),
+        None,
+    );
+    let map = dump(
+        json!({ "__class": "__symbol__RPG::Map", "__type": "object", "__symbol__@width": 17 }),
+        None,
+    );
+
+    std::fs::write(data.join("Actors.rvdata2"), &actors).unwrap();
+    std::fs::write(data.join("Maps/Map001.rvdata2"), &map).unwrap();
+    std::fs::write(data.join("Scripts.rvdata2"), dump(json!([]), None)).unwrap();
+    std::fs::write(data.join("Broken.rvdata2"), b"\x04\x08[").unwrap();
+    std::fs::write(data.join("readme.txt"), b"not marshal").unwrap();
+
+    let processed = Arc::new(AtomicUsize::new(0));
+    let counter = processed.clone();
+
+    let options = ConvertOptions {
+        skip: vec!["Scripts".to_string()],
+        pretty: true,
+        threads: 2,
+        progress: Some(Box::new(move |_, _, total| {
+            assert_eq!(total, 3);
+            counter.fetch_add(1, Ordering::Relaxed);
+        })),
+        ..Default::default()
+    };
+
+    let report = dir_to_json(&data, &json_dir, &options).unwrap();
+
+    assert_eq!(
+        report.converted,
+        [
+            PathBuf::from("Actors.rvdata2"),
+            PathBuf::from("Maps/Map001.rvdata2")
+        ]
+    );
+    assert_eq!(report.skipped, [PathBuf::from("Scripts.rvdata2")]);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, PathBuf::from("Broken.rvdata2"));
+    assert_eq!(processed.load(Ordering::Relaxed), 3);
+
+    let json: String = std::fs::read_to_string(json_dir.join("Maps/Map001.rvdata2.json")).unwrap();
+    assert!(json.contains("\n  \"__symbol__@width\": 17\n"));
+
+    let report = json_to_dir(&json_dir, &restored, &ConvertOptions::default()).unwrap();
+
+    assert_eq!(report.converted.len(), 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(
+        std::fs::read(restored.join("Actors.rvdata2")).unwrap(),
+        actors
+    );
+    assert_eq!(
+        std::fs::read(restored.join("Maps/Map001.rvdata2")).unwrap(),
+        map
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn file_roundtrip() {
+    let root = std::env::temp_dir().join(format!("marshal-rs-convert-file-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+
+    let marshal = dump(
+        json!({ "__class": "__symbol__RPG::Map", "__type": "object", "__symbol__@width": 17 }),
+        None,
+    );
+    let (src, json_file, restored) = (
+        root.join("Map001.rvdata2"),
+        root.join("Json/Map001.json"),
+        root.join("Restored/Map001.rvdata2"),
+    );
+    std::fs::write(&src, &marshal).unwrap();
+
+    let options = ConvertOptions {
+        pretty: true,
+        ..Default::default()
+    };
+
+    marshal_file_to_json_file(&src, &json_file, &options).unwrap();
+    assert!(std::fs::read_to_string(&json_file)
+        .unwrap()
+        .contains("\n  \"__symbol__@width\": 17\n"));
+
+    json_file_to_marshal_file(&json_file, &restored, &options).unwrap();
+    assert_eq!(std::fs::read(&restored).unwrap(), marshal);
+
+    // Failed conversions leave existing files untouched, and don't leave temporary files behind
+    std::fs::write(&src, b"\x04\x08[").unwrap();
+    let err = marshal_file_to_json_file(&src, &json_file, &options).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(std::fs::read_to_string(&json_file)
+        .unwrap()
+        .contains("RPG::Map"));
+    assert_eq!(std::fs::read_dir(root.join("Json")).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
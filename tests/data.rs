@@ -0,0 +1,79 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    data::{DataHandler, DataRegistry},
+    Dumper, Loader,
+};
+use serde_json::{json, Value};
+
+/// Handler of `Geo::Point`, whose `_dump_data` returns "x,y" string.
+struct Point;
+
+impl DataHandler for Point {
+    fn load(&self, data: Value) -> Result<Value, String> {
+        let string: &str = data.as_str().ok_or("data isn't a string")?;
+        let (x, y) = string.split_once(',').ok_or("missing comma")?;
+
+        Ok(json!({ "x": x.parse::<i64>().unwrap(), "y": y.parse::<i64>().unwrap() }))
+    }
+
+    fn dump(&self, value: &Value) -> Result<Value, String> {
+        match (value["x"].as_i64(), value["y"].as_i64()) {
+            (Some(x), Some(y)) => Ok(json!(format!("{x},{y}"))),
+            _ => Err("coordinates aren't integers".to_string()),
+        }
+    }
+}
+
+#[test]
+fn data_handlers() {
+    let mut registry = DataRegistry::new();
+    registry.register("Geo::Point", Point);
+
+    // Geo::Point with "1,2" data
+    let bytes: &[u8] = b"\x04\x08d:\x0fGeo::PointI\"\x081,2\x06:\x06ET";
+
+    let value: Value = Loader::builder()
+        .data_registry(&registry)
+        .build()
+        .load(bytes, None, None)
+        .unwrap();
+
+    assert_eq!(
+        value,
+        json!({ "__class": "__symbol__Geo::Point", "__type": "object", "__data": { "x": 1, "y": 2 } })
+    );
+
+    let mut dumper = Dumper::builder().data_registry(&registry).build();
+
+    assert_eq!(dumper.try_dump(value.clone(), None).unwrap(), bytes);
+
+    let mut invalid: Value = value.clone();
+    invalid["__data"]["x"] = json!("one");
+
+    assert_eq!(
+        dumper.try_dump(invalid, None).unwrap_err().to_string(),
+        "Data handler of Geo::Point failed: coordinates aren't integers"
+    );
+
+    // Geo::Point with 1 data
+    assert_eq!(
+        Loader::builder()
+            .data_registry(&registry)
+            .build()
+            .load(b"\x04\x08d:\x0fGeo::Pointi\x06", None, None)
+            .unwrap_err()
+            .to_string(),
+        "Data handler of Geo::Point failed: data isn't a string"
+    );
+
+    assert!(registry.unregister("Geo::Point"));
+    assert!(registry.get("Geo::Point").is_none());
+    assert_eq!(
+        Loader::builder()
+            .data_registry(&registry)
+            .build()
+            .load(bytes, None, None)
+            .unwrap()["__data"],
+        json!("1,2")
+    );
+}
@@ -0,0 +1,72 @@
+#![cfg(all(feature = "decimal", not(feature = "sonic")))]
+use marshal_rs::{decimal::RubyDecimal, dump, load, FromValue, IntoValue};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+fn big_decimal(dumped: &str) -> Value {
+    json!({ "__class": "__symbol__BigDecimal", "__type": "object", "__userDefined": dumped.as_bytes() })
+}
+
+#[test]
+fn decimal_from_ruby() {
+    // Marshal.dump(BigDecimal("1.5"))
+    let marshal: &[u8] = b"\x04\x08u:\x0fBigDecimal\x0e18:0.15e1";
+    let value: Value = load(marshal, None, None).unwrap();
+
+    assert_eq!(value, big_decimal("18:0.15e1"));
+    assert_eq!(
+        Decimal::from_value(&value).unwrap(),
+        Decimal::from_str("1.5").unwrap()
+    );
+    assert_eq!(
+        dump(Decimal::from_str("1.50").unwrap().into_value(), None),
+        marshal
+    );
+}
+
+#[test]
+fn decimal_roundtrip() {
+    for (decimal, dumped) in [
+        ("0", "9:0.0"),
+        ("100", "9:0.1e3"),
+        ("-123.456", "18:-0.123456e3"),
+        ("0.000001", "9:0.1e-5"),
+        ("1234567890.5", "27:0.12345678905e10"),
+    ] {
+        let decimal: Decimal = Decimal::from_str(decimal).unwrap();
+        let value: Value = decimal.into_value();
+
+        assert_eq!(value, big_decimal(dumped));
+        assert_eq!(Decimal::from_value(&value).unwrap(), decimal);
+    }
+
+    let value: Value = big_decimal("9:-0.0");
+    let decimal: Decimal = Decimal::from_value(&value).unwrap();
+
+    assert!(decimal.is_zero() && decimal.is_sign_negative());
+    assert_eq!(decimal.into_value(), value);
+
+    // Precision markers, that differ from the estimated ones, are kept
+    let value: Value = big_decimal("36:0.15e1");
+    let decimal: RubyDecimal = RubyDecimal::from_value(&value).unwrap();
+
+    assert_eq!(decimal.precision, 36);
+    assert_eq!(decimal.into_value(), value);
+}
+
+#[test]
+fn decimal_errors() {
+    let message = |value: Value| RubyDecimal::from_value(&value).unwrap_err().to_string();
+
+    assert_eq!(message(json!(1.5)), "Expected BigDecimal, found Float");
+    assert_eq!(
+        message(big_decimal("9:NaN")),
+        "Invalid BigDecimal: NaN can't be represented"
+    );
+    assert_eq!(
+        message(big_decimal("0.15e1")),
+        "Invalid BigDecimal: missing precision"
+    );
+    assert!(message(big_decimal("9:0.1e100")).starts_with("Invalid BigDecimal: "));
+}
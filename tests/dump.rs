@@ -1,9 +1,10 @@
 #![allow(clippy::approx_constant)]
-use marshal_rs::dump;
+use marshal_rs::{dump, Dumper, InstanceVarPolicy};
 #[cfg(not(feature = "sonic"))]
 use serde_json::json;
 #[cfg(feature = "sonic")]
 use sonic_rs::json;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[test]
 fn null() {
@@ -33,6 +34,36 @@ fn fixnum_negative() {
     assert_eq!(dump(json!(-16777216), None), b"\x04\x08i\xFD\0\0\0");
 }
 
+#[test]
+fn large_integers() {
+    // Ruby dumps integers, that don't fit in 31 bits, as Bignums
+    assert_eq!(
+        dump(json!(1073741823), None),
+        b"\x04\x08i\x04\xFF\xFF\xFF\x3F"
+    );
+    assert_eq!(dump(json!(-1073741824), None), b"\x04\x08i\xFC\0\0\0\xC0");
+    assert_eq!(dump(json!(1073741824), None), b"\x04\x08l+\x07\0\0\0\x40");
+    assert_eq!(
+        dump(json!(-1073741825), None),
+        b"\x04\x08l-\x07\x01\0\0\x40"
+    );
+    assert_eq!(
+        dump(json!(i64::MIN), None),
+        b"\x04\x08l-\x09\0\0\0\0\0\0\0\x80"
+    );
+    assert_eq!(
+        dump(json!(u64::MAX), None),
+        b"\x04\x08l+\x09\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF"
+    );
+    assert_eq!(
+        dump(
+            json!({ "__integer__-1": 1, "__integer__4294967296": 2 }),
+            None
+        ),
+        b"\x04\x08{\x07i\xFAi\x06l+\x08\0\0\0\0\x01\0i\x07"
+    );
+}
+
 #[test]
 fn bignum_positive() {
     assert_eq!(
@@ -58,6 +89,38 @@ fn bignum_positive() {
         ),
         b"\x04\x08l+\n\0\0\0\0\0\0\0\0\x08\0"
     );
+
+    assert_eq!(
+        dump(json!({"__type": "bigint", "value": "3879905959"}), None),
+        b"\x04\x08l+\x07\xa7\xaa\x42\xe7"
+    );
+
+    assert_eq!(
+        dump(
+            json!({"__type": "bigint", "value": "18446744073709551617"}),
+            None,
+        ),
+        b"\x04\x08l+\n\x01\0\0\0\0\0\0\0\x01\0"
+    );
+}
+
+#[test]
+fn bignum_raw() {
+    // Big Integers, loaded without `bigint` feature
+    assert_eq!(
+        dump(
+            json!({"__type": "bigint", "negative": true, "data": [0, 0, 0, 0, 0, 0, 0, 0, 2, 0]}),
+            None,
+        ),
+        b"\x04\x08l-\n\0\0\0\0\0\0\0\0\x02\0"
+    );
+    assert_eq!(
+        dump(
+            json!({"__type": "bigint", "negative": false, "data": [1, 2, 3]}),
+            None,
+        ),
+        b"\x04\x08l+\x07\x01\x02\x03\0"
+    );
 }
 
 #[test]
@@ -87,6 +150,28 @@ fn bignum_negative() {
     );
 }
 
+#[test]
+fn bignum_decimal() {
+    // Decimal strings are converted without `bigint` feature too
+    assert_eq!(
+        dump(json!({"__type": "bigint", "value": "0"}), None),
+        b"\x04\x08l+\x06\0\0"
+    );
+    assert_eq!(
+        dump(json!({"__type": "bigint", "value": "-0065536"}), None),
+        b"\x04\x08l-\x07\0\0\x01\0"
+    );
+
+    for value in [json!("12a"), json!("-"), json!(""), json!(1)] {
+        let error = Dumper::new()
+            .try_dump(json!({"__type": "bigint", "value": value}), None)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("isn't a valid Big Integer value."));
+    }
+}
+
 #[test]
 fn float() {
     assert_eq!(dump(json!(0), None), b"\x04\x08i\0");
@@ -125,6 +210,18 @@ fn string_nonutf8() {
     );
 }
 
+#[test]
+fn string_with_encoding() {
+    // Strings in other encodings, loaded without `encodings` feature
+    assert_eq!(
+        dump(
+            json!({ "__type": "bytes", "data": [0xBA, 0xBA, 0xD7, 0xD6, 0xC4, 0xDA], "encoding": "GBK" }),
+            None
+        ),
+        b"\x04\x08I\"\x0b\xBA\xBA\xD7\xD6\xC4\xDA\x06:\rencoding\"\x08GBK"
+    );
+}
+
 #[test]
 fn string_binary() {
     assert_eq!(
@@ -201,3 +298,254 @@ fn object() {
         b"\x04\x08o:\x11CustomObject\x06:\x0a@dataI\"\x10object data\x06:\x06ET"
     );
 }
+
+#[test]
+fn extended_object() {
+    assert_eq!(
+        dump(
+            json!({ "__class": "__symbol__Object", "__type": "object", "__ruby_extends__": ["__symbol__Comparable"] }),
+            None
+        ),
+        b"\x04\x08e:\x0fComparableo:\x0bObject\x00"
+    );
+}
+
+#[test]
+fn builder() {
+    let mut dumper = Dumper::builder()
+        .instance_var_prefix("_")
+        .capacity(16)
+        .build();
+    let object = json!({ "__class": "__symbol__A", "__type": "object", "__symbol___a": 1 });
+
+    assert_eq!(
+        dumper.dump(object.clone(), None),
+        b"\x04\x08o:\x06A\x06:\x07@ai\x06"
+    );
+    assert_eq!(
+        dumper.dump(object, Some("_")),
+        b"\x04\x08o:\x06A\x06:\x07@ai\x06"
+    );
+}
+
+#[test]
+fn cancellation() {
+    let flag: AtomicBool = AtomicBool::new(true);
+    let mut dumper = Dumper::builder().cancel_flag(&flag).build();
+
+    assert_eq!(
+        dumper.try_dump(json!([1]), None).unwrap_err().to_string(),
+        "Dump was cancelled after 2 bytes."
+    );
+
+    flag.store(false, Ordering::Relaxed);
+    assert_eq!(
+        dumper.try_dump(json!([1]), None).unwrap(),
+        b"\x04\x08[\x06i\x06"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Dump was cancelled after 2 bytes.")]
+fn cancelled_dump_panics() {
+    let flag: AtomicBool = AtomicBool::new(true);
+    Dumper::builder()
+        .cancel_flag(&flag)
+        .build()
+        .dump(json!([1]), None);
+}
+
+#[test]
+#[should_panic(expected = "a isn't a valid instance variable name.")]
+fn rejected_instance_var_panics() {
+    // Incomplete data isn't returned, even if valid instance variables precede the rejected one
+    Dumper::builder()
+        .instance_var_policy(InstanceVarPolicy::Error)
+        .build()
+        .dump(
+            json!({ "__class": "__symbol__A", "__type": "object", "__symbol__@b": 1, "__symbol__a": 2 }),
+            None,
+        );
+}
+
+#[test]
+#[should_panic(expected = "12a isn't a valid Big Integer value.")]
+fn invalid_bignum_panics() {
+    dump(json!({"__type": "bigint", "value": "12a"}), None);
+}
+
+#[test]
+fn instance_var_policy() {
+    let object = json!({ "__class": "__symbol__A", "__type": "object", "__symbol__a": 1 });
+    let range = json!({
+        "__class": "__symbol__Range", "__type": "object",
+        "__symbol__excl": false, "__symbol__begin": 1, "__symbol__end": 2
+    });
+
+    assert_eq!(
+        dump(object.clone(), None),
+        b"\x04\x08o:\x06A\x06:\x06ai\x06"
+    );
+
+    let mut dumper = Dumper::builder()
+        .instance_var_policy(InstanceVarPolicy::Error)
+        .build();
+
+    assert_eq!(
+        dumper
+            .try_dump(object.clone(), None)
+            .unwrap_err()
+            .to_string(),
+        "a isn't a valid instance variable name."
+    );
+    assert_eq!(
+        dumper.try_dump(range.clone(), None).unwrap(),
+        dump(range.clone(), None)
+    );
+
+    let mut dumper = Dumper::builder()
+        .instance_var_policy(InstanceVarPolicy::Prefix)
+        .build();
+
+    assert_eq!(
+        dumper.try_dump(object.clone(), None).unwrap(),
+        b"\x04\x08o:\x06A\x06:\x07@ai\x06"
+    );
+    assert_eq!(
+        dumper
+            .try_dump(
+                json!({ "__class": "__symbol__A", "__type": "object", "__symbol__@1": 1 }),
+                None
+            )
+            .unwrap_err()
+            .to_string(),
+        "@1 isn't a valid instance variable name."
+    );
+
+    let mut dumper = Dumper::builder()
+        .instance_var_prefix("_")
+        .instance_var_policy(InstanceVarPolicy::Error)
+        .build();
+    let prefixed = json!({ "__class": "__symbol__A", "__type": "object", "__symbol___a": 1 });
+
+    assert_eq!(
+        dumper.try_dump(prefixed, None).unwrap(),
+        b"\x04\x08o:\x06A\x06:\x07@ai\x06"
+    );
+    assert_eq!(
+        dumper
+            .try_dump(json!({ "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1 } }), None)
+            .unwrap(),
+        b"\x04\x08S:\x0aPoint\x06:\x06xi\x06"
+    );
+}
+
+#[test]
+fn string_encodings() {
+    let string = |value: &str, encoding: &str| {
+        dump(
+            json!({ "__type": "string", "value": value, "encoding": encoding }),
+            None,
+        )
+    };
+
+    assert_eq!(string("ab", "ASCII-8BIT"), b"\x04\x08\"\x07ab");
+    assert_eq!(string("ab", "US-ASCII"), b"\x04\x08I\"\x07ab\x06:\x06EF");
+    assert_eq!(string("é", "US-ASCII"), dump(json!("é"), None));
+    assert_eq!(string("ab", "UTF-8"), dump(json!("ab"), None));
+    assert_eq!(string("ab", "Klingon"), dump(json!("ab"), None));
+
+    #[cfg(feature = "encodings")]
+    {
+        // "テ" in Shift_JIS
+        assert_eq!(
+            string("テ", "Shift_JIS"),
+            b"\x04\x08I\"\x07\x83\x65\x06:\x0dencoding\"\x0eShift_JIS"
+        );
+        assert_eq!(
+            marshal_rs::load(&string("テ", "Shift_JIS"), None, None).unwrap(),
+            json!("テ")
+        );
+        // Unmappable characters
+        assert_eq!(string("😀", "Shift_JIS"), dump(json!("😀"), None));
+    }
+}
+
+#[test]
+#[cfg(not(feature = "sonic"))]
+fn malformed_values() {
+    let error = |value| Dumper::new().try_dump(value, None).unwrap_err().to_string();
+
+    assert_eq!(
+        error(json!({ "__class": { "__type": "bytes", "data": [65] }, "__type": "object" })),
+        r#"{"__type":"bytes","data":[65]} isn't a valid symbol."#
+    );
+    assert_eq!(
+        error(json!({ "__integer__1x": 1 })),
+        "Hash key __integer__1x can't be converted to a Ruby value."
+    );
+    assert_eq!(
+        error(json!({ "__type": "bytes", "data": "A" })),
+        r#"Bytes "A" aren't an array of bytes."#
+    );
+    assert_eq!(
+        error(json!({ "__type": "class" })),
+        "Class name isn't a string."
+    );
+    assert_eq!(
+        error(json!({ "__type": "regexp", "expression": "a" })),
+        "Regexp expression and flags aren't strings."
+    );
+    assert_eq!(
+        error(json!({ "__type": "set" })),
+        r#"Unknown object type "set"."#
+    );
+    assert_eq!(
+        error(json!({ "__class": "__symbol__Point", "__type": "struct", "__members": 1 })),
+        "Instance variables 1 aren't an object."
+    );
+    assert_eq!(
+        error(json!({ "__class": "__symbol__A", "__type": "object", "__ruby_extends__": "B" })),
+        r#"Extended modules "B" aren't an array."#
+    );
+
+    // Integer keys, that don't fit in 64 bits, are written as Big Integers
+    assert_eq!(
+        Dumper::new()
+            .try_dump(json!({ "__integer__99999999999999999999": 1 }), None)
+            .unwrap(),
+        b"\x04\x08{\x06l+\x0a\xff\xff\x0fc-^\xc7k\x05\0i\x06"
+    );
+}
+
+#[test]
+#[cfg(not(feature = "sonic"))]
+fn dump_ref() {
+    let value = json!({
+        "__class": "__symbol__A",
+        "__type": "object",
+        "__symbol___a": [
+            { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1 } },
+            { "__symbol__key": "value", "__ruby_default__": 0 },
+            { "__class": "__symbol__B", "__type": "object", "__userDefined": [1, 2], "__symbol__@b": 2 },
+        ],
+    });
+    let original = value.clone();
+    let mut dumper = Dumper::new();
+    let bytes: Vec<u8> = dumper.dump_ref(&value, Some("_"));
+
+    assert_eq!(value, original);
+    assert_eq!(dumper.dump_ref(&value, Some("_")), bytes);
+    assert_eq!(dumper.dump(value, Some("_")), bytes);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn dump_json() {
+    let value = serde_json::json!({ "__symbol__a": ["__symbol__b", 1.5, 4294967296u64, null] });
+
+    assert_eq!(
+        marshal_rs::dump_json(&value),
+        b"\x04\x08{\x06I\"\x10__symbol__a\x06:\x06ET[\x09I\"\x10__symbol__b\x06;\x00Tf\x081.5l+\x08\x00\x00\x00\x00\x01\x000"
+    );
+}
@@ -0,0 +1,13 @@
+use marshal_rs_macros::include_marshal;
+#[cfg(not(feature = "sonic"))]
+use serde_json::json;
+#[cfg(feature = "sonic")]
+use sonic_rs::json;
+
+#[test]
+fn include_marshal() {
+    let embedded = include_marshal!("tests/fixtures/array.marshal");
+
+    assert_eq!(embedded.bytes(), b"\x04\x08[\x07i\x06I\"\x06a\x06:\x06ET");
+    assert_eq!(embedded.load(), json!([1, "a"]));
+}
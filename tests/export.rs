@@ -0,0 +1,262 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::export::{from_csv, from_ndjson, to_csv, to_ndjson, CsvOptions, NdjsonReader};
+#[cfg(feature = "arrow")]
+use marshal_rs::export::{to_parquet, to_record_batch};
+#[cfg(feature = "sqlite")]
+use marshal_rs::export::{to_sqlite, NestedMode, SqliteOptions};
+use serde_json::json;
+use std::io::ErrorKind;
+
+#[test]
+fn csv_roundtrip() {
+    let value = json!([
+        null,
+        { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@id": 1, "__symbol__@name": "Eric, \"the\" Brave", "__symbol__@note": "line\nbreak", "__symbol__@params": [1, 2.5, null], "__symbol__@kind": "__symbol__hero" },
+        { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@id": 2, "__symbol__@name": "42", "__symbol__@note": "", "__symbol__@params": { "a": true }, "__symbol__@kind": "'quoted" },
+        { "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@id": 3, "__symbol__@name": "true", "__symbol__@note": null, "__symbol__@params": [], "__symbol__@kind": ":colon" },
+    ]);
+
+    let csv = to_csv(&value, CsvOptions::default()).unwrap();
+
+    assert!(csv.starts_with("class,@id,@name,@note,@params,@kind\n,,,,,\n"));
+    assert!(csv.contains(
+        "RPG::Actor,1,\"Eric, \"\"the\"\" Brave\",\"line\nbreak\",\"[1,2.5,null]\",:hero\n"
+    ));
+    assert!(csv.contains("RPG::Actor,2,'42,',\"{\"\"a\"\":true}\",''quoted\n"));
+    assert_eq!(from_csv(&csv, CsvOptions::default()).unwrap(), value);
+
+    let options = CsvOptions {
+        delimiter: ';',
+        ..Default::default()
+    };
+    let csv = to_csv(&value, options).unwrap();
+
+    assert!(csv.starts_with("class;@id;@name;@note;@params;@kind\n"));
+    assert_eq!(from_csv(&csv, options).unwrap(), value);
+}
+
+#[test]
+fn csv_without_coercion() {
+    let value = json!([{ "__class": "__symbol__Item", "__type": "object", "__symbol__@id": 1, "__symbol__@name": "'1" }]);
+    let options = CsvOptions {
+        coerce: false,
+        ..Default::default()
+    };
+
+    let csv = to_csv(&value, options).unwrap();
+
+    assert_eq!(csv, "class,@id,@name\nItem,1,'1\n");
+    assert_eq!(
+        from_csv(&csv, options).unwrap(),
+        json!([{ "__class": "__symbol__Item", "__type": "object", "__symbol__@id": "1", "__symbol__@name": "'1" }])
+    );
+}
+
+#[test]
+fn csv_errors() {
+    assert!(to_csv(&json!({}), CsvOptions::default()).is_err());
+    assert!(to_csv(&json!([null, 1]), CsvOptions::default())
+        .unwrap_err()
+        .to_string()
+        .contains("index 1"));
+
+    assert!(from_csv("@id\n1\n", CsvOptions::default()).is_err());
+    assert!(from_csv("class,@id\nItem\n", CsvOptions::default()).is_err());
+    assert!(from_csv("class,@id\nItem,\"1\n", CsvOptions::default()).is_err());
+}
+
+#[test]
+fn ndjson_roundtrip() {
+    let value = json!([
+        null,
+        "line\nbreak",
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 1, "__symbol__@name": "Potion" },
+        [1, { "__type": "bytes", "data": [0, 255] }],
+    ]);
+
+    let mut ndjson: Vec<u8> = Vec::new();
+    to_ndjson(&mut ndjson, &value).unwrap();
+
+    assert_eq!(ndjson.iter().filter(|&&byte| byte == b'\n').count(), 4);
+    assert_eq!(from_ndjson(ndjson.as_slice()).unwrap(), value);
+
+    let mut reader = NdjsonReader::new("1\n\n  \r\n[2]\r\n{\"a\":3}".as_bytes());
+
+    assert_eq!(reader.next().unwrap().unwrap(), json!(1));
+    assert_eq!(reader.next().unwrap().unwrap(), json!([2]));
+    assert_eq!(reader.next().unwrap().unwrap(), json!({ "a": 3 }));
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn ndjson_errors() {
+    assert_eq!(
+        to_ndjson(Vec::new(), &json!({})).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+
+    let err = from_ndjson("1\n2\n{\n".as_bytes()).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 3"));
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn record_batch() {
+    use arrow_array::{cast::AsArray, types::*, Array};
+    use arrow_schema::DataType;
+
+    let value = json!([
+        null,
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 1, "__symbol__@price": 10, "__symbol__@consumable": true, "__symbol__@name": "Potion", "__symbol__@icon": { "__type": "bytes", "data": [1, 2] }, "__symbol__@effects": [1] },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 2, "__symbol__@price": 2.5, "__symbol__@consumable": null, "__symbol__@name": "__symbol__key", "__symbol__@icon": null, "__symbol__@effects": "none" },
+    ]);
+
+    let batch = to_record_batch(&value).unwrap();
+    let schema = batch.schema();
+    let types: Vec<(&str, &DataType)> = schema
+        .fields()
+        .iter()
+        .map(|field| (field.name().as_str(), field.data_type()))
+        .collect();
+
+    assert_eq!(
+        types,
+        [
+            ("class", &DataType::Utf8),
+            ("@id", &DataType::Int64),
+            ("@price", &DataType::Float64),
+            ("@consumable", &DataType::Boolean),
+            ("@name", &DataType::Utf8),
+            ("@icon", &DataType::Binary),
+            ("@effects", &DataType::Utf8),
+        ]
+    );
+    assert_eq!(batch.num_rows(), 3);
+
+    let class = batch.column(0).as_string::<i32>();
+    assert!(class.is_null(0));
+    assert_eq!(class.value(1), "RPG::Item");
+
+    assert_eq!(batch.column(1).as_primitive::<Int64Type>().value(2), 2);
+    assert_eq!(batch.column(2).as_primitive::<Float64Type>().value(1), 10.0);
+    assert!(batch.column(3).as_boolean().is_null(2));
+    assert_eq!(batch.column(4).as_string::<i32>().value(2), ":key");
+    assert_eq!(batch.column(5).as_binary::<i32>().value(1), [1, 2]);
+    assert_eq!(batch.column(6).as_string::<i32>().value(1), "[1]");
+
+    let mut parquet: Vec<u8> = Vec::new();
+    to_parquet(&mut parquet, &value).unwrap();
+
+    let batches: Vec<_> = parquet::arrow::arrow_reader::ParquetRecordBatchReader::try_new(
+        bytes::Bytes::from(parquet),
+        16,
+    )
+    .unwrap()
+    .collect::<Result<_, _>>()
+    .unwrap();
+
+    assert_eq!(batches, [batch]);
+    assert!(to_record_batch(&json!([1])).is_err());
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite() {
+    use rusqlite::Connection;
+
+    let path = std::env::temp_dir().join(format!("marshal-rs-export-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let value = json!([
+        null,
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 1, "__symbol__@name": "Potion", "__symbol__@consumable": true, "__symbol__@icon": { "__type": "bytes", "data": [1, 2] },
+          "__symbol__@effects": [{ "__class": "__symbol__RPG::Effect", "__type": "object", "__symbol__@code": 11 }, 5],
+          "__symbol__@meta": { "__symbol__tag": "heal", "__integer__2": [1] } },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@id": 2, "__symbol__@name": "__symbol__key", "__symbol__@consumable": false, "__symbol__@icon": null,
+          "__symbol__@effects": [], "__symbol__@meta": {} },
+    ]);
+
+    to_sqlite(&path, &value, SqliteOptions::default()).unwrap();
+
+    {
+        let connection = Connection::open(&path).unwrap();
+        type ItemRow = (i64, i64, String, bool, Option<Vec<u8>>, String);
+
+        let rows: Vec<ItemRow> = connection
+            .prepare("SELECT __rowid, \"@id\", \"@name\", \"@consumable\", \"@icon\", \"@effects\" FROM \"RPG::Item\"")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            [
+                (1, 1, "Potion".to_string(), true, Some(vec![1, 2]), "[{\"__class\":\"__symbol__RPG::Effect\",\"__type\":\"object\",\"__symbol__@code\":11},5]".to_string()),
+                (2, 2, ":key".to_string(), false, None, "[]".to_string()),
+            ]
+        );
+    }
+
+    assert!(to_sqlite(&path, &value, SqliteOptions::default()).is_err());
+
+    let options = SqliteOptions {
+        nested: NestedMode::ChildTables,
+        replace: true,
+    };
+    to_sqlite(&path, &value, options).unwrap();
+
+    {
+        let connection = Connection::open(&path).unwrap();
+        let effects: Vec<(i64, i64, i64)> = connection
+            .prepare("SELECT __parent, __key, value FROM \"RPG::Item.@effects\"")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(effects, [(1, 0, 1), (1, 1, 5)]);
+
+        let code: i64 = connection
+            .query_row(
+                "SELECT \"@code\" FROM \"RPG::Effect\" WHERE __rowid = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(code, 11);
+
+        let meta: Vec<(String, Option<String>)> = connection
+            .prepare("SELECT CAST(__key AS TEXT), value FROM \"RPG::Item.@meta\"")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            meta,
+            [
+                (":tag".to_string(), Some("heal".to_string())),
+                ("2".to_string(), None)
+            ]
+        );
+
+        let nested: i64 = connection
+            .query_row(
+                "SELECT value FROM \"RPG::Item.@meta.value\" WHERE __parent = 2",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(nested, 1);
+    }
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(to_sqlite(&path, &json!([1]), SqliteOptions::default()).is_err());
+    let _ = std::fs::remove_file(&path);
+}
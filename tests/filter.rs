@@ -0,0 +1,90 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::filter::{eval, Filter};
+use serde_json::json;
+
+fn map() -> serde_json::Value {
+    json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@name": "Town",
+        "__symbol__@events": [
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Guard", "__symbol__@x": 1 },
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss", "__symbol__@x": 7.5 },
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Chest", "__symbol__@x": 3 }
+        ],
+        "__symbol__@data": { "__symbol__bgm": "town", "__integer__1": "one", "plain key": [true] }
+    })
+}
+
+#[test]
+fn paths() {
+    let map = map();
+
+    assert_eq!(eval(".", &map).unwrap(), vec![map.clone()]);
+    assert_eq!(eval(".@name", &map).unwrap(), [json!("Town")]);
+    assert_eq!(eval(".__symbol__@name", &map).unwrap(), [json!("Town")]);
+    assert_eq!(eval(".@events[-1].@name", &map).unwrap(), [json!("Chest")]);
+    assert_eq!(eval(".@events.[0] | .@x", &map).unwrap(), [json!(1)]);
+    assert_eq!(eval(".@data.bgm", &map).unwrap(), [json!("town")]);
+    assert_eq!(
+        eval(".@data[\"plain key\"][0]", &map).unwrap(),
+        [json!(true)]
+    );
+    assert_eq!(eval(".@missing.@name", &map).unwrap(), [json!(null)]);
+    assert_eq!(eval(".@events[5]", &map).unwrap(), [json!(null)]);
+    assert_eq!(
+        eval(".@events[] | .@name", &map).unwrap(),
+        [json!("Guard"), json!("Boss"), json!("Chest")]
+    );
+    assert_eq!(eval(".@events | length", &map).unwrap(), [json!(3)]);
+    assert_eq!(
+        eval(".@events[0] | keys", &map).unwrap(),
+        [json!(["__symbol__@name", "__symbol__@x"])]
+    );
+    assert_eq!(eval(".@data | class", &map).unwrap(), [json!("Hash")]);
+}
+
+#[test]
+fn conditions() {
+    let map = map();
+
+    assert_eq!(
+        eval(".@events[] | select(.@name == \"Boss\") | .@x", &map).unwrap(),
+        [json!(7.5)]
+    );
+    assert_eq!(
+        eval(".@events[] | select(.@x >= 3 and .@x < 7) | .@name", &map).unwrap(),
+        [json!("Chest")]
+    );
+    assert_eq!(
+        eval(
+            ".@events[] | select(.@x == 1 or (.@name | . > \"C\" and . != \"Guard\")) | .@name",
+            &map
+        )
+        .unwrap(),
+        [json!("Guard"), json!("Chest")]
+    );
+    assert_eq!(
+        eval(
+            ".. | select(class == \"RPG::Event\") | .@x | . == 1 | not",
+            &map
+        )
+        .unwrap(),
+        [json!(false), json!(true), json!(true)]
+    );
+    assert_eq!(eval("1 == 1.0", &map).unwrap(), [json!(true)]);
+}
+
+#[test]
+fn errors() {
+    let map = map();
+
+    for expression in [
+        "", ".[", ".@x ==", "\"open", "unknown", "select(.", ". )", ". & .",
+    ] {
+        assert!(Filter::parse(expression).is_err(), "{expression}");
+    }
+
+    assert!(eval(".@name[]", &map).is_err());
+    assert!(eval(".@events < 1", &map).is_err());
+}
@@ -0,0 +1,100 @@
+use marshal_rs::{
+    dump,
+    forensics::{carve, extract_strings},
+};
+#[cfg(not(feature = "sonic"))]
+use serde_json::json;
+#[cfg(feature = "sonic")]
+use sonic_rs::json;
+
+#[test]
+fn carve_documents() {
+    let first: Vec<u8> = dump(json!([1, "\x04\x08"]), None);
+    let second: Vec<u8> = dump(json!({ "__symbol__key": 2.5 }), None);
+
+    let mut blob: Vec<u8> = vec![0xFF, 0x04];
+    blob.extend(&first);
+    // Truncated document
+    blob.extend(b"\x00\x04\x08[\x07i\x06");
+    blob.extend(&second);
+    // Hash with nil key
+    blob.extend(b"\x04\x08{\x060i\x06");
+
+    let documents = carve(&blob);
+    let offsets: Vec<usize> = documents.iter().map(|(offset, _)| *offset).collect();
+    let second_offset: usize = 2 + first.len() + 7;
+
+    assert_eq!(
+        offsets,
+        [
+            2,
+            2 + first.len() + 1,
+            second_offset,
+            second_offset + second.len()
+        ]
+    );
+    assert_eq!(documents[0].1.as_ref().unwrap(), &json!([1, "\x04\x08"]));
+    assert!(documents[1].1.is_err());
+    assert_eq!(
+        documents[2].1.as_ref().unwrap(),
+        &json!({ "__symbol__key": 2.5 })
+    );
+    assert_eq!(
+        documents[3].1.as_ref().unwrap(),
+        &json!({ "__nil__null": 1 })
+    );
+
+    assert!(carve(b"no documents").is_empty());
+}
+
+#[test]
+fn extract_broken_strings() {
+    let mut bytes: Vec<u8> = b"\x04\x08[\x09\xee".to_vec();
+    // Binary string, a string in Shift_JIS, a symbol, linked to `encoding`, and a string with a control character
+    bytes.extend(b"\"\x0abytes");
+    bytes.extend(b"I\"\x07\x82\xa0\x06:\x0dencoding\"\x0eShift_JIS");
+    bytes.extend(b"I:\x07\xc3\xa9\x06;\x06\"\x0aUTF-8");
+    bytes.extend(b"\"\x07a\x07i\x06");
+
+    let strings = extract_strings(&bytes, 1);
+    let found: Vec<(usize, bool, &str, &str, bool)> = strings
+        .iter()
+        .map(|string| {
+            (
+                string.offset,
+                string.symbol,
+                string.text.as_str(),
+                string.encoding.as_str(),
+                string.guessed,
+            )
+        })
+        .collect();
+
+    #[cfg(feature = "encodings")]
+    assert_eq!(
+        found,
+        [
+            (5, false, "bytes", "UTF-8", true),
+            (13, false, "あ", "Shift_JIS", false),
+            (40, true, "é", "UTF-8", false),
+        ]
+    );
+    // Shift_JIS can't be decoded, so its instance variable is found instead
+    #[cfg(not(feature = "encodings"))]
+    assert_eq!(
+        found,
+        [
+            (5, false, "bytes", "UTF-8", true),
+            (18, true, "encoding", "UTF-8", true),
+            (28, false, "Shift_JIS", "UTF-8", true),
+            (40, true, "é", "UTF-8", false),
+        ]
+    );
+
+    let long_strings = extract_strings(&bytes, 5);
+
+    assert_eq!(long_strings[0].text, "bytes");
+    assert!(long_strings
+        .iter()
+        .all(|string| string.text.chars().count() >= 5));
+}
@@ -0,0 +1,26 @@
+#![cfg(all(feature = "arbitrary", not(feature = "sonic")))]
+use arbitrary::{Arbitrary, Unstructured};
+use marshal_rs::{dump, fuzz::ArbitraryValue, load};
+
+#[test]
+fn arbitrary_roundtrip() {
+    // Deterministic pseudo-random input, so failures are reproducible
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+
+    for _ in 0..512 {
+        let input: Vec<u8> = (0..1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        let ArbitraryValue(value) =
+            ArbitraryValue::arbitrary(&mut Unstructured::new(&input)).unwrap();
+        let loaded = load(&dump(value.clone(), None), None, None).unwrap();
+
+        assert_eq!(loaded, value);
+    }
+}
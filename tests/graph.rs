@@ -0,0 +1,51 @@
+#![cfg(feature = "graph")]
+use marshal_rs::graph::{analyze, GraphEdge};
+
+#[test]
+fn shared() {
+    // [a, a, "str", "str"], where a = Object.new
+    let graph = analyze(b"\x04\x08[\x09o:\x0bObject\x00@\x06I\"\x08str\x06:\x06ET@\x07").unwrap();
+
+    assert_eq!(graph.graph.node_count(), 3);
+    assert_eq!(
+        graph.graph[graph.graph.node_indices().nth(1).unwrap()]
+            .class
+            .as_deref(),
+        Some("Object")
+    );
+    assert_eq!(graph.shared(), vec![1, 2]);
+    assert_eq!(
+        graph
+            .graph
+            .edge_weights()
+            .filter(|&&edge| edge == GraphEdge::Link)
+            .count(),
+        2
+    );
+    assert!(!graph.has_cycles());
+}
+
+#[test]
+fn cycles() {
+    // a = []; a << a
+    let graph = analyze(b"\x04\x08[\x06@\x00").unwrap();
+
+    assert!(graph.has_cycles());
+    assert_eq!(graph.cycles(), &[(0, 0)]);
+}
+
+#[test]
+fn to_dot() {
+    let dot: String = analyze(b"\x04\x08[\x07[\x00@\x06").unwrap().to_dot();
+
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains("#1 Array"));
+    assert!(dot.contains("0 -> 1 [ style = dashed"));
+}
+
+#[test]
+fn invalid() {
+    assert!(analyze(b"\x04\x09[\x00").is_err());
+    assert!(analyze(b"\x04\x08[\x06@\x07").is_err());
+    assert!(analyze(b"\x04\x08[\x07").is_err());
+}
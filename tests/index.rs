@@ -0,0 +1,77 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    dump,
+    index::{DocumentEntry, DocumentIndex},
+    load,
+};
+use serde_json::json;
+use std::io::Cursor;
+
+#[test]
+fn index_documents() {
+    let documents: Vec<Vec<u8>> = vec![
+        dump(
+            json!({ "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Eric", "__symbol__@exp": 1.5 }),
+            None,
+        ),
+        dump(
+            json!({ "__integer__1": [null, true, false], "__ruby_default__": "__symbol__none" }),
+            None,
+        ),
+        dump(
+            json!([{ "__type": "bigint", "negative": true, "data": [0, 0, 0, 0, 0, 0, 0, 0, 2, 0] }, { "__type": "regexp", "expression": "a+", "flags": "i" }]),
+            None,
+        ),
+        b"\x04\x08u:\x09Time\x06\x00".to_vec(),
+        b"\x04\x08e:\x0aMyModo:\x0bObject\x00".to_vec(),
+        b"\x04\x08[\x07:\x06a;\x00".to_vec(),
+    ];
+
+    let bytes: Vec<u8> = documents.concat();
+    let mut index = DocumentIndex::build(Cursor::new(bytes.clone())).unwrap();
+
+    assert_eq!(index.len(), documents.len());
+
+    let mut offset: u64 = 0;
+
+    for (number, document) in documents.iter().enumerate() {
+        let length: u64 = document.len() as u64;
+
+        assert_eq!(index.entries()[number], DocumentEntry { offset, length });
+        assert_eq!(&index.read(number).unwrap(), document);
+        assert_eq!(
+            index.load(number, None, None).unwrap(),
+            load(document, None, None).unwrap()
+        );
+
+        offset += length;
+    }
+
+    assert!(index.read(documents.len()).is_err());
+}
+
+#[test]
+fn index_appended_documents() {
+    let first: Vec<u8> = dump(json!("first"), None);
+    let second: Vec<u8> = dump(json!([1, 2, 3]), None);
+
+    // The second document is still being written
+    let mut bytes: Vec<u8> = [&first[..], &second[..4]].concat();
+    let index = DocumentIndex::build(Cursor::new(bytes.clone())).unwrap();
+    assert_eq!(index.len(), 1);
+
+    bytes.extend_from_slice(&second[4..]);
+    bytes.extend_from_slice(&first);
+
+    let entries: Vec<DocumentEntry> = index.entries().to_vec();
+    let mut index = DocumentIndex::from_entries(Cursor::new(bytes), entries);
+
+    assert_eq!(index.update().unwrap(), 2);
+    assert_eq!(index.load(1, None, None).unwrap(), json!([1, 2, 3]));
+    assert_eq!(index.load(2, None, None).unwrap(), json!("first"));
+    assert_eq!(index.update().unwrap(), 0);
+
+    let invalid: Vec<u8> = [&first[..], b"\x04\x08x"].concat();
+    assert!(DocumentIndex::build(Cursor::new(invalid)).is_err());
+    assert!(DocumentIndex::build(Cursor::new(b"\x04\x09T".to_vec())).is_err());
+}
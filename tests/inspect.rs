@@ -0,0 +1,61 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{dump, inspect::profile, load, ValueExt};
+use serde_json::json;
+
+#[test]
+fn profile_classes() {
+    let value = json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@events": [
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss", "__symbol__@id": 1 },
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Chest", "__symbol__@id": 2 }
+        ],
+        "__symbol__@data": { "__type": "bytes", "data": [1, 2, 3] }
+    });
+    let profile = profile(&value);
+
+    assert_eq!(profile.classes["RPG::Event"].count, 2);
+    assert_eq!(profile.classes["RPG::Event"].string_bytes, 9);
+    assert_eq!(profile.classes["RPG::Map"].string_bytes, 12);
+    assert_eq!(profile.classes["String"].count, 3);
+    assert_eq!(profile.classes["Integer"].count, 2);
+    assert_eq!(profile.by_size()[0].0, "RPG::Map");
+}
+
+#[test]
+fn profile_estimated_size() {
+    let bytes: &[u8] = b"\x04\x08o:\x11CustomObject\x06:\x0a@dataI\"\x10object data\x06:\x06ET";
+    let value = load(bytes, None, None).unwrap();
+    let profile = profile(&value);
+
+    // Estimate doesn't account for version bytes and symbol links
+    assert_eq!(
+        profile.classes["CustomObject"].estimated_size,
+        dump(value, None).len() - 2
+    );
+}
+
+#[test]
+fn summarize_truncates() {
+    let value = json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@data": { "__class": "__symbol__Table", "__type": "object", "__userDefined": vec![0; 20] },
+        "__symbol__@events": {
+            "__integer__1": { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Boss", "__symbol__@pages": [1, 2, 3] },
+            "__symbol__note": "x".repeat(50),
+            "__integer__3": null
+        }
+    });
+
+    assert_eq!(
+        value.summarize(2, 2),
+        format!(
+            "RPG::Map (2 ivars)\n  @data: Table (_dump, 20 bytes)\n  @events: Hash (3 entries)\n    1: RPG::Event (2 ivars) …\n    :note: {:?}… (50 bytes)\n    … 1 more",
+            "x".repeat(40)
+        )
+    );
+    assert_eq!(value.summarize(0, 10), "RPG::Map (2 ivars) …");
+    assert_eq!(json!([]).summarize(1, 1), "Array (0)");
+}
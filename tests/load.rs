@@ -1,12 +1,18 @@
 #![allow(clippy::approx_constant)]
-use marshal_rs::{load, StringMode};
+use marshal_rs::{
+    load,
+    load::{LoadErrorKind, PLACEHOLDER_CLASS},
+    raw::Constants,
+    DisallowedClassPolicy, DuplicateKeyPolicy, Loader, NonFiniteFloatPolicy, Preset, StringMode,
+};
 #[cfg(not(feature = "sonic"))]
 use serde_json::json;
 #[cfg(feature = "sonic")]
 use sonic_rs::json;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[test]
-#[should_panic(expected = "Incompatible Marshal file format or version.")]
+#[should_panic(expected = "Incompatible Marshal file format or version: found 4.9, expected 4.8.")]
 fn invalid_marshal_version() {
     load(b"\x04\x090", None, None).unwrap();
 }
@@ -58,6 +64,20 @@ fn fixnum_negative() {
 }
 
 #[test]
+#[cfg(not(feature = "bigint"))]
+fn bignum_raw() {
+    let marshal: &[u8] = b"\x04\x08l-\n\0\0\0\0\0\0\0\0\x02\0";
+    let value = load(marshal, None, None).unwrap();
+
+    assert_eq!(
+        value,
+        json!({"__type": "bigint", "negative": true, "data": [0, 0, 0, 0, 0, 0, 0, 0, 2, 0]})
+    );
+    assert_eq!(marshal_rs::dump(value, None), marshal);
+}
+
+#[test]
+#[cfg(feature = "bigint")]
 fn bignum_positive() {
     assert_eq!(
         load(b"\x04\x08l+\n\0\0\0\0\0\0\0\0\x02\0", None, None).unwrap(),
@@ -76,6 +96,7 @@ fn bignum_positive() {
 }
 
 #[test]
+#[cfg(feature = "bigint")]
 fn bignum_negative() {
     assert_eq!(
         load(b"\x04\x08l-\n\0\0\0\0\0\0\0\0\x02\0", None, None).unwrap(),
@@ -124,6 +145,49 @@ fn string_utf8() {
 }
 
 #[test]
+fn non_finite_floats() {
+    // [NaN, Infinity, -Infinity, 1.5]
+    let bytes: &[u8] = b"\x04\x08[\x09f\x08nanf\x08inff\x09-inff\x081.5";
+    let load = |policy: NonFiniteFloatPolicy| {
+        Loader::builder()
+            .non_finite_float_policy(policy)
+            .build()
+            .load(bytes, None, None)
+    };
+
+    assert_eq!(
+        load(NonFiniteFloatPolicy::Null).unwrap(),
+        json!([null, null, null, 1.5])
+    );
+    assert_eq!(
+        load(NonFiniteFloatPolicy::String).unwrap(),
+        json!(["NaN", "Infinity", "-Infinity", 1.5])
+    );
+    assert_eq!(
+        load(NonFiniteFloatPolicy::Error).unwrap_err().to_string(),
+        "Float nan can't be represented at position 5."
+    );
+
+    let tagged = load(NonFiniteFloatPolicy::Tagged).unwrap();
+
+    assert_eq!(tagged[1], json!({ "__type": "float", "value": "inf" }));
+    assert_eq!(marshal_rs::dump(tagged, None), bytes);
+}
+
+#[test]
+fn string_us_ascii() {
+    let marshal: &[u8] = b"\x04\x08I\"\x081.5\x06:\x06EF";
+    let value = load(marshal, None, None).unwrap();
+
+    assert_eq!(
+        value,
+        json!({ "__type": "string", "value": "1.5", "encoding": "US-ASCII" })
+    );
+    assert_eq!(marshal_rs::dump(value, None), marshal);
+}
+
+#[test]
+#[cfg(feature = "encodings")]
 fn string_nonutf8() {
     assert_eq!(
         load(
@@ -136,6 +200,19 @@ fn string_nonutf8() {
     );
 }
 
+#[test]
+#[cfg(not(feature = "encodings"))]
+fn string_nonutf8_undecoded() {
+    let marshal: &[u8] = b"\x04\x08I\"\x0b\xBA\xBA\xD7\xD6\xC4\xDA\x06:\rencoding\"\x08GBK";
+    let value = load(marshal, None, None).unwrap();
+
+    assert_eq!(
+        value,
+        json!({ "__type": "bytes", "data": [0xBA, 0xBA, 0xD7, 0xD6, 0xC4, 0xDA], "encoding": "GBK" })
+    );
+    assert_eq!(marshal_rs::dump(value, None), marshal);
+}
+
 #[test]
 fn string_binary() {
     assert_eq!(
@@ -234,3 +311,451 @@ fn object() {
         json!({"__class": "__symbol__CustomObject", "__symbol__@data": "object data", "__type": "object"})
     );
 }
+
+#[test]
+fn duplicate_keys() {
+    let bytes: &[u8] = b"\x04\x08{\x07i\x06i\x06i\x06i\x07";
+    let mut loader = Loader::new();
+
+    assert_eq!(
+        loader.load(bytes, None, None).unwrap(),
+        json!({"__integer__1": 2})
+    );
+    assert_eq!(loader.warnings().len(), 1);
+
+    loader.set_duplicate_key_policy(DuplicateKeyPolicy::FirstWins);
+    assert_eq!(
+        loader.load(bytes, None, None).unwrap(),
+        json!({"__integer__1": 1})
+    );
+
+    loader.set_duplicate_key_policy(DuplicateKeyPolicy::Collect);
+    assert_eq!(
+        loader.load(bytes, None, None).unwrap(),
+        json!({"__integer__1": 2})
+    );
+    assert_eq!(
+        loader.duplicates(),
+        &[("__integer__1".to_string(), json!(1))]
+    );
+
+    loader.set_duplicate_key_policy(DuplicateKeyPolicy::Error);
+    assert!(loader.load(bytes, None, None).is_err());
+    assert!(loader
+        .load(
+            b"\x04\x08o:\x0bObject\x07:\x07@ai\x06;\x06i\x07",
+            None,
+            None
+        )
+        .is_err());
+}
+
+#[test]
+fn builder_presets() {
+    // "a" without encoding, and { 1 => 1, 1 => 2 }
+    let string = b"\x04\x08\"\x06a";
+    let duplicates = b"\x04\x08{\x07i\x06i\x06i\x06i\x07";
+
+    let mut loader = Loader::builder().preset(Preset::RpgMakerXp).build();
+    assert_eq!(loader.load(string, None, None).unwrap(), json!("a"));
+    assert_eq!(
+        loader.load(string, Some(StringMode::Binary), None).unwrap(),
+        json!({ "__type": "bytes", "data": [97] })
+    );
+
+    let mut loader = Loader::builder().preset(Preset::RailsCache).build();
+    assert_eq!(
+        loader.load(string, None, None).unwrap(),
+        json!({ "__type": "bytes", "data": [97] })
+    );
+    assert!(loader.load(duplicates, None, None).is_err());
+
+    let mut loader = Loader::builder()
+        .preset(Preset::RailsCache)
+        .duplicate_key_policy(DuplicateKeyPolicy::FirstWins)
+        .instance_var_prefix("_")
+        .build();
+    assert_eq!(
+        loader.load(duplicates, None, None).unwrap(),
+        json!({ "__integer__1": 1 })
+    );
+    assert_eq!(
+        loader
+            .load(b"\x04\x08o:\x06A\x06:\x07@ai\x06", None, None)
+            .unwrap(),
+        json!({ "__class": "__symbol__A", "__type": "object", "__symbol___a": 1 })
+    );
+}
+
+#[test]
+fn bytes_input() {
+    let body: bytes::Bytes = bytes::Bytes::from_static(b"\x04\x08[\x06i\x06");
+    let vec: Vec<u8> = body.to_vec();
+
+    assert_eq!(load(&body, None, None).unwrap(), json!([1]));
+    assert_eq!(load(&vec, None, None).unwrap(), json!([1]));
+    assert_eq!(Loader::new().load(&body, None, None).unwrap(), json!([1]));
+}
+
+#[test]
+fn loader_outlives_buffers() {
+    let mut loader = Loader::builder().instance_var_prefix("_").build();
+
+    for integer in 1..4u8 {
+        // Buffers and prefixes only have to live for the duration of the call
+        let buffer: Vec<u8> = vec![0x04, 0x08, b'[', 0x06, b'i', integer + 5];
+        let prefix: String = String::from("@");
+
+        assert_eq!(
+            loader.load(&buffer, None, Some(&prefix)).unwrap(),
+            json!([integer])
+        );
+    }
+
+    // Tables and warnings of the last load are kept by the Loader
+    loader.set_duplicate_key_policy(DuplicateKeyPolicy::Collect);
+    let buffer: Vec<u8> = b"\x04\x08{\x07i\x06i\x06i\x06i\x07".to_vec();
+    assert_eq!(
+        loader.load(&buffer, None, None).unwrap(),
+        json!({ "__integer__1": 2 })
+    );
+    drop(buffer);
+    assert_eq!(loader.duplicates().len(), 1);
+}
+
+#[test]
+fn hardened() {
+    let nested = |depth: usize| [&b"\x04\x08"[..], &b"[\x06".repeat(depth), b"0"].concat();
+    let (shallow, deep): (Vec<u8>, Vec<u8>) = (nested(32), nested(100));
+    let mut loader = Loader::hardened();
+
+    assert_eq!(
+        loader
+            .load(b"\x04\x08{\x06i\x06I\"\x06a\x06:\x06ET", None, None)
+            .unwrap(),
+        json!({ "__integer__1": "a" })
+    );
+
+    // Object.new, class reference, trailing bytes, duplicate keys and NaN
+    assert!(loader
+        .load(b"\x04\x08o:\x0bObject\x00", None, None)
+        .is_err());
+    assert!(loader.load(b"\x04\x08c\x0bObject", None, None).is_err());
+    assert!(loader.load(b"\x04\x080\x00", None, None).is_err());
+    assert!(loader
+        .load(b"\x04\x08{\x07i\x06i\x06i\x06i\x07", None, None)
+        .is_err());
+    assert!(loader.load(b"\x04\x08f\x08nan", None, None).is_err());
+    // Invalid UTF-8, marked as UTF-8
+    assert!(loader
+        .load(b"\x04\x08I\"\x06\xff\x06:\x06ET", None, None)
+        .is_err());
+
+    // Length of 2 ** 24, and nesting depths of 32 and 100
+    assert!(loader
+        .load(b"\x04\x08[\x04\x00\x00\x00\x01", None, None)
+        .is_err());
+    assert!(loader.load(&shallow, None, None).is_ok());
+    assert!(loader.load(&deep, None, None).is_err());
+
+    let classes: &[&str] = &["Object"];
+    let mut loader = Loader::builder()
+        .hardened()
+        .allowed_classes(classes)
+        .build();
+    assert_eq!(
+        loader
+            .load(b"\x04\x08o:\x0bObject\x00", None, None)
+            .unwrap(),
+        json!({ "__class": "__symbol__Object", "__type": "object" })
+    );
+    assert!(loader.load(b"\x04\x08o:\x06A\x00", None, None).is_err());
+}
+
+#[test]
+fn hardened_nil_and_boolean_keys() {
+    let mut loader = Loader::hardened();
+
+    // {nil => 1}, {true => 1} and {false => 1}, which used to panic
+    for (bytes, hash) in [
+        (&b"\x04\x08{\x060i\x06"[..], json!({ "__nil__null": 1 })),
+        (b"\x04\x08{\x06Ti\x06", json!({ "__boolean__true": 1 })),
+        (b"\x04\x08{\x06Fi\x06", json!({ "__boolean__false": 1 })),
+    ] {
+        assert_eq!(loader.load(bytes, None, None).unwrap(), hash);
+        assert_eq!(marshal_rs::dump(hash, None), bytes);
+    }
+
+    // {"__true__" => 1} keeps its string key, that only looks like a marker
+    let bytes: &[u8] = b"\x04\x08{\x06I\"\x0d__true__\x06:\x06ETi\x06";
+    let hash = loader.load(bytes, None, None).unwrap();
+
+    assert_eq!(hash, json!({ "__true__": 1 }));
+    assert_eq!(marshal_rs::dump(hash, None), bytes);
+
+    // {"__type" => "bytes"} with an encoding instance variable, which only strings have
+    assert_eq!(
+        loader
+            .load(
+                b"\x04\x08I{\x06I\"\x0b__type\x06:\x06ETI\"\x0abytes\x06;\x00T\x06;\x00T",
+                None,
+                None
+            )
+            .unwrap_err()
+            .to_string(),
+        "Encoding instance variable of a value, that isn't a string, before position 35."
+    );
+}
+
+#[test]
+fn hardened_floats() {
+    let mut loader = Loader::hardened();
+
+    // Infinities would be loaded as null, and dumped back as nil
+    assert_eq!(
+        loader
+            .load(b"\x04\x08f\x08inf", None, None)
+            .unwrap_err()
+            .to_string(),
+        "Infinity can't be represented at position 3."
+    );
+    assert_eq!(
+        loader
+            .load(b"\x04\x08f\x09-inf", None, None)
+            .unwrap_err()
+            .to_string(),
+        "-Infinity can't be represented at position 3."
+    );
+
+    // Exponent isn't dropped
+    assert_eq!(
+        loader.load(b"\x04\x08f\x0c1.0e-05", None, None).unwrap(),
+        json!(0.00001)
+    );
+    assert_eq!(
+        load(b"\x04\x08f\x0c1.0e-05", None, None).unwrap(),
+        json!(0.00001)
+    );
+}
+
+#[test]
+fn denied_classes() {
+    let placeholder = format!("__symbol__{PLACEHOLDER_CLASS}");
+    let allowed: &[&str] = &["Object", "Comparable"];
+    let mut loader = Loader::builder()
+        .allowed_classes(allowed)
+        .denied_classes(&["Object"])
+        .build();
+
+    // Denied classes win over allowed ones
+    assert!(loader
+        .load(b"\x04\x08o:\x0bObject\x00", None, None)
+        .is_err());
+    // [Object, Comparable]
+    assert!(loader
+        .load(b"\x04\x08[\x07c\x0bObjectm\x0fComparable", None, None)
+        .is_err());
+
+    loader.set_disallowed_class_policy(DisallowedClassPolicy::Placeholder);
+    loader.set_allowed_classes(&["Comparable"]);
+    loader.set_denied_classes(&[]);
+
+    // [A.new.extend(Comparable), Struct::B.new, Object]
+    let value = loader
+        .load(
+            b"\x04\x08[\x08e:\x0fComparableo:\x06A\x00S:\x0eStruct::B\x00c\x0bObject",
+            None,
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        value,
+        json!([
+            { "__class": placeholder, "__type": "object", "__ruby_extends__": ["__symbol__Comparable"] },
+            { "__class": placeholder, "__type": "struct", "__members": {} },
+            { "__class": PLACEHOLDER_CLASS, "__type": "class" }
+        ])
+    );
+    assert_eq!(loader.warnings().len(), 3);
+    assert!(loader.warnings()[2].starts_with("Class Object is not allowed"));
+}
+
+#[test]
+fn cancellation() {
+    let flag: AtomicBool = AtomicBool::new(true);
+    let mut loader = Loader::builder().cancel_flag(&flag).build();
+
+    assert_eq!(
+        loader
+            .load(b"\x04\x08[\x06i\x06", None, None)
+            .unwrap_err()
+            .to_string(),
+        "Load was cancelled at position 2."
+    );
+
+    flag.store(false, Ordering::Relaxed);
+    assert_eq!(
+        loader.load(b"\x04\x08[\x06i\x06", None, None).unwrap(),
+        json!([1])
+    );
+}
+
+#[test]
+fn malformed_links() {
+    // Link to the 6th object, and an unknown structure type
+    assert!(load(b"\x04\x08[\x06@\x0b", None, None).is_err());
+    assert!(load(b"\x04\x08[\x06;\x00", None, None).is_err());
+    assert!(load(b"\x04\x08[\x06X", None, None).is_err());
+}
+
+#[test]
+fn invalid_lengths() {
+    // Huge and negative sizes of Arrays, Hashes, Bignums and strings
+    let cases: [(&[u8], i32, usize); 5] = [
+        (b"\x04\x08[\x04\xff\xff\xff\x7f", 2147483647, 3),
+        (b"\x04\x08[\xfa", -1, 3),
+        (b"\x04\x08{\xfa", -1, 3),
+        (b"\x04\x08l+\x03\xff\xff\xff", 16777215, 4),
+        (b"\x04\x08\"\xfa", -1, 3),
+    ];
+
+    for (bytes, value, offset) in cases {
+        let err = load(bytes, None, None).unwrap_err();
+
+        assert_eq!(err.kind(), LoadErrorKind::InvalidLength { value, offset });
+        assert_eq!(
+            err.to_string(),
+            format!("Invalid length {value} at position {offset}.")
+        );
+    }
+
+    // Lengths, that the remaining data can hold, fail only at their end
+    let err = load(b"\x04\x08[\x07i\x06", None, None).unwrap_err();
+
+    assert_eq!(err.kind(), LoadErrorKind::Other);
+    assert_eq!(err.to_string(), "Marshal data is too short.");
+}
+
+#[test]
+fn invalid_struct_members() {
+    // Struct::A with members, named by Arrays of bytes, that aren't bytes or UTF-8
+    for bytes in [
+        &b"\x04\x08S:\x06A\x06[\x06i\x02\xe8\x03i\x06"[..],
+        b"\x04\x08S:\x06A\x06[\x06i\x01\xffi\x06",
+    ] {
+        assert!(load(bytes, None, None)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid struct member name"));
+    }
+}
+
+#[test]
+fn memory_budget() {
+    // [[string of 1000 bytes], and 1000 links to it]
+    let mut bytes: Vec<u8> = b"\x04\x08[".to_vec();
+    marshal_rs::raw::write_int(&mut bytes, 1001);
+    bytes.extend_from_slice(b"[\x06\"");
+    marshal_rs::raw::write_int(&mut bytes, 1000);
+    bytes.extend_from_slice(&[b'a'; 1000]);
+    bytes.extend_from_slice(&b"@\x06".repeat(1000));
+
+    let mut loader = Loader::builder().track_allocations(true).build();
+    let value = loader.load(&bytes, Some(StringMode::UTF8), None).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 1001);
+    assert!(loader.allocated() > 1_000_000);
+
+    let mut loader = Loader::builder().memory_budget(100_000).build();
+    assert!(loader.load(&bytes, Some(StringMode::UTF8), None).is_err());
+    assert!(loader.load(b"\x04\x08[\x06i\x06", None, None).is_ok());
+    assert!(loader.allocated() > 0);
+
+    assert_eq!(Loader::new().allocated(), 0);
+}
+
+#[test]
+fn profile() {
+    // [{a: "x"}, @1] with the Hash linked
+    let bytes: &[u8] = b"\x04\x08[\x07{\x06:\x06aI\"\x06x\x06:\x06ET@\x06";
+    let mut loader = Loader::builder().profile(true).build();
+    loader.load(bytes, None, None).unwrap();
+
+    let profile = loader.profile().unwrap();
+    let stats = |structure_type: Constants| profile.types[&structure_type];
+
+    assert_eq!(stats(Constants::Array).count, 1);
+    assert_eq!(stats(Constants::Array).bytes, bytes.len() - 2);
+    assert_eq!(stats(Constants::Hash).count, 1);
+    assert_eq!(stats(Constants::Link).count, 1);
+    assert_eq!(stats(Constants::Link).bytes, 2);
+    assert_eq!(stats(Constants::Symbol).count, 2);
+    assert_eq!(stats(Constants::String).bytes, 3);
+    assert!(stats(Constants::Array).time >= stats(Constants::Hash).time);
+    assert!(stats(Constants::Array).self_time <= stats(Constants::Array).time);
+    assert_eq!(profile.by_self_time().len(), profile.types.len());
+
+    // Each load starts a new profile
+    loader.load(b"\x04\x08i\x06", None, None).unwrap();
+    assert_eq!(loader.profile().unwrap().types.len(), 1);
+
+    assert!(Loader::new().profile().is_none());
+}
+
+#[test]
+fn self_links() {
+    // a = []; a << a; a << "b"; a << a[1]
+    assert_eq!(
+        load(b"\x04\x08[\x08@\x00I\"\x06b\x06:\x06ET@\x06", None, None).unwrap(),
+        json!([[0, 0, 0], "b", "b"])
+    );
+}
+
+#[test]
+fn symbol_keys_as_strings() {
+    let mut loader = Loader::builder().symbol_keys_as_strings(true).build();
+
+    // { a: { b: 1 }, c: Hash.new(0) }, where the default Hash is empty
+    let bytes: &[u8] = b"\x04\x08{\x07:\x06a{\x06:\x06bi\x06:\x06c}\x00i\x00";
+    let value = loader.load(bytes, None, None).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "a": { "b": 1, "__ruby_symbol_keys__": true },
+            "c": { "__ruby_default__": 0 },
+            "__ruby_symbol_keys__": true
+        })
+    );
+    assert_eq!(marshal_rs::dump(value, None), bytes);
+
+    // { a: 1, "b" => 2, :__c => 3 } and { :__a => 1 }
+    assert_eq!(
+        loader
+            .load(b"\x04\x08[\x07{\x08:\x06ai\x06I\"\x06b\x06:\x06ETi\x07:\x08__ci\x08{\x06:\x08__ai\x06", None, None)
+            .unwrap(),
+        json!([
+            { "__symbol__a": 1, "b": 2, "__symbol____c": 3 },
+            { "__symbol____a": 1 }
+        ])
+    );
+}
+
+#[test]
+fn struct_members_as_strings() {
+    let mut loader = Loader::builder().struct_members_as_strings(true).build();
+
+    // Point.new(1, [2]), with members y, x and __z
+    let bytes: &[u8] = b"\x04\x08S:\x0aPoint\x08:\x06yi\x06:\x06x[\x06i\x07:\x08__z0";
+    let value = loader.load(bytes, None, None).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "__class": "__symbol__Point",
+            "__type": "struct",
+            "__members": { "y": 1, "x": [2], "__symbol____z": null }
+        })
+    );
+    assert_eq!(marshal_rs::dump(value, None), bytes);
+}
@@ -0,0 +1,70 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{merge::three_way, Path};
+use serde_json::json;
+
+#[test]
+fn merge_disjoint_changes() {
+    let base = json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@name": "Town",
+        "__symbol__@events": [
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Guard", "__symbol__@x": 1 },
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Chest", "__symbol__@x": 2 }
+        ],
+        "__symbol__@bgm": "town"
+    });
+
+    let mut ours = base.clone();
+    ours["__symbol__@events"][0]["__symbol__@name"] = json!("Wache");
+    ours.as_object_mut().unwrap().remove("__symbol__@bgm");
+
+    let mut theirs = base.clone();
+    theirs["__symbol__@events"][0]["__symbol__@x"] = json!(5);
+    theirs["__symbol__@events"][1]["__symbol__@name"] = json!("Truhe");
+    theirs["__symbol__@music"] = json!("new");
+
+    let merged = three_way(&base, &ours, &theirs).unwrap();
+
+    assert_eq!(merged["__symbol__@events"][0]["__symbol__@name"], "Wache");
+    assert_eq!(merged["__symbol__@events"][0]["__symbol__@x"], 5);
+    assert_eq!(merged["__symbol__@events"][1]["__symbol__@name"], "Truhe");
+    assert_eq!(merged["__symbol__@music"], "new");
+    assert!(merged.get("__symbol__@bgm").is_none());
+}
+
+#[test]
+fn merge_conflicts() {
+    let base =
+        json!({ "__symbol__@name": "Sword", "__symbol__@tags": [1, 2], "__symbol__@price": 1 });
+    let ours = json!({ "__symbol__@name": "Schwert", "__symbol__@tags": [1, 2, 3] });
+    let theirs =
+        json!({ "__symbol__@name": "Épée", "__symbol__@tags": [1], "__symbol__@price": 2 });
+
+    let conflicts = three_way(&base, &ours, &theirs).unwrap_err();
+    let paths: Vec<String> = conflicts
+        .iter()
+        .map(|conflict| conflict.path.to_string())
+        .collect();
+
+    assert_eq!(
+        paths,
+        vec!["/__symbol__@name", "/__symbol__@tags", "/__symbol__@price"]
+    );
+    assert_eq!(conflicts[0].theirs, Some(json!("Épée")));
+    assert_eq!(conflicts[2].ours, None);
+    assert_eq!(
+        conflicts[0].to_string(),
+        "Conflict at /__symbol__@name: base \"Sword\", ours \"Schwert\", theirs \"Épée\""
+    );
+
+    // Changing the class replaces the object as a whole
+    let base = json!({ "__class": "__symbol__A", "__type": "object", "__symbol__@a": 1 });
+    let ours = json!({ "__class": "__symbol__B", "__type": "object", "__symbol__@a": 1 });
+    let theirs = json!({ "__class": "__symbol__A", "__type": "object", "__symbol__@a": 2 });
+
+    assert_eq!(
+        three_way(&base, &ours, &theirs).unwrap_err()[0].path,
+        Path::new()
+    );
+}
@@ -0,0 +1,91 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    php::{from_php, to_php},
+    ValueExt,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn php_roundtrip() {
+    let value = json!([
+        null, true, false, 0, -1, 9223372036854775807i64, 1.5, -0.25, "text \"\u{e9}\";",
+        { "__type": "bytes", "data": [0, 255, 34] },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@stats": [1, 2] },
+        { "__class": "__symbol__Time", "__type": "object", "__userDefined": [1, 2, 3] },
+        { "__class": "Comparable", "__type": "module", "__old": false },
+        { "__integer__1": "one", "__integer__-5": "negative", "key": "string", "__ruby_default__": 0 },
+        [[]]
+    ]);
+
+    let php: Vec<u8> = value.to_php();
+    assert_eq!(Value::from_php(&php).unwrap(), value);
+}
+
+#[test]
+fn php_mapping() {
+    let value = json!({
+        "__symbol__sym": "__symbol__value",
+        "__float__1.5": { "__type": "bigint", "value": "36893488147419103232" },
+        "__array__[1]": { "__type": "regexp", "expression": "a/b", "flags": "im" },
+        "struct": { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1 } },
+        "empty": {},
+    });
+
+    assert_eq!(
+        to_php(&value),
+        b"a:5:{s:3:\"sym\";s:5:\"value\";s:3:\"1.5\";s:20:\"36893488147419103232\";s:12:\"__array__[1]\";s:8:\"/a\\/b/is\";\
+s:6:\"struct\";O:5:\"Point\":1:{s:1:\"x\";i:1;}s:5:\"empty\";a:0:{}}"
+    );
+    assert_eq!(
+        from_php(&to_php(&value)).unwrap(),
+        json!({
+            "sym": "value",
+            "1.5": "36893488147419103232",
+            "__array__[1]": "/a\\/b/is",
+            "struct": { "__class": "__symbol__Point", "__type": "object", "__symbol__@x": 1 },
+            "empty": [],
+        })
+    );
+}
+
+#[test]
+fn php_from_php() {
+    // serialize(["a" => [1, null, true, 1.5], 5 => "x", "obj" => new Foo\Bar()]), with public $name, protected $p and private $q
+    let php: &[u8] = b"a:3:{s:1:\"a\";a:4:{i:0;i:1;i:1;N;i:2;b:1;i:3;d:1.5;}i:5;s:1:\"x\";s:3:\"obj\";O:7:\"Foo\\Bar\":3:{s:4:\"name\";s:1:\"n\";s:4:\"\0*\0p\";i:1;s:10:\"\0Foo\\Bar\0q\";i:2;}}";
+
+    assert_eq!(
+        from_php(php).unwrap(),
+        json!({
+            "a": [1, null, true, 1.5],
+            "__integer__5": "x",
+            "obj": { "__class": "__symbol__Foo::Bar", "__type": "object", "__symbol__@name": "n", "__symbol__@p": 1, "__symbol__@q": 2 },
+        })
+    );
+
+    // $object = new stdClass(); serialize([$object, $object]);
+    assert_eq!(
+        from_php(b"a:2:{i:0;O:8:\"stdClass\":0:{}i:1;r:2;}").unwrap(),
+        json!([{ "__class": "__symbol__stdClass", "__type": "object" }, { "__class": "__symbol__stdClass", "__type": "object" }])
+    );
+
+    // $value = 1; serialize([&$value, &$value]);
+    assert_eq!(from_php(b"a:2:{i:0;i:1;i:1;R:2;}").unwrap(), json!([1, 1]));
+
+    // serialize(new ArrayObject());
+    assert_eq!(
+        from_php(b"C:11:\"ArrayObject\":21:{x:i:0;a:0:{};m:a:0:{}}").unwrap(),
+        json!({ "__class": "__symbol__ArrayObject", "__type": "object", "__userDefined": b"x:i:0;a:0:{};m:a:0:{}".to_vec() })
+    );
+}
+
+#[test]
+fn php_errors() {
+    assert!(from_php(b"").is_err());
+    assert!(from_php(b"i:1").is_err());
+    assert!(from_php(b"i:1;N;").is_err());
+    assert!(from_php(b"s:5:\"abc\";").is_err());
+    assert!(from_php(b"a:1000000:{}").is_err());
+    assert!(from_php(b"a:1:{i:0;r:5;}").is_err());
+    assert!(from_php(b"E:7:\"Foo:Bar\";").is_err());
+    assert!(from_php(&b"a:1:{i:0;".repeat(200)).is_err());
+}
@@ -0,0 +1,69 @@
+#![cfg(all(feature = "bigint", not(feature = "sonic")))]
+use marshal_rs::{
+    pickle::{from_pickle, to_pickle},
+    ValueExt,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn pickle_roundtrip() {
+    let value = json!([
+        null, true, false, 0, 255, 256, 65536, -1, 2147483648u64, -9223372036854775808i64, 18446744073709551615u64, 1.5, "text \u{e9}",
+        { "__type": "bytes", "data": [0, 255] },
+        { "__type": "bytes", "data": vec![7; 300] },
+        { "__type": "bigint", "value": "-36893488147419103232" },
+        { "__type": "regexp", "expression": "a+b", "flags": "ixm" },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@stats": [1, 2] },
+        { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1, "__symbol__y": 2 } },
+        { "__class": "Comparable", "__type": "module", "__old": false },
+        { "__class": "__symbol__Time", "__type": "object", "__userDefined": [1, 2, 3] },
+        { "__integer__1": "one", "__float__1.5": "float", "__array__[1,[2]]": "array", "key": "string", "__ruby_default__": 0 },
+        [], {}
+    ]);
+
+    let pickle: Vec<u8> = value.to_pickle();
+    assert_eq!(Value::from_pickle(&pickle).unwrap(), value);
+}
+
+#[test]
+fn pickle_symbols() {
+    let value = json!({ "__symbol__key": ["__symbol__a", "b"] });
+
+    assert_eq!(to_pickle(&value), to_pickle(&json!({ "key": ["a", "b"] })));
+    assert_eq!(
+        from_pickle(&to_pickle(&value)).unwrap(),
+        json!({ "key": ["a", "b"] })
+    );
+}
+
+#[test]
+fn pickle_from_python() {
+    // pickle.dumps({"a": [1, None, True, 2.5, b"\\x00\\xff", "\u{e9}", (1, 2), {1, 2}], 1: "one", (1, 2): "tuple", "big": 2 ** 70, "neg": -2 ** 40,
+    //     "re": re.compile("ab+", re.I | re.S), "item": Item(), "shared": [shared, shared]}, protocol=...)
+    let protocol_2: &[u8] = b"\x80\x02}q\x00(X\x01\x00\x00\x00aq\x01]q\x02(K\x01N\x88G@\x04\x00\x00\x00\x00\x00\x00c_codecs\nencode\nq\x03X\x03\x00\x00\x00\x00\xc3\xbfq\x04X\x06\x00\x00\x00latin1q\x05\x86q\x06Rq\x07X\x02\x00\x00\x00\xc3\xa9q\x08K\x01K\x02\x86q\tc__builtin__\nset\nq\n]q\x0b(K\x01K\x02e\x85q\x0cRq\reK\x01X\x03\x00\x00\x00oneq\x0eh\tX\x05\x00\x00\x00tupleq\x0fX\x03\x00\x00\x00bigq\x10\x8a\t\x00\x00\x00\x00\x00\x00\x00\x00@X\x03\x00\x00\x00negq\x11\x8a\x06\x00\x00\x00\x00\x00\xffX\x02\x00\x00\x00req\x12cre\n_compile\nq\x13X\x03\x00\x00\x00ab+q\x14K2\x86q\x15Rq\x16X\x04\x00\x00\x00itemq\x17c__main__\nItem\nq\x18)\x81q\x19}q\x1a(X\x04\x00\x00\x00nameq\x1bX\x05\x00\x00\x00Swordq\x1cX\x05\x00\x00\x00priceq\x1dK\nubX\x06\x00\x00\x00sharedq\x1e]q\x1f(]q K\x01ah eu.";
+    let protocol_4: &[u8] = b"\x80\x04\x95\xd3\x00\x00\x00\x00\x00\x00\x00}\x94(\x8c\x01a\x94]\x94(K\x01N\x88G@\x04\x00\x00\x00\x00\x00\x00C\x02\x00\xff\x94\x8c\x02\xc3\xa9\x94K\x01K\x02\x86\x94\x8f\x94(K\x01K\x02\x90eK\x01\x8c\x03one\x94h\x05\x8c\x05tuple\x94\x8c\x03big\x94\x8a\t\x00\x00\x00\x00\x00\x00\x00\x00@\x8c\x03neg\x94\x8a\x06\x00\x00\x00\x00\x00\xff\x8c\x02re\x94h\x0b\x8c\x08_compile\x94\x93\x94\x8c\x03ab+\x94K2\x86\x94R\x94\x8c\x04item\x94\x8c\x08__main__\x94\x8c\x04Item\x94\x93\x94)\x81\x94}\x94(\x8c\x04name\x94\x8c\x05Sword\x94\x8c\x05price\x94K\nub\x8c\x06shared\x94]\x94(]\x94K\x01ah\x1ceu.";
+
+    let expected = json!({
+        "a": [1, null, true, 2.5, { "__type": "bytes", "data": [0, 255] }, "\u{e9}", [1, 2], [1, 2]],
+        "__integer__1": "one",
+        "__array__[1,2]": "tuple",
+        "big": { "__type": "bigint", "value": "1180591620717411303424" },
+        "neg": -1099511627776i64,
+        "re": { "__type": "regexp", "expression": "ab+", "flags": "im" },
+        "item": { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@price": 10 },
+        "shared": [[1], [1]],
+    });
+
+    assert_eq!(from_pickle(protocol_2).unwrap(), expected);
+    assert_eq!(from_pickle(protocol_4).unwrap(), expected);
+}
+
+#[test]
+fn pickle_errors() {
+    assert!(from_pickle(b"").is_err());
+    assert!(from_pickle(b"\x80\x03N").is_err());
+    assert!(from_pickle(b"\x80\x03N..").is_err());
+    assert!(from_pickle(b"\x80\x03cos\nsystem\nX\x02\x00\x00\x00lsR.").is_err());
+    // Recursive list: l = []; l.append(l)
+    assert!(from_pickle(b"\x80\x03]q\x00h\x00a.").is_err());
+}
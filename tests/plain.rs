@@ -0,0 +1,95 @@
+#![cfg(feature = "serde")]
+use marshal_rs::{dump, load_json, NonFiniteFloatPolicy, PlainJsonOptions};
+use serde_json::json;
+
+#[test]
+fn numbers_and_links() {
+    // [2 ** 64, 2 ** 40, -(2 ** 63), [[1], [1]], [<link to itself>], 1.5, inf]
+    let bytes: &[u8] = b"\x04\x08[\x0cl+\x0a\x00\x00\x00\x00\x00\x00\x00\x00\x01\x00l+\x08\x00\x00\x00\x00\x00\x01l-\x09\x00\x00\x00\x00\x00\x00\x00\x80[\x07[\x06i\x06@\x0a[\x06@\x0bf\x081.5f\x08inf";
+
+    assert_eq!(
+        load_json(bytes, PlainJsonOptions::default()).unwrap(),
+        json!([
+            "18446744073709551616",
+            1099511627776u64,
+            i64::MIN,
+            [[1], [1]],
+            [null],
+            1.5,
+            null
+        ])
+    );
+
+    let options = PlainJsonOptions {
+        non_finite_float_policy: NonFiniteFloatPolicy::String,
+        ..Default::default()
+    };
+
+    assert_eq!(load_json(bytes, options).unwrap()[6], json!("Infinity"));
+}
+
+#[test]
+fn objects_and_hashes() {
+    let bytes: Vec<u8> = dump(
+        json!({
+            "__symbol__key": "__symbol__value",
+            "__integer__1": [
+                { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" },
+                { "__class": "__symbol__Pair", "__type": "struct", "__members": { "__symbol__left": 1 } }
+            ]
+        }),
+        None,
+    );
+
+    assert_eq!(
+        load_json(&bytes, PlainJsonOptions::default()).unwrap(),
+        json!({ "key": "value", "1": [{ "name": "Sword" }, { "left": 1 }] })
+    );
+
+    let options = PlainJsonOptions {
+        class_key: Some("$class".to_string()),
+        instance_var_prefix: "_".to_string(),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        load_json(&bytes, options).unwrap()["1"],
+        json!([{ "$class": "Item", "_name": "Sword" }, { "$class": "Pair", "left": 1 }])
+    );
+}
+
+#[test]
+fn strings() {
+    let binary: &[u8] = b"\x04\x08\"\x07\xff\x00";
+
+    assert_eq!(
+        load_json(binary, PlainJsonOptions::default()).unwrap(),
+        json!("\u{fffd}\u{0}")
+    );
+    assert_eq!(
+        load_json(
+            binary,
+            PlainJsonOptions {
+                binary_as_bytes: true,
+                ..Default::default()
+            }
+        )
+        .unwrap(),
+        json!([255, 0])
+    );
+
+    // US-ASCII "ab", and Shift_JIS "ア"
+    let encoded: &[u8] =
+        b"\x04\x08[\x07I\"\x07ab\x06:\x06EFI\"\x07\x83A\x06:\x0dencoding\"\x0eShift_JIS";
+    let expected = if cfg!(feature = "encodings") {
+        json!(["ab", "ア"])
+    } else {
+        json!(["ab", "\u{fffd}A"])
+    };
+
+    assert_eq!(
+        load_json(encoded, PlainJsonOptions::default()).unwrap(),
+        expected
+    );
+    assert!(load_json(b"\x04\x08[\x07i\x06", PlainJsonOptions::default()).is_err());
+}
@@ -0,0 +1,91 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    pool::{Pool, TablePool},
+    Dumper, Loader,
+};
+use serde_json::json;
+
+#[test]
+fn tables_are_returned() {
+    let pool = TablePool::new();
+    let bytes: Vec<u8> = marshal_rs::dump(json!(["__symbol__a", "__symbol__a"]), None);
+
+    let mut loader = Loader::builder().pool(&pool).build();
+    assert_eq!(
+        loader.load(&bytes, None, None).unwrap(),
+        json!(["__symbol__a", "__symbol__a"])
+    );
+    assert_eq!(pool.len(), 1);
+
+    // Tables are returned after failed loads too
+    assert!(loader.load(b"\x04\x08[\x07", None, None).is_err());
+    assert_eq!(pool.len(), 1);
+
+    let mut dumper = Dumper::builder().pool(&pool).build();
+    pool.recycle(dumper.dump(json!(null), None));
+    assert_eq!(pool.len(), 3);
+
+    pool.clear();
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn buffers_are_recycled() {
+    let pool = TablePool::new();
+    let mut dumper = Dumper::builder().pool(&pool).capacity(0).build();
+
+    let bytes: Vec<u8> = dumper.dump(json!("a".repeat(1000)), None);
+    let capacity: usize = bytes.capacity();
+    pool.recycle(bytes);
+    assert_eq!(pool.len(), 2);
+
+    // The buffer is taken, and only tables remain
+    let bytes: Vec<u8> = dumper.dump(json!(null), None);
+    assert_eq!(bytes, [4, 8, b'0']);
+    assert_eq!(bytes.capacity(), capacity);
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn pool_is_shared_by_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+    assert_send_sync::<Pool>();
+    assert_send_sync::<TablePool>();
+    assert_send::<Loader>();
+
+    let pool = Pool::new(Loader::builder().max_depth(2), Dumper::builder());
+
+    std::thread::scope(|scope| {
+        for number in 0..8 {
+            let pool = &pool;
+
+            scope.spawn(move || {
+                let bytes: Vec<u8> = pool.dumper().dump(json!([number]), None);
+                assert_eq!(
+                    pool.loader().load(&bytes, None, None).unwrap(),
+                    json!([number])
+                );
+                assert!(pool
+                    .loader()
+                    .load(b"\x04\x08[\x06[\x06[\x00", None, None)
+                    .is_err());
+
+                pool.recycle(bytes);
+            });
+        }
+    });
+
+    // Each thread returned its instances, and at most 8 of each were built, depending on how threads overlapped
+    let len: usize = pool.len();
+    assert!((3..=24).contains(&len), "{len}");
+
+    // Instances are handed out again, and returned when dropped
+    let loader = pool.loader();
+    assert_eq!(pool.len(), len - 1);
+    drop(loader);
+    assert_eq!(pool.len(), len);
+
+    pool.clear();
+    assert!(pool.is_empty());
+}
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "python", not(feature = "sonic")))]
+use marshal_rs::{dump, python::register};
+use pyo3::{ffi::c_str, prelude::*, types::PyModule};
+use serde_json::json;
+
+#[test]
+fn python_roundtrip() {
+    pyo3::prepare_freethreaded_python();
+
+    let bytes: Vec<u8> = dump(
+        json!({
+            "__symbol__key": [1, { "__type": "bigint", "value": "36893488147419103232" }, 1.5, null],
+            "__integer__2": { "__type": "bytes", "data": [0, 255] },
+            "__array__[1]": "unhashable",
+            "item": {
+                "__class": "__symbol__Item",
+                "__type": "object",
+                "__symbol__@name": "Sword",
+                "__ruby_extends__": ["__symbol__Enumerable"],
+            },
+            "point": { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1 } },
+            "class": { "__class": "RPG::Map", "__type": "class" },
+            "regexp": { "__type": "regexp", "expression": "a+", "flags": "i" },
+            "date": { "__class": "__symbol__Date", "__type": "object", "__userDefined": [1, 2] },
+        }),
+        None,
+    );
+
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "marshal_rs").unwrap();
+        register(&module).unwrap();
+
+        let globals = module.dict();
+        globals.set_item("data", &bytes).unwrap();
+
+        py.run(
+            c_str!(
+                r#"
+value = loads(bytes(data))
+
+assert value[Symbol("key")][0] == 1
+assert value[Symbol("key")][1] == 36893488147419103232
+assert value[2] == b"\x00\xff"
+assert value["__array__[1]"] == "unhashable"
+
+item = value["item"]
+assert (item.class_name, item.kind, item.attributes, item.extends) == ("Item", "object", {"@name": "Sword"}, ["Enumerable"])
+assert value["point"].kind == "struct" and value["point"].attributes == {"x": 1}
+assert (value["class"].class_name, value["class"].kind) == ("RPG::Map", "class")
+assert (value["regexp"].data, value["regexp"].attributes) == ("a+", {"flags": "i"})
+assert (value["date"].kind, value["date"].data) == ("user_defined", b"\x01\x02")
+
+assert dumps(value) == bytes(data)
+assert loads(dumps([Symbol("a"), 2 ** 70, (1,)])) == [Symbol("a"), 2 ** 70, [1]]
+assert loads(dumps(RubyObject("Item", attributes={"@id": 1}))).attributes == {"@id": 1}
+
+try:
+    dumps(object())
+    assert False
+except TypeError as error:
+    assert str(error) == "Objects of type object can't be dumped"
+
+try:
+    loads(b"\x04\x08[")
+    assert False
+except ValueError:
+    pass
+"#
+            ),
+            Some(&globals),
+            None,
+        )
+        .map_err(|err| err.print(py))
+        .unwrap();
+    });
+}
@@ -0,0 +1,107 @@
+use marshal_rs::raw::{
+    check_version, encode_int, int_size, read_int, write_int, Constants, MarshalVersion, Reader,
+    VERSION_HEADER,
+};
+
+#[test]
+fn int_encoding() {
+    // Encodings, produced by Ruby's Marshal.dump
+    let cases: [(i32, &[u8]); 12] = [
+        (0, b"\x00"),
+        (1, b"\x06"),
+        (122, b"\x7F"),
+        (123, b"\x01\x7B"),
+        (-1, b"\xFA"),
+        (-123, b"\x80"),
+        (-124, b"\xFF\x84"),
+        (-256, b"\xFF\x00"),
+        (256, b"\x02\x00\x01"),
+        (-257, b"\xFE\xFF\xFE"),
+        (70000, b"\x03\x70\x11\x01"),
+        (-1073741824, b"\xFC\x00\x00\x00\xC0"),
+    ];
+
+    for (number, bytes) in cases {
+        let mut buffer = Vec::new();
+        write_int(&mut buffer, number);
+
+        assert_eq!(buffer, bytes, "{number}");
+        assert_eq!(int_size(number), bytes.len());
+
+        let (encoded, length) = encode_int(number);
+        assert_eq!(&encoded[..length], bytes);
+        assert_eq!(read_int(bytes).unwrap(), (number, bytes.len()));
+    }
+
+    for number in [i32::MIN, -65537, -65536, 65535, 65536, 16777216, i32::MAX] {
+        let mut buffer = Vec::new();
+        write_int(&mut buffer, number);
+        assert_eq!(read_int(&buffer).unwrap(), (number, buffer.len()));
+    }
+
+    assert!(read_int(b"\x02\x00").is_err());
+}
+
+#[test]
+fn reader() {
+    assert!(check_version(&VERSION_HEADER).is_ok());
+    assert!(check_version(b"\x04\x09").is_err());
+    assert!(check_version(b"\x04").is_err());
+
+    assert_eq!(Constants::try_from(b'o').unwrap(), Constants::Object);
+    assert!(Constants::try_from(b'+').is_err());
+
+    let mut reader = Reader::new(b"\x04\x08I\"\x06a\x06:\x06ET");
+    reader.read_version().unwrap();
+
+    assert_eq!(reader.read_type().unwrap(), Constants::InstanceVar);
+    assert_eq!(reader.read_type().unwrap(), Constants::String);
+    assert_eq!(reader.read_chunk().unwrap(), b"a");
+    assert_eq!(reader.read_int().unwrap(), 1);
+    assert_eq!(reader.position(), 7);
+    assert!(reader.read_bytes(10).is_err());
+
+    assert_eq!(
+        Reader::new(b"\xFA").read_chunk().unwrap_err().to_string(),
+        "Invalid length -1 at position 0."
+    );
+    assert_eq!(Reader::new(b"\x07\x00\x00").read_length().unwrap(), 2);
+    assert_eq!(
+        Reader::new(b"\x08\x00\x00")
+            .read_length()
+            .unwrap_err()
+            .to_string(),
+        "Invalid length 3 at position 0."
+    );
+    assert!(Reader::new(b"x").read_type().is_err());
+}
+
+#[test]
+fn versions() {
+    assert_eq!(
+        MarshalVersion::detect(&VERSION_HEADER),
+        Some(MarshalVersion::CURRENT)
+    );
+    assert_eq!(MarshalVersion::CURRENT.to_string(), "4.8");
+    assert_eq!(MarshalVersion::detect(b"\x04"), None);
+
+    let mut loader = marshal_rs::Loader::new();
+
+    assert_eq!(
+        loader
+            .load(b"\x04\x06[\x00", None, None)
+            .unwrap_err()
+            .to_string(),
+        "Incompatible Marshal file format or version: found 4.6, expected 4.8."
+    );
+    assert_eq!(
+        loader.detected_version(),
+        Some(MarshalVersion { major: 4, minor: 6 })
+    );
+
+    loader.load(b"\x04\x080", None, None).unwrap();
+    assert_eq!(loader.detected_version(), Some(MarshalVersion::CURRENT));
+
+    assert!(loader.load(b"", None, None).is_err());
+    assert_eq!(loader.detected_version(), None);
+}
@@ -0,0 +1,92 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    ron::{from_ron, to_ron},
+    ValueExt,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn ron_roundtrip() {
+    let value = json!([
+        null, true, 1, -2.5, 3.0, 18446744073709551615u64, "text \"quoted\"\n\u{1}", "__symbol__sym",
+        { "__type": "bytes", "data": [0, 255, 16, 32] },
+        { "__type": "bigint", "value": "36893488147419103232" },
+        { "__type": "regexp", "expression": "a/b", "flags": "im" },
+        { "__class": "Comparable", "__type": "module", "__old": false },
+        { "__class": "Kernel", "__type": "module", "__old": true },
+        { "__class": "Object", "__type": "class" },
+        { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1, "__symbol__y": 2 } },
+        { "__class": "__symbol__RPG::Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@stats": { "__symbol__atk": 10, "__object__{\"__class\":\"__symbol__Key\",\"__type\":\"object\"}": 1 } },
+        { "__class": "__symbol__Odd", "__type": "object", "__symbol__@class": 1 },
+        { "__class": "__symbol__Time", "__type": "object", "__userDefined": [1, 2, 3] },
+        { "__integer__1": "one", "__float__1.5": "float", "__array__[1,2]": "array", "__symbol__key": "symbol", "__ruby_default__": 0 },
+        [], {}
+    ]);
+
+    let ron = value.to_ron();
+
+    assert!(ron.contains("\n    Symbol(\"sym\"),\n"));
+    assert!(ron.contains("\n    3.0,\n"));
+    assert!(ron.contains("\"text \\\"quoted\\\"\\n\\u0001\""));
+    assert!(ron.contains("\n    Bytes([0, 255, 16, 32]),\n"));
+    assert!(ron.contains("\n    Regexp(\"a/b\", \"im\"),\n"));
+    assert!(ron.contains("\n    OldModule(\"Kernel\"),\n"));
+    assert!(ron.contains(
+        "\n    Struct(\n        class: \"Point\",\n        x: 1,\n        y: 2,\n    ),\n"
+    ));
+    assert!(ron.contains(
+        "\n    Object(\n        class: \"RPG::Item\",\n        name: \"Sword\",\n        stats: {\n            Symbol(\"atk\"): 10,\n            Object(\n                class: \"Key\",\n            ): 1,\n        },\n    ),\n"
+    ));
+    assert!(ron.contains("\n    Raw({\n        \"__class\": Symbol(\"Odd\"),"));
+    assert!(ron.contains("\n        1: \"one\",\n        1.5: \"float\",\n"));
+    assert!(ron.ends_with("\n    [],\n    {},\n]"));
+    assert_eq!(Value::from_ron(&ron).unwrap(), value);
+}
+
+#[test]
+fn hand_written_ron() {
+    let ron = r#"
+        // Edited by hand
+        Object(class: "Actor", name: "Eric", /* old: "Erik" */ level: 5, tags: [Symbol("hero"),], nothing: ())
+    "#;
+
+    assert_eq!(
+        from_ron(ron).unwrap(),
+        json!({ "__class": "__symbol__Actor", "__type": "object", "__symbol__@name": "Eric", "__symbol__@level": 5, "__symbol__@tags": ["__symbol__hero"], "__symbol__@nothing": null })
+    );
+
+    assert_eq!(to_ron(&json!({})), "{}");
+    assert_eq!(from_ron("1_000").unwrap(), json!(1000));
+    assert_eq!(from_ron("\"\\u00e9\"").unwrap(), json!("é"));
+}
+
+#[test]
+fn invalid_ron() {
+    let error = |ron: &str| from_ron(ron).unwrap_err().to_string();
+
+    assert_eq!(
+        error("[1,\n  Unknown(1)]"),
+        "Unknown variant Unknown at RON line 2, column 3"
+    );
+    assert_eq!(
+        error("Object(name: 1)"),
+        "Object without class at RON line 1, column 16"
+    );
+    assert_eq!(error("[1, 2"), "Expected `]` at RON line 1, column 6");
+    assert_eq!(
+        error("\"text"),
+        "Unterminated string at RON line 1, column 6"
+    );
+    assert_eq!(
+        error("Bytes([256])"),
+        "Expected a byte at RON line 1, column 11"
+    );
+    assert_eq!(
+        error("1 2"),
+        "Unexpected trailing characters at RON line 1, column 3"
+    );
+    assert_eq!(
+        from_ron("{ true: 1 }").unwrap(),
+        json!({ "__boolean__true": 1 })
+    );
+}
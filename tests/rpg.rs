@@ -0,0 +1,68 @@
+#![cfg(all(feature = "rpg", not(feature = "sonic")))]
+use marshal_rs::{
+    dump, load,
+    rpg::{Actor, Event, EventCommand, EventPage, Feature, Map},
+    FromValue, IntoValue,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[test]
+fn map_roundtrip() {
+    let mut page = EventPage::default();
+    page.list.push(EventCommand {
+        code: 101,
+        indent: 0,
+        parameters: vec![json!("Actor1"), json!(0)],
+    });
+
+    let map = Map {
+        width: 17,
+        height: 13,
+        display_name: "Town".to_string(),
+        events: BTreeMap::from([(
+            1,
+            Event {
+                id: 1,
+                name: "EV001".to_string(),
+                pages: vec![page],
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    };
+
+    let value = map.clone().into_value();
+
+    assert_eq!(
+        value["__symbol__@events"]["__integer__1"]["__symbol__@pages"][0]["__symbol__@list"][0],
+        json!({ "__class": "__symbol__RPG::EventCommand", "__type": "object", "__symbol__@code": 101, "__symbol__@indent": 0, "__symbol__@parameters": ["Actor1", 0] })
+    );
+
+    let loaded = load(&dump(value, None), None, None).unwrap();
+    assert_eq!(Map::from_value(&loaded).unwrap(), map);
+}
+
+#[test]
+fn actor_from_value() {
+    let mut value = Actor {
+        id: 1,
+        name: "Eric".to_string(),
+        features: vec![Feature {
+            code: 23,
+            data_id: 0,
+            value: 1.0,
+        }],
+        equips: vec![1, 0, 0, 0, 0],
+        ..Default::default()
+    }
+    .into_value();
+
+    assert_eq!(Actor::from_value(&value).unwrap().features[0].code, 23);
+
+    value["__symbol__@features"][0]["__symbol__@code"] = json!("23");
+    assert_eq!(
+        Actor::from_value(&value).unwrap_err().to_string(),
+        "@features: [0]: @code: Expected 32-bit Integer, found String"
+    );
+}
@@ -0,0 +1,99 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    dump,
+    rpgmaker::{detect, detect_file, load_file, load_save, Engine, Format},
+};
+use serde_json::json;
+
+// [nil, RPG::Actor { @name: "Eric" }], written by Ruby 1.8
+const XP_ACTORS: &[u8] = b"\x04\x08[\x070o:\x0fRPG::Actor\x06:\x0a@name\"\x09Eric";
+
+#[test]
+fn detect_engine() {
+    let xp = dump(
+        json!([{ "__class": "__symbol__RPG::AudioFile", "__type": "object", "__symbol__@name": { "__type": "bytes", "data": [97] } }]),
+        None,
+    );
+    let vx = dump(
+        json!({ "__class": "__symbol__RPG::BGM", "__type": "object", "__symbol__@volume": 100 }),
+        None,
+    );
+    let vx_ace = dump(json!(["text"]), None);
+
+    assert_eq!(
+        detect(&xp),
+        Some(Format {
+            engine: Engine::Xp,
+            save: false
+        })
+    );
+    assert_eq!(
+        detect(&vx),
+        Some(Format {
+            engine: Engine::Vx,
+            save: false
+        })
+    );
+    assert_eq!(
+        detect(&vx_ace),
+        Some(Format {
+            engine: Engine::VxAce,
+            save: false
+        })
+    );
+    assert_eq!(detect(XP_ACTORS), None);
+    assert_eq!(detect(b"not marshal"), None);
+
+    let save: Vec<u8> = [vx_ace.clone(), dump(json!(1), None)].concat();
+    assert_eq!(
+        detect(&save),
+        Some(Format {
+            engine: Engine::VxAce,
+            save: true
+        })
+    );
+    assert_eq!(
+        load_save(&save, Engine::VxAce).unwrap(),
+        vec![json!(["text"]), json!(1)]
+    );
+
+    assert_eq!(Engine::from_extension("RVDATA2"), Some(Engine::VxAce));
+    assert_eq!(Engine::from_extension("json"), None);
+}
+
+#[test]
+fn detect_and_load_file() {
+    let dir = std::env::temp_dir().join(format!("marshal-rs-rpgmaker-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let actors = dir.join("Actors.rxdata");
+    std::fs::write(&actors, XP_ACTORS).unwrap();
+
+    let save = dir.join("Save01.rxdata");
+    std::fs::write(&save, [XP_ACTORS, XP_ACTORS].concat()).unwrap();
+
+    assert_eq!(
+        detect_file(&actors).unwrap(),
+        Some(Format {
+            engine: Engine::Xp,
+            save: false
+        })
+    );
+
+    // Strings without encodings are decoded as UTF-8 with the XP preset
+    let actor =
+        json!({ "__class": "__symbol__RPG::Actor", "__type": "object", "__symbol__@name": "Eric" });
+    let (format, documents) = load_file(&save).unwrap();
+
+    assert_eq!(
+        format,
+        Format {
+            engine: Engine::Xp,
+            save: true
+        }
+    );
+    assert_eq!(documents, vec![json!([null, actor]); 2]);
+    assert!(load_file(dir.join("Missing.rxdata")).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
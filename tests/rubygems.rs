@@ -0,0 +1,174 @@
+#![cfg(all(feature = "rubygems", not(feature = "sonic")))]
+use marshal_rs::{
+    dump, load,
+    rubygems::{
+        parse_index, read_index, GemDependency, GemRequirement, GemSpecification, GemVersion,
+        IndexEntry,
+    },
+    FromValue, IntoValue,
+};
+use serde_json::{json, Value};
+
+fn gem_version(version: &str) -> Value {
+    json!({ "__class": "__symbol__Gem::Version", "__type": "object", "__userMarshal": [version] })
+}
+
+fn entry(name: &str, version: &str, platform: &str) -> IndexEntry {
+    IndexEntry {
+        name: name.to_string(),
+        version: version.to_string(),
+        platform: platform.to_string(),
+    }
+}
+
+/// Compresses the data like `Gem::Util.gzip`, with a file name in the header.
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut gzip: Vec<u8> = vec![0x1f, 0x8b, 8, 1 << 3, 0, 0, 0, 0, 0, 3];
+    gzip.extend_from_slice(b"specs.4.8\0");
+    gzip.extend(miniz_oxide::deflate::compress_to_vec(data, 6));
+    gzip.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    gzip
+}
+
+#[test]
+fn index_entries() {
+    let index: Vec<u8> = dump(
+        json!([
+            ["rake", gem_version("13.2.1"), "ruby"],
+            ["nokogiri", gem_version("1.18.0"), "x86_64-linux-gnu"],
+            [
+                "json",
+                { "__class": "__symbol__Gem::Version", "__type": "object", "__symbol__@version": "1.1.0" },
+                { "__class": "__symbol__Gem::Platform", "__type": "object", "__symbol__@cpu": "x86", "__symbol__@os": "mswin32", "__symbol__@version": "60" },
+            ],
+        ]),
+        None,
+    );
+    let entries: Vec<IndexEntry> = vec![
+        entry("rake", "13.2.1", "ruby"),
+        entry("nokogiri", "1.18.0", "x86_64-linux-gnu"),
+        entry("json", "1.1.0", "x86-mswin32-60"),
+    ];
+
+    assert_eq!(parse_index(&index).unwrap(), entries);
+    assert_eq!(parse_index(&gzip(&index)).unwrap(), entries);
+
+    let path = std::env::temp_dir().join(format!("marshal-rs-specs-{}.4.8.gz", std::process::id()));
+    std::fs::write(&path, gzip(&index)).unwrap();
+    let read: Vec<IndexEntry> = read_index(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read, entries);
+}
+
+#[test]
+fn index_errors() {
+    let index: Vec<u8> = dump(json!([["rake", "13.2.1", "ruby"]]), None);
+    let message = |bytes: &[u8]| parse_index(bytes).unwrap_err().to_string();
+
+    assert_eq!(
+        message(&index),
+        "Entry 0 is not a [name, Gem::Version, platform] tuple."
+    );
+    assert_eq!(message(&dump(json!({}), None)), "Index is not an Array.");
+
+    let mut corrupted: Vec<u8> = gzip(&index);
+    let length: usize = corrupted.len();
+    corrupted[length - 5] ^= 1;
+
+    assert_eq!(message(&corrupted), "Gzip stream checksum mismatch.");
+    assert_eq!(message(&corrupted[..12]), "Truncated gzip stream.");
+}
+
+fn requirements(requirements: &[(&str, &str)]) -> GemRequirement {
+    GemRequirement {
+        requirements: requirements
+            .iter()
+            .map(|(operator, version)| {
+                (
+                    operator.to_string(),
+                    GemVersion {
+                        version: version.to_string(),
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn gem_requirement_from_ruby() {
+    // Marshal.dump(Gem::Requirement.new("~> 1.0"))
+    let marshal: &[u8] = b"\x04\x08U:\x15Gem::Requirement[\x06[\x06[\x07I\"\x07~>\x06:\x06ETU:\x11Gem::Version[\x06I\"\x081.0\x06;\x06T";
+    let value = load(marshal, None, None).unwrap();
+    let requirement: GemRequirement = GemRequirement::from_value(&value).unwrap();
+
+    assert_eq!(requirement, requirement_of("~>", "1.0"));
+    assert_eq!(requirement.to_string(), "~> 1.0");
+    assert_eq!(dump(requirement.into_value(), None), marshal);
+
+    assert_eq!(
+        requirements(&[(">=", "1.0"), ("<", "3")]).to_string(),
+        ">= 1.0, < 3"
+    );
+}
+
+fn requirement_of(operator: &str, version: &str) -> GemRequirement {
+    requirements(&[(operator, version)])
+}
+
+#[test]
+fn gem_specification_roundtrip() {
+    let specification = GemSpecification {
+        rubygems_version: "3.5.22".to_string(),
+        specification_version: 4,
+        name: "rake".to_string(),
+        version: GemVersion {
+            version: "13.2.1".to_string(),
+        },
+        date: json!(null),
+        summary: "Make-like program".to_string(),
+        required_ruby_version: requirement_of(">=", "2.3"),
+        required_rubygems_version: requirement_of(">=", "1.3.2"),
+        original_platform: json!(null),
+        dependencies: vec![GemDependency {
+            name: "minitest".to_string(),
+            requirement: requirement_of("~>", "5.0"),
+            kind: "__symbol__development".to_string(),
+            prerelease: Some(false),
+        }],
+        rubyforge_project: json!(""),
+        email: json!(["hsbt@ruby-lang.org"]),
+        authors: vec!["Hiroshi SHIBATA".to_string()],
+        description: None,
+        homepage: Some("https://github.com/ruby/rake".to_string()),
+        has_rdoc: json!(true),
+        platform: json!("ruby"),
+        licenses: Some(vec!["MIT".to_string()]),
+        metadata: Some(json!({ "bug_tracker_uri": "https://github.com/ruby/rake/issues" })),
+    };
+
+    let marshal: Vec<u8> = dump(specification.clone().into_value(), None);
+    let loaded: GemSpecification =
+        GemSpecification::from_value(&load(&marshal, None, None).unwrap()).unwrap();
+
+    assert_eq!(loaded, specification);
+
+    let legacy = GemSpecification {
+        licenses: None,
+        metadata: None,
+        ..specification
+    };
+
+    assert_eq!(
+        GemSpecification::from_value(&legacy.clone().into_value()).unwrap(),
+        legacy
+    );
+    assert_eq!(
+        GemSpecification::from_value(&json!({ "__class": "__symbol__Gem::Specification", "__type": "object", "__userDefined": dump(json!([]), None) }))
+            .unwrap_err()
+            .to_string(),
+        "Gem::Specification's dumped data is not an Array of at least 17 attributes"
+    );
+}
@@ -0,0 +1,99 @@
+#![cfg(all(feature = "test-utils", not(feature = "sonic")))]
+use marshal_rs::test_utils::{
+    assert_golden_dir, assert_roundtrip, assert_value_roundtrip, golden_files, golden_path,
+    ruby_available, ruby_cross_check, Divergence, RUBY_VAR,
+};
+use serde_json::json;
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+
+#[test]
+fn roundtrip() {
+    assert_eq!(
+        assert_roundtrip(b"\x04\x08[\x07i\x06I\"\x06a\x06:\x06ET"),
+        json!([1, "a"])
+    );
+    assert_value_roundtrip(&json!({ "__symbol__key": [null, true, 1.5] }));
+}
+
+#[test]
+#[should_panic(expected = "Failed to load Marshal data")]
+fn roundtrip_invalid() {
+    assert_roundtrip(b"\x04\x08[\x07");
+}
+
+#[test]
+fn golden() {
+    let dir = std::env::temp_dir().join(format!("marshal-rs-golden-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+    write(dir.join("array.marshal"), b"\x04\x08[\x06i\x06").unwrap();
+    write(dir.join("nil"), b"\x04\x080").unwrap();
+
+    let files = golden_files(&dir);
+    assert_eq!(files, vec![dir.join("array.marshal"), dir.join("nil")]);
+
+    // First run creates fixtures, second run compares against them
+    assert_golden_dir(&dir);
+    assert_golden_dir(&dir);
+
+    let golden = golden_path(&dir.join("array.marshal"));
+    assert_eq!(golden, dir.join("array.marshal.json"));
+    assert_eq!(read_to_string(&golden).unwrap(), "[\n  1\n]\n");
+    assert_eq!(golden_files(&dir).len(), 2);
+
+    write(&golden, "[2]").unwrap();
+    let result = std::panic::catch_unwind(|| assert_golden_dir(&dir));
+
+    remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn ruby_cross_check_divergences() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("marshal-rs-ruby-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+
+    // Fake Ruby, which always dumps 2 and fails to load marshal-rs output, if asked to
+    let ruby = dir.join("ruby");
+    write(
+        &ruby,
+        "#!/bin/sh\n[ \"$1\" = -v ] && exit 0\ncat > /dev/null\nprintf '\\004\\010i\\007'\nexit $FAKE_RUBY_EXIT\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&ruby, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    std::env::set_var(RUBY_VAR, &ruby);
+    std::env::set_var("FAKE_RUBY_EXIT", "0");
+    assert!(ruby_available());
+
+    let divergences = ruby_cross_check(b"\x04\x08i\x06").unwrap();
+    assert_eq!(
+        divergences,
+        vec![
+            Divergence::Bytes {
+                offset: 3,
+                ruby: b"\x04\x08i\x07".to_vec(),
+                rust: b"\x04\x08i\x06".to_vec()
+            },
+            Divergence::Value {
+                ruby: json!(2),
+                rust: json!(1)
+            }
+        ]
+    );
+
+    std::env::set_var("FAKE_RUBY_EXIT", "2");
+    let divergences = ruby_cross_check(b"\x04\x08i\x06").unwrap();
+    assert!(matches!(divergences[0], Divergence::RubyRejected(_)));
+
+    std::env::set_var("FAKE_RUBY_EXIT", "1");
+    assert!(ruby_cross_check(b"\x04\x08i\x06").is_err());
+
+    std::env::set_var(RUBY_VAR, dir.join("missing"));
+    assert!(!ruby_available());
+    assert!(ruby_cross_check(b"\x04\x08i\x06").is_err());
+
+    remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,77 @@
+use marshal_rs::{
+    dump, load,
+    testgen::{generate, Generator, GeneratorOptions, TypeWeights},
+};
+
+#[test]
+fn generated_documents_load() {
+    let mut generator: Generator = Generator::new(GeneratorOptions {
+        seed: 7,
+        link_density: 0.2,
+        ..Default::default()
+    });
+
+    for _ in 0..256 {
+        let bytes: Vec<u8> = generator.generate();
+        let value = load(&bytes, None, None).unwrap();
+
+        assert_eq!(load(&dump(value.clone(), None), None, None).unwrap(), value);
+    }
+}
+
+#[test]
+fn generation_is_deterministic() {
+    let options: GeneratorOptions = GeneratorOptions {
+        seed: 12345,
+        ..Default::default()
+    };
+
+    assert_eq!(generate(options), generate(options));
+    assert_ne!(
+        generate(options),
+        generate(GeneratorOptions {
+            seed: 54321,
+            ..options
+        })
+    );
+}
+
+#[test]
+fn generation_options() {
+    let weights: TypeWeights = TypeWeights {
+        nil: 0,
+        boolean: 0,
+        fixnum: 0,
+        bignum: 0,
+        float: 0,
+        string: 0,
+        symbol: 0,
+        regexp: 0,
+        array: 1,
+        hash: 0,
+        object: 0,
+        structure: 0,
+        user_defined: 0,
+    };
+
+    // Only arrays can be generated, so at the maximum depth there's nothing left but nil
+    let bytes: Vec<u8> = generate(GeneratorOptions {
+        max_depth: 0,
+        weights,
+        ..Default::default()
+    });
+    assert_eq!(bytes, b"\x04\x080");
+
+    let bytes: Vec<u8> = generate(GeneratorOptions {
+        max_depth: 3,
+        max_length: 1,
+        link_density: 0.0,
+        weights,
+        seed: 1,
+        ..Default::default()
+    });
+    assert!(bytes
+        .iter()
+        .skip(2)
+        .all(|&byte| b"[\x000\x06".contains(&byte)));
+}
@@ -0,0 +1,56 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{tracked::Tracked, Path};
+use serde_json::json;
+
+#[test]
+fn dirty_paths() {
+    let mut map = Tracked::new(json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@events": [
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Guard", "__symbol__@x": 1 },
+            { "__class": "__symbol__RPG::Event", "__type": "object", "__symbol__@name": "Chest", "__symbol__@x": 4 },
+        ],
+    }));
+
+    let events = Path::from_pointer("/__symbol__@events").unwrap();
+    let guard_x = Path::from_pointer("/__symbol__@events/0/__symbol__@x").unwrap();
+    let chest = Path::from_pointer("/__symbol__@events/1").unwrap();
+
+    // Missing paths are neither changed nor recorded
+    assert!(map
+        .get_mut(&Path::from_pointer("/__symbol__@events/2").unwrap())
+        .is_none());
+    assert!(map
+        .replace(&Path::from_dotted("@width").unwrap(), json!(20))
+        .is_err());
+    assert!(!map.is_dirty());
+
+    // Keys are recorded, as they're stored
+    let x = Path::from_dotted("@events[0].@x").unwrap();
+    assert_eq!(map.replace(&x, json!(2)).unwrap(), json!(1));
+    assert_eq!(
+        map.value()["__symbol__@events"][0]["__symbol__@x"],
+        json!(2)
+    );
+    assert_eq!(map.dirty_paths(), std::slice::from_ref(&guard_x));
+
+    assert!(map.is_path_dirty(&events));
+    assert!(map.is_path_dirty(&guard_x));
+    assert!(!map.is_path_dirty(&chest));
+
+    // Recording a subtree replaces records inside of it
+    map.get_mut(&events).unwrap().as_array_mut().unwrap().pop();
+    assert_eq!(map.dirty_paths(), std::slice::from_ref(&events));
+
+    map.get_mut(&guard_x).unwrap();
+    assert_eq!(map.dirty_paths(), [events]);
+
+    map.mark_clean();
+    assert!(!map.is_dirty());
+
+    map.modify(|value| value["__symbol__@width"] = json!(20));
+    assert_eq!(map.dirty_paths(), [Path::new()]);
+    assert!(map.is_path_dirty(&chest));
+    assert_eq!(map.into_value()["__symbol__@width"], json!(20));
+}
@@ -0,0 +1,120 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{
+    dump, load,
+    translate::{
+        apply, apply_to_bytes, extract, from_csv, from_po, to_csv, to_po, ExtractOptions,
+        TranslationEntry,
+    },
+    Path,
+};
+use serde_json::json;
+
+fn sample() -> serde_json::Value {
+    json!([
+        null,
+        {
+            "__class": "__symbol__RPG::Item",
+            "__type": "object",
+            "__symbol__@name": "Sword, \"sharp\"",
+            "__symbol__@description": "Line one\nLine two",
+            "__symbol__@icon": "__symbol__sword",
+            "__symbol__@note": ""
+        },
+        {
+            "__class": "__symbol__RPG::System",
+            "__type": "object",
+            "__symbol__@words": { "__symbol__attack": "Attack" }
+        }
+    ])
+}
+
+#[test]
+fn extract_strings() {
+    let value = sample();
+
+    let entries = extract(&value, &ExtractOptions::default());
+    let paths: Vec<String> = entries.iter().map(|entry| entry.path.to_string()).collect();
+
+    assert_eq!(
+        paths,
+        [
+            "/1/__symbol__@name",
+            "/1/__symbol__@description",
+            "/2/__symbol__@words/__symbol__attack"
+        ]
+    );
+    assert_eq!(entries[2].class.as_deref(), Some("RPG::System"));
+
+    let options = ExtractOptions {
+        classes: vec!["RPG::Item".to_string()],
+        include_empty: true,
+    };
+
+    assert_eq!(extract(&value, &options).len(), 3);
+}
+
+#[test]
+fn csv_and_po_roundtrip() {
+    let mut entries = extract(&sample(), &ExtractOptions::default());
+    entries[0].translation = "Schwert, \"scharf\"".to_string();
+    entries[1].translation = "Zeile eins\r\nZeile zwei\t\\".to_string();
+
+    assert_eq!(from_csv(&to_csv(&entries)).unwrap(), entries);
+    assert_eq!(from_po(&to_po(&entries)).unwrap(), entries);
+
+    assert!(from_csv("path,class,source,translation\n\"/0").is_err());
+    assert!(from_csv("path,class,source,translation\n/0,a,b\n").is_err());
+    assert!(from_po("msgid \"unterminated\n").is_err());
+}
+
+#[test]
+fn po_continuation_lines() {
+    let po = "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\n#. RPG::Item\nmsgctxt \"/1/__symbol__@name\"\nmsgid \"\"\n\"Long \"\n\"name\"\nmsgstr \"Langer \"\n\"Name\"\n\nmsgctxt \"/0\"\nmsgid \"x\"\nmsgstr \"\"\n";
+
+    assert_eq!(
+        from_po(po).unwrap(),
+        [
+            TranslationEntry {
+                path: Path::from_pointer("/1/__symbol__@name").unwrap(),
+                class: Some("RPG::Item".to_string()),
+                source: "Long name".to_string(),
+                translation: "Langer Name".to_string(),
+            },
+            TranslationEntry {
+                path: Path::from_pointer("/0").unwrap(),
+                class: None,
+                source: "x".to_string(),
+                translation: String::new(),
+            }
+        ]
+    );
+}
+
+#[test]
+fn apply_translations() {
+    let mut value = sample();
+    let mut entries = extract(&value, &ExtractOptions::default());
+    entries[0].translation = "Schwert".to_string();
+    entries[1].source = "Outdated".to_string();
+    entries[1].translation = "Veraltet".to_string();
+    entries.push(TranslationEntry {
+        path: Path::from_pointer("/1/__symbol__@icon").unwrap(),
+        class: None,
+        source: "__symbol__sword".to_string(),
+        translation: "__symbol__schwert".to_string(),
+    });
+
+    let bytes = dump(value.clone(), None);
+    let report = apply(&mut value, &entries);
+
+    assert_eq!(report.applied, 1);
+    assert_eq!(report.changed, [entries[1].path.clone()]);
+    assert_eq!(report.missing, [entries[3].path.clone()]);
+    assert_eq!(value[1]["__symbol__@name"], "Schwert");
+    assert_eq!(value[1]["__symbol__@icon"], "__symbol__sword");
+
+    let (translated, bytes_report) = apply_to_bytes(&bytes, &entries).unwrap();
+
+    assert_eq!(bytes_report, report);
+    assert_eq!(load(&translated, None, None).unwrap(), value);
+}
@@ -0,0 +1,150 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{codegen::Codegen, dump, load, FromValue, IntoValue};
+use marshal_rs_macros::{FromValue, IntoValue};
+use serde_json::json;
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+#[marshal(class = "RPG::Event")]
+pub struct Event {
+    pub id: i32,
+    pub name: String,
+    #[marshal(name = "@x_position")]
+    pub x: f64,
+    pub r#type: Option<i64>,
+    pub pages: Vec<Page>,
+    pub parent: Option<Box<Event>>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+#[marshal(class = "RPG::Event::Page")]
+pub struct Page {
+    pub list: serde_json::Value,
+}
+
+#[test]
+fn derive_conversions() {
+    let value = json!({
+        "__class": "__symbol__RPG::Event",
+        "__type": "object",
+        "__symbol__@id": 1,
+        "__symbol__@name": "Chest",
+        "__symbol__@x_position": 2.5,
+        "__symbol__@type": null,
+        "__symbol__@pages": [
+            { "__class": "__symbol__RPG::Event::Page", "__type": "object", "__symbol__@list": [1, 2] }
+        ],
+        "__symbol__@parent": null
+    });
+
+    let event = Event::from_value(&value).unwrap();
+    assert_eq!(event.name, "Chest");
+    assert_eq!(event.x, 2.5);
+    assert_eq!(event.r#type, None);
+    assert_eq!(event.pages[0].list, json!([1, 2]));
+
+    let dumped = dump(event.clone().into_value(), None);
+    assert_eq!(load(&dumped, None, None).unwrap(), value);
+
+    let mut invalid = value.clone();
+    invalid["__symbol__@pages"][0]["__class"] = json!("__symbol__RPG::Map");
+    assert_eq!(
+        Event::from_value(&invalid).unwrap_err().to_string(),
+        "@pages: [0]: Expected RPG::Event::Page, found RPG::Map"
+    );
+}
+
+#[test]
+fn codegen() {
+    let mut codegen = Codegen::new();
+    codegen.add(&json!({
+        "__class": "__symbol__RPG::Map",
+        "__type": "object",
+        "__symbol__@events": {
+            "__integer__1": {
+                "__class": "__symbol__RPG::Event",
+                "__type": "object",
+                "__symbol__@id": 1,
+                "__symbol__@type": 0,
+                "__symbol__@pages": [],
+                "__symbol__@parent": null
+            },
+            "__integer__2": {
+                "__class": "__symbol__Game::Event",
+                "__type": "object",
+                "__symbol__@x": 1.5,
+                "__symbol__@next": { "__class": "__symbol__Game::Event", "__type": "object", "__symbol__@x": 2 }
+            }
+        },
+        "__symbol__@data": { "__type": "bytes", "data": [1] }
+    }));
+
+    assert_eq!(
+        codegen.generate(),
+        r#"// Generated by marshal-rs codegen.
+
+use marshal_rs_macros::{FromValue, IntoValue};
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+#[marshal(class = "Game::Event")]
+pub struct GameEvent {
+    pub x: f64,
+    pub next: Option<Box<GameEvent>>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+#[marshal(class = "RPG::Event")]
+pub struct RPGEvent {
+    pub id: i64,
+    pub r#type: i64,
+    pub pages: Vec<serde_json::Value>,
+    pub parent: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, FromValue, IntoValue)]
+#[marshal(class = "RPG::Map")]
+pub struct Map {
+    pub events: serde_json::Value,
+    pub data: serde_json::Value,
+}
+"#
+    );
+}
+
+#[cfg(feature = "path-to-error")]
+#[test]
+fn path_to_error() {
+    use marshal_rs::typed::{from_str, from_value};
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    struct Actor {
+        #[serde(rename = "__symbol__@name")]
+        _name: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct System {
+        #[serde(rename = "__symbol__@actors")]
+        _actors: Vec<Option<Actor>>,
+    }
+
+    let value = json!({
+        "__symbol__@actors": [null, { "__symbol__@name": "Ralph" }, { "__symbol__@name": "Ulrika" }, { "__symbol__@name": ["Bennett"] }]
+    });
+
+    assert_eq!(
+        from_value::<System>(&value).unwrap_err().to_string(),
+        "at @actors[3].@name: invalid type: sequence, expected a string"
+    );
+    assert!(from_value::<Actor>(&value["__symbol__@actors"][1]).is_ok());
+    assert_eq!(
+        from_str::<System>(r#"{ "__symbol__@actors": [{ "__symbol__@name": 1 }] }"#)
+            .unwrap_err()
+            .to_string(),
+        "at @actors[0].@name: invalid type: integer `1`, expected a string at line 1 column 46"
+    );
+    assert_eq!(
+        from_str::<Vec<i32>>("[1] 2").unwrap_err().to_string(),
+        "trailing characters at line 1 column 5"
+    );
+}
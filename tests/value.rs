@@ -0,0 +1,700 @@
+#![cfg(not(feature = "sonic"))]
+use marshal_rs::{dump, load, Path, ValueExt, ValueKind};
+use serde_json::{json, Value};
+
+#[test]
+fn get_or_insert_with() {
+    let mut object = json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@id": 1 });
+
+    assert_eq!(
+        object.get_or_insert_with("@id", || json!(2)).unwrap(),
+        &json!(1)
+    );
+
+    object
+        .get_or_insert_with("@tags", Value::array_empty)
+        .unwrap()
+        .as_array_mut()
+        .unwrap()
+        .push(json!("tag"));
+    assert_eq!(object["__symbol__@tags"], json!(["tag"]));
+
+    assert!(json!({})
+        .get_or_insert_with("@tags", Value::array_empty)
+        .is_err());
+    assert!(json!(null)
+        .get_or_insert_with("@tags", Value::array_empty)
+        .is_err());
+}
+
+#[test]
+fn get_or_insert_with_key() {
+    let mut hash = json!({});
+
+    *hash.get_or_insert_with_key(&json!(1), || json!(0)).unwrap() = json!(5);
+    hash.get_or_insert_with_key(&json!("__symbol__key"), Value::object_empty)
+        .unwrap();
+
+    assert_eq!(hash, json!({ "__integer__1": 5, "__symbol__key": {} }));
+    assert!(json!({ "__type": "object" })
+        .get_or_insert_with_key(&json!(1), || json!(0))
+        .is_err());
+    *hash
+        .get_or_insert_with_key(&json!(null), || json!(0))
+        .unwrap() = json!(6);
+    assert_eq!(hash["__nil__null"], json!(6));
+}
+
+#[test]
+fn retain_recursive() {
+    let mut value = json!({
+        "__class": "__symbol__Item",
+        "__type": "object",
+        "__symbol__@name": "Sword",
+        "__symbol__@tags": ["sharp", 1, { "__type": "bytes", "data": [1, 2] }]
+    });
+    let mut paths: Vec<String> = Vec::new();
+
+    value.retain_recursive(|path, value| {
+        paths.push(path.to_string());
+        !value.is_number()
+    });
+
+    assert_eq!(
+        value,
+        json!({
+            "__class": "__symbol__Item",
+            "__type": "object",
+            "__symbol__@name": "Sword",
+            "__symbol__@tags": ["sharp", { "__type": "bytes", "data": [1, 2] }]
+        })
+    );
+    assert_eq!(
+        paths,
+        [
+            "/__symbol__@tags/0",
+            "/__symbol__@tags/1",
+            "/__symbol__@tags/2",
+            "/__symbol__@name",
+            "/__symbol__@tags"
+        ]
+    );
+}
+
+#[test]
+fn prune() {
+    let mut value = json!({ "a": null, "b": [null, 1, []], "c": { "d": "" }, "e": "" });
+
+    value.prune_nulls();
+    assert_eq!(value, json!({ "b": [1, []], "c": { "d": "" }, "e": "" }));
+
+    value.prune_empty();
+    assert_eq!(value, json!({ "b": [1] }));
+}
+
+#[test]
+fn object_hash_conversion() {
+    let object = json!({
+        "__class": "__symbol__Item",
+        "__type": "object",
+        "__symbol__@name": "Sword",
+        "__symbol__@price": 10
+    });
+    let hash = json!({ "__symbol__name": "Sword", "__symbol__price": 10 });
+
+    assert_eq!(object.clone().object_into_hash().unwrap(), hash);
+    assert_eq!(hash.hash_into_object("Item").unwrap(), object);
+    assert_eq!(
+        json!({ "name": "Sword" }).hash_into_object("Item").unwrap(),
+        json!({ "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword" })
+    );
+
+    assert!(json!({ "__integer__1": 1 })
+        .hash_into_object("Item")
+        .is_err());
+    assert!(json!({ "__type": "struct" }).object_into_hash().is_err());
+    assert!(json!([]).hash_into_object("Item").is_err());
+}
+
+#[test]
+fn coerce_keys_to_strings() {
+    let mut value = json!([{
+        "__symbol__a": { "__integer__1": true },
+        "b": { "__class": "__symbol__Item", "__type": "object", "__symbol__@c": {} }
+    }]);
+    value.coerce_keys_to_strings();
+
+    assert_eq!(
+        value,
+        json!([{
+            "a": { "1": true },
+            "b": { "__class": "__symbol__Item", "__type": "object", "__symbol__@c": {} }
+        }])
+    );
+}
+
+#[test]
+fn flatten() {
+    let value = json!({
+        "__class": "__symbol__Map",
+        "__type": "object",
+        "__symbol__@events": [null, { "__symbol__@name": "Boss", "a.b": [] }],
+        "__symbol__@data": { "__type": "bytes", "data": [1, 2] }
+    });
+    let flat = value.flatten();
+
+    assert_eq!(
+        Value::from(flat.clone()),
+        json!({
+            "__class": "__symbol__Map",
+            "__type": "object",
+            "__symbol__@events[0]": null,
+            "__symbol__@events[1].__symbol__@name": "Boss",
+            "__symbol__@events[1].a\\.b": [],
+            "__symbol__@data": { "__type": "bytes", "data": [1, 2] }
+        })
+    );
+    assert_eq!(Value::unflatten(&flat).unwrap(), value);
+}
+
+#[test]
+fn unflatten() {
+    let flat = json!({ "a[2]": 1, "b": true });
+    assert_eq!(
+        Value::unflatten(flat.as_object().unwrap()).unwrap(),
+        json!({ "a": [null, null, 1], "b": true })
+    );
+
+    let conflicting = json!({ "a[0]": 1, "a.b": true });
+    assert!(Value::unflatten(conflicting.as_object().unwrap()).is_err());
+
+    let malformed = json!({ "a[x]": 1 });
+    assert!(Value::unflatten(malformed.as_object().unwrap()).is_err());
+}
+
+#[test]
+fn constants() {
+    assert_eq!(Value::NULL, json!(null));
+    assert_eq!(Value::TRUE, json!(true));
+    assert_eq!(Value::FALSE, json!(false));
+}
+
+#[test]
+fn get_key() {
+    let mut hash = json!({ "__integer__1": "one", "__float__1.5": true, "__array__[1,2]": null });
+
+    assert_eq!(hash.get_key(&json!(1)), Some(&json!("one")));
+    assert_eq!(hash.get_key(&json!(1.5)), Some(&json!(true)));
+    assert_eq!(hash.get_key(&json!([1, 2])), Some(&json!(null)));
+    assert_eq!(hash.get_key(&json!(2)), None);
+
+    *hash.get_key_mut(&json!(1)).unwrap() = json!("uno");
+    assert_eq!(hash["__integer__1"], json!("uno"));
+
+    assert_eq!(
+        json!({ "__type": "object" }).get_key(&json!("__type")),
+        None
+    );
+    assert_eq!(json!([1]).get_key(&json!(0)), None);
+}
+
+#[test]
+fn at() {
+    let mut value = json!({
+        "__class": "__symbol__Map",
+        "__type": "object",
+        "__symbol__@events": [null, { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1 } }],
+        "__symbol__@data": { "__integer__2": "two", "name": "string key" },
+    });
+
+    assert_eq!(value.at("@events").at(1).at("x"), 1);
+    assert_eq!(value.at("@data").at(2), "two");
+    assert_eq!(value.at("@data").at("name"), "string key");
+    assert_eq!(
+        Path::from_pointer("/__symbol__@events/1/x")
+            .unwrap()
+            .segments()
+            .iter()
+            .fold(&value, |value, segment| value.at(segment)),
+        1
+    );
+    assert!(value.at("@events").at("x").at(5).is_null());
+    assert!(json!(1).at("@a").at(0).is_null());
+
+    *value
+        .at_mut("@events")
+        .unwrap()
+        .at_mut(1)
+        .unwrap()
+        .at_mut("x")
+        .unwrap() = json!(2);
+    assert_eq!(value["__symbol__@events"][1]["__members"]["__symbol__x"], 2);
+    assert!(value.at_mut("@missing").is_none());
+    assert!(value.at_mut(0).is_none());
+}
+
+#[test]
+fn collect_and_extend() {
+    let hash =
+        Value::hash_from_entries([(json!(1), json!("one")), (json!(1.5), json!(true))]).unwrap();
+
+    assert_eq!(hash.get_key(&json!(1)), Some(&json!("one")));
+    assert_eq!(load(&dump(hash.clone(), None), None, None).unwrap(), hash);
+    let hash =
+        Value::hash_from_entries([(json!(false), json!(1)), (json!(null), json!(2))]).unwrap();
+    assert_eq!(hash, json!({ "__boolean__false": 1, "__nil__null": 2 }));
+    assert_eq!(load(&dump(hash.clone(), None), None, None).unwrap(), hash);
+
+    let mut array: Value = (1..=2).collect();
+    array.extend_elements([json!(3)]).unwrap();
+    assert_eq!(array, json!([1, 2, 3]));
+
+    let mut object = json!({ "__class": "__symbol__Item", "__type": "object" });
+    object
+        .extend_entries([(json!("__symbol__@id"), json!(1))])
+        .unwrap();
+    assert_eq!(object["__symbol__@id"], 1);
+
+    assert_eq!(
+        array.extend_entries([]).unwrap_err().to_string(),
+        "Only Hashes and objects can be extended with entries."
+    );
+    assert_eq!(
+        object.extend_elements([]).unwrap_err().to_string(),
+        "Only arrays can be extended with elements."
+    );
+}
+
+#[test]
+fn class_predicates() {
+    let event = json!({ "__class": "__symbol__RPG::Event", "__type": "object" });
+    let wrapped = json!({ "__class": "__symbol__Name", "__type": "object", "__wrapped": "text" });
+    let class = json!({ "__class": "RPG::Map", "__type": "class" });
+
+    assert_eq!(event.class_name(), Some("RPG::Event"));
+    assert_eq!(class.class_name(), Some("RPG::Map"));
+    assert_eq!(json!({ "__class": "key" }).class_name(), None);
+    assert_eq!(json!("__symbol__RPG::Event").class_name(), None);
+
+    assert!(event.is_instance_of("RPG::Event"));
+    assert!(!event.is_instance_of("RPG"));
+    assert!(event.class_matches(|class| class.starts_with("RPG::")));
+    assert!(!json!(null).class_matches(|_| true));
+
+    assert!(wrapped.is_subclass_value("Name"));
+    assert!(!event.is_subclass_value("RPG::Event"));
+}
+
+#[test]
+fn metadata_setters() {
+    let item = json!({ "__class": "__symbol__Item", "__type": "object" })
+        .with_class("__symbol__Weapon")
+        .with_extension("Comparable")
+        .with_extension("Enumerable");
+
+    assert!(item.is_instance_of("Weapon"));
+    assert_eq!(
+        dump(item, None),
+        b"\x04\x08e:\x0fComparablee:\x0fEnumerableo:\x0bWeapon\x00"
+    );
+
+    let list = json!([1]).with_user_class(true);
+
+    assert!(list.is_subclass_value("Array"));
+    assert!(list.clone().with_class("List").is_subclass_value("List"));
+    assert_eq!(list.with_user_class(false), json!([1]));
+
+    assert_eq!(json!(1).with_class("Foo"), json!(1));
+    assert_eq!(json!("a").with_extension("Foo"), json!("a"));
+    assert_eq!(
+        json!("__symbol__a").with_user_class(true),
+        json!("__symbol__a")
+    );
+}
+
+#[test]
+fn find_duplicates() {
+    let event = json!({ "__class": "__symbol__Event", "__type": "object", "__symbol__@name": "Chest", "__symbol__@list": [1, 2, 3] });
+    let value = json!({
+        "a": [event.clone(), event],
+        "b": { "__symbol__@list": [1, 2, 3], "__symbol__@name": "Chest", "__class": "__symbol__Event", "__type": "object" },
+        "c": ["Chest", [1, 2, 3], "x", "x"]
+    });
+
+    let duplicates = value.find_duplicates(5);
+    let paths: Vec<Vec<String>> = duplicates
+        .iter()
+        .map(|group| group.paths.iter().map(|path| path.to_string()).collect())
+        .collect();
+
+    assert_eq!(
+        paths,
+        [
+            vec!["/a/0", "/a/1", "/b"],
+            vec![
+                "/a/0/__symbol__@name",
+                "/a/1/__symbol__@name",
+                "/b/__symbol__@name",
+                "/c/0"
+            ],
+            vec![
+                "/a/0/__symbol__@list",
+                "/a/1/__symbol__@list",
+                "/b/__symbol__@list",
+                "/c/1"
+            ],
+            vec!["/c/2", "/c/3"],
+        ]
+    );
+    assert_eq!(duplicates[0].savings, (duplicates[0].size - 2) * 2);
+    assert_eq!(value.find_duplicates(10).len(), 2);
+    assert!(json!([[1], [2]]).find_duplicates(0).is_empty());
+}
+
+#[test]
+fn walk_strings_mut() {
+    let mut value = json!({
+        "__class": "__symbol__Item",
+        "__type": "object",
+        "__symbol__@name": "sword",
+        "__symbol__@tags": ["sharp", "__symbol__blade", { "__type": "bytes", "data": [1] }],
+        "__symbol__@hash": { "key": "value" }
+    });
+    let mut paths: Vec<String> = Vec::new();
+
+    value.walk_strings_mut(false, |path, string| {
+        paths.push(path.to_string());
+        string.push('!');
+    });
+
+    assert_eq!(
+        paths,
+        [
+            "/__symbol__@name",
+            "/__symbol__@tags/0",
+            "/__symbol__@hash/key"
+        ]
+    );
+    assert_eq!(value["__symbol__@name"], json!("sword!"));
+    assert_eq!(value["__symbol__@tags"][1], json!("__symbol__blade"));
+    assert_eq!(value["__class"], json!("__symbol__Item"));
+
+    value.walk_strings_mut(true, |_, string| *string = string.to_uppercase());
+    assert_eq!(value["__symbol__@tags"][1], json!("__symbol__BLADE"));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_walks() {
+    use marshal_rs::testgen::{Generator, GeneratorOptions};
+    use std::sync::Mutex;
+
+    let mut generator: Generator = Generator::new(GeneratorOptions {
+        seed: 3,
+        ..Default::default()
+    });
+    let mut value: Value = (0..16)
+        .map(|_| load(&generator.generate(), None, None).unwrap())
+        .collect();
+
+    let mut expected: Value = value.clone();
+    expected.walk_strings_mut(true, |path, string| *string = format!("{path}:{string}"));
+    value.par_map_strings(true, |path, string| *string = format!("{path}:{string}"));
+    assert_eq!(value, expected);
+
+    let mut expected: Vec<String> = Vec::new();
+    value.clone().retain_recursive(|path, _| {
+        expected.push(path.to_string());
+        true
+    });
+
+    let paths: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    value.par_walk(|path, _| paths.lock().unwrap().push(path.to_string()));
+
+    let mut paths: Vec<String> = paths.into_inner().unwrap();
+    // Unlike retain_recursive(), par_walk() visits the root
+    assert_eq!(paths.iter().filter(|path| path.is_empty()).count(), 1);
+    paths.retain(|path| !path.is_empty());
+    paths.sort();
+    expected.sort();
+    assert!(expected.len() > 10);
+    assert_eq!(paths, expected);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn replace_matching() {
+    use marshal_rs::{Path, PathSegment, ReplaceOptions};
+    use regex::Regex;
+
+    let mut value = json!([
+        { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Old Sword", "__symbol__@note": ["Old"] },
+        { "__class": "__symbol__Skill", "__type": "object", "__symbol__@name": "Old Magic" },
+        "Old", "__symbol__Old"
+    ]);
+    let regex = Regex::new("Old").unwrap();
+
+    let report = value.replace_matching(
+        &regex,
+        "New",
+        &ReplaceOptions {
+            classes: vec!["Item".to_string()],
+            ..Default::default()
+        },
+    );
+    assert_eq!(report.count, 2);
+    assert_eq!(
+        report.paths.iter().map(Path::to_string).collect::<Vec<_>>(),
+        ["/0/__symbol__@name", "/0/__symbol__@note/0"]
+    );
+    assert_eq!(value[1]["__symbol__@name"], json!("Old Magic"));
+
+    let prefix = Path::from_dotted("[1]").unwrap();
+    assert_eq!(prefix.segments(), &[PathSegment::Index(1)]);
+
+    let report = value.replace_matching(
+        &regex,
+        "New",
+        &ReplaceOptions {
+            path_prefix: Some(prefix),
+            ..Default::default()
+        },
+    );
+    assert_eq!(report.count, 1);
+
+    let report = value.replace_matching(
+        &regex,
+        "New",
+        &ReplaceOptions {
+            include_symbols: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(report.count, 2);
+    assert_eq!(value[2], json!("New"));
+    assert_eq!(value[3], json!("__symbol__New"));
+}
+
+#[test]
+#[cfg(feature = "encodings")]
+fn transcode_strings() {
+    use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1251};
+
+    // "テスト" in Shift_JIS, invalid Shift_JIS, and "тест" in Windows-1251
+    let mut value = json!({
+        "a": { "__type": "bytes", "data": [131, 101, 131, 88, 131, 103] },
+        "b": { "__type": "bytes", "data": [255, 255] },
+        "c": "plain"
+    });
+    let report = value.transcode_strings(SHIFT_JIS, UTF_8);
+
+    assert_eq!(report.converted, 1);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].to_string(), "/b");
+    assert_eq!(value["a"], json!("テスト"));
+    assert_eq!(value["c"], json!("plain"));
+
+    let mut value = json!([{ "__type": "bytes", "data": [242, 229, 241, 242] }]);
+    value.transcode_strings(WINDOWS_1251, SHIFT_JIS);
+    value.decode_bytes(SHIFT_JIS);
+    assert_eq!(value, json!(["тест"]));
+}
+
+#[test]
+fn string_table() {
+    use marshal_rs::Path;
+
+    let mut value = json!({
+        "__class": "__symbol__Map",
+        "__type": "object",
+        "__symbol__@name": "Town",
+        "__symbol__@events": [{ "__symbol__@text": "Hello" }, "__symbol__hidden"]
+    });
+    let mut table = value.string_table();
+
+    assert_eq!(
+        table
+            .iter()
+            .map(|(path, string)| (path.to_string(), string.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("/__symbol__@name".to_string(), "Town"),
+            ("/__symbol__@events/0/__symbol__@text".to_string(), "Hello")
+        ]
+    );
+
+    table[1].1 = "Hallo".to_string();
+    assert_eq!(value.apply_string_table(table.clone()).unwrap(), 1);
+    assert_eq!(
+        value["__symbol__@events"][0]["__symbol__@text"],
+        json!("Hallo")
+    );
+
+    let original = value.clone();
+    table[0].1 = "Stadt".to_string();
+    table.push((
+        Path::from_pointer("/__symbol__@events/1").unwrap(),
+        "x".to_string(),
+    ));
+
+    assert!(value.apply_string_table(table.clone()).is_err());
+    assert_eq!(value, original);
+
+    table.pop();
+    table.push((
+        Path::from_pointer("/__symbol__@events/5").unwrap(),
+        "x".to_string(),
+    ));
+    assert!(value.apply_string_table(table).is_err());
+}
+
+#[test]
+fn estimate_marshal_size() {
+    let mut numbers: Vec<Value> = Vec::new();
+
+    for bound in [
+        0,
+        122,
+        123,
+        255,
+        256,
+        65535,
+        65536,
+        16777215,
+        16777216,
+        i32::MAX,
+    ] {
+        numbers.extend([
+            json!(bound),
+            json!(-bound),
+            json!(bound - 1),
+            json!(-bound - 1),
+        ]);
+    }
+
+    #[cfg_attr(not(feature = "bigint"), allow(unused_mut))]
+    let mut values = vec![
+        json!(null),
+        json!(numbers),
+        json!([1.5, -0.0, f64::MAX, "text", "ユニコード"]),
+        json!({ "__type": "bytes", "data": [0, 255, 128] }),
+        json!({ "__type": "bytes", "data": [0xBA, 0xBA], "encoding": "GBK" }),
+        json!({ "__type": "bigint", "negative": true, "data": [0, 0, 0, 0, 0, 0, 0, 0, 2] }),
+        json!({ "__type": "regexp", "expression": "a+", "flags": "im" }),
+        json!({
+            "__class": "__symbol__Item", "__type": "object",
+            "__symbol__@name": "Sword", "__symbol__@tags": ["__symbol__sharp", "__symbol__sharp"],
+            "__symbol__@owner": { "__class": "__symbol__Item", "__type": "object" }
+        }),
+        json!({ "__integer__1": true, "__symbol__a": false, "b": { "c": [] } }),
+    ];
+
+    #[cfg(feature = "bigint")]
+    values.push(json!({ "__type": "bigint", "value": "36893488147419103232" }));
+
+    for value in values {
+        assert_eq!(
+            value.estimate_marshal_size(),
+            marshal_rs::dump(value.clone(), None).len(),
+            "{value}"
+        );
+    }
+}
+
+#[test]
+fn tree_metrics() {
+    let value = json!({
+        "__symbol__players": [
+            {
+                "__class": "__symbol__Player", "__type": "object",
+                "__symbol__@name": { "__type": "bytes", "data": [65] },
+                "__symbol__@score": { "__type": "bigint", "value": "36893488147419103232" }
+            },
+            { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1, "__symbol__y": 2.5 } }
+        ],
+        "__ruby_default__": null
+    });
+
+    assert_eq!(json!("text").depth(), 1);
+    assert_eq!(json!([]).depth(), 1);
+    assert_eq!(value.depth(), 4);
+    assert_eq!(value.count_nodes(), 9);
+    assert_eq!(value.kind(), ValueKind::Hash);
+
+    let counts = value.count_by_kind();
+    let expected = [
+        (ValueKind::Nil, 1),
+        (ValueKind::Integer, 2),
+        (ValueKind::Float, 1),
+        (ValueKind::String, 1),
+        (ValueKind::Array, 1),
+        (ValueKind::Hash, 1),
+        (ValueKind::Object, 1),
+        (ValueKind::Struct, 1),
+    ];
+
+    assert_eq!(counts, expected.into_iter().collect());
+}
+
+#[test]
+fn canonicalize() {
+    let mut value = json!({
+        "__integer__07": { "c": "__symbol__c", "__ruby_default__": 0, "a": 1 },
+        "__object__{\"__type\":\"object\",\"__class\":\"__symbol__A\"}": [],
+        "__ruby_extends__": ["__symbol__M"],
+    });
+
+    let mut sorted = value.clone();
+    sorted.sort_keys_recursive();
+
+    assert_eq!(
+        sorted.as_object().unwrap().keys().collect::<Vec<_>>(),
+        [
+            "__ruby_extends__",
+            "__integer__07",
+            "__object__{\"__type\":\"object\",\"__class\":\"__symbol__A\"}"
+        ]
+    );
+    assert_eq!(
+        sorted["__integer__07"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["__ruby_default__", "a", "c"]
+    );
+
+    value.canonicalize();
+
+    assert_eq!(
+        value.to_string(),
+        r#"{"__ruby_extends__":["__symbol__M"],"__integer__7":{"__ruby_default__":0,"a":1,"c":"__symbol__c"},"__object__{\"__class\":\"__symbol__A\",\"__type\":\"object\"}":[]}"#
+    );
+}
+
+#[test]
+fn container_accessors() {
+    let hash = json!({ "__integer__1": "a", "__symbol__b": null, "__ruby_default__": 0, "__ruby_extends__": ["__symbol__M"] });
+
+    assert_eq!(hash.len(), 2);
+    assert_eq!(hash.keys(), ["__integer__1", "__symbol__b"]);
+    assert_eq!(hash.values(), [&json!("a"), &Value::Null]);
+
+    let wrapped =
+        json!({ "__class": "__symbol__Config", "__type": "object", "__wrapped": { "a": 1 } });
+
+    assert_eq!(wrapped.len(), 1);
+    assert_eq!(wrapped.keys(), ["a"]);
+
+    let data = json!({ "__class": "__symbol__Point", "__type": "object", "__data": [1, 2] });
+
+    assert!(data.is_empty());
+    assert!(data.values().is_empty());
+
+    assert_eq!(json!("__symbol__name").len(), 4);
+    assert_eq!(json!("text").with_string_encoding("Shift_JIS").len(), 4);
+    assert!(json!({ "__type": "bigint", "value": "36893488147419103232" }).is_empty());
+    assert!(json!("").is_empty());
+    assert!(json!(null).keys().is_empty());
+    assert_eq!(json!([1, [2]]).values(), [&json!(1), &json!([2])]);
+}
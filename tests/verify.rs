@@ -0,0 +1,78 @@
+use marshal_rs::{dump, verify::roundtrip_report};
+#[cfg(not(feature = "sonic"))]
+use serde_json::json;
+#[cfg(feature = "sonic")]
+use sonic_rs::json;
+
+fn reasons(original: &[u8]) -> Vec<String> {
+    roundtrip_report(original)
+        .unwrap()
+        .divergences
+        .into_iter()
+        .map(|divergence| divergence.reason)
+        .collect()
+}
+
+#[test]
+fn identical() {
+    let original: Vec<u8> = dump(
+        json!([1, "text", 1.5, { "__class": "__symbol__A", "__type": "object", "__symbol__@a": "__symbol__b" }]),
+        None,
+    );
+    let report = roundtrip_report(&original).unwrap();
+
+    assert!(report.is_identical());
+    assert_eq!(report.dumped, original);
+}
+
+#[test]
+#[cfg(feature = "bigint")]
+fn bignum_padding() {
+    // 2 ** 32, written with an extra word of zeros
+    let report = roundtrip_report(b"\x04\x08l+\x09\x00\x00\x00\x00\x01\x00\x00\x00").unwrap();
+
+    assert_eq!(report.dumped, b"\x04\x08l+\x08\x00\x00\x00\x00\x01\x00");
+    assert_eq!(report.divergences.len(), 1);
+    assert_eq!(report.divergences[0].offset, 2);
+    assert_eq!(
+        report.divergences[0].token,
+        Some(marshal_rs::raw::Constants::Bignum)
+    );
+    assert_eq!(
+        report.divergences[0].to_string(),
+        "Bignum at 2 (dumped at 2): bignum padding changed"
+    );
+}
+
+#[test]
+fn divergences() {
+    // [1.5e0, 2.5], where the second Float is unchanged
+    assert_eq!(
+        reasons(b"\x04\x08[\x07f\x0a1.5e0f\x082.5"),
+        ["float reformatted"]
+    );
+
+    // ["a", "a"], where the second String is a link to the first one
+    assert_eq!(
+        reasons(b"\x04\x08[\x07I\"\x06a\x06:\x06ET@\x06"),
+        ["object link expanded"]
+    );
+
+    // Object with instance variables in reverse order of their symbols
+    assert!(reasons(b"\x04\x08o:\x06A\x07:\x07@bi\x06:\x07@ai\x07").is_empty());
+}
+
+#[test]
+#[cfg(feature = "encodings")]
+fn transcoded_string() {
+    // "a" in Shift_JIS
+    assert_eq!(
+        reasons(b"\x04\x08I\"\x06a\x06:\x0dencoding\"\x0eShift_JIS"),
+        ["ivar encoding dropped", "ivar E added"]
+    );
+}
+
+#[test]
+fn invalid_data() {
+    assert!(roundtrip_report(b"\x04\x08[\x07").is_err());
+}
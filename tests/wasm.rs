@@ -0,0 +1,21 @@
+#![cfg(all(feature = "wasm", not(feature = "sonic")))]
+use marshal_rs::wasm::{dump_json, load_json};
+
+#[test]
+fn wasm_json_roundtrip() {
+    // [:a, "b", 1]
+    let bytes: Vec<u8> = vec![
+        0x04, 0x08, 0x5b, 0x08, 0x3a, 0x06, 0x61, 0x49, 0x22, 0x06, 0x62, 0x06, 0x3a, 0x06, 0x45,
+        0x54, 0x69, 0x06,
+    ];
+
+    let json: String = load_json(&bytes, None, None).unwrap();
+    assert_eq!(json, r#"["__symbol__a","b",1]"#);
+    assert_eq!(dump_json(&json, None).unwrap(), bytes);
+
+    let binary: String = load_json(&bytes, Some("binary".to_string()), None).unwrap();
+    assert_eq!(
+        binary,
+        r#"["__symbol__a",{"__type":"bytes","data":[98]},1]"#
+    );
+}
@@ -0,0 +1,64 @@
+#![cfg(all(feature = "yaml", not(feature = "sonic")))]
+use marshal_rs::{
+    yaml::{from_yaml, to_yaml},
+    ValueExt,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn yaml_roundtrip() {
+    let value = json!([
+        null, true, 1, -2.5, "text", ":not a symbol", "__symbol__sym",
+        { "__type": "bytes", "data": [0, 255, 16, 32] },
+        { "__type": "bigint", "value": "36893488147419103232" },
+        { "__type": "regexp", "expression": "a/b", "flags": "im" },
+        { "__class": "Comparable", "__type": "module", "__old": false },
+        { "__class": "Object", "__type": "class" },
+        { "__class": "__symbol__Point", "__type": "struct", "__members": { "__symbol__x": 1, "__symbol__y": 2 } },
+        { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@stats": { "__symbol__atk": 10, "__object__{\"__class\":\"__symbol__Key\",\"__type\":\"object\"}": 1 } },
+        { "__class": "__symbol__Time", "__type": "object", "__userDefined": [1, 2, 3] },
+        { "__integer__1": "one", "__float__1.5": "float", "__array__[1,2]": "array", "__symbol__key": "symbol", "__ruby_default__": 0 }
+    ]);
+
+    let yaml = value.to_yaml().unwrap();
+
+    assert!(yaml.contains("- !ruby/symbol sym\n"));
+    assert!(yaml.contains("- !binary AP8QIA==\n"));
+    assert!(yaml.contains("- !ruby/regexp /a/b/im\n"));
+    assert!(yaml.contains("- !ruby/struct:Point\n  x: 1\n  y: 2\n"));
+    assert!(yaml.contains(
+        "- !ruby/object:Item\n  name: Sword\n  stats:\n    :atk: 10\n    !ruby/object:Key {}: 1\n"
+    ));
+    assert_eq!(Value::from_yaml(&yaml).unwrap(), value);
+}
+
+#[test]
+fn psych_yaml() {
+    // Output of Psych.dump in Ruby 3.3
+    let yaml = "---
+- :weapon
+- !ruby/object:Item
+  name: Sword
+  price: 100
+  note: !binary |-
+    /w==
+- !ruby/sym other
+- :name: hash
+  1: one
+";
+
+    assert_eq!(
+        from_yaml(yaml).unwrap(),
+        json!([
+            "__symbol__weapon",
+            { "__class": "__symbol__Item", "__type": "object", "__symbol__@name": "Sword", "__symbol__@price": 100, "__symbol__@note": { "__type": "bytes", "data": [255] } },
+            "__symbol__other",
+            { "__symbol__name": "hash", "__integer__1": "one" }
+        ])
+    );
+
+    assert_eq!(to_yaml(&json!("__symbol__a")).unwrap(), "!ruby/symbol a\n");
+    assert!(from_yaml("!ruby/range {begin: 1, end: 2}").is_err());
+    assert!(from_yaml(".inf").is_err());
+    assert!(from_yaml("[").is_err());
+}